@@ -1,12 +1,45 @@
 use {
     crate::utility::Loader,
+    base64::Engine,
     intermediary::{
+        METEORA_DAMM_V2_PROGRAM_ID,
+        METEORA_DLMM_PROGRAM_ID,
         METEORA_V1_PROGRAM_ID,
         METEORA_V1_VAULT_PROGRAM_ID,
+        ORCA_WHIRLPOOL_PROGRAM_ID,
+        PHOENIX_PROGRAM_ID,
         PROGRAM_ID,
-        extern_source::meteora_v1::{
-            Pool,
-            Vault,
+        PUMP_FUN_PROGRAM_ID,
+        PUMP_SWAP_PROGRAM_ID,
+        dex::SwapCalculationResult,
+        extern_source::{
+            meteora_damm_v2::Pool as MeteoraDammV2Pool,
+            meteora_dlmm::{
+                BinArray,
+                LbPair,
+            },
+            meteora_v1::{
+                CurveType,
+                DepegType,
+                MARINADE_STATE,
+                Pool,
+                SOLIDO_STATE,
+                Vault,
+            },
+            orca_whirlpool::{
+                TICK_ARRAY_SIZE,
+                Whirlpool,
+            },
+            phoenix::MarketHeader,
+            pump_fun::{
+                BondingCurve,
+                Global,
+            },
+            pump_swap::{
+                GlobalConfig,
+                Pool as PumpSwapPool,
+            },
+            spl_token_2022,
         },
         instruction::{
             Dex_,
@@ -17,6 +50,8 @@ use {
             MUCH_USED_STATIC_ACCOUNTS,
             PdaResolver,
             QUANTITY_OF_MUCH_USED_DYNAMIC_ACCOUNTS,
+            SwapMode,
+            TokenAccountToClose,
         },
     },
     solana_program::{
@@ -24,6 +59,7 @@ use {
         program_pack::Pack,
     },
     solana_rpc_client::rpc_client::RpcClient,
+    solana_rpc_client_api::config::RpcSimulateTransactionConfig,
     solana_sdk::{
         address_lookup_table::state::AddressLookupTable,
         commitment_config::{
@@ -37,6 +73,7 @@ use {
             v0::Message as Message_,
         },
         pubkey::Pubkey,
+        signature::Signature,
         signer::{
             Signer,
             keypair::Keypair,
@@ -59,6 +96,9 @@ impl CommandProcessor {
     const ERROR_INTERMEDIARY_INVALID_TRADER: &'static str = "Intermediary invalid trader.";
     const ERROR_INVALID_ACCOUNT_LAMPORTS: &'static str = "Invalid account lamports.";
     const ERROR_INVALID_ACCOUNT_PUBKEY: &'static str = "Invalid account pubkey.";
+    const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+    // Stores the fee config of the Pump.fun and PumpSwap programs.
+    const PUMP_FEE_PROGRAM_ID: Pubkey = Pubkey::from_str_const("pfeeUxB6jkeY1Hxd7CsFCAjcbHA9rWtchMGdZ6VojVZ");
     pub fn initialize(
         rpc_client: &RpcClient,
         intermediary_manager_keypair_file_path: &str,
@@ -232,13 +272,240 @@ impl CommandProcessor {
         println!("Signature: {}", &signature);
         Ok(())
     }
+    pub fn register_quote_mint(
+        rpc_client: &RpcClient,
+        intermediary_pubkey: &str,
+        intermediary_manager_keypair_file_path: &str,
+        quote_mint_pubkey: &str,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        let intermediary_manager_keypair = Loader::load_keypair_from_file(intermediary_manager_keypair_file_path)?;
+        let intermediary_manager = intermediary_manager_keypair.pubkey();
+        let intermediary = Pubkey::from_str(intermediary_pubkey)?;
+        let quote_mint = Pubkey::from_str(quote_mint_pubkey)?;
+        let intermediary_ = Self::load_intermediary_for_manager(rpc_client, &intermediary, &intermediary_manager)?;
+        let token_program = rpc_client.get_account(&quote_mint)?.owner;
+        let (quote_token_account, quote_token_account_pubkey_bump_seed) = Self::resolve_quote_token_account(&intermediary, &intermediary_, &quote_mint);
+        println!("quote_token_account: {}", &quote_token_account);
+        let instructions = vec![
+            Instruction::register_quote_mint(
+                &PROGRAM_ID,
+                &intermediary,
+                &intermediary_manager,
+                &quote_token_account,
+                &intermediary_.self_authority,
+                &quote_mint,
+                &solana_program::system_program::ID,
+                &solana_program::sysvar::rent::ID,
+                &token_program,
+                quote_token_account_pubkey_bump_seed,
+            )?,
+        ];
+        let signers = vec![&intermediary_manager_keypair];
+        let recent_blockhash = rpc_client.get_latest_blockhash()?;
+        let message = Message::new_with_blockhash(instructions.as_slice(), Some(&intermediary_manager), &recent_blockhash);
+        let transaction = Transaction::new(signers.as_slice(), message, recent_blockhash);
+        let signature = rpc_client.send_transaction(&transaction)?;
+        println!("Signature: {}", &signature);
+        Ok(())
+    }
+    // Deposits from the associated token account of the manager.
+    pub fn deposit_quote_funds(
+        rpc_client: &RpcClient,
+        intermediary_pubkey: &str,
+        intermediary_manager_keypair_file_path: &str,
+        quote_mint_pubkey: &str,
+        amount_to_treasury: u64,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        let intermediary_manager_keypair = Loader::load_keypair_from_file(intermediary_manager_keypair_file_path)?;
+        let intermediary_manager = intermediary_manager_keypair.pubkey();
+        let intermediary = Pubkey::from_str(intermediary_pubkey)?;
+        let quote_mint = Pubkey::from_str(quote_mint_pubkey)?;
+        let intermediary_ = Self::load_intermediary_for_manager(rpc_client, &intermediary, &intermediary_manager)?;
+        let token_program = rpc_client.get_account(&quote_mint)?.owner;
+        let intermediary_manager_token_account = Self::get_associated_token_address(&intermediary_manager, &quote_mint, &token_program);
+        let (quote_token_account, quote_token_account_pubkey_bump_seed) = Self::resolve_quote_token_account(&intermediary, &intermediary_, &quote_mint);
+        let instructions = vec![
+            Instruction::deposit_quote_funds(
+                &PROGRAM_ID,
+                &intermediary,
+                &intermediary_manager,
+                &intermediary_manager_token_account,
+                &quote_token_account,
+                &quote_mint,
+                &token_program,
+                amount_to_treasury,
+                quote_token_account_pubkey_bump_seed,
+            )?,
+        ];
+        let signers = vec![&intermediary_manager_keypair];
+        let recent_blockhash = rpc_client.get_latest_blockhash()?;
+        let message = Message::new_with_blockhash(instructions.as_slice(), Some(&intermediary_manager), &recent_blockhash);
+        let transaction = Transaction::new(signers.as_slice(), message, recent_blockhash);
+        let signature = rpc_client.send_transaction(&transaction)?;
+        println!("Signature: {}", &signature);
+        Ok(())
+    }
+    // Withdraws to the existing associated token account of the manager.
+    pub fn withdraw_quote_funds(
+        rpc_client: &RpcClient,
+        intermediary_pubkey: &str,
+        intermediary_manager_keypair_file_path: &str,
+        quote_mint_pubkey: &str,
+        amount_from_treasury: u64,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        let intermediary_manager_keypair = Loader::load_keypair_from_file(intermediary_manager_keypair_file_path)?;
+        let intermediary_manager = intermediary_manager_keypair.pubkey();
+        let intermediary = Pubkey::from_str(intermediary_pubkey)?;
+        let quote_mint = Pubkey::from_str(quote_mint_pubkey)?;
+        let intermediary_ = Self::load_intermediary_for_manager(rpc_client, &intermediary, &intermediary_manager)?;
+        let token_program = rpc_client.get_account(&quote_mint)?.owner;
+        let intermediary_manager_token_account = Self::get_associated_token_address(&intermediary_manager, &quote_mint, &token_program);
+        let (quote_token_account, quote_token_account_pubkey_bump_seed) = Self::resolve_quote_token_account(&intermediary, &intermediary_, &quote_mint);
+        let quote_token_amount =
+            spl_token_2022::unpack_account_amount(rpc_client.get_account(&quote_token_account)?.data.as_slice()).ok_or("Invalid quote_token_account account state.")?;
+        if quote_token_amount < amount_from_treasury {
+            return Err(format!("The maximum amount from treasury is {}", quote_token_amount).into());
+        }
+        let instructions = vec![
+            Instruction::withdraw_quote_funds(
+                &PROGRAM_ID,
+                &intermediary,
+                &intermediary_manager,
+                &quote_token_account,
+                &intermediary_manager_token_account,
+                &intermediary_.self_authority,
+                &quote_mint,
+                &token_program,
+                amount_from_treasury,
+                quote_token_account_pubkey_bump_seed,
+            )?,
+        ];
+        let signers = vec![&intermediary_manager_keypair];
+        let recent_blockhash = rpc_client.get_latest_blockhash()?;
+        let message = Message::new_with_blockhash(instructions.as_slice(), Some(&intermediary_manager), &recent_blockhash);
+        let transaction = Transaction::new(signers.as_slice(), message, recent_blockhash);
+        let signature = rpc_client.send_transaction(&transaction)?;
+        println!("Signature: {}", &signature);
+        Ok(())
+    }
+    // Withdraws to the associated token account of the manager, if the destination token account is not presented.
+    pub fn withdraw_token(
+        rpc_client: &RpcClient,
+        intermediary_pubkey: &str,
+        intermediary_manager_keypair_file_path: &str,
+        mint_pubkey: &str,
+        amount: Option<u64>,
+        destination_token_account_pubkey: Option<&str>,
+        close_token_account: bool,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        let intermediary_manager_keypair = Loader::load_keypair_from_file(intermediary_manager_keypair_file_path)?;
+        let intermediary_manager = intermediary_manager_keypair.pubkey();
+        let intermediary = Pubkey::from_str(intermediary_pubkey)?;
+        let mint = Pubkey::from_str(mint_pubkey)?;
+        let intermediary_ = Self::load_intermediary_for_manager(rpc_client, &intermediary, &intermediary_manager)?;
+        let token_program = rpc_client.get_account(&mint)?.owner;
+        let destination_token_account = match destination_token_account_pubkey {
+            Some(destination_token_account_pubkey_) => Pubkey::from_str(destination_token_account_pubkey_)?,
+            None => Self::get_associated_token_address(&intermediary_manager, &mint, &token_program),
+        };
+        let (token_account, token_account_pubkey_bump_seed) = PdaResolver::token_account_find(&intermediary, &mint);
+        let token_amount = spl_token_2022::unpack_account_amount(rpc_client.get_account(&token_account)?.data.as_slice()).ok_or("Invalid token_account account state.")?;
+        // The whole amount is withdrawn by default.
+        let amount_ = amount.unwrap_or(token_amount);
+        if token_amount < amount_ {
+            return Err(format!("The maximum amount is {}", token_amount).into());
+        }
+        let instructions = vec![
+            Instruction::withdraw_token(
+                &PROGRAM_ID,
+                &intermediary,
+                &intermediary_manager,
+                &token_account,
+                &destination_token_account,
+                &intermediary_.self_authority,
+                &mint,
+                &token_program,
+                amount_,
+                token_account_pubkey_bump_seed,
+                close_token_account,
+            )?,
+        ];
+        let signers = vec![&intermediary_manager_keypair];
+        let recent_blockhash = rpc_client.get_latest_blockhash()?;
+        let message = Message::new_with_blockhash(instructions.as_slice(), Some(&intermediary_manager), &recent_blockhash);
+        let transaction = Transaction::new(signers.as_slice(), message, recent_blockhash);
+        let signature = rpc_client.send_transaction(&transaction)?;
+        println!("Signature: {}", &signature);
+        Ok(())
+    }
+    // The rent is refunded to the fee payer, which is the trader or the manager.
+    pub fn close_token_accounts(rpc_client: &RpcClient, intermediary_pubkey: &str, receiver_keypair_file_path: &str, mint_pubkeys: &str) -> Result<(), Box<dyn Error + 'static>> {
+        let receiver_keypair = Loader::load_keypair_from_file(receiver_keypair_file_path)?;
+        let receiver = receiver_keypair.pubkey();
+        let intermediary = Pubkey::from_str(intermediary_pubkey)?;
+        let intermediary_account = rpc_client.get_account(&intermediary)?;
+        let intermediary_data = intermediary_account.data.as_slice();
+        let intermediary_ = bytemuck::from_bytes::<Intermediary>(intermediary_data);
+        if !intermediary_.is_initialized() {
+            return Err(Self::ERROR_INTERMEDIARY_IS_NOT_INITIALIZED.into());
+        }
+        if receiver != intermediary_.trader && receiver != intermediary_.manager {
+            return Err(Self::ERROR_INVALID_ACCOUNT_PUBKEY.into());
+        }
+        let mut token_accounts = vec![];
+        '_a: for mint_pubkey in mint_pubkeys.split(',') {
+            let token_mint = Pubkey::from_str(mint_pubkey.trim())?;
+            let (token_account, token_account_pubkey_bump_seed) = PdaResolver::token_account_find(&intermediary, &token_mint);
+            token_accounts.push((
+                token_account,
+                TokenAccountToClose {
+                    token_mint,
+                    token_account_pubkey_bump_seed,
+                },
+            ));
+        }
+        let instructions = vec![
+            Instruction::close_token_accounts(
+                &PROGRAM_ID,
+                &intermediary,
+                &receiver,
+                &intermediary_.self_authority,
+                &spl_token::ID,
+                &spl_token_2022::ID,
+                token_accounts.iter().map(|(token_account, token_account_to_close)| (token_account, *token_account_to_close)).collect(),
+            )?,
+        ];
+        let signers = vec![&receiver_keypair];
+        let recent_blockhash = rpc_client.get_latest_blockhash()?;
+        let message = Message::new_with_blockhash(instructions.as_slice(), Some(&receiver), &recent_blockhash);
+        let transaction = Transaction::new(signers.as_slice(), message, recent_blockhash);
+        let signature = rpc_client.send_transaction(&transaction)?;
+        println!("Signature: {}", &signature);
+        Ok(())
+    }
     pub fn swap(
         rpc_client: &RpcClient,
         intermediary_pubkey: &str,
         intermediary_trader_keypair_file_path: &str,
+        quote_mint_pubkey: &str,
         amount_in: u64,
         min_amount_out: u64,
+        is_from_quote_to_token: bool,
+        orca_whirlpool_pubkey: Option<&str>,
+        meteora_dlmm_pubkey: Option<&str>,
+        meteora_damm_v2_pubkey: Option<&str>,
+        pump_fun_pubkey: Option<&str>,
+        pump_swap_pubkey: Option<&str>,
+        phoenix_pubkey: Option<&str>,
+        swap_mode: &str,
+        amounts_in: Option<&str>,
+        parts: Option<u8>,
+        valid_until_slot: Option<u64>,
+        valid_until_unix_timestamp: Option<i64>,
+        max_price_impact_bps: Option<u16>,
+        max_slippage_bps: Option<u16>,
     ) -> Result<(), Box<dyn Error + 'static>> {
+        let swap_mode_ = Self::resolve_swap_mode(swap_mode, amounts_in, parts)?;
         let intermediary_trader_keypair = Loader::load_keypair_from_file(intermediary_trader_keypair_file_path)?;
         let intermediary_trader = intermediary_trader_keypair.pubkey();
         let intermediary = Pubkey::from_str(intermediary_pubkey)?;
@@ -253,9 +520,176 @@ impl CommandProcessor {
         }
         let common_address_lookup_table_account = rpc_client.get_account(&intermediary_.common_address_lookup_table)?;
         let common_address_lookup_table = AddressLookupTable::deserialize(common_address_lookup_table_account.data.as_slice())?;
-        if common_address_lookup_table.addresses.len() != QUANTITY_OF_MUCH_USED_DYNAMIC_ACCOUNTS as usize + MUCH_USED_STATIC_ACCOUNTS.len() {
-            return Err("Invalid common_address_lookup_table account state.".into());
+        Self::check_common_address_lookup_table(&intermediary, intermediary_, common_address_lookup_table.addresses.as_ref())?;
+        let quote_mint = Pubkey::from_str(quote_mint_pubkey)?;
+        let (quote_token_account, quote_token_account_pubkey_bump_seed) = Self::resolve_quote_token_account(&intermediary, intermediary_, &quote_mint);
+        Self::resolve_dexes(
+            rpc_client,
+            intermediary_,
+            &intermediary_trader,
+            &quote_mint,
+            is_from_quote_to_token,
+            orca_whirlpool_pubkey,
+            meteora_dlmm_pubkey,
+            meteora_damm_v2_pubkey,
+            pump_fun_pubkey,
+            pump_swap_pubkey,
+            phoenix_pubkey,
+            |token_mint, dexes| {
+                let (token_account, token_account_pubkey_bump_seed) = PdaResolver::token_account_find(&intermediary, token_mint);
+                let instructions = vec![
+                    Instruction::swap(
+                        &PROGRAM_ID,
+                        &intermediary,
+                        &intermediary_trader,
+                        &quote_token_account,
+                        &intermediary_.self_authority,
+                        &token_account,
+                        &quote_mint,
+                        token_mint,
+                        &solana_program::system_program::ID,
+                        &solana_program::sysvar::rent::ID,
+                        &spl_token::ID,
+                        &spl_token_2022::ID,
+                        dexes,
+                        amount_in,
+                        min_amount_out,
+                        token_account_pubkey_bump_seed,
+                        quote_token_account_pubkey_bump_seed,
+                        is_from_quote_to_token,
+                        true,
+                        swap_mode_,
+                        valid_until_slot,
+                        valid_until_unix_timestamp,
+                        max_price_impact_bps,
+                        max_slippage_bps,
+                    )?,
+                ];
+                let common_address_lookup_table_account_ = AddressLookupTableAccount {
+                    key: intermediary_.common_address_lookup_table,
+                    addresses: common_address_lookup_table.addresses.to_vec(),
+                };
+                let signers = vec![&intermediary_trader_keypair];
+                let recent_blockhash = rpc_client.get_latest_blockhash()?;
+                let message = Message_::try_compile(&intermediary_trader, instructions.as_slice(), [common_address_lookup_table_account_].as_slice(), recent_blockhash)?;
+                let versioned_transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), signers.as_slice())?;
+                let signature = rpc_client.send_transaction(&versioned_transaction)?;
+                println!("Signature: {}", &signature);
+                Ok(())
+            },
+        )
+    }
+    // Simulates the Quote instruction, so nothing is signed and no fee is paid.
+    pub fn quote(
+        rpc_client: &RpcClient,
+        intermediary_pubkey: &str,
+        quote_mint_pubkey: &str,
+        amount_in: u64,
+        is_from_quote_to_token: bool,
+        orca_whirlpool_pubkey: Option<&str>,
+        meteora_dlmm_pubkey: Option<&str>,
+        meteora_damm_v2_pubkey: Option<&str>,
+        pump_fun_pubkey: Option<&str>,
+        pump_swap_pubkey: Option<&str>,
+        phoenix_pubkey: Option<&str>,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        let intermediary = Pubkey::from_str(intermediary_pubkey)?;
+        let intermediary_account = rpc_client.get_account(&intermediary)?;
+        let intermediary_data = intermediary_account.data.as_slice();
+        let intermediary_ = bytemuck::from_bytes::<Intermediary>(intermediary_data);
+        if !intermediary_.is_initialized() {
+            return Err(Self::ERROR_INTERMEDIARY_IS_NOT_INITIALIZED.into());
+        }
+        let common_address_lookup_table_account = rpc_client.get_account(&intermediary_.common_address_lookup_table)?;
+        let common_address_lookup_table = AddressLookupTable::deserialize(common_address_lookup_table_account.data.as_slice())?;
+        Self::check_common_address_lookup_table(&intermediary, intermediary_, common_address_lookup_table.addresses.as_ref())?;
+        let quote_mint = Pubkey::from_str(quote_mint_pubkey)?;
+        let (quote_token_account, quote_token_account_pubkey_bump_seed) = Self::resolve_quote_token_account(&intermediary, intermediary_, &quote_mint);
+        let swap_calculation_results = Self::resolve_dexes(
+            rpc_client,
+            intermediary_,
+            &intermediary_.trader,
+            &quote_mint,
+            is_from_quote_to_token,
+            orca_whirlpool_pubkey,
+            meteora_dlmm_pubkey,
+            meteora_damm_v2_pubkey,
+            pump_fun_pubkey,
+            pump_swap_pubkey,
+            phoenix_pubkey,
+            |token_mint, dexes| {
+                let (token_account, token_account_pubkey_bump_seed) = PdaResolver::token_account_find(&intermediary, token_mint);
+                let instructions = vec![
+                    Instruction::quote(
+                        &PROGRAM_ID,
+                        &intermediary,
+                        &quote_token_account,
+                        &intermediary_.self_authority,
+                        &token_account,
+                        &quote_mint,
+                        token_mint,
+                        dexes,
+                        amount_in,
+                        token_account_pubkey_bump_seed,
+                        quote_token_account_pubkey_bump_seed,
+                        is_from_quote_to_token,
+                        true,
+                    )?,
+                ];
+                let common_address_lookup_table_account_ = AddressLookupTableAccount {
+                    key: intermediary_.common_address_lookup_table,
+                    addresses: common_address_lookup_table.addresses.to_vec(),
+                };
+                let recent_blockhash = rpc_client.get_latest_blockhash()?;
+                let message = Message_::try_compile(&intermediary_.trader, instructions.as_slice(), [common_address_lookup_table_account_].as_slice(), recent_blockhash)?;
+                let versioned_transaction = VersionedTransaction {
+                    signatures: vec![Signature::default(); message.header.num_required_signatures as usize],
+                    message: VersionedMessage::V0(message),
+                };
+                let rpc_simulate_transaction_config = RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    ..RpcSimulateTransactionConfig::default()
+                };
+                let rpc_simulate_transaction_result = rpc_client.simulate_transaction_with_config(&versioned_transaction, rpc_simulate_transaction_config)?.value;
+                if let Some(transaction_error) = rpc_simulate_transaction_result.err {
+                    return Err(format!("Quote simulation failed: {}.", transaction_error).into());
+                }
+                let return_data = match rpc_simulate_transaction_result.return_data {
+                    Some(return_data_) => return_data_,
+                    None => return Err("Quote simulation returned no data.".into()),
+                };
+                if return_data.program_id != PROGRAM_ID.to_string() {
+                    return Err(Self::ERROR_INVALID_ACCOUNT_PUBKEY.into());
+                }
+                let return_data_ = base64::engine::general_purpose::STANDARD.decode(return_data.data.0.as_str())?;
+                Ok(<Vec<SwapCalculationResult> as borsh::de::BorshDeserialize>::deserialize(&mut return_data_.as_slice())?)
+            },
+        )?;
+        '_a: for swap_calculation_result in swap_calculation_results.iter() {
+            println!(
+                "Pool: {}, amount_in: {}, amount_in_fee: {}, amount_out: {}.",
+                &swap_calculation_result.pool, swap_calculation_result.amount_in, swap_calculation_result.amount_in_fee, swap_calculation_result.amount_out
+            );
         }
+        Ok(())
+    }
+    // Resolves the accounts of the hard-coded Meteora V1 pool and of the presented Dexes of the same token pair
+    // and passes the token mint of the pair with the Dexes to the 'f'.
+    fn resolve_dexes<T>(
+        rpc_client: &RpcClient,
+        intermediary_: &Intermediary,
+        rent_payer: &Pubkey,
+        quote_mint: &Pubkey,
+        is_from_quote_to_token: bool,
+        orca_whirlpool_pubkey: Option<&str>,
+        meteora_dlmm_pubkey: Option<&str>,
+        meteora_damm_v2_pubkey: Option<&str>,
+        pump_fun_pubkey: Option<&str>,
+        pump_swap_pubkey: Option<&str>,
+        phoenix_pubkey: Option<&str>,
+        f: impl FnOnce(&Pubkey, Vec<Dex_<'_>>) -> Result<T, Box<dyn Error + 'static>>,
+    ) -> Result<T, Box<dyn Error + 'static>> {
         let pool_pubkey = Pubkey::from_str_const("4RnP2XvmeN21nCz8tPspGEiSTzBJcsMn1eeY5mzm8N1d");
         let pool_account = rpc_client.get_account(&pool_pubkey)?;
         const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
@@ -266,62 +700,752 @@ impl CommandProcessor {
         let b_vault_account = rpc_client.get_account(&pool.b_vault)?;
         let b_vault =
             <Vault as borsh::de::BorshDeserialize>::deserialize(&mut &(b_vault_account.data.as_slice()[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..])).unwrap();
-        let quote_mint = spl_token::native_mint::id();
-        let (token_mint, protocol_token_fee) = if a_vault.token_mint == quote_mint {
-            (b_vault.token_mint, pool.protocol_token_a_fee)
+        let token_mint = if a_vault.token_mint == *quote_mint {
+            b_vault.token_mint
+        } else if b_vault.token_mint == *quote_mint {
+            a_vault.token_mint
         } else {
-            (a_vault.token_mint, pool.protocol_token_b_fee)
+            return Err("Invalid Meteora V1 token mints.".into());
         };
-        let (token_account, token_account_pubkey_bump_seed) = PdaResolver::token_account_find(&intermediary, &token_mint);
-        let instructions = vec![
-            Instruction::swap(
-                &PROGRAM_ID,
-                &intermediary,
-                &intermediary_trader,
-                &intermediary_.w_sol_token_account,
-                &intermediary_.self_authority,
-                &token_account,
-                &quote_mint,
-                &token_mint,
-                &solana_program::system_program::ID,
-                &solana_program::sysvar::rent::ID,
-                &spl_token::ID,
-                vec![
-                    Dex_::MeteoraV1 {
-                        meteora_v1_program: &METEORA_V1_PROGRAM_ID,
-                        clock:  &solana_program::sysvar::clock::ID,
-                        pool: &pool_pubkey,
-                        a_vault: &pool.a_vault,
-                        b_vault: &pool.b_vault,
-                        a_token_vault: &a_vault.token_vault,
-                        b_token_vault: &b_vault.token_vault,
-                        a_vault_lp_mint: &a_vault.lp_mint,
-                        b_vault_lp_mint: &b_vault.lp_mint,
-                        a_vault_lp: &pool.a_vault_lp,
-                        b_vault_lp: &pool.b_vault_lp,
-                        protocol_token_fee: &protocol_token_fee,
-                        vault_program: &METEORA_V1_VAULT_PROGRAM_ID,
-                        token_program: &spl_token::ID,
-                    },
-                ],
-                amount_in,
-                min_amount_out,
-                token_account_pubkey_bump_seed,
-                true,
-                true,
-
-            )?,
+        // The protocol fee is charged in the input mint.
+        let in_mint = if is_from_quote_to_token {
+            *quote_mint
+        } else {
+            token_mint
+        };
+        let protocol_token_fee = if a_vault.token_mint == in_mint {
+            pool.protocol_token_a_fee
+        } else {
+            pool.protocol_token_b_fee
+        };
+        // The token program of the Dexes with the one token program account is the owner of the token mint.
+        let token_program = rpc_client.get_account(&token_mint)?.owner;
+        let meteora_v1_depeg_accounts = match pool.curve_type {
+            CurveType::Stable {
+                ref depeg,
+                ..
+            } => {
+                match depeg.depeg_type {
+                    DepegType::None => vec![],
+                    DepegType::Marinade => vec![MARINADE_STATE],
+                    DepegType::Lido => vec![SOLIDO_STATE],
+                    DepegType::SplStake => vec![pool.stake],
+                }
+            }
+            CurveType::ConstantProduct => vec![],
+        };
+        let mut dexes = vec![
+            Dex_::MeteoraV1 {
+                meteora_v1_program: &METEORA_V1_PROGRAM_ID,
+                clock: &solana_program::sysvar::clock::ID,
+                pool: &pool_pubkey,
+                a_vault: &pool.a_vault,
+                b_vault: &pool.b_vault,
+                a_token_vault: &a_vault.token_vault,
+                b_token_vault: &b_vault.token_vault,
+                a_vault_lp_mint: &a_vault.lp_mint,
+                b_vault_lp_mint: &b_vault.lp_mint,
+                a_vault_lp: &pool.a_vault_lp,
+                b_vault_lp: &pool.b_vault_lp,
+                protocol_token_fee: &protocol_token_fee,
+                vault_program: &METEORA_V1_VAULT_PROGRAM_ID,
+                token_program: &token_program,
+                depeg_accounts: meteora_v1_depeg_accounts.as_slice(),
+            },
         ];
-        let common_address_lookup_table_account_ = AddressLookupTableAccount {
-            key: intermediary_.common_address_lookup_table,
-            addresses: common_address_lookup_table.addresses.to_vec(),
+        let orca_whirlpool_accounts = match orca_whirlpool_pubkey {
+            Some(orca_whirlpool_pubkey_) => {
+                Some(Self::resolve_orca_whirlpool_accounts(
+                    rpc_client,
+                    orca_whirlpool_pubkey_,
+                    quote_mint,
+                    &token_mint,
+                    is_from_quote_to_token,
+                )?)
+            }
+            None => None,
         };
-        let signers = vec![&intermediary_trader_keypair];
-        let recent_blockhash = rpc_client.get_latest_blockhash()?;
-        let message = Message_::try_compile(&intermediary_trader, instructions.as_slice(), [common_address_lookup_table_account_].as_slice(), recent_blockhash)?;
-        let versioned_transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), signers.as_slice())?;
-        let signature = rpc_client.send_transaction(&versioned_transaction)?;
-        println!("Signature: {}", &signature);
+        if let Some(ref orca_whirlpool_accounts_) = orca_whirlpool_accounts {
+            dexes.push(Dex_::OrcaWhirlpool {
+                whirlpool_program: &ORCA_WHIRLPOOL_PROGRAM_ID,
+                token_program: &orca_whirlpool_accounts_.token_program,
+                whirlpool: &orca_whirlpool_accounts_.whirlpool,
+                token_vault_a: &orca_whirlpool_accounts_.token_vault_a,
+                token_vault_b: &orca_whirlpool_accounts_.token_vault_b,
+                tick_array_0: &orca_whirlpool_accounts_.tick_arrays[0],
+                tick_array_1: &orca_whirlpool_accounts_.tick_arrays[1],
+                tick_array_2: &orca_whirlpool_accounts_.tick_arrays[2],
+                oracle: &orca_whirlpool_accounts_.oracle,
+            });
+        }
+        let meteora_dlmm_accounts = match meteora_dlmm_pubkey {
+            Some(meteora_dlmm_pubkey_) => Some(Self::resolve_meteora_dlmm_accounts(rpc_client, meteora_dlmm_pubkey_, quote_mint, &token_mint, is_from_quote_to_token)?),
+            None => None,
+        };
+        if let Some(ref meteora_dlmm_accounts_) = meteora_dlmm_accounts {
+            dexes.push(Dex_::MeteoraDlmm {
+                meteora_dlmm_program: &METEORA_DLMM_PROGRAM_ID,
+                lb_pair: &meteora_dlmm_accounts_.lb_pair,
+                bin_array_bitmap_extension: meteora_dlmm_accounts_.bin_array_bitmap_extension.as_ref(),
+                reserve_x: &meteora_dlmm_accounts_.reserve_x,
+                reserve_y: &meteora_dlmm_accounts_.reserve_y,
+                token_x_mint: &meteora_dlmm_accounts_.token_x_mint,
+                token_y_mint: &meteora_dlmm_accounts_.token_y_mint,
+                oracle: &meteora_dlmm_accounts_.oracle,
+                token_x_program: &meteora_dlmm_accounts_.token_x_program,
+                token_y_program: &meteora_dlmm_accounts_.token_y_program,
+                event_authority: &meteora_dlmm_accounts_.event_authority,
+                bin_arrays: meteora_dlmm_accounts_.bin_arrays.as_slice(),
+            });
+        }
+        let meteora_damm_v2_accounts = match meteora_damm_v2_pubkey {
+            Some(meteora_damm_v2_pubkey_) => Some(Self::resolve_meteora_damm_v2_accounts(rpc_client, meteora_damm_v2_pubkey_, quote_mint, &token_mint)?),
+            None => None,
+        };
+        if let Some(ref meteora_damm_v2_accounts_) = meteora_damm_v2_accounts {
+            dexes.push(Dex_::MeteoraDammV2 {
+                meteora_damm_v2_program: &METEORA_DAMM_V2_PROGRAM_ID,
+                pool_authority: &meteora_damm_v2_accounts_.pool_authority,
+                pool: &meteora_damm_v2_accounts_.pool,
+                token_a_vault: &meteora_damm_v2_accounts_.token_a_vault,
+                token_b_vault: &meteora_damm_v2_accounts_.token_b_vault,
+                token_a_mint: &meteora_damm_v2_accounts_.token_a_mint,
+                token_b_mint: &meteora_damm_v2_accounts_.token_b_mint,
+                token_a_program: &meteora_damm_v2_accounts_.token_a_program,
+                token_b_program: &meteora_damm_v2_accounts_.token_b_program,
+                event_authority: &meteora_damm_v2_accounts_.event_authority,
+            });
+        }
+        if pump_fun_pubkey.is_some() && *quote_mint != spl_token::native_mint::ID {
+            return Err("Pump.fun supports only the WSol quote mint.".into());
+        }
+        let pump_fun_accounts = match pump_fun_pubkey {
+            Some(pump_fun_pubkey_) => {
+                Some(Self::resolve_pump_fun_accounts(
+                    rpc_client,
+                    pump_fun_pubkey_,
+                    &token_mint,
+                    &token_program,
+                    &intermediary_.self_authority,
+                )?)
+            }
+            None => None,
+        };
+        if let Some(ref pump_fun_accounts_) = pump_fun_accounts {
+            dexes.push(Dex_::PumpFun {
+                pump_fun_program: &PUMP_FUN_PROGRAM_ID,
+                global: &pump_fun_accounts_.global,
+                fee_recipient: &pump_fun_accounts_.fee_recipient,
+                mint: &token_mint,
+                bonding_curve: &pump_fun_accounts_.bonding_curve,
+                associated_bonding_curve: &pump_fun_accounts_.associated_bonding_curve,
+                creator_vault: &pump_fun_accounts_.creator_vault,
+                event_authority: &pump_fun_accounts_.event_authority,
+                global_volume_accumulator: &pump_fun_accounts_.global_volume_accumulator,
+                user_volume_accumulator: &pump_fun_accounts_.user_volume_accumulator,
+                fee_config: &pump_fun_accounts_.fee_config,
+                fee_program: &pump_fun_accounts_.fee_program,
+                system_program: &solana_program::system_program::ID,
+                token_program: &token_program,
+                w_sol_token_mint: quote_mint,
+                temporary_w_sol_token_account: &intermediary_.temporary_w_sol_token_account,
+                rent_payer,
+                self_authority: &intermediary_.self_authority,
+            });
+        }
+        let pump_swap_accounts = match pump_swap_pubkey {
+            Some(pump_swap_pubkey_) => Some(Self::resolve_pump_swap_accounts(rpc_client, pump_swap_pubkey_, quote_mint, &token_mint, &intermediary_.self_authority)?),
+            None => None,
+        };
+        if let Some(ref pump_swap_accounts_) = pump_swap_accounts {
+            dexes.push(Dex_::PumpSwap {
+                pump_swap_program: &PUMP_SWAP_PROGRAM_ID,
+                pool: &pump_swap_accounts_.pool,
+                global_config: &pump_swap_accounts_.global_config,
+                base_mint: &pump_swap_accounts_.base_mint,
+                quote_mint: &pump_swap_accounts_.quote_mint,
+                pool_base_token_account: &pump_swap_accounts_.pool_base_token_account,
+                pool_quote_token_account: &pump_swap_accounts_.pool_quote_token_account,
+                protocol_fee_recipient: &pump_swap_accounts_.protocol_fee_recipient,
+                protocol_fee_recipient_token_account: &pump_swap_accounts_.protocol_fee_recipient_token_account,
+                base_token_program: &pump_swap_accounts_.base_token_program,
+                quote_token_program: &pump_swap_accounts_.quote_token_program,
+                system_program: &solana_program::system_program::ID,
+                associated_token_program: &Self::ASSOCIATED_TOKEN_PROGRAM_ID,
+                event_authority: &pump_swap_accounts_.event_authority,
+                coin_creator_vault_ata: &pump_swap_accounts_.coin_creator_vault_ata,
+                coin_creator_vault_authority: &pump_swap_accounts_.coin_creator_vault_authority,
+                global_volume_accumulator: &pump_swap_accounts_.global_volume_accumulator,
+                user_volume_accumulator: &pump_swap_accounts_.user_volume_accumulator,
+                fee_config: &pump_swap_accounts_.fee_config,
+                fee_program: &Self::PUMP_FEE_PROGRAM_ID,
+            });
+        }
+        let phoenix_accounts = match phoenix_pubkey {
+            Some(phoenix_pubkey_) => Some(Self::resolve_phoenix_accounts(rpc_client, phoenix_pubkey_, quote_mint, &token_mint)?),
+            None => None,
+        };
+        if let Some(ref phoenix_accounts_) = phoenix_accounts {
+            dexes.push(Dex_::Phoenix {
+                phoenix_program: &PHOENIX_PROGRAM_ID,
+                log_authority: &phoenix_accounts_.log_authority,
+                market: &phoenix_accounts_.market,
+                base_vault: &phoenix_accounts_.base_vault,
+                quote_vault: &phoenix_accounts_.quote_vault,
+                token_program: &phoenix_accounts_.token_program,
+            });
+        }
+        f(&token_mint, dexes)
+    }
+    // Resolves the swap mode with its parameters from the command line arguments.
+    fn resolve_swap_mode(swap_mode: &str, amounts_in: Option<&str>, parts: Option<u8>) -> Result<SwapMode, Box<dyn Error + 'static>> {
+        let swap_mode_ = match swap_mode {
+            "first_match" => SwapMode::FirstMatch,
+            "best_execution" => SwapMode::BestExecution,
+            "split" => {
+                let amounts_in_ = match amounts_in {
+                    Some(amounts_in_) => amounts_in_,
+                    None => return Err("Amounts in are required for the 'split' swap mode.".into()),
+                };
+                let mut amounts_in__: Vec<u64> = vec![];
+                '_a: for amount_in in amounts_in_.split(',') {
+                    amounts_in__.push(amount_in.trim().parse::<u64>()?);
+                }
+                SwapMode::Split {
+                    amounts_in: amounts_in__,
+                }
+            }
+            "split_by_marginal_price" => {
+                let parts_ = match parts {
+                    Some(parts_) => parts_,
+                    None => return Err("Parts are required for the 'split_by_marginal_price' swap mode.".into()),
+                };
+                SwapMode::SplitByMarginalPrice {
+                    parts: parts_,
+                }
+            }
+            "exact_out" => SwapMode::ExactOut,
+            _ => return Err("Invalid swap mode.".into()),
+        };
+        Ok(swap_mode_)
+    }
+    // Resolves the Orca Whirlpool swap accounts with the tick arrays in the swap direction.
+    fn resolve_orca_whirlpool_accounts(
+        rpc_client: &RpcClient,
+        orca_whirlpool_pubkey: &str,
+        quote_mint: &Pubkey,
+        token_mint: &Pubkey,
+        is_from_quote_to_token: bool,
+    ) -> Result<OrcaWhirlpoolAccounts, Box<dyn Error + 'static>> {
+        const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
+        let whirlpool = Pubkey::from_str(orca_whirlpool_pubkey)?;
+        let whirlpool_account = rpc_client.get_account(&whirlpool)?;
+        if whirlpool_account.owner != ORCA_WHIRLPOOL_PROGRAM_ID {
+            return Err(Self::ERROR_INVALID_ACCOUNT_PUBKEY.into());
+        }
+        let whirlpool_ =
+            <Whirlpool as borsh::de::BorshDeserialize>::deserialize(&mut &(whirlpool_account.data.as_slice()[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..]))?;
+        let (in_mint, out_mint) = if is_from_quote_to_token {
+            (quote_mint, token_mint)
+        } else {
+            (token_mint, quote_mint)
+        };
+        let a_to_b = if *in_mint == whirlpool_.token_mint_a && *out_mint == whirlpool_.token_mint_b {
+            true
+        } else if *in_mint == whirlpool_.token_mint_b && *out_mint == whirlpool_.token_mint_a {
+            false
+        } else {
+            return Err("Invalid Orca Whirlpool token mints.".into());
+        };
+        // https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/util/sparse_swap.rs
+        let ticks_in_array = TICK_ARRAY_SIZE * whirlpool_.tick_spacing as i32;
+        let shifted_tick_current_index = if a_to_b {
+            whirlpool_.tick_current_index
+        } else {
+            whirlpool_.tick_current_index + whirlpool_.tick_spacing as i32
+        };
+        let start_tick_index = shifted_tick_current_index.div_euclid(ticks_in_array) * ticks_in_array;
+        let mut tick_arrays = [Pubkey::default(); 3];
+        '_a: for (index, tick_array) in tick_arrays.iter_mut().enumerate() {
+            let start_tick_index_ = if a_to_b {
+                start_tick_index - index as i32 * ticks_in_array
+            } else {
+                start_tick_index + index as i32 * ticks_in_array
+            };
+            *tick_array = Pubkey::find_program_address(
+                [
+                    b"tick_array".as_slice(),
+                    whirlpool.as_ref(),
+                    start_tick_index_.to_string().as_bytes(),
+                ]
+                .as_slice(),
+                &ORCA_WHIRLPOOL_PROGRAM_ID,
+            )
+            .0;
+        }
+        let oracle = Pubkey::find_program_address(
+            [
+                b"oracle".as_slice(),
+                whirlpool.as_ref(),
+            ]
+            .as_slice(),
+            &ORCA_WHIRLPOOL_PROGRAM_ID,
+        )
+        .0;
+        let token_program = Self::resolve_token_program(rpc_client, &whirlpool_.token_mint_a, &whirlpool_.token_mint_b)?;
+        Ok(OrcaWhirlpoolAccounts {
+            whirlpool,
+            token_vault_a: whirlpool_.token_vault_a,
+            token_vault_b: whirlpool_.token_vault_b,
+            tick_arrays,
+            oracle,
+            token_program,
+        })
+    }
+    // Resolves the Meteora DLMM swap accounts with the existing bin arrays in the swap direction and the token programs of the pair mints.
+    fn resolve_meteora_dlmm_accounts(
+        rpc_client: &RpcClient,
+        meteora_dlmm_pubkey: &str,
+        quote_mint: &Pubkey,
+        token_mint: &Pubkey,
+        is_from_quote_to_token: bool,
+    ) -> Result<MeteoraDlmmAccounts, Box<dyn Error + 'static>> {
+        const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
+        const BIN_ARRAYS_QUANTITY: i64 = 3;
+        let lb_pair = Pubkey::from_str(meteora_dlmm_pubkey)?;
+        let lb_pair_account = rpc_client.get_account(&lb_pair)?;
+        if lb_pair_account.owner != METEORA_DLMM_PROGRAM_ID {
+            return Err(Self::ERROR_INVALID_ACCOUNT_PUBKEY.into());
+        }
+        let lb_pair_ = <LbPair as borsh::de::BorshDeserialize>::deserialize(&mut &(lb_pair_account.data.as_slice()[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..]))?;
+        let (in_mint, out_mint) = if is_from_quote_to_token {
+            (quote_mint, token_mint)
+        } else {
+            (token_mint, quote_mint)
+        };
+        let swap_for_y = if *in_mint == lb_pair_.token_x_mint && *out_mint == lb_pair_.token_y_mint {
+            true
+        } else if *in_mint == lb_pair_.token_y_mint && *out_mint == lb_pair_.token_x_mint {
+            false
+        } else {
+            return Err("Invalid Meteora DLMM token mints.".into());
+        };
+        // https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/utils/seeds.rs
+        let bin_array_bitmap_extension = Pubkey::find_program_address(
+            [
+                b"bitmap".as_slice(),
+                lb_pair.as_ref(),
+            ]
+            .as_slice(),
+            &METEORA_DLMM_PROGRAM_ID,
+        )
+        .0;
+        let event_authority = Pubkey::find_program_address([b"__event_authority".as_slice()].as_slice(), &METEORA_DLMM_PROGRAM_ID).0;
+        let active_bin_array_index = BinArray::bin_id_to_bin_array_index(lb_pair_.active_id) as i64;
+        let mut bin_arrays = Vec::<Pubkey>::with_capacity(BIN_ARRAYS_QUANTITY as usize);
+        '_a: for index in 0..BIN_ARRAYS_QUANTITY {
+            let bin_array_index = if swap_for_y {
+                active_bin_array_index - index
+            } else {
+                active_bin_array_index + index
+            };
+            bin_arrays.push(
+                Pubkey::find_program_address(
+                    [
+                        b"bin_array".as_slice(),
+                        lb_pair.as_ref(),
+                        bin_array_index.to_le_bytes().as_slice(),
+                    ]
+                    .as_slice(),
+                    &METEORA_DLMM_PROGRAM_ID,
+                )
+                .0,
+            );
+        }
+        let mut accounts = [bin_array_bitmap_extension].to_vec();
+        accounts.extend(bin_arrays.iter());
+        let accounts_ = rpc_client.get_multiple_accounts(accounts.as_slice())?;
+        // Not initialized bin arrays are skipped by the pair bitmap.
+        let bin_arrays_ = bin_arrays.into_iter().zip(accounts_[1..].iter()).filter(|(_, account)| account.is_some()).map(|(bin_array, _)| bin_array).collect::<Vec<Pubkey>>();
+        if bin_arrays_.is_empty() {
+            return Err("Meteora DLMM bin arrays are not initialized.".into());
+        }
+        let mint_accounts = rpc_client.get_multiple_accounts(
+            [
+                lb_pair_.token_x_mint,
+                lb_pair_.token_y_mint,
+            ]
+            .as_slice(),
+        )?;
+        let token_x_program = mint_accounts[0].as_ref().ok_or(Self::ERROR_INVALID_ACCOUNT_PUBKEY)?.owner;
+        let token_y_program = mint_accounts[1].as_ref().ok_or(Self::ERROR_INVALID_ACCOUNT_PUBKEY)?.owner;
+        Ok(MeteoraDlmmAccounts {
+            lb_pair,
+            bin_array_bitmap_extension: accounts_[0].as_ref().map(|_| bin_array_bitmap_extension),
+            reserve_x: lb_pair_.reserve_x,
+            reserve_y: lb_pair_.reserve_y,
+            token_x_mint: lb_pair_.token_x_mint,
+            token_y_mint: lb_pair_.token_y_mint,
+            oracle: lb_pair_.oracle,
+            event_authority,
+            bin_arrays: bin_arrays_,
+            token_x_program,
+            token_y_program,
+        })
+    }
+    // Resolves the Meteora DAMM v2 swap accounts with the token programs of the pool mints.
+    fn resolve_meteora_damm_v2_accounts(
+        rpc_client: &RpcClient,
+        meteora_damm_v2_pubkey: &str,
+        quote_mint: &Pubkey,
+        token_mint: &Pubkey,
+    ) -> Result<MeteoraDammV2Accounts, Box<dyn Error + 'static>> {
+        const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
+        let pool = Pubkey::from_str(meteora_damm_v2_pubkey)?;
+        let pool_account = rpc_client.get_account(&pool)?;
+        if pool_account.owner != METEORA_DAMM_V2_PROGRAM_ID {
+            return Err(Self::ERROR_INVALID_ACCOUNT_PUBKEY.into());
+        }
+        let pool_ =
+            <MeteoraDammV2Pool as borsh::de::BorshDeserialize>::deserialize(&mut &(pool_account.data.as_slice()[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..]))?;
+        if !(pool_.token_a_mint == *quote_mint && pool_.token_b_mint == *token_mint) && !(pool_.token_a_mint == *token_mint && pool_.token_b_mint == *quote_mint) {
+            return Err("Invalid Meteora DAMM v2 token mints.".into());
+        }
+        // https://github.com/MeteoraAg/damm-v2/blob/main/programs/cp-amm/src/constants.rs
+        let pool_authority = Pubkey::find_program_address([b"pool_authority".as_slice()].as_slice(), &METEORA_DAMM_V2_PROGRAM_ID).0;
+        let event_authority = Pubkey::find_program_address([b"__event_authority".as_slice()].as_slice(), &METEORA_DAMM_V2_PROGRAM_ID).0;
+        let mint_accounts = rpc_client.get_multiple_accounts(
+            [
+                pool_.token_a_mint,
+                pool_.token_b_mint,
+            ]
+            .as_slice(),
+        )?;
+        let token_a_program = mint_accounts[0].as_ref().ok_or(Self::ERROR_INVALID_ACCOUNT_PUBKEY)?.owner;
+        let token_b_program = mint_accounts[1].as_ref().ok_or(Self::ERROR_INVALID_ACCOUNT_PUBKEY)?.owner;
+        Ok(MeteoraDammV2Accounts {
+            pool,
+            pool_authority,
+            token_a_vault: pool_.token_a_vault,
+            token_b_vault: pool_.token_b_vault,
+            token_a_mint: pool_.token_a_mint,
+            token_b_mint: pool_.token_b_mint,
+            token_a_program,
+            token_b_program,
+            event_authority,
+        })
+    }
+    // Resolves the Pump.fun swap accounts for the bonding curve of the token mint.
+    fn resolve_pump_fun_accounts(
+        rpc_client: &RpcClient,
+        pump_fun_pubkey: &str,
+        token_mint: &Pubkey,
+        token_program: &Pubkey,
+        self_authority: &Pubkey,
+    ) -> Result<PumpFunAccounts, Box<dyn Error + 'static>> {
+        const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
+        let bonding_curve = Pubkey::from_str(pump_fun_pubkey)?;
+        // https://github.com/pump-fun/pump-public-docs/blob/main/idl/pump.json
+        if bonding_curve
+            != Pubkey::find_program_address(
+                [
+                    b"bonding-curve".as_slice(),
+                    token_mint.as_ref(),
+                ]
+                .as_slice(),
+                &PUMP_FUN_PROGRAM_ID,
+            )
+            .0
+        {
+            return Err("Invalid Pump.fun bonding curve.".into());
+        }
+        let global = Pubkey::find_program_address([b"global".as_slice()].as_slice(), &PUMP_FUN_PROGRAM_ID).0;
+        let accounts = rpc_client.get_multiple_accounts(
+            [
+                bonding_curve,
+                global,
+            ]
+            .as_slice(),
+        )?;
+        let bonding_curve_account = accounts[0].as_ref().ok_or(Self::ERROR_INVALID_ACCOUNT_PUBKEY)?;
+        let global_account = accounts[1].as_ref().ok_or(Self::ERROR_INVALID_ACCOUNT_PUBKEY)?;
+        if bonding_curve_account.owner != PUMP_FUN_PROGRAM_ID || global_account.owner != PUMP_FUN_PROGRAM_ID {
+            return Err(Self::ERROR_INVALID_ACCOUNT_PUBKEY.into());
+        }
+        let bonding_curve_ = <BondingCurve as borsh::de::BorshDeserialize>::deserialize(
+            &mut &(bonding_curve_account.data.as_slice()[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..]),
+        )?;
+        let global_ = <Global as borsh::de::BorshDeserialize>::deserialize(&mut &(global_account.data.as_slice()[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..]))?;
+        let creator_vault = Pubkey::find_program_address(
+            [
+                b"creator-vault".as_slice(),
+                bonding_curve_.creator.as_ref(),
+            ]
+            .as_slice(),
+            &PUMP_FUN_PROGRAM_ID,
+        )
+        .0;
+        let fee_config = Pubkey::find_program_address(
+            [
+                b"fee_config".as_slice(),
+                PUMP_FUN_PROGRAM_ID.as_ref(),
+            ]
+            .as_slice(),
+            &Self::PUMP_FEE_PROGRAM_ID,
+        )
+        .0;
+        let (global_volume_accumulator, user_volume_accumulator) = Self::resolve_pump_volume_accumulators(&PUMP_FUN_PROGRAM_ID, self_authority);
+        Ok(PumpFunAccounts {
+            global,
+            fee_recipient: global_.fee_recipient,
+            bonding_curve,
+            associated_bonding_curve: Self::get_associated_token_address(&bonding_curve, token_mint, token_program),
+            creator_vault,
+            event_authority: Pubkey::find_program_address([b"__event_authority".as_slice()].as_slice(), &PUMP_FUN_PROGRAM_ID).0,
+            global_volume_accumulator,
+            user_volume_accumulator,
+            fee_config,
+            fee_program: Self::PUMP_FEE_PROGRAM_ID,
+        })
+    }
+    // Resolves the PumpSwap swap accounts with the token programs of the pool mints.
+    fn resolve_pump_swap_accounts(
+        rpc_client: &RpcClient,
+        pump_swap_pubkey: &str,
+        quote_mint: &Pubkey,
+        token_mint: &Pubkey,
+        self_authority: &Pubkey,
+    ) -> Result<PumpSwapAccounts, Box<dyn Error + 'static>> {
+        const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
+        let pool = Pubkey::from_str(pump_swap_pubkey)?;
+        let pool_account = rpc_client.get_account(&pool)?;
+        if pool_account.owner != PUMP_SWAP_PROGRAM_ID {
+            return Err(Self::ERROR_INVALID_ACCOUNT_PUBKEY.into());
+        }
+        let pool_ = <PumpSwapPool as borsh::de::BorshDeserialize>::deserialize(&mut &(pool_account.data.as_slice()[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..]))?;
+        if !(pool_.quote_mint == *quote_mint && pool_.base_mint == *token_mint) && !(pool_.quote_mint == *token_mint && pool_.base_mint == *quote_mint) {
+            return Err("Invalid PumpSwap token mints.".into());
+        }
+        // https://github.com/pump-fun/pump-public-docs/blob/main/idl/pump_amm.json
+        let global_config = Pubkey::find_program_address([b"global_config".as_slice()].as_slice(), &PUMP_SWAP_PROGRAM_ID).0;
+        let accounts = rpc_client.get_multiple_accounts(
+            [
+                global_config,
+                pool_.base_mint,
+                pool_.quote_mint,
+            ]
+            .as_slice(),
+        )?;
+        let global_config_account = accounts[0].as_ref().ok_or(Self::ERROR_INVALID_ACCOUNT_PUBKEY)?;
+        let global_config_ = <GlobalConfig as borsh::de::BorshDeserialize>::deserialize(
+            &mut &(global_config_account.data.as_slice()[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..]),
+        )?;
+        let base_token_program = accounts[1].as_ref().ok_or(Self::ERROR_INVALID_ACCOUNT_PUBKEY)?.owner;
+        let quote_token_program = accounts[2].as_ref().ok_or(Self::ERROR_INVALID_ACCOUNT_PUBKEY)?.owner;
+        let protocol_fee_recipient = global_config_.protocol_fee_recipients[0];
+        let coin_creator_vault_authority = Pubkey::find_program_address(
+            [
+                b"creator_vault".as_slice(),
+                pool_.coin_creator.as_ref(),
+            ]
+            .as_slice(),
+            &PUMP_SWAP_PROGRAM_ID,
+        )
+        .0;
+        let fee_config = Pubkey::find_program_address(
+            [
+                b"fee_config".as_slice(),
+                PUMP_SWAP_PROGRAM_ID.as_ref(),
+            ]
+            .as_slice(),
+            &Self::PUMP_FEE_PROGRAM_ID,
+        )
+        .0;
+        let (global_volume_accumulator, user_volume_accumulator) = Self::resolve_pump_volume_accumulators(&PUMP_SWAP_PROGRAM_ID, self_authority);
+        Ok(PumpSwapAccounts {
+            pool,
+            global_config,
+            base_mint: pool_.base_mint,
+            quote_mint: pool_.quote_mint,
+            pool_base_token_account: pool_.pool_base_token_account,
+            pool_quote_token_account: pool_.pool_quote_token_account,
+            protocol_fee_recipient,
+            protocol_fee_recipient_token_account: Self::get_associated_token_address(&protocol_fee_recipient, &pool_.quote_mint, &quote_token_program),
+            base_token_program,
+            quote_token_program,
+            event_authority: Pubkey::find_program_address([b"__event_authority".as_slice()].as_slice(), &PUMP_SWAP_PROGRAM_ID).0,
+            coin_creator_vault_ata: Self::get_associated_token_address(&coin_creator_vault_authority, &pool_.quote_mint, &quote_token_program),
+            coin_creator_vault_authority,
+            global_volume_accumulator,
+            user_volume_accumulator,
+            fee_config,
+        })
+    }
+    fn resolve_phoenix_accounts(rpc_client: &RpcClient, phoenix_pubkey: &str, quote_mint: &Pubkey, token_mint: &Pubkey) -> Result<PhoenixAccounts, Box<dyn Error + 'static>> {
+        let market = Pubkey::from_str(phoenix_pubkey)?;
+        let market_account = rpc_client.get_account(&market)?;
+        if market_account.owner != PHOENIX_PROGRAM_ID {
+            return Err(Self::ERROR_INVALID_ACCOUNT_PUBKEY.into());
+        }
+        let market_header = MarketHeader::unpack(market_account.data.as_slice()).ok_or("Invalid Phoenix market account data.")?;
+        if !(market_header.quote_mint == *quote_mint && market_header.base_mint == *token_mint)
+            && !(market_header.quote_mint == *token_mint && market_header.base_mint == *quote_mint)
+        {
+            return Err("Invalid Phoenix token mints.".into());
+        }
+        // https://github.com/Ellipsis-Labs/phoenix-v1/blob/master/src/program/loaders.rs
+        let log_authority = Pubkey::find_program_address([b"log".as_slice()].as_slice(), &PHOENIX_PROGRAM_ID).0;
+        let token_program = Self::resolve_token_program(rpc_client, &market_header.base_mint, &market_header.quote_mint)?;
+        Ok(PhoenixAccounts {
+            log_authority,
+            market,
+            base_vault: market_header.base_vault,
+            quote_vault: market_header.quote_vault,
+            token_program,
+        })
+    }
+    // Resolves the one token program of the Dex, which should be the owner of both mints.
+    fn resolve_token_program(rpc_client: &RpcClient, mint_a: &Pubkey, mint_b: &Pubkey) -> Result<Pubkey, Box<dyn Error + 'static>> {
+        let mint_accounts = rpc_client.get_multiple_accounts(
+            [
+                *mint_a,
+                *mint_b,
+            ]
+            .as_slice(),
+        )?;
+        let token_program_a = mint_accounts[0].as_ref().ok_or(Self::ERROR_INVALID_ACCOUNT_PUBKEY)?.owner;
+        let token_program_b = mint_accounts[1].as_ref().ok_or(Self::ERROR_INVALID_ACCOUNT_PUBKEY)?.owner;
+        if token_program_a != token_program_b {
+            return Err("The mints of different token programs are not supported by the Dex.".into());
+        }
+        Ok(token_program_a)
+    }
+    // Are the same for Pump.fun and PumpSwap programs.
+    fn resolve_pump_volume_accumulators(program_id: &Pubkey, user: &Pubkey) -> (Pubkey, Pubkey) {
+        let global_volume_accumulator = Pubkey::find_program_address([b"global_volume_accumulator".as_slice()].as_slice(), program_id).0;
+        let user_volume_accumulator = Pubkey::find_program_address(
+            [
+                b"user_volume_accumulator".as_slice(),
+                user.as_ref(),
+            ]
+            .as_slice(),
+            program_id,
+        )
+        .0;
+        (global_volume_accumulator, user_volume_accumulator)
+    }
+    // The WSol treasury is created during the initialization, the treasury of other quote mint - by the registration.
+    fn resolve_quote_token_account(intermediary: &Pubkey, intermediary_: &Intermediary, quote_mint: &Pubkey) -> (Pubkey, u8) {
+        if *quote_mint == spl_token::native_mint::ID {
+            (intermediary_.w_sol_token_account, intermediary_.w_sol_token_account_pubkey_bump_seed)
+        } else {
+            PdaResolver::quote_token_account_find(intermediary, quote_mint)
+        }
+    }
+    fn load_intermediary_for_manager(rpc_client: &RpcClient, intermediary: &Pubkey, intermediary_manager: &Pubkey) -> Result<Intermediary, Box<dyn Error + 'static>> {
+        let intermediary_account = rpc_client.get_account(intermediary)?;
+        let intermediary_ = *bytemuck::from_bytes::<Intermediary>(intermediary_account.data.as_slice());
+        if !intermediary_.is_initialized() {
+            return Err(Self::ERROR_INTERMEDIARY_IS_NOT_INITIALIZED.into());
+        }
+        if *intermediary_manager != intermediary_.manager {
+            return Err(Self::ERROR_INTERMEDIARY_INVALID_MANAGER.into());
+        }
+        Ok(intermediary_)
+    }
+    // The table of the Intermediary initialized before the new much used static accounts were added
+    // is shorter, but is the leading part of the current list, so only its addresses are used.
+    fn check_common_address_lookup_table(intermediary: &Pubkey, intermediary_: &Intermediary, addresses: &[Pubkey]) -> Result<(), Box<dyn Error + 'static>> {
+        let mut common_address_lookup_table_accounts = vec![
+            *intermediary,
+            intermediary_.w_sol_token_account,
+            intermediary_.self_authority,
+        ];
+        common_address_lookup_table_accounts.extend_from_slice(MUCH_USED_STATIC_ACCOUNTS.as_slice());
+        if addresses.len() < QUANTITY_OF_MUCH_USED_DYNAMIC_ACCOUNTS as usize || !common_address_lookup_table_accounts.starts_with(addresses) {
+            return Err("Invalid common_address_lookup_table account state.".into());
+        }
         Ok(())
     }
+    fn get_associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            [
+                owner.as_ref(),
+                token_program.as_ref(),
+                mint.as_ref(),
+            ]
+            .as_slice(),
+            &Self::ASSOCIATED_TOKEN_PROGRAM_ID,
+        )
+        .0
+    }
+}
+struct OrcaWhirlpoolAccounts {
+    whirlpool: Pubkey,
+    token_vault_a: Pubkey,
+    token_vault_b: Pubkey,
+    tick_arrays: [Pubkey; 3],
+    oracle: Pubkey,
+    token_program: Pubkey,
+}
+struct MeteoraDlmmAccounts {
+    lb_pair: Pubkey,
+    bin_array_bitmap_extension: Option<Pubkey>,
+    reserve_x: Pubkey,
+    reserve_y: Pubkey,
+    token_x_mint: Pubkey,
+    token_y_mint: Pubkey,
+    oracle: Pubkey,
+    event_authority: Pubkey,
+    bin_arrays: Vec<Pubkey>,
+    token_x_program: Pubkey,
+    token_y_program: Pubkey,
+}
+struct MeteoraDammV2Accounts {
+    pool: Pubkey,
+    pool_authority: Pubkey,
+    token_a_vault: Pubkey,
+    token_b_vault: Pubkey,
+    token_a_mint: Pubkey,
+    token_b_mint: Pubkey,
+    token_a_program: Pubkey,
+    token_b_program: Pubkey,
+    event_authority: Pubkey,
+}
+struct PumpFunAccounts {
+    global: Pubkey,
+    fee_recipient: Pubkey,
+    bonding_curve: Pubkey,
+    associated_bonding_curve: Pubkey,
+    creator_vault: Pubkey,
+    event_authority: Pubkey,
+    global_volume_accumulator: Pubkey,
+    user_volume_accumulator: Pubkey,
+    fee_config: Pubkey,
+    fee_program: Pubkey,
+}
+struct PumpSwapAccounts {
+    pool: Pubkey,
+    global_config: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    pool_base_token_account: Pubkey,
+    pool_quote_token_account: Pubkey,
+    protocol_fee_recipient: Pubkey,
+    protocol_fee_recipient_token_account: Pubkey,
+    base_token_program: Pubkey,
+    quote_token_program: Pubkey,
+    event_authority: Pubkey,
+    coin_creator_vault_ata: Pubkey,
+    coin_creator_vault_authority: Pubkey,
+    global_volume_accumulator: Pubkey,
+    user_volume_accumulator: Pubkey,
+    fee_config: Pubkey,
+}
+struct PhoenixAccounts {
+    log_authority: Pubkey,
+    market: Pubkey,
+    base_vault: Pubkey,
+    quote_vault: Pubkey,
+    token_program: Pubkey,
 }
@@ -26,14 +26,43 @@ impl Processor {
         const COMMAND_INITIALIZE: &str = "initialize";
         const COMMAND_DEPOSIT_FUNDS: &str = "deposit_funds";
         const COMMAND_WITHDRAW_FUNDS: &str = "withdraw_funds";
+        const COMMAND_REGISTER_QUOTE_MINT: &str = "register_quote_mint";
+        const COMMAND_DEPOSIT_QUOTE_FUNDS: &str = "deposit_quote_funds";
+        const COMMAND_WITHDRAW_QUOTE_FUNDS: &str = "withdraw_quote_funds";
+        const COMMAND_WITHDRAW_TOKEN: &str = "withdraw_token";
+        const COMMAND_CLOSE_TOKEN_ACCOUNTS: &str = "close_token_accounts";
         const COMMAND_SWAP: &str = "swap";
+        const COMMAND_QUOTE: &str = "quote";
         const ARGUMENT_INTERMEDIARY_MANAGER: &str = "intermediary_manager";
         const ARGUMENT_INTERMEDIARY_TRADER: &str = "intermediary_trader";
         const ARGUMENT_LAMPORTS_TO_TREASURY: &str = "lamports_to_treasury";
         const ARGUMENT_LAMPORTS_FROM_TREASURY: &str = "lamports_from_treasury";
+        const ARGUMENT_QUOTE_MINT: &str = "quote_mint";
+        const ARGUMENT_AMOUNT_TO_TREASURY: &str = "amount_to_treasury";
+        const ARGUMENT_AMOUNT_FROM_TREASURY: &str = "amount_from_treasury";
+        const ARGUMENT_MINT: &str = "mint";
+        const ARGUMENT_AMOUNT: &str = "amount";
+        const ARGUMENT_DESTINATION_TOKEN_ACCOUNT: &str = "destination_token_account";
+        const ARGUMENT_CLOSE_TOKEN_ACCOUNT: &str = "close_token_account";
+        const ARGUMENT_RECEIVER: &str = "receiver";
+        const ARGUMENT_MINTS: &str = "mints";
         const ARGUMENT_INTERMEDIARY: &str = "intermediary";
         const ARGUMENT_AMOUNT_IN: &str = "amount_in";
         const ARGUMENT_MIN_AMOUNT_OUT: &str = "min_amount_out";
+        const ARGUMENT_IS_FROM_QUOTE_TO_TOKEN: &str = "is_from_quote_to_token";
+        const ARGUMENT_ORCA_WHIRLPOOL: &str = "orca_whirlpool";
+        const ARGUMENT_METEORA_DLMM: &str = "meteora_dlmm";
+        const ARGUMENT_METEORA_DAMM_V2: &str = "meteora_damm_v2";
+        const ARGUMENT_PUMP_FUN: &str = "pump_fun";
+        const ARGUMENT_PUMP_SWAP: &str = "pump_swap";
+        const ARGUMENT_PHOENIX: &str = "phoenix";
+        const ARGUMENT_SWAP_MODE: &str = "swap_mode";
+        const ARGUMENT_AMOUNTS_IN: &str = "amounts_in";
+        const ARGUMENT_PARTS: &str = "parts";
+        const ARGUMENT_VALID_UNTIL_SLOT: &str = "valid_until_slot";
+        const ARGUMENT_VALID_UNTIL_UNIX_TIMESTAMP: &str = "valid_until_unix_timestamp";
+        const ARGUMENT_MAX_PRICE_IMPACT_BPS: &str = "max_price_impact_bps";
+        const ARGUMENT_MAX_SLIPPAGE_BPS: &str = "max_slippage_bps";
         const ARGUMENT_SOLANA_RPC_URL: &str = "solana_rpc_url";
         let command = clap::command!()
             .arg(Arg::new(ARGUMENT_SOLANA_RPC_URL).required(true).long(ARGUMENT_SOLANA_RPC_URL))
@@ -57,12 +86,126 @@ impl Processor {
                     .arg(Arg::new(ARGUMENT_INTERMEDIARY_MANAGER).required(true).long(ARGUMENT_INTERMEDIARY_MANAGER).help("Fee payer keypair.json file path."))
                     .arg(Arg::new(ARGUMENT_LAMPORTS_FROM_TREASURY).required(true).long(ARGUMENT_LAMPORTS_FROM_TREASURY).help("Lamports from treasury.")),
             )
+            .subcommand(
+                Command::new(COMMAND_REGISTER_QUOTE_MINT)
+                    .arg(Arg::new(ARGUMENT_INTERMEDIARY).required(true).long(ARGUMENT_INTERMEDIARY).help("Intermediary pubkey."))
+                    .arg(Arg::new(ARGUMENT_INTERMEDIARY_MANAGER).required(true).long(ARGUMENT_INTERMEDIARY_MANAGER).help("Fee payer keypair.json file path."))
+                    .arg(Arg::new(ARGUMENT_QUOTE_MINT).required(true).long(ARGUMENT_QUOTE_MINT).help("Quote mint pubkey.")),
+            )
+            .subcommand(
+                Command::new(COMMAND_DEPOSIT_QUOTE_FUNDS)
+                    .arg(Arg::new(ARGUMENT_INTERMEDIARY).required(true).long(ARGUMENT_INTERMEDIARY).help("Intermediary pubkey."))
+                    .arg(Arg::new(ARGUMENT_INTERMEDIARY_MANAGER).required(true).long(ARGUMENT_INTERMEDIARY_MANAGER).help("Fee payer keypair.json file path."))
+                    .arg(Arg::new(ARGUMENT_QUOTE_MINT).required(true).long(ARGUMENT_QUOTE_MINT).help("Quote mint pubkey."))
+                    .arg(Arg::new(ARGUMENT_AMOUNT_TO_TREASURY).required(true).long(ARGUMENT_AMOUNT_TO_TREASURY).help("Amount to treasury.")),
+            )
+            .subcommand(
+                Command::new(COMMAND_WITHDRAW_QUOTE_FUNDS)
+                    .arg(Arg::new(ARGUMENT_INTERMEDIARY).required(true).long(ARGUMENT_INTERMEDIARY).help("Intermediary pubkey."))
+                    .arg(Arg::new(ARGUMENT_INTERMEDIARY_MANAGER).required(true).long(ARGUMENT_INTERMEDIARY_MANAGER).help("Fee payer keypair.json file path."))
+                    .arg(Arg::new(ARGUMENT_QUOTE_MINT).required(true).long(ARGUMENT_QUOTE_MINT).help("Quote mint pubkey."))
+                    .arg(Arg::new(ARGUMENT_AMOUNT_FROM_TREASURY).required(true).long(ARGUMENT_AMOUNT_FROM_TREASURY).help("Amount from treasury.")),
+            )
+            .subcommand(
+                Command::new(COMMAND_WITHDRAW_TOKEN)
+                    .arg(Arg::new(ARGUMENT_INTERMEDIARY).required(true).long(ARGUMENT_INTERMEDIARY).help("Intermediary pubkey."))
+                    .arg(Arg::new(ARGUMENT_INTERMEDIARY_MANAGER).required(true).long(ARGUMENT_INTERMEDIARY_MANAGER).help("Fee payer keypair.json file path."))
+                    .arg(Arg::new(ARGUMENT_MINT).required(true).long(ARGUMENT_MINT).help("Token mint pubkey."))
+                    .arg(Arg::new(ARGUMENT_AMOUNT).required(false).long(ARGUMENT_AMOUNT).help("Amount. The whole amount if not presented."))
+                    .arg(
+                        Arg::new(ARGUMENT_DESTINATION_TOKEN_ACCOUNT)
+                            .required(false)
+                            .long(ARGUMENT_DESTINATION_TOKEN_ACCOUNT)
+                            .help("Destination token account pubkey. The associated token account of the manager if not presented."),
+                    )
+                    .arg(
+                        Arg::new(ARGUMENT_CLOSE_TOKEN_ACCOUNT)
+                            .required(false)
+                            .long(ARGUMENT_CLOSE_TOKEN_ACCOUNT)
+                            .default_value("false")
+                            .help("Close the emptied token account with the rent to the manager."),
+                    ),
+            )
+            .subcommand(
+                Command::new(COMMAND_CLOSE_TOKEN_ACCOUNTS)
+                    .arg(Arg::new(ARGUMENT_INTERMEDIARY).required(true).long(ARGUMENT_INTERMEDIARY).help("Intermediary pubkey."))
+                    .arg(Arg::new(ARGUMENT_RECEIVER).required(true).long(ARGUMENT_RECEIVER).help("Fee payer keypair.json file path. The trader or the manager."))
+                    .arg(Arg::new(ARGUMENT_MINTS).required(true).long(ARGUMENT_MINTS).help("Comma-separated token mint pubkeys.")),
+            )
             .subcommand(
                 Command::new(COMMAND_SWAP)
                     .arg(Arg::new(ARGUMENT_INTERMEDIARY).required(true).long(ARGUMENT_INTERMEDIARY).help("Intermediary pubkey."))
                     .arg(Arg::new(ARGUMENT_INTERMEDIARY_TRADER).required(true).long(ARGUMENT_INTERMEDIARY_TRADER).help("Fee payer keypair.json file path."))
+                    .arg(
+                        Arg::new(ARGUMENT_QUOTE_MINT)
+                            .required(false)
+                            .long(ARGUMENT_QUOTE_MINT)
+                            .default_value("So11111111111111111111111111111111111111112")
+                            .help("Registered quote mint pubkey. WSol by default."),
+                    )
                     .arg(Arg::new(ARGUMENT_AMOUNT_IN).required(true).long(ARGUMENT_AMOUNT_IN).help("Amount in."))
-                    .arg(Arg::new(ARGUMENT_MIN_AMOUNT_OUT).required(true).long(ARGUMENT_MIN_AMOUNT_OUT).help("Min amount out.")),
+                    .arg(Arg::new(ARGUMENT_MIN_AMOUNT_OUT).required(true).long(ARGUMENT_MIN_AMOUNT_OUT).help("Min amount out."))
+                    .arg(
+                        Arg::new(ARGUMENT_IS_FROM_QUOTE_TO_TOKEN)
+                            .required(false)
+                            .long(ARGUMENT_IS_FROM_QUOTE_TO_TOKEN)
+                            .default_value("true")
+                            .help("Swap direction. 'true' - from quote mint to token, 'false' - from token to quote mint."),
+                    )
+                    .arg(Arg::new(ARGUMENT_ORCA_WHIRLPOOL).required(false).long(ARGUMENT_ORCA_WHIRLPOOL).help("Orca Whirlpool pubkey for the same token pair."))
+                    .arg(Arg::new(ARGUMENT_METEORA_DLMM).required(false).long(ARGUMENT_METEORA_DLMM).help("Meteora DLMM pair pubkey for the same token pair."))
+                    .arg(Arg::new(ARGUMENT_METEORA_DAMM_V2).required(false).long(ARGUMENT_METEORA_DAMM_V2).help("Meteora DAMM v2 pool pubkey for the same token pair."))
+                    .arg(Arg::new(ARGUMENT_PUMP_FUN).required(false).long(ARGUMENT_PUMP_FUN).help("Pump.fun bonding curve pubkey for the token."))
+                    .arg(Arg::new(ARGUMENT_PUMP_SWAP).required(false).long(ARGUMENT_PUMP_SWAP).help("PumpSwap pool pubkey for the same token pair."))
+                    .arg(Arg::new(ARGUMENT_PHOENIX).required(false).long(ARGUMENT_PHOENIX).help("Phoenix market pubkey for the same token pair."))
+                    .arg(
+                        Arg::new(ARGUMENT_SWAP_MODE)
+                            .required(false)
+                            .long(ARGUMENT_SWAP_MODE)
+                            .default_value("first_match")
+                            .help("Swap mode. 'first_match', 'best_execution', 'split', 'split_by_marginal_price' or 'exact_out'."),
+                    )
+                    .arg(
+                        Arg::new(ARGUMENT_AMOUNTS_IN)
+                            .required(false)
+                            .long(ARGUMENT_AMOUNTS_IN)
+                            .help("Comma-separated amounts in for the 'split' swap mode. One value for each Dex in the order of the Dexes."),
+                    )
+                    .arg(Arg::new(ARGUMENT_PARTS).required(false).long(ARGUMENT_PARTS).help("Quantity of parts for the 'split_by_marginal_price' swap mode."))
+                    .arg(Arg::new(ARGUMENT_VALID_UNTIL_SLOT).required(false).long(ARGUMENT_VALID_UNTIL_SLOT).help("The last slot in which the swap is valid."))
+                    .arg(
+                        Arg::new(ARGUMENT_VALID_UNTIL_UNIX_TIMESTAMP)
+                            .required(false)
+                            .long(ARGUMENT_VALID_UNTIL_UNIX_TIMESTAMP)
+                            .help("The last unix timestamp at which the swap is valid."),
+                    )
+                    .arg(Arg::new(ARGUMENT_MAX_PRICE_IMPACT_BPS).required(false).long(ARGUMENT_MAX_PRICE_IMPACT_BPS).help("Max price impact in basis points."))
+                    .arg(Arg::new(ARGUMENT_MAX_SLIPPAGE_BPS).required(false).long(ARGUMENT_MAX_SLIPPAGE_BPS).help("Max slippage in basis points.")),
+            )
+            .subcommand(
+                Command::new(COMMAND_QUOTE)
+                    .arg(Arg::new(ARGUMENT_INTERMEDIARY).required(true).long(ARGUMENT_INTERMEDIARY).help("Intermediary pubkey."))
+                    .arg(
+                        Arg::new(ARGUMENT_QUOTE_MINT)
+                            .required(false)
+                            .long(ARGUMENT_QUOTE_MINT)
+                            .default_value("So11111111111111111111111111111111111111112")
+                            .help("Registered quote mint pubkey. WSol by default."),
+                    )
+                    .arg(Arg::new(ARGUMENT_AMOUNT_IN).required(true).long(ARGUMENT_AMOUNT_IN).help("Amount in."))
+                    .arg(
+                        Arg::new(ARGUMENT_IS_FROM_QUOTE_TO_TOKEN)
+                            .required(false)
+                            .long(ARGUMENT_IS_FROM_QUOTE_TO_TOKEN)
+                            .default_value("true")
+                            .help("Swap direction. 'true' - from quote mint to token, 'false' - from token to quote mint."),
+                    )
+                    .arg(Arg::new(ARGUMENT_ORCA_WHIRLPOOL).required(false).long(ARGUMENT_ORCA_WHIRLPOOL).help("Orca Whirlpool pubkey for the same token pair."))
+                    .arg(Arg::new(ARGUMENT_METEORA_DLMM).required(false).long(ARGUMENT_METEORA_DLMM).help("Meteora DLMM pair pubkey for the same token pair."))
+                    .arg(Arg::new(ARGUMENT_METEORA_DAMM_V2).required(false).long(ARGUMENT_METEORA_DAMM_V2).help("Meteora DAMM v2 pool pubkey for the same token pair."))
+                    .arg(Arg::new(ARGUMENT_PUMP_FUN).required(false).long(ARGUMENT_PUMP_FUN).help("Pump.fun bonding curve pubkey for the token."))
+                    .arg(Arg::new(ARGUMENT_PUMP_SWAP).required(false).long(ARGUMENT_PUMP_SWAP).help("PumpSwap pool pubkey for the same token pair."))
+                    .arg(Arg::new(ARGUMENT_PHOENIX).required(false).long(ARGUMENT_PHOENIX).help("Phoenix market pubkey for the same token pair.")),
             );
         let arg_matches = command.get_matches();
         let solana_rpc_url = arg_matches.get_one::<String>(ARGUMENT_SOLANA_RPC_URL).unwrap();
@@ -98,13 +241,91 @@ impl Processor {
                     arg_matches_.get_one::<String>(ARGUMENT_LAMPORTS_FROM_TREASURY).unwrap().parse::<u64>()?,
                 )
             }
+            (COMMAND_REGISTER_QUOTE_MINT, arg_matches_) => {
+                CommandProcessor::register_quote_mint(
+                    &rpc_client,
+                    arg_matches_.get_one::<String>(ARGUMENT_INTERMEDIARY).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_INTERMEDIARY_MANAGER).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_QUOTE_MINT).unwrap().as_str(),
+                )
+            }
+            (COMMAND_DEPOSIT_QUOTE_FUNDS, arg_matches_) => {
+                CommandProcessor::deposit_quote_funds(
+                    &rpc_client,
+                    arg_matches_.get_one::<String>(ARGUMENT_INTERMEDIARY).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_INTERMEDIARY_MANAGER).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_QUOTE_MINT).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_AMOUNT_TO_TREASURY).unwrap().parse::<u64>()?,
+                )
+            }
+            (COMMAND_WITHDRAW_QUOTE_FUNDS, arg_matches_) => {
+                CommandProcessor::withdraw_quote_funds(
+                    &rpc_client,
+                    arg_matches_.get_one::<String>(ARGUMENT_INTERMEDIARY).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_INTERMEDIARY_MANAGER).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_QUOTE_MINT).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_AMOUNT_FROM_TREASURY).unwrap().parse::<u64>()?,
+                )
+            }
+            (COMMAND_WITHDRAW_TOKEN, arg_matches_) => {
+                CommandProcessor::withdraw_token(
+                    &rpc_client,
+                    arg_matches_.get_one::<String>(ARGUMENT_INTERMEDIARY).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_INTERMEDIARY_MANAGER).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_MINT).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_AMOUNT).map(|amount| amount.parse::<u64>()).transpose()?,
+                    arg_matches_.get_one::<String>(ARGUMENT_DESTINATION_TOKEN_ACCOUNT).map(|destination_token_account| destination_token_account.as_str()),
+                    arg_matches_.get_one::<String>(ARGUMENT_CLOSE_TOKEN_ACCOUNT).unwrap().parse::<bool>()?,
+                )
+            }
+            (COMMAND_CLOSE_TOKEN_ACCOUNTS, arg_matches_) => {
+                CommandProcessor::close_token_accounts(
+                    &rpc_client,
+                    arg_matches_.get_one::<String>(ARGUMENT_INTERMEDIARY).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_RECEIVER).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_MINTS).unwrap().as_str(),
+                )
+            }
             (COMMAND_SWAP, arg_matches_) => {
                 CommandProcessor::swap(
                     &rpc_client,
                     arg_matches_.get_one::<String>(ARGUMENT_INTERMEDIARY).unwrap().as_str(),
                     arg_matches_.get_one::<String>(ARGUMENT_INTERMEDIARY_TRADER).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_QUOTE_MINT).unwrap().as_str(),
                     arg_matches_.get_one::<String>(ARGUMENT_AMOUNT_IN).unwrap().parse::<u64>()?,
                     arg_matches_.get_one::<String>(ARGUMENT_MIN_AMOUNT_OUT).unwrap().parse::<u64>()?,
+                    arg_matches_.get_one::<String>(ARGUMENT_IS_FROM_QUOTE_TO_TOKEN).unwrap().parse::<bool>()?,
+                    arg_matches_.get_one::<String>(ARGUMENT_ORCA_WHIRLPOOL).map(|orca_whirlpool| orca_whirlpool.as_str()),
+                    arg_matches_.get_one::<String>(ARGUMENT_METEORA_DLMM).map(|meteora_dlmm| meteora_dlmm.as_str()),
+                    arg_matches_.get_one::<String>(ARGUMENT_METEORA_DAMM_V2).map(|meteora_damm_v2| meteora_damm_v2.as_str()),
+                    arg_matches_.get_one::<String>(ARGUMENT_PUMP_FUN).map(|pump_fun| pump_fun.as_str()),
+                    arg_matches_.get_one::<String>(ARGUMENT_PUMP_SWAP).map(|pump_swap| pump_swap.as_str()),
+                    arg_matches_.get_one::<String>(ARGUMENT_PHOENIX).map(|phoenix| phoenix.as_str()),
+                    arg_matches_.get_one::<String>(ARGUMENT_SWAP_MODE).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_AMOUNTS_IN).map(|amounts_in| amounts_in.as_str()),
+                    arg_matches_.get_one::<String>(ARGUMENT_PARTS).map(|parts| parts.parse::<u8>()).transpose()?,
+                    arg_matches_.get_one::<String>(ARGUMENT_VALID_UNTIL_SLOT).map(|valid_until_slot| valid_until_slot.parse::<u64>()).transpose()?,
+                    arg_matches_
+                        .get_one::<String>(ARGUMENT_VALID_UNTIL_UNIX_TIMESTAMP)
+                        .map(|valid_until_unix_timestamp| valid_until_unix_timestamp.parse::<i64>())
+                        .transpose()?,
+                    arg_matches_.get_one::<String>(ARGUMENT_MAX_PRICE_IMPACT_BPS).map(|max_price_impact_bps| max_price_impact_bps.parse::<u16>()).transpose()?,
+                    arg_matches_.get_one::<String>(ARGUMENT_MAX_SLIPPAGE_BPS).map(|max_slippage_bps| max_slippage_bps.parse::<u16>()).transpose()?,
+                )
+            }
+            (COMMAND_QUOTE, arg_matches_) => {
+                CommandProcessor::quote(
+                    &rpc_client,
+                    arg_matches_.get_one::<String>(ARGUMENT_INTERMEDIARY).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_QUOTE_MINT).unwrap().as_str(),
+                    arg_matches_.get_one::<String>(ARGUMENT_AMOUNT_IN).unwrap().parse::<u64>()?,
+                    arg_matches_.get_one::<String>(ARGUMENT_IS_FROM_QUOTE_TO_TOKEN).unwrap().parse::<bool>()?,
+                    arg_matches_.get_one::<String>(ARGUMENT_ORCA_WHIRLPOOL).map(|orca_whirlpool| orca_whirlpool.as_str()),
+                    arg_matches_.get_one::<String>(ARGUMENT_METEORA_DLMM).map(|meteora_dlmm| meteora_dlmm.as_str()),
+                    arg_matches_.get_one::<String>(ARGUMENT_METEORA_DAMM_V2).map(|meteora_damm_v2| meteora_damm_v2.as_str()),
+                    arg_matches_.get_one::<String>(ARGUMENT_PUMP_FUN).map(|pump_fun| pump_fun.as_str()),
+                    arg_matches_.get_one::<String>(ARGUMENT_PUMP_SWAP).map(|pump_swap| pump_swap.as_str()),
+                    arg_matches_.get_one::<String>(ARGUMENT_PHOENIX).map(|phoenix| phoenix.as_str()),
                 )
             }
             _ => Err(LOGIC_ERROR.into()),
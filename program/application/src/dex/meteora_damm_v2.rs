@@ -0,0 +1,465 @@
+use {
+    super::{
+        BaseData,
+        Dex,
+        SwapCalculationResult,
+        get_transfer_fee_config,
+        get_virtual_reserves,
+    },
+    crate::{
+        METEORA_DAMM_V2_PROGRAM_ID,
+        error::Error,
+        extern_source::{
+            meteora_damm_v2::{
+                ActivationType,
+                CollectFeeMode,
+                FEE_DENOMINATOR,
+                FeeSchedulerMode,
+                Pool,
+                PoolStatus,
+                Rounding,
+            },
+            spl_token_2022::TransferFeeConfig,
+        },
+        state::PdaResolver,
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        instruction::{
+            AccountMeta,
+            Instruction,
+        },
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
+pub struct MeteoraDammV2;
+struct PoolState {
+    pool: Pubkey,
+    pool_: Box<Pool>,
+    a_to_b: bool,
+    is_fees_on_input: bool,
+    current_point: u64,
+    // Token-2022 transfer fee configs of the mints.
+    in_transfer_fee_config: Option<TransferFeeConfig>,
+    out_transfer_fee_config: Option<TransferFeeConfig>,
+    epoch: u64,
+}
+impl PoolState {
+    // Returns the amount out before the trading fee on output. Returns None if the swap goes out of the pool price range.
+    fn calculate_curve_amount_out(&self, amount_in: u64) -> Option<u64> {
+        let (amount_out, _) = if self.a_to_b {
+            self.pool_.get_swap_result_from_a_to_b(amount_in)?
+        } else {
+            self.pool_.get_swap_result_from_b_to_a(amount_in)?
+        };
+        Some(amount_out)
+    }
+    // Returns the amount in before the trading fee on input. Returns None if the swap goes out of the pool price range.
+    fn calculate_curve_amount_in(&self, amount_out: u64) -> Option<u64> {
+        let next_sqrt_price = crate::extern_source::meteora_damm_v2::get_next_sqrt_price_from_output(self.pool_.sqrt_price, self.pool_.liquidity, amount_out, self.a_to_b)?;
+        if self.a_to_b {
+            if next_sqrt_price < self.pool_.sqrt_min_price {
+                return None;
+            }
+            crate::extern_source::meteora_damm_v2::get_delta_amount_a_unsigned(next_sqrt_price, self.pool_.sqrt_price, self.pool_.liquidity, Rounding::Up)
+        } else {
+            if next_sqrt_price > self.pool_.sqrt_max_price {
+                return None;
+            }
+            crate::extern_source::meteora_damm_v2::get_delta_amount_b_unsigned(self.pool_.sqrt_price, next_sqrt_price, self.pool_.liquidity, Rounding::Up)
+        }
+    }
+    // Returns the trade fee in the input token with the input transfer fee and the amount out.
+    //
+    // This is a slightly modified selective code from https://github.com/MeteoraAg/damm-v2/blob/main/programs/cp-amm/src/instructions/ix_swap.rs
+    fn calculate_amount_out(&self, amount_in: u64) -> Result<Option<(u64, u64)>, ProgramError> {
+        let in_transfer_fee = match self.in_transfer_fee_config {
+            Some(ref transfer_fee_config) => transfer_fee_config.calculate_epoch_fee(self.epoch, amount_in).ok_or(ProgramError::ArithmeticOverflow)?,
+            None => 0,
+        };
+        let actual_amount_in = amount_in.checked_sub(in_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        if actual_amount_in == 0 {
+            return Ok(None);
+        }
+        let fees = &self.pool_.pool_fees;
+        let (amount_out, trade_fee) = if self.is_fees_on_input {
+            let (amount_in_after_fee, trade_fee) =
+                fees.get_fee_on_amount(actual_amount_in, self.current_point, self.pool_.activation_point).ok_or(ProgramError::ArithmeticOverflow)?;
+            let amount_out = match self.calculate_curve_amount_out(amount_in_after_fee) {
+                Some(amount_out_) => amount_out_,
+                None => return Ok(None),
+            };
+            (amount_out, trade_fee)
+        } else {
+            let amount_out_before_fee = match self.calculate_curve_amount_out(actual_amount_in) {
+                Some(amount_out_) => amount_out_,
+                None => return Ok(None),
+            };
+            if amount_out_before_fee == 0 {
+                return Ok(None);
+            }
+            let (amount_out, trade_fee) = fees.get_fee_on_amount(amount_out_before_fee, self.current_point, self.pool_.activation_point).ok_or(ProgramError::ArithmeticOverflow)?;
+            // The fee is charged in the output token, so its input token equivalent is taken.
+            let trade_fee_ = (trade_fee as u128)
+                .checked_mul(actual_amount_in as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?
+                .checked_div(amount_out_before_fee as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            (amount_out, u64::try_from(trade_fee_).map_err(|_| ProgramError::ArithmeticOverflow)?)
+        };
+        if amount_out == 0 {
+            return Ok(None);
+        }
+        let out_transfer_fee = match self.out_transfer_fee_config {
+            Some(ref transfer_fee_config) => transfer_fee_config.calculate_epoch_fee(self.epoch, amount_out).ok_or(ProgramError::ArithmeticOverflow)?,
+            None => 0,
+        };
+        let amount_out_ = amount_out.checked_sub(out_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        if amount_out_ == 0 {
+            return Ok(None);
+        }
+        Ok(Some((trade_fee.checked_add(in_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?, amount_out_)))
+    }
+    // Returns the estimated amount in for the amount out, which is corrected with the forward calculation.
+    fn estimate_amount_in(&self, amount_out: u64) -> Result<Option<u64>, ProgramError> {
+        let out_transfer_fee = match self.out_transfer_fee_config {
+            Some(ref transfer_fee_config) => transfer_fee_config.calculate_inverse_epoch_fee(self.epoch, amount_out).ok_or(ProgramError::ArithmeticOverflow)?,
+            None => 0,
+        };
+        let actual_amount_out = amount_out.checked_add(out_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        let trade_fee_numerator = self.pool_.pool_fees.get_trade_fee_numerator(self.current_point, self.pool_.activation_point).ok_or(ProgramError::ArithmeticOverflow)?;
+        // amount * (1 - trade_fee_numerator / FEE_DENOMINATOR) = amount_after_fee
+        let get_amount_before_fee = |amount_after_fee: u64| -> Option<u64> {
+            crate::extern_source::meteora_damm_v2::mul_div_u64(amount_after_fee, FEE_DENOMINATOR, FEE_DENOMINATOR.checked_sub(trade_fee_numerator)?, Rounding::Up)
+        };
+        let amount_in = if self.is_fees_on_input {
+            let amount_in_after_fee = match self.calculate_curve_amount_in(actual_amount_out) {
+                Some(amount_in_) => amount_in_,
+                None => return Ok(None),
+            };
+            get_amount_before_fee(amount_in_after_fee).ok_or(ProgramError::ArithmeticOverflow)?
+        } else {
+            let amount_out_before_fee = get_amount_before_fee(actual_amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+            match self.calculate_curve_amount_in(amount_out_before_fee) {
+                Some(amount_in_) => amount_in_,
+                None => return Ok(None),
+            }
+        };
+        let in_transfer_fee = match self.in_transfer_fee_config {
+            Some(ref transfer_fee_config) => transfer_fee_config.calculate_inverse_epoch_fee(self.epoch, amount_in).ok_or(ProgramError::ArithmeticOverflow)?,
+            None => 0,
+        };
+        Ok(Some(amount_in.checked_add(in_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?))
+    }
+}
+impl MeteoraDammV2 {
+    const SWAP_ACCOUNTS_QUANTITY: usize = 10;
+    const BASE_OUT_CALCULATION_ITERATIONS_QUANTITY: usize = 8;
+    const POOL_AUTHORITY: Pubkey = Pubkey::from_str_const("HLnpSz9h2S4hiLQ43rnSD9XkcUThA7B8hQMKmDaiTLcC");
+    // https://github.com/MeteoraAg/damm-v2/blob/main/programs/cp-amm/src/instructions/ix_swap.rs
+    pub fn create_swap_instruction(
+        program_id: &Pubkey,
+        pool_authority: &Pubkey,
+        pool: &Pubkey,
+        input_token_account: &Pubkey,
+        output_token_account: &Pubkey,
+        token_a_vault: &Pubkey,
+        token_b_vault: &Pubkey,
+        token_a_mint: &Pubkey,
+        token_b_mint: &Pubkey,
+        payer: &Pubkey,
+        token_a_program: &Pubkey,
+        token_b_program: &Pubkey,
+        referral_token_account: Option<&Pubkey>,
+        event_authority: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+    ) -> Instruction {
+        const DISCRIMINATOR: [u8; 8] = [
+            248,
+            198,
+            158,
+            145,
+            225,
+            117,
+            135,
+            200,
+        ];
+        let mut data = Vec::<u8>::with_capacity(24);
+        data.extend(DISCRIMINATOR);
+        data.extend(amount_in.to_le_bytes());
+        data.extend(minimum_amount_out.to_le_bytes());
+        // The optional account is replaced by the program id.
+        let referral_token_account_ = match referral_token_account {
+            Some(referral_token_account__) => AccountMeta::new(*referral_token_account__, false),
+            None => AccountMeta::new_readonly(*program_id, false),
+        };
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*pool_authority, false),
+                AccountMeta::new(*pool, false),
+                AccountMeta::new(*input_token_account, false),
+                AccountMeta::new(*output_token_account, false),
+                AccountMeta::new(*token_a_vault, false),
+                AccountMeta::new(*token_b_vault, false),
+                AccountMeta::new_readonly(*token_a_mint, false),
+                AccountMeta::new_readonly(*token_b_mint, false),
+                AccountMeta::new_readonly(*payer, true),
+                AccountMeta::new_readonly(*token_a_program, false),
+                AccountMeta::new_readonly(*token_b_program, false),
+                referral_token_account_,
+                AccountMeta::new_readonly(*event_authority, false),
+                AccountMeta::new_readonly(*program_id, false),
+            ],
+            data,
+        }
+    }
+    // Returns None if the pool is not available for swap.
+    fn load_pool_state<'a, 'b, 'c>(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<PoolState>, ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let meteora_damm_v2_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_a_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_b_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_a_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_b_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_a_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_b_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _event_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        if *meteora_damm_v2_program.key != METEORA_DAMM_V2_PROGRAM_ID || *pool_authority.key != Self::POOL_AUTHORITY {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if base_data.with_checks && (!pool.is_writable || !token_a_vault.is_writable || !token_b_vault.is_writable) {
+            return Err(Error::InvalidAccountConfigurationFlags.into());
+        }
+        // This is a slightly modified selective code from https://github.com/MeteoraAg/damm-v2/blob/main/programs/cp-amm/src/instructions/ix_swap.rs
+        // that loads the pool state for swap calculation.
+        let pool_state = {
+            if pool.owner != meteora_damm_v2_program.key {
+                return Err(Error::InvalidAmmAccountOwner.into());
+            }
+            let pool_data = pool.data.borrow();
+            if pool_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR
+                || pool_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] != Pool::DISCRIMINATOR
+            {
+                return Err(Error::InvalidAccountData.into());
+            }
+            let pool_ = Box::new(<Pool as borsh::de::BorshDeserialize>::deserialize(
+                &mut &pool_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..],
+            )?);
+            if *token_a_vault.key != pool_.token_a_vault
+                || *token_b_vault.key != pool_.token_b_vault
+                || *token_a_mint.key != pool_.token_a_mint
+                || *token_b_mint.key != pool_.token_b_mint
+                || *token_a_program.key != *token_a_mint.owner
+                || *token_b_program.key != *token_b_mint.owner
+            {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            match PoolStatus::try_from(pool_.pool_status).map_err(|_| ProgramError::InvalidArgument)? {
+                PoolStatus::Enable => {}
+                PoolStatus::Disable => return Ok(None),
+            }
+            // The unknown fee modes are not supported.
+            let collect_fee_mode = match CollectFeeMode::try_from(pool_.collect_fee_mode) {
+                Ok(collect_fee_mode_) => collect_fee_mode_,
+                Err(_) => return Ok(None),
+            };
+            if pool_.pool_fees.base_fee.period_frequency != 0 && FeeSchedulerMode::try_from(pool_.pool_fees.base_fee.fee_scheduler_mode).is_err() {
+                return Ok(None);
+            }
+            let clock_ = Clock::get()?;
+            let current_point = match ActivationType::try_from(pool_.activation_type).map_err(|_| ProgramError::InvalidArgument)? {
+                ActivationType::Slot => clock_.slot,
+                ActivationType::Timestamp => clock_.unix_timestamp as u64,
+            };
+            // Only the whitelisted vault can swap before the activation.
+            if current_point < pool_.activation_point {
+                return Ok(None);
+            }
+            let (in_mint, out_mint) = if base_data.is_from_quote_to_token {
+                (base_data.quote_mint, base_data.token_mint)
+            } else {
+                (base_data.token_mint, base_data.quote_mint)
+            };
+            let a_to_b = if *in_mint == pool_.token_a_mint && *out_mint == pool_.token_b_mint {
+                true
+            } else if *in_mint == pool_.token_b_mint && *out_mint == pool_.token_a_mint {
+                false
+            } else {
+                return Err(Error::InvalidTokenMint.into());
+            };
+            let token_a_transfer_fee_config = get_transfer_fee_config(token_a_mint);
+            let token_b_transfer_fee_config = get_transfer_fee_config(token_b_mint);
+            let (in_transfer_fee_config, out_transfer_fee_config) = if a_to_b {
+                (token_a_transfer_fee_config, token_b_transfer_fee_config)
+            } else {
+                (token_b_transfer_fee_config, token_a_transfer_fee_config)
+            };
+            PoolState {
+                pool: *pool.key,
+                pool_,
+                a_to_b,
+                is_fees_on_input: crate::extern_source::meteora_damm_v2::is_fees_on_input(&collect_fee_mode, a_to_b),
+                current_point,
+                in_transfer_fee_config,
+                out_transfer_fee_config,
+                epoch: clock_.epoch,
+            }
+        };
+        Ok(Some(pool_state))
+    }
+    fn invoke_swap<'a, 'b, 'c>(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        amount_in: u64,
+        minimum_amount_out: u64,
+    ) -> Result<(), ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let meteora_damm_v2_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_a_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_b_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_a_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_b_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_a_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_b_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let event_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let (input_token_account, output_token_account) = if base_data.is_from_quote_to_token {
+            (base_data.quote_token_account, base_data.token_account)
+        } else {
+            (base_data.token_account, base_data.quote_token_account)
+        };
+        let instruction = Self::create_swap_instruction(
+            meteora_damm_v2_program.key,
+            pool_authority.key,
+            pool.key,
+            input_token_account.key,
+            output_token_account.key,
+            token_a_vault.key,
+            token_b_vault.key,
+            token_a_mint.key,
+            token_b_mint.key,
+            base_data.self_authority.key,
+            token_a_program.key,
+            token_b_program.key,
+            None,
+            event_authority.key,
+            amount_in,
+            minimum_amount_out,
+        );
+        solana_program::program::invoke_signed(
+            &instruction,
+            vec![
+                pool_authority.clone(),
+                pool.clone(),
+                input_token_account.clone(),
+                output_token_account.clone(),
+                token_a_vault.clone(),
+                token_b_vault.clone(),
+                token_a_mint.clone(),
+                token_b_mint.clone(),
+                base_data.self_authority.clone(),
+                token_a_program.clone(),
+                token_b_program.clone(),
+                event_authority.clone(),
+                meteora_damm_v2_program.clone(),
+            ]
+            .as_slice(),
+            [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
+        )
+    }
+}
+impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for MeteoraDammV2 {
+    fn get_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_max_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_pool_account_index(&'a self) -> usize {
+        2
+    }
+    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        let (trade_fee, amount_out_) = match pool_state.calculate_amount_out(base_data.amount_in)? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let (in_reserve, out_reserve) = get_virtual_reserves(pool_state.pool_.sqrt_price, pool_state.a_to_b);
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.pool,
+            amount_in: base_data.amount_in,
+            amount_in_fee: trade_fee,
+            amount_out: amount_out_,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, base_data.amount_in, base_data.min_amount_out)
+    }
+    // The exact input swap instruction is used, so the amount in is calculated here
+    // and the regular swap is done with the exact amount out as minimum.
+    fn do_swap_calculation_base_out(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        let amount_out = base_data.min_amount_out;
+        if amount_out == 0 {
+            return Ok(None);
+        }
+        let mut amount_in_ = match pool_state.estimate_amount_in(amount_out)? {
+            Some(amount_in__) => amount_in__,
+            None => return Ok(None),
+        };
+        // The fees and the curve round in favor of the pool, so the estimation is corrected with the forward calculation.
+        for _ in 0..Self::BASE_OUT_CALCULATION_ITERATIONS_QUANTITY {
+            let (trade_fee, amount_out_) = match pool_state.calculate_amount_out(amount_in_)? {
+                Some(data) => data,
+                None => return Ok(None),
+            };
+            if amount_out_ >= amount_out {
+                let (in_reserve, out_reserve) = get_virtual_reserves(pool_state.pool_.sqrt_price, pool_state.a_to_b);
+                return Ok(Some(SwapCalculationResult {
+                    pool: pool_state.pool,
+                    amount_in: amount_in_,
+                    amount_in_fee: trade_fee,
+                    amount_out: amount_out_,
+                    in_reserve,
+                    out_reserve,
+                }));
+            }
+            let amount_in_shortage = ((amount_out - amount_out_) as u128)
+                .checked_mul(amount_in_ as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?
+                .checked_div(amount_out as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            amount_in_ = amount_in_
+                .checked_add(u64::try_from(amount_in_shortage).map_err(|_| ProgramError::ArithmeticOverflow)?)
+                .and_then(|amount_in__| amount_in__.checked_add(1))
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+        }
+        Ok(None)
+    }
+    fn do_swap_base_out(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        swap_calculation_result: &SwapCalculationResult,
+    ) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, swap_calculation_result.amount_in, base_data.min_amount_out)
+    }
+}
@@ -0,0 +1,469 @@
+use {
+    super::{
+        BaseData,
+        Dex,
+        SwapCalculationResult,
+    },
+    crate::{
+        METEORA_DLMM_PROGRAM_ID,
+        error::Error,
+        extern_source::meteora_dlmm::{
+            ActivationType,
+            BinArray,
+            LbPair,
+            ONE,
+            PairStatus,
+        },
+        state::PdaResolver,
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        instruction::{
+            AccountMeta,
+            Instruction,
+        },
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
+pub struct MeteoraDlmm;
+struct PoolState {
+    pool: Pubkey,
+    // With the references updated for the current timestamp.
+    lb_pair: Box<LbPair>,
+    swap_for_y: bool,
+    // In the order of passing.
+    bin_arrays: Vec<BinArray>,
+}
+impl MeteoraDlmm {
+    // The accounts before the bin arrays.
+    const FIXED_SWAP_ACCOUNTS_QUANTITY: usize = 11;
+    const BIN_ARRAYS_MAX_QUANTITY: usize = 5;
+    // https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/instructions/swap.rs
+    pub fn create_swap_instruction(
+        program_id: &Pubkey,
+        lb_pair: &Pubkey,
+        bin_array_bitmap_extension: Option<&Pubkey>,
+        reserve_x: &Pubkey,
+        reserve_y: &Pubkey,
+        user_token_in: &Pubkey,
+        user_token_out: &Pubkey,
+        token_x_mint: &Pubkey,
+        token_y_mint: &Pubkey,
+        oracle: &Pubkey,
+        user: &Pubkey,
+        token_x_program: &Pubkey,
+        token_y_program: &Pubkey,
+        event_authority: &Pubkey,
+        bin_arrays: &[&Pubkey],
+        // The amount in and the minimum amount out for the exact input swap,
+        // the maximum amount in and the amount out for the exact output swap.
+        amount_0: u64,
+        amount_1: u64,
+        is_exact_out: bool,
+    ) -> Instruction {
+        const SWAP_DISCRIMINATOR: [u8; 8] = [
+            248,
+            198,
+            158,
+            145,
+            225,
+            117,
+            135,
+            200,
+        ];
+        const SWAP_EXACT_OUT_DISCRIMINATOR: [u8; 8] = [
+            250,
+            73,
+            101,
+            33,
+            38,
+            207,
+            75,
+            184,
+        ];
+        let mut data = Vec::<u8>::with_capacity(24);
+        if is_exact_out {
+            data.extend(SWAP_EXACT_OUT_DISCRIMINATOR);
+        } else {
+            data.extend(SWAP_DISCRIMINATOR);
+        }
+        data.extend(amount_0.to_le_bytes());
+        data.extend(amount_1.to_le_bytes());
+        // The optional accounts are replaced by the program id.
+        let mut accounts = vec![
+            AccountMeta::new(*lb_pair, false),
+            AccountMeta::new_readonly(*bin_array_bitmap_extension.unwrap_or(program_id), false),
+            AccountMeta::new(*reserve_x, false),
+            AccountMeta::new(*reserve_y, false),
+            AccountMeta::new(*user_token_in, false),
+            AccountMeta::new(*user_token_out, false),
+            AccountMeta::new_readonly(*token_x_mint, false),
+            AccountMeta::new_readonly(*token_y_mint, false),
+            AccountMeta::new(*oracle, false),
+            AccountMeta::new_readonly(*program_id, false),
+            AccountMeta::new_readonly(*user, true),
+            AccountMeta::new_readonly(*token_x_program, false),
+            AccountMeta::new_readonly(*token_y_program, false),
+            AccountMeta::new_readonly(*event_authority, false),
+            AccountMeta::new_readonly(*program_id, false),
+        ];
+        '_a: for bin_array in bin_arrays.iter() {
+            accounts.push(AccountMeta::new(**bin_array, false));
+        }
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    }
+    // Returns None if the pool is not available for swap.
+    fn load_pool_state<'a, 'b, 'c>(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<PoolState>, ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let meteora_dlmm_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let lb_pair = solana_program::account_info::next_account_info(account_info_iter)?;
+        let bin_array_bitmap_extension = solana_program::account_info::next_account_info(account_info_iter)?;
+        let reserve_x = solana_program::account_info::next_account_info(account_info_iter)?;
+        let reserve_y = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_x_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_y_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let oracle = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_x_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_y_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _event_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        if *meteora_dlmm_program.key != METEORA_DLMM_PROGRAM_ID {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if base_data.with_checks && (!lb_pair.is_writable || !reserve_x.is_writable || !reserve_y.is_writable || !oracle.is_writable) {
+            return Err(Error::InvalidAccountConfigurationFlags.into());
+        }
+        // This is a slightly modified selective code from https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/instructions/swap.rs
+        // that loads the pair state and the bin arrays for swap calculation.
+        let pool_state = {
+            if lb_pair.owner != meteora_dlmm_program.key
+                || (*bin_array_bitmap_extension.key != METEORA_DLMM_PROGRAM_ID && bin_array_bitmap_extension.owner != meteora_dlmm_program.key)
+            {
+                return Err(Error::InvalidAmmAccountOwner.into());
+            }
+            let lb_pair_data = lb_pair.data.borrow();
+            if lb_pair_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR
+                || lb_pair_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] != LbPair::DISCRIMINATOR
+            {
+                return Err(Error::InvalidAccountData.into());
+            }
+            let mut lb_pair_ = Box::new(<LbPair as borsh::de::BorshDeserialize>::deserialize(
+                &mut &lb_pair_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..],
+            )?);
+            if *reserve_x.key != lb_pair_.reserve_x
+                || *reserve_y.key != lb_pair_.reserve_y
+                || *token_x_mint.key != lb_pair_.token_x_mint
+                || *token_y_mint.key != lb_pair_.token_y_mint
+                || *oracle.key != lb_pair_.oracle
+            {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            // The Token-2022 mints require the swap2 instruction.
+            if *token_x_mint.owner != spl_token::ID || *token_y_mint.owner != spl_token::ID {
+                return Ok(None);
+            }
+            if *token_x_program.key != spl_token::ID || *token_y_program.key != spl_token::ID {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            let clock_ = Clock::get()?;
+            let activation_type = ActivationType::try_from(lb_pair_.activation_type).map_err(|_| ProgramError::InvalidArgument)?;
+            let current_point = match activation_type {
+                ActivationType::Slot => clock_.slot,
+                ActivationType::Timestamp => clock_.unix_timestamp as u64,
+            };
+            match PairStatus::try_from(lb_pair_.status).map_err(|_| ProgramError::InvalidArgument)? {
+                PairStatus::Enabled => {}
+                PairStatus::Disabled => return Ok(None),
+            }
+            if current_point < lb_pair_.activation_point {
+                return Ok(None);
+            }
+            let (in_mint, out_mint) = if base_data.is_from_quote_to_token {
+                (base_data.quote_mint, base_data.token_mint)
+            } else {
+                (base_data.token_mint, base_data.quote_mint)
+            };
+            let swap_for_y = if *in_mint == lb_pair_.token_x_mint && *out_mint == lb_pair_.token_y_mint {
+                true
+            } else if *in_mint == lb_pair_.token_y_mint && *out_mint == lb_pair_.token_x_mint {
+                false
+            } else {
+                return Err(Error::InvalidTokenMint.into());
+            };
+            lb_pair_.update_references(clock_.unix_timestamp).ok_or(ProgramError::ArithmeticOverflow)?;
+            let mut bin_arrays = Vec::<BinArray>::with_capacity(dex_accounts.len() - Self::FIXED_SWAP_ACCOUNTS_QUANTITY);
+            '_a: for bin_array in account_info_iter {
+                if bin_array.owner != meteora_dlmm_program.key {
+                    return Err(Error::InvalidAmmAccountOwner.into());
+                }
+                if base_data.with_checks && !bin_array.is_writable {
+                    return Err(Error::InvalidAccountConfigurationFlags.into());
+                }
+                let bin_array_data = bin_array.data.borrow();
+                if bin_array_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR
+                    || bin_array_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] != BinArray::DISCRIMINATOR
+                {
+                    return Err(Error::InvalidAccountData.into());
+                }
+                let bin_array_ = BinArray::unpack(&bin_array_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..]).ok_or(Error::InvalidAccountData)?;
+                if bin_array_.lb_pair != *lb_pair.key {
+                    return Err(Error::InvalidAccountData.into());
+                }
+                bin_arrays.push(bin_array_);
+            }
+            PoolState {
+                pool: *lb_pair.key,
+                lb_pair: lb_pair_,
+                swap_for_y,
+                bin_arrays,
+            }
+        };
+        Ok(Some(pool_state))
+    }
+    // Returns the amount in, the amount out and the trade fee. Returns None if the passed bin arrays
+    // do not cover the amount.
+    //
+    // This is a slightly modified selective code from https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/instructions/swap.rs
+    // where the passed bin arrays are traversed instead of the bin array bitmap. The bin arrays should be passed
+    // in the swap direction, as the CPI requires the same. The active bin jumps over the not passed bin arrays
+    // as over the not initialized ones.
+    fn calculate_swap(pool_state: &PoolState, amount_specified: u64, is_exact_out: bool) -> Result<Option<(u64, u64, u64)>, ProgramError> {
+        let swap_for_y = pool_state.swap_for_y;
+        let mut lb_pair = pool_state.lb_pair.clone();
+        let mut amount_specified_left = amount_specified;
+        let mut amount_calculated: u64 = 0;
+        let mut fee_amount: u64 = 0;
+        let mut is_bin_array_used = false;
+        '_a: for bin_array in pool_state.bin_arrays.iter() {
+            let (lower_bin_id, upper_bin_id) = BinArray::get_bin_array_lower_upper_bin_id(bin_array.index as i32);
+            let is_behind = if swap_for_y {
+                lower_bin_id > lb_pair.active_id
+            } else {
+                upper_bin_id < lb_pair.active_id
+            };
+            // The bin arrays behind the active bin in the swap direction are skipped before the first used one.
+            if is_behind {
+                if is_bin_array_used {
+                    return Err(Error::InvalidAccountData.into());
+                }
+                continue '_a;
+            }
+            if !bin_array.is_bin_id_within_range(lb_pair.active_id) {
+                lb_pair.active_id = if swap_for_y {
+                    upper_bin_id
+                } else {
+                    lower_bin_id
+                };
+            }
+            is_bin_array_used = true;
+            while bin_array.is_bin_id_within_range(lb_pair.active_id) {
+                lb_pair.update_volatility_accumulator().ok_or(ProgramError::ArithmeticOverflow)?;
+                let active_bin = bin_array.get_bin(lb_pair.active_id).ok_or(Error::InvalidAccountData)?;
+                let price = active_bin.get_or_compute_bin_price(lb_pair.active_id, lb_pair.bin_step).ok_or(ProgramError::ArithmeticOverflow)?;
+                if !active_bin.is_empty(!swap_for_y) {
+                    if is_exact_out {
+                        let bin_max_amount_out = active_bin.get_max_amount_out(swap_for_y);
+                        let (amount_in, amount_out) = if amount_specified_left >= bin_max_amount_out {
+                            (active_bin.get_max_amount_in(price, swap_for_y).ok_or(ProgramError::ArithmeticOverflow)?, bin_max_amount_out)
+                        } else {
+                            (
+                                crate::extern_source::meteora_dlmm::Bin::get_amount_in(amount_specified_left, price, swap_for_y).ok_or(ProgramError::ArithmeticOverflow)?,
+                                amount_specified_left,
+                            )
+                        };
+                        let fee = lb_pair.compute_fee(amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
+                        amount_calculated =
+                            amount_calculated.checked_add(amount_in.checked_add(fee).ok_or(ProgramError::ArithmeticOverflow)?).ok_or(ProgramError::ArithmeticOverflow)?;
+                        amount_specified_left = amount_specified_left.checked_sub(amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+                        fee_amount = fee_amount.checked_add(fee).ok_or(ProgramError::ArithmeticOverflow)?;
+                    } else {
+                        let (amount_in_with_fees, amount_out, fee) = active_bin.swap(amount_specified_left, price, swap_for_y, &lb_pair).ok_or(ProgramError::ArithmeticOverflow)?;
+                        amount_specified_left = amount_specified_left.checked_sub(amount_in_with_fees).ok_or(ProgramError::ArithmeticOverflow)?;
+                        amount_calculated = amount_calculated.checked_add(amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+                        fee_amount = fee_amount.checked_add(fee).ok_or(ProgramError::ArithmeticOverflow)?;
+                    }
+                }
+                if amount_specified_left == 0 {
+                    break '_a;
+                }
+                if lb_pair.advance_active_bin(swap_for_y).is_none() {
+                    return Ok(None);
+                }
+            }
+        }
+        if amount_specified_left != 0 || amount_calculated == 0 {
+            return Ok(None);
+        }
+        if is_exact_out {
+            Ok(Some((amount_calculated, amount_specified, fee_amount)))
+        } else {
+            Ok(Some((amount_specified, amount_calculated, fee_amount)))
+        }
+    }
+    // The Q64.64 price of the active bin defines the spot price. Are returned in the order of the swap direction.
+    fn get_virtual_reserves(pool_state: &PoolState) -> Result<(u64, u64), ProgramError> {
+        let price = crate::extern_source::meteora_dlmm::get_price_from_id(pool_state.lb_pair.active_id, pool_state.lb_pair.bin_step).ok_or(ProgramError::ArithmeticOverflow)?;
+        let mut reserve_x = ONE;
+        let mut reserve_y = price;
+        let bits = (u128::BITS - reserve_x.leading_zeros()).max(u128::BITS - reserve_y.leading_zeros());
+        if bits > u64::BITS {
+            reserve_x >>= bits - u64::BITS;
+            reserve_y >>= bits - u64::BITS;
+        }
+        let reserve_x_ = (reserve_x as u64).max(1);
+        let reserve_y_ = (reserve_y as u64).max(1);
+        if pool_state.swap_for_y {
+            Ok((reserve_x_, reserve_y_))
+        } else {
+            Ok((reserve_y_, reserve_x_))
+        }
+    }
+    fn invoke_swap<'a, 'b, 'c>(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        is_exact_out: bool,
+        amount_0: u64,
+        amount_1: u64,
+    ) -> Result<(), ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let meteora_dlmm_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let lb_pair = solana_program::account_info::next_account_info(account_info_iter)?;
+        let bin_array_bitmap_extension = solana_program::account_info::next_account_info(account_info_iter)?;
+        let reserve_x = solana_program::account_info::next_account_info(account_info_iter)?;
+        let reserve_y = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_x_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_y_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let oracle = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_x_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_y_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let event_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let bin_arrays = &dex_accounts[Self::FIXED_SWAP_ACCOUNTS_QUANTITY..];
+        let (user_token_in, user_token_out) = if base_data.is_from_quote_to_token {
+            (base_data.quote_token_account, base_data.token_account)
+        } else {
+            (base_data.token_account, base_data.quote_token_account)
+        };
+        let bin_array_bitmap_extension_ = if *bin_array_bitmap_extension.key == METEORA_DLMM_PROGRAM_ID {
+            None
+        } else {
+            Some(bin_array_bitmap_extension.key)
+        };
+        let instruction = Self::create_swap_instruction(
+            meteora_dlmm_program.key,
+            lb_pair.key,
+            bin_array_bitmap_extension_,
+            reserve_x.key,
+            reserve_y.key,
+            user_token_in.key,
+            user_token_out.key,
+            token_x_mint.key,
+            token_y_mint.key,
+            oracle.key,
+            base_data.self_authority.key,
+            token_x_program.key,
+            token_y_program.key,
+            event_authority.key,
+            bin_arrays.iter().map(|bin_array| bin_array.key).collect::<Vec<&Pubkey>>().as_slice(),
+            amount_0,
+            amount_1,
+            is_exact_out,
+        );
+        let mut account_infos = vec![
+            lb_pair.clone(),
+            bin_array_bitmap_extension.clone(),
+            reserve_x.clone(),
+            reserve_y.clone(),
+            user_token_in.clone(),
+            user_token_out.clone(),
+            token_x_mint.clone(),
+            token_y_mint.clone(),
+            oracle.clone(),
+            meteora_dlmm_program.clone(),
+            base_data.self_authority.clone(),
+            token_x_program.clone(),
+            token_y_program.clone(),
+            event_authority.clone(),
+        ];
+        account_infos.extend(bin_arrays.iter().cloned());
+        solana_program::program::invoke_signed(
+            &instruction,
+            account_infos.as_slice(),
+            [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
+        )
+    }
+}
+impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for MeteoraDlmm {
+    // At least one bin array is required.
+    fn get_swap_accounts_quantity(&'a self) -> usize {
+        Self::FIXED_SWAP_ACCOUNTS_QUANTITY + 1
+    }
+    fn get_max_swap_accounts_quantity(&'a self) -> usize {
+        Self::FIXED_SWAP_ACCOUNTS_QUANTITY + Self::BIN_ARRAYS_MAX_QUANTITY
+    }
+    fn get_pool_account_index(&'a self) -> usize {
+        1
+    }
+    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        if base_data.amount_in == 0 {
+            return Ok(None);
+        }
+        let (_, amount_out, trade_fee) = match Self::calculate_swap(&pool_state, base_data.amount_in, false)? {
+            Some(swap_) => swap_,
+            None => return Ok(None),
+        };
+        let (in_reserve, out_reserve) = Self::get_virtual_reserves(&pool_state)?;
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.pool,
+            amount_in: base_data.amount_in,
+            amount_in_fee: trade_fee,
+            amount_out,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, false, base_data.amount_in, base_data.min_amount_out)
+    }
+    fn do_swap_calculation_base_out(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        if base_data.min_amount_out == 0 {
+            return Ok(None);
+        }
+        let (amount_in, _, trade_fee) = match Self::calculate_swap(&pool_state, base_data.min_amount_out, true)? {
+            Some(swap_) => swap_,
+            None => return Ok(None),
+        };
+        let (in_reserve, out_reserve) = Self::get_virtual_reserves(&pool_state)?;
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.pool,
+            amount_in,
+            amount_in_fee: trade_fee,
+            amount_out: base_data.min_amount_out,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    fn do_swap_base_out(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        _swap_calculation_result: &SwapCalculationResult,
+    ) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, true, base_data.amount_in, base_data.min_amount_out)
+    }
+}
@@ -8,15 +8,28 @@ use {
         METEORA_V1_PROGRAM_ID,
         METEORA_V1_VAULT_PROGRAM_ID,
         error::Error,
-        extern_source::meteora_v1::{
-            ActivationType,
-            ConstantProduct,
-            CurveType,
-            Pool,
-            SwapCurve,
-            SwapResult,
-            TradeDirection,
-            Vault,
+        extern_source::{
+            CheckedCeilDiv,
+            meteora_v1::{
+                ActivationType,
+                BASE_CACHE_EXPIRES,
+                ConstantProduct,
+                CurveType,
+                Depeg,
+                DepegType,
+                MARINADE_STATE,
+                Pool,
+                PoolFees,
+                SOLIDO_STATE,
+                StableSwap,
+                SwapCurve,
+                SwapResult,
+                TradeDirection,
+                Vault,
+                get_marinade_virtual_price,
+                get_solido_virtual_price,
+                get_spl_stake_virtual_price,
+            },
         },
         state::PdaResolver,
     },
@@ -32,14 +45,106 @@ use {
         pubkey::Pubkey,
         sysvar::Sysvar,
     },
-    spl_token::state::{
-        Account,
-        Mint,
-    },
+    spl_token::state::Mint,
 };
 const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
 pub struct MeteoraV1;
+struct PoolState {
+    pool: Pubkey,
+    trade_direction: TradeDirection,
+    fees: PoolFees,
+    // None for the constant product curve.
+    stable_swap: Option<StableSwap>,
+    current_time: u64,
+    in_vault: Box<Vault>,
+    out_vault: Box<Vault>,
+    in_vault_lp: u64,
+    in_vault_lp_mint: u64,
+    out_vault_lp_mint: u64,
+    out_vault_token_account: u64,
+    in_token_total_amount: u64,
+    out_token_total_amount: u64,
+}
+impl PoolState {
+    // The spot price of the stable curve is measured by the swap of this part of the in reserve.
+    const STABLE_SPOT_PRICE_RESERVE_DIVISOR: u64 = 10_000;
+    fn swap(&self, amount_in: u64) -> Option<SwapResult> {
+        match self.stable_swap {
+            Some(ref stable_swap) => stable_swap.swap(amount_in, self.in_token_total_amount, self.out_token_total_amount, self.trade_direction),
+            None => ConstantProduct.swap(amount_in, self.in_token_total_amount, self.out_token_total_amount, self.trade_direction),
+        }
+    }
+    // Returns the amount in without fees, which is enough for the amount out.
+    fn estimate_amount_in(&self, amount_out: u64) -> Option<u128> {
+        match self.stable_swap {
+            Some(ref stable_swap) => {
+                stable_swap
+                    .get_source_amount(amount_out, self.in_token_total_amount, self.out_token_total_amount, self.trade_direction)
+                    .map(|amount_in| amount_in as u128)
+            }
+            None => {
+                // (in + amount_in) * (out - amount_out) = in * out
+                // => amount_in = in * amount_out / (out - amount_out)
+                Some((self.in_token_total_amount as u128).checked_mul(amount_out as u128)?.checked_ceil_div((self.out_token_total_amount - amount_out) as u128)?.0)
+            }
+        }
+    }
+    // Returns the reserves defining the spot price. The stable curve price is not defined by the reserves ratio.
+    fn get_reserves(&self) -> Option<(u64, u64)> {
+        match self.stable_swap {
+            Some(_) => {
+                let amount_in = (self.in_token_total_amount / Self::STABLE_SPOT_PRICE_RESERVE_DIVISOR).max(1);
+                let amount_out = self.swap(amount_in)?.destination_amount_swapped;
+                Some((amount_in, u64::try_from(amount_out).ok()?))
+            }
+            None => Some((self.in_token_total_amount, self.out_token_total_amount)),
+        }
+    }
+    // Returns the trade fee and the amount out, or None if the pool can not give the amount out.
+    fn calculate_amount_out(&self, amount_in: u64) -> Result<Option<(u64, u64)>, ProgramError> {
+        let trade_fee = self.fees.trading_fee(amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
+        let protocol_fee = self.fees.protocol_trading_fee(trade_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        let trade_fee_: u64 = trade_fee.checked_sub(protocol_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        let in_amount_after_protocol_fee = amount_in.checked_sub(protocol_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        let before_in_token_total_amount = self.in_token_total_amount;
+        let mut in_vault = self.in_vault.clone();
+        let in_lp = in_vault.get_unmint_amount(self.current_time, in_amount_after_protocol_fee, self.in_vault_lp_mint).ok_or(ProgramError::ArithmeticOverflow)?;
+        in_vault.total_amount = in_vault.total_amount.checked_add(in_amount_after_protocol_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        let after_in_token_total_amount = in_vault
+            .get_amount_by_share(
+                self.current_time,
+                in_lp.checked_add(self.in_vault_lp).ok_or(ProgramError::ArithmeticOverflow)?,
+                self.in_vault_lp_mint.checked_add(in_lp).ok_or(ProgramError::ArithmeticOverflow)?,
+            )
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let actual_in_amount = after_in_token_total_amount.checked_sub(before_in_token_total_amount).ok_or(ProgramError::ArithmeticOverflow)?;
+        let actual_in_amount_after_fee = actual_in_amount.checked_sub(trade_fee_).ok_or(ProgramError::ArithmeticOverflow)?;
+        let SwapResult {
+            destination_amount_swapped,
+            ..
+        } = self.swap(actual_in_amount_after_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        let out_vault_lp = self
+            .out_vault
+            .get_unmint_amount(
+                self.current_time,
+                destination_amount_swapped.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?,
+                self.out_vault_lp_mint,
+            )
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let amount_out = self.out_vault.get_amount_by_share(self.current_time, out_vault_lp, self.out_vault_lp_mint).ok_or(ProgramError::ArithmeticOverflow)?;
+        if amount_out > self.out_vault_token_account {
+            return Ok(None);
+        }
+        Ok(Some((trade_fee, amount_out)))
+    }
+}
 impl MeteoraV1 {
+    const FIXED_SWAP_ACCOUNTS_QUANTITY: usize = 14;
+    // The depeg pool (Marinade, Lido or SplStake) needs the stake account to update the base virtual price.
+    const DEPEG_ACCOUNTS_MAX_QUANTITY: usize = 1;
+    // The amount in for the exact amount out is searched by the forward calculation
+    // starting from the inverse curve estimation.
+    const BASE_OUT_CALCULATION_ITERATIONS_QUANTITY: usize = 8;
     pub fn create_swap_instruction(
         program_id: &Pubkey,
         pool: &Pubkey,
@@ -57,6 +162,7 @@ impl MeteoraV1 {
         user: &Pubkey,
         vault_program: &Pubkey,
         token_program: &Pubkey,
+        depeg_accounts: &[&Pubkey],
         amount_in: u64,
         minimum_amount_out: u64,
     ) -> Instruction {
@@ -74,36 +180,40 @@ impl MeteoraV1 {
         data.extend(DISCRIMINATOR);
         data.extend(amount_in.to_le_bytes());
         data.extend(minimum_amount_out.to_le_bytes());
+        let mut accounts = Vec::<AccountMeta>::with_capacity(15 + depeg_accounts.len());
+        accounts.extend([
+            // https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/programs/dynamic-amm/src/instructions/swap.rs#L5
+            AccountMeta::new(*pool, false),
+            AccountMeta::new(*user_source_token, false),
+            AccountMeta::new(*user_destination_token, false),
+            AccountMeta::new(*a_vault, false),
+            AccountMeta::new(*b_vault, false),
+            AccountMeta::new(*a_token_vault, false),
+            AccountMeta::new(*b_token_vault, false),
+            AccountMeta::new(*a_vault_lp_mint, false),
+            AccountMeta::new(*b_vault_lp_mint, false),
+            AccountMeta::new(*a_vault_lp, false),
+            AccountMeta::new(*b_vault_lp, false),
+            AccountMeta::new(*protocol_token_fee, false),
+            AccountMeta::new_readonly(*user, true),
+            AccountMeta::new_readonly(*vault_program, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ]);
+        // https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/programs/dynamic-amm/src/lib.rs
+        //
+        // The depeg accounts are passed as the remaining accounts.
+        '_a: for depeg_account in depeg_accounts.iter() {
+            accounts.push(AccountMeta::new_readonly(**depeg_account, false));
+        }
         Instruction {
             program_id: *program_id,
-            accounts: vec![
-                // https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/programs/dynamic-amm/src/instructions/swap.rs#L5
-                AccountMeta::new(*pool, false),
-                AccountMeta::new(*user_source_token, false),
-                AccountMeta::new(*user_destination_token, false),
-                AccountMeta::new(*a_vault, false),
-                AccountMeta::new(*b_vault, false),
-                AccountMeta::new(*a_token_vault, false),
-                AccountMeta::new(*b_token_vault, false),
-                AccountMeta::new(*a_vault_lp_mint, false),
-                AccountMeta::new(*b_vault_lp_mint, false),
-                AccountMeta::new(*a_vault_lp, false),
-                AccountMeta::new(*b_vault_lp, false),
-                AccountMeta::new(*protocol_token_fee, false),
-                AccountMeta::new_readonly(*user, true),
-                AccountMeta::new_readonly(*vault_program, false),
-                AccountMeta::new_readonly(*token_program, false),
-            ],
+            accounts,
             data,
         }
     }
-}
-impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for MeteoraV1 {
-    fn get_swap_accounts_quantity(&'a self) -> usize {
-        14
-    }
-    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, first_account_index: usize) -> Result<Option<SwapCalculationResult>, ProgramError> {
-        let account_info_iter = &mut base_data.accounts.iter().skip(first_account_index).take(self.get_swap_accounts_quantity());
+    // Returns None if the pool is not available for swap.
+    fn load_pool_state<'a, 'b, 'c>(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<PoolState>, ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
         let meteora_v1_program = solana_program::account_info::next_account_info(account_info_iter)?;
         let clock = solana_program::account_info::next_account_info(account_info_iter)?;
         let pool = solana_program::account_info::next_account_info(account_info_iter)?;
@@ -136,13 +246,13 @@ impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for MeteoraV1 {
             return Err(Error::InvalidAccountConfigurationFlags.into());
         }
         // This is a slightly modified selective code from https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/dynamic-amm-quote/src/lib.rs#L58
-        // that calculates the swap possibility.
+        // that loads the pool state for swap calculation.
         //
         // In ideal case all structures here should be deserealized from accounts in zero-copy context.
         // But in the source code the structures are serialized with 'borsh' and without zero-copy.
-        let swap_calculation_result = {
+        let pool_state = {
             let clock_ = Clock::from_account_info(clock)?;
-            let (trade_direction, trade_fee, protocol_fee) = match check_pool(pool, base_data, &clock_)? {
+            let (trade_direction, fees, curve_type, stake) = match check_pool(pool, base_data, &clock_)? {
                 Some(data) => data,
                 None => return Ok(None),
             };
@@ -152,61 +262,58 @@ impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for MeteoraV1 {
             let vault_b = Box::new(<Vault as borsh::de::BorshDeserialize>::deserialize(
                 &mut &b_vault.data.borrow()[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..],
             )?);
-            let pool_vault_a_lp_token = Account::unpack_unchecked(&a_vault_lp.data.borrow())?.amount;
-            let pool_vault_b_lp_token = Account::unpack_unchecked(&b_vault_lp.data.borrow())?.amount;
+            let pool_vault_a_lp_token = crate::extern_source::spl_token_2022::unpack_account_amount(&a_vault_lp.data.borrow()).ok_or(Error::InvalidAccountData)?;
+            let pool_vault_b_lp_token = crate::extern_source::spl_token_2022::unpack_account_amount(&b_vault_lp.data.borrow()).ok_or(Error::InvalidAccountData)?;
             let vault_a_lp_mint = Mint::unpack_unchecked(&a_vault_lp_mint.data.borrow())?.supply;
             let vault_b_lp_mint = Mint::unpack_unchecked(&b_vault_lp_mint.data.borrow())?.supply;
-            let vault_a_token = Account::unpack_unchecked(&a_token_vault.data.borrow())?.amount;
-            let vault_b_token = Account::unpack_unchecked(&b_token_vault.data.borrow())?.amount;
-            // https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/rust-client/src/instructions/dynamic_amm/quote.rs#L79
-            // https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/programs/dynamic-amm/src/instructions/swap.rs#L5
-            //
-            // MeteoraV1 swap instruction does not receive the 'pool_.stake' account to work with its data.
+            let vault_a_token = crate::extern_source::spl_token_2022::unpack_account_amount(&a_token_vault.data.borrow()).ok_or(Error::InvalidAccountData)?;
+            let vault_b_token = crate::extern_source::spl_token_2022::unpack_account_amount(&b_token_vault.data.borrow()).ok_or(Error::InvalidAccountData)?;
             let current_time: u64 = clock_.unix_timestamp.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?;
+            let stable_swap = match curve_type {
+                CurveType::ConstantProduct => None,
+                CurveType::Stable {
+                    amp,
+                    token_multiplier,
+                    mut depeg,
+                    ..
+                } => {
+                    if !update_base_virtual_price(&mut depeg, &stake, current_time, account_info_iter.as_slice())? {
+                        return Ok(None);
+                    }
+                    Some(StableSwap {
+                        amp,
+                        token_multiplier,
+                        depeg,
+                    })
+                }
+            };
             let token_a_amount = vault_a.get_amount_by_share(current_time, pool_vault_a_lp_token, vault_a_lp_mint).ok_or(ProgramError::ArithmeticOverflow)?;
             let token_b_amount = vault_b.get_amount_by_share(current_time, pool_vault_b_lp_token, vault_b_lp_mint).ok_or(ProgramError::ArithmeticOverflow)?;
-            let (mut in_vault, out_vault, in_vault_lp, in_vault_lp_mint, out_vault_lp_mint, out_vault_token_account, in_token_total_amount, out_token_total_amount) =
+            let (in_vault, out_vault, in_vault_lp, in_vault_lp_mint, out_vault_lp_mint, out_vault_token_account, in_token_total_amount, out_token_total_amount) =
                 match trade_direction {
                     TradeDirection::AtoB => (vault_a, vault_b, pool_vault_a_lp_token, vault_a_lp_mint, vault_b_lp_mint, vault_b_token, token_a_amount, token_b_amount),
                     TradeDirection::BtoA => (vault_b, vault_a, pool_vault_b_lp_token, vault_b_lp_mint, vault_a_lp_mint, vault_a_token, token_b_amount, token_a_amount),
                 };
-            let trade_fee_: u64 = trade_fee.checked_sub(protocol_fee).ok_or(ProgramError::ArithmeticOverflow)?;
-            let in_amount_after_protocol_fee =
-                base_data.amount_in.checked_sub(protocol_fee).ok_or(ProgramError::ArithmeticOverflow)?;
-            let before_in_token_total_amount = in_token_total_amount;
-            let in_lp = in_vault.get_unmint_amount(current_time, in_amount_after_protocol_fee, in_vault_lp_mint).ok_or(ProgramError::ArithmeticOverflow)?;
-            in_vault.total_amount = in_vault.total_amount.checked_add(in_amount_after_protocol_fee).ok_or(ProgramError::ArithmeticOverflow)?;
-            let after_in_token_total_amount = in_vault
-                .get_amount_by_share(
-                    current_time,
-                    in_lp.checked_add(in_vault_lp).ok_or(ProgramError::ArithmeticOverflow)?,
-                    in_vault_lp_mint.checked_add(in_lp).ok_or(ProgramError::ArithmeticOverflow)?,
-                )
-                .ok_or(ProgramError::ArithmeticOverflow)?;
-            let actual_in_amount = after_in_token_total_amount.checked_sub(before_in_token_total_amount).ok_or(ProgramError::ArithmeticOverflow)?;
-            let actual_in_amount_after_fee = actual_in_amount.checked_sub(trade_fee_).ok_or(ProgramError::ArithmeticOverflow)?;
-            let swap_curve = ConstantProduct;
-            let SwapResult {
-                destination_amount_swapped,
-                ..
-            } = swap_curve.swap(actual_in_amount_after_fee, in_token_total_amount, out_token_total_amount, trade_direction).ok_or(ProgramError::ArithmeticOverflow)?;
-            let out_vault_lp = out_vault
-                .get_unmint_amount(current_time, destination_amount_swapped.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?, out_vault_lp_mint)
-                .ok_or(ProgramError::ArithmeticOverflow)?;
-            let amount_out_ = out_vault.get_amount_by_share(current_time, out_vault_lp, out_vault_lp_mint).ok_or(ProgramError::ArithmeticOverflow)?;
-            if amount_out_ > out_vault_token_account {
-                return Ok(None);
-            }
-            SwapCalculationResult {
+            PoolState {
                 pool: *pool.key,
-                amount_in_fee: trade_fee,
-                amount_out: amount_out_,
+                trade_direction,
+                fees,
+                stable_swap,
+                current_time,
+                in_vault,
+                out_vault,
+                in_vault_lp,
+                in_vault_lp_mint,
+                out_vault_lp_mint,
+                out_vault_token_account,
+                in_token_total_amount,
+                out_token_total_amount,
             }
         };
-        Ok(Some(swap_calculation_result))
+        Ok(Some(pool_state))
     }
-    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, first_account_index: usize) -> Result<(), ProgramError> {
-        let account_info_iter = &mut base_data.accounts.iter().skip(first_account_index).take(self.get_swap_accounts_quantity());
+    fn invoke_swap<'a, 'b, 'c>(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>], amount_in: u64, min_amount_out: u64) -> Result<(), ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
         let meteora_v1_program = solana_program::account_info::next_account_info(account_info_iter)?;
         let _clock = solana_program::account_info::next_account_info(account_info_iter)?;
         let pool = solana_program::account_info::next_account_info(account_info_iter)?;
@@ -221,94 +328,152 @@ impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for MeteoraV1 {
         let protocol_token_fee = solana_program::account_info::next_account_info(account_info_iter)?;
         let vault_program = solana_program::account_info::next_account_info(account_info_iter)?;
         let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
-        if base_data.is_from_quote_to_token {
-            solana_program::program::invoke_signed(
-                &Self::create_swap_instruction(
-                    meteora_v1_program.key,
-                    pool.key,
-                    base_data.quote_token_account.key,
-                    base_data.token_account.key,
-                    a_vault.key,
-                    b_vault.key,
-                    a_token_vault.key,
-                    b_token_vault.key,
-                    a_vault_lp_mint.key,
-                    b_vault_lp_mint.key,
-                    a_vault_lp.key,
-                    b_vault_lp.key,
-                    protocol_token_fee.key,
-                    base_data.self_authority.key,
-                    vault_program.key,
-                    token_program.key,
-                    base_data.amount_in,
-                    base_data.min_amount_out,
-                ),
-                vec![
-                    pool.clone(),
-                    base_data.quote_token_account.clone(),
-                    base_data.token_account.clone(),
-                    a_vault.clone(),
-                    b_vault.clone(),
-                    a_token_vault.clone(),
-                    b_token_vault.clone(),
-                    a_vault_lp_mint.clone(),
-                    b_vault_lp_mint.clone(),
-                    a_vault_lp.clone(),
-                    b_vault_lp.clone(),
-                    protocol_token_fee.clone(),
-                    base_data.self_authority.clone(),
-                    vault_program.clone(),
-                    token_program.clone(),
-                ]
-                .as_slice(),
-                [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
-            )
+        let depeg_accounts = &dex_accounts[Self::FIXED_SWAP_ACCOUNTS_QUANTITY..];
+        let (user_source_token, user_destination_token) = if base_data.is_from_quote_to_token {
+            (base_data.quote_token_account, base_data.token_account)
         } else {
-            solana_program::program::invoke_signed(
-                &Self::create_swap_instruction(
-                    meteora_v1_program.key,
-                    pool.key,
-                    base_data.token_account.key,
-                    base_data.quote_token_account.key,
-                    a_vault.key,
-                    b_vault.key,
-                    a_token_vault.key,
-                    b_token_vault.key,
-                    a_vault_lp_mint.key,
-                    b_vault_lp_mint.key,
-                    a_vault_lp.key,
-                    b_vault_lp.key,
-                    protocol_token_fee.key,
-                    base_data.self_authority.key,
-                    vault_program.key,
-                    token_program.key,
-                    base_data.amount_in,
-                    base_data.min_amount_out,
-                ),
-                vec![
-                    pool.clone(),
-                    base_data.token_account.clone(),
-                    base_data.quote_token_account.clone(),
-                    a_vault.clone(),
-                    b_vault.clone(),
-                    a_token_vault.clone(),
-                    b_token_vault.clone(),
-                    a_vault_lp_mint.clone(),
-                    b_vault_lp_mint.clone(),
-                    a_vault_lp.clone(),
-                    b_vault_lp.clone(),
-                    protocol_token_fee.clone(),
-                    base_data.self_authority.clone(),
-                    vault_program.clone(),
-                    token_program.clone(),
-                ]
-                .as_slice(),
-                [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
-            )
+            (base_data.token_account, base_data.quote_token_account)
+        };
+        let mut account_infos = vec![
+            pool.clone(),
+            user_source_token.clone(),
+            user_destination_token.clone(),
+            a_vault.clone(),
+            b_vault.clone(),
+            a_token_vault.clone(),
+            b_token_vault.clone(),
+            a_vault_lp_mint.clone(),
+            b_vault_lp_mint.clone(),
+            a_vault_lp.clone(),
+            b_vault_lp.clone(),
+            protocol_token_fee.clone(),
+            base_data.self_authority.clone(),
+            vault_program.clone(),
+            token_program.clone(),
+        ];
+        account_infos.extend(depeg_accounts.iter().cloned());
+        solana_program::program::invoke_signed(
+            &Self::create_swap_instruction(
+                meteora_v1_program.key,
+                pool.key,
+                user_source_token.key,
+                user_destination_token.key,
+                a_vault.key,
+                b_vault.key,
+                a_token_vault.key,
+                b_token_vault.key,
+                a_vault_lp_mint.key,
+                b_vault_lp_mint.key,
+                a_vault_lp.key,
+                b_vault_lp.key,
+                protocol_token_fee.key,
+                base_data.self_authority.key,
+                vault_program.key,
+                token_program.key,
+                depeg_accounts.iter().map(|depeg_account| depeg_account.key).collect::<Vec<&Pubkey>>().as_slice(),
+                amount_in,
+                min_amount_out,
+            ),
+            account_infos.as_slice(),
+            [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
+        )
+    }
+}
+impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for MeteoraV1 {
+    fn get_swap_accounts_quantity(&'a self) -> usize {
+        Self::FIXED_SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_max_swap_accounts_quantity(&'a self) -> usize {
+        Self::FIXED_SWAP_ACCOUNTS_QUANTITY + Self::DEPEG_ACCOUNTS_MAX_QUANTITY
+    }
+    fn get_pool_account_index(&'a self) -> usize {
+        2
+    }
+    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        let (trade_fee, amount_out_) = match pool_state.calculate_amount_out(base_data.amount_in)? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let (in_reserve, out_reserve) = pool_state.get_reserves().ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.pool,
+            amount_in: base_data.amount_in,
+            amount_in_fee: trade_fee,
+            amount_out: amount_out_,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, base_data.amount_in, base_data.min_amount_out)
+    }
+    // MeteoraV1 has no exact output swap instruction, so the amount in is calculated here
+    // and the regular swap is done with the exact amount out as minimum.
+    fn do_swap_calculation_base_out(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        let amount_out = base_data.min_amount_out;
+        if amount_out == 0 || amount_out >= pool_state.out_token_total_amount || amount_out > pool_state.out_vault_token_account {
+            return Ok(None);
+        }
+        let amount_in_after_fee = pool_state.estimate_amount_in(amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+        // amount_in * (1 - trade_fee_rate) = amount_in_after_fee
+        let amount_in_ = if pool_state.fees.trade_fee_numerator == 0 {
+            amount_in_after_fee
+        } else {
+            amount_in_after_fee
+                .checked_mul(pool_state.fees.trade_fee_denominator as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?
+                .checked_ceil_div(pool_state.fees.trade_fee_denominator.checked_sub(pool_state.fees.trade_fee_numerator).ok_or(ProgramError::ArithmeticOverflow)? as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?
+                .0
+        };
+        let mut amount_in_: u64 = amount_in_.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?;
+        let (in_reserve, out_reserve) = pool_state.get_reserves().ok_or(ProgramError::ArithmeticOverflow)?;
+        // The vaults' share conversions round down, so the estimation is corrected with the forward calculation.
+        for _ in 0..Self::BASE_OUT_CALCULATION_ITERATIONS_QUANTITY {
+            let (trade_fee, amount_out_) = match pool_state.calculate_amount_out(amount_in_)? {
+                Some(data) => data,
+                None => return Ok(None),
+            };
+            if amount_out_ >= amount_out {
+                return Ok(Some(SwapCalculationResult {
+                    pool: pool_state.pool,
+                    amount_in: amount_in_,
+                    amount_in_fee: trade_fee,
+                    amount_out: amount_out_,
+                    in_reserve,
+                    out_reserve,
+                }));
+            }
+            let amount_in_shortage = ((amount_out - amount_out_) as u128)
+                .checked_mul(amount_in_ as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?
+                .checked_div(amount_out as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            amount_in_ = amount_in_
+                .checked_add(u64::try_from(amount_in_shortage).map_err(|_| ProgramError::ArithmeticOverflow)?)
+                .and_then(|amount_in__| amount_in__.checked_add(1))
+                .ok_or(ProgramError::ArithmeticOverflow)?;
         }
+        Ok(None)
+    }
+    fn do_swap_base_out(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        swap_calculation_result: &SwapCalculationResult,
+    ) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, swap_calculation_result.amount_in, base_data.min_amount_out)
     }
 }
-fn check_pool(pool: &AccountInfo, base_data: &BaseData, clock_: &Clock) -> Result<Option<(TradeDirection, u64, u64)>, ProgramError> {
+fn check_pool(pool: &AccountInfo, base_data: &BaseData, clock_: &Clock) -> Result<Option<(TradeDirection, PoolFees, CurveType, Pubkey)>, ProgramError> {
     let in_token_mint = if base_data.is_from_quote_to_token {
         base_data.quote_mint
     } else {
@@ -317,9 +482,6 @@ fn check_pool(pool: &AccountInfo, base_data: &BaseData, clock_: &Clock) -> Resul
     let pool_ = Box::new(<Pool as borsh::de::BorshDeserialize>::deserialize(
         &mut &pool.data.borrow()[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..],
     )?);
-    if pool_.stake != Pubkey::default() {
-        return Ok(None);
-    }
     let activation_type = ActivationType::try_from(pool_.bootstrapping.activation_type).map_err(|_| ProgramError::InvalidArgument)?;
     let current_point = match activation_type {
         ActivationType::Slot => clock_.slot,
@@ -331,21 +493,62 @@ fn check_pool(pool: &AccountInfo, base_data: &BaseData, clock_: &Clock) -> Resul
     if current_point < pool_.bootstrapping.activation_point {
         return Ok(None);
     }
-    match pool_.curve_type {
-        CurveType::ConstantProduct => {}
-        // https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/dynamic-amm-quote/src/lib.rs#L91
-        //
-        // MeteoraV1 swap instruction does not receive the 'pool_.stake' account to work with its data.
-        _ => return Ok(None),
-    }
     if *in_token_mint != pool_.token_a_mint && *in_token_mint != pool_.token_b_mint {
         return Err(Error::InvalidTokenMint.into());
     }
-    let trade_fee = pool_.fees.trading_fee(base_data.amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
-    let protocol_fee = pool_.fees.protocol_trading_fee(trade_fee).ok_or(ProgramError::ArithmeticOverflow)?;
-    if *in_token_mint == pool_.token_a_mint {
-        Ok(Some((TradeDirection::AtoB, trade_fee, protocol_fee)))
+    let trade_direction = if *in_token_mint == pool_.token_a_mint {
+        TradeDirection::AtoB
     } else {
-        Ok(Some((TradeDirection::BtoA, trade_fee, protocol_fee)))
+        TradeDirection::BtoA
+    };
+    let Pool {
+        fees,
+        curve_type,
+        stake,
+        ..
+    } = *pool_;
+    Ok(Some((trade_direction, fees, curve_type, stake)))
+}
+// This is a slightly modified selective code from https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/dynamic-amm-quote/src/depeg/mod.rs
+// that updates the expired base virtual price of the depeg pool from the stake account.
+//
+// Returns false if the stake account is needed but not presented.
+// The 'pool_stake' is the SPL stake pool of the SplStake depeg pool.
+fn update_base_virtual_price(depeg: &mut Depeg, pool_stake: &Pubkey, current_time: u64, depeg_accounts: &[AccountInfo]) -> Result<bool, ProgramError> {
+    if depeg.depeg_type.is_none() {
+        return Ok(true);
+    }
+    let cache_expire_time = depeg.base_cache_updated.checked_add(BASE_CACHE_EXPIRES).ok_or(ProgramError::ArithmeticOverflow)?;
+    if current_time <= cache_expire_time {
+        return Ok(true);
     }
+    let stake = match depeg_accounts.first() {
+        Some(stake_) => stake_,
+        None => return Ok(false),
+    };
+    let stake_data = stake.data.borrow();
+    let virtual_price = match depeg.depeg_type {
+        DepegType::Marinade => {
+            if *stake.key != MARINADE_STATE {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            get_marinade_virtual_price(&stake_data)
+        }
+        DepegType::Lido => {
+            if *stake.key != SOLIDO_STATE {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            get_solido_virtual_price(&stake_data)
+        }
+        DepegType::SplStake => {
+            if *pool_stake == Pubkey::default() || stake.key != pool_stake {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            get_spl_stake_virtual_price(&stake_data)
+        }
+        DepegType::None => return Ok(true),
+    };
+    depeg.base_virtual_price = virtual_price.ok_or(Error::InvalidAccountData)?;
+    depeg.base_cache_updated = current_time;
+    Ok(true)
 }
@@ -1,23 +1,60 @@
+pub mod meteora_damm_v2;
+pub mod meteora_dlmm;
 pub mod meteora_v1;
+pub mod orca_whirlpool;
+pub mod phoenix;
+pub mod pump_fun;
+pub mod pump_swap;
+pub mod raydium_clmm;
+pub mod raydium_cpmm;
 pub mod raydium_v4;
 use {
-    crate::state::Intermediary,
+    crate::{
+        extern_source::spl_token_2022::TransferFeeConfig,
+        state::Intermediary,
+    },
     solana_program::{
         account_info::AccountInfo,
         program_error::ProgramError,
         pubkey::Pubkey,
     },
 };
+// Each method receives only the Dex swap accounts from the 'BaseData::accounts'.
 pub trait Dex<'a, 'b, 'c> {
+    // The minimum and the maximum quantity of the Dex swap accounts. Are equal for the Dex with the fixed accounts set.
     fn get_swap_accounts_quantity(&'a self) -> usize;
-    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, first_account_index: usize) -> Result<Option<SwapCalculationResult>, ProgramError>;
-    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, first_account_index: usize) -> Result<(), ProgramError>;
+    fn get_max_swap_accounts_quantity(&'a self) -> usize;
+    // Index of the pool account among the Dex swap accounts.
+    fn get_pool_account_index(&'a self) -> usize;
+    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError>;
+    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<(), ProgramError>;
+    // For the exact output swap the 'base_data.amount_in' is the maximum amount in,
+    // and the 'base_data.min_amount_out' is the exact amount out.
+    fn do_swap_calculation_base_out(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError>;
+    fn do_swap_base_out(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        swap_calculation_result: &SwapCalculationResult,
+    ) -> Result<(), ProgramError>;
 }
+#[derive(Debug, borsh::BorshSerialize, borsh::BorshDeserialize)]
 pub struct SwapCalculationResult {
     pub pool: Pubkey,
+    pub amount_in: u64,
     pub amount_in_fee: u64,
     pub amount_out: u64,
+    // The pre-trade reserves which define the spot price.
+    pub in_reserve: u64,
+    pub out_reserve: u64,
+}
+impl SwapCalculationResult {
+    // The bigger 'amount_out' is better. With equal 'amount_out' the lower 'amount_in_fee' is better.
+    pub fn is_better_than(&self, other: &Self) -> bool {
+        self.amount_out > other.amount_out || (self.amount_out == other.amount_out && self.amount_in_fee < other.amount_in_fee)
+    }
 }
+#[derive(Clone, Copy)]
 pub struct BaseData<'a, 'b> {
     pub accounts: &'a [AccountInfo<'b>],
     pub intermediary: &'a AccountInfo<'b>,
@@ -32,3 +69,29 @@ pub struct BaseData<'a, 'b> {
     pub is_from_quote_to_token: bool,
     pub with_checks: bool,
 }
+// Returns the transfer fee config for Token-2022 mint with the extension.
+pub fn get_transfer_fee_config(mint: &AccountInfo) -> Option<TransferFeeConfig> {
+    if *mint.owner != crate::extern_source::spl_token_2022::ID {
+        return None;
+    }
+    TransferFeeConfig::unpack_from_mint(&mint.data.borrow())
+}
+// The virtual reserves of the unit liquidity at the current Q64.64 square root price define the spot price
+// of the concentrated liquidity pool. Are returned in the order of the swap direction.
+fn get_virtual_reserves(sqrt_price_x64: u128, zero_for_one: bool) -> (u64, u64) {
+    // Approximately 2^128 / sqrt_price_x64, which fits into 'u128' for the valid prices.
+    let mut reserve_0 = u128::MAX / sqrt_price_x64.max(1);
+    let mut reserve_1 = sqrt_price_x64;
+    let bits = (u128::BITS - reserve_0.leading_zeros()).max(u128::BITS - reserve_1.leading_zeros());
+    if bits > u64::BITS {
+        reserve_0 >>= bits - u64::BITS;
+        reserve_1 >>= bits - u64::BITS;
+    }
+    let reserve_0_ = (reserve_0 as u64).max(1);
+    let reserve_1_ = (reserve_1 as u64).max(1);
+    if zero_for_one {
+        (reserve_0_, reserve_1_)
+    } else {
+        (reserve_1_, reserve_0_)
+    }
+}
@@ -0,0 +1,504 @@
+use {
+    super::{
+        BaseData,
+        Dex,
+        SwapCalculationResult,
+        get_virtual_reserves,
+    },
+    crate::{
+        ORCA_WHIRLPOOL_PROGRAM_ID,
+        error::Error,
+        extern_source::orca_whirlpool::{
+            MAX_SQRT_PRICE_X64,
+            MIN_SQRT_PRICE_X64,
+            TICK_ARRAY_SIZE,
+            TickArray,
+            Whirlpool,
+        },
+        state::PdaResolver,
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        instruction::{
+            AccountMeta,
+            Instruction,
+        },
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
+pub struct OrcaWhirlpool;
+struct PoolState {
+    pool: Pubkey,
+    fee_rate: u16,
+    tick_spacing: u16,
+    liquidity: u128,
+    sqrt_price: u128,
+    tick_current_index: i32,
+    a_to_b: bool,
+    // The consecutive tick arrays in the swap direction, starting from the tick array with the current tick.
+    tick_arrays: Vec<TickArray>,
+}
+impl OrcaWhirlpool {
+    const SWAP_ACCOUNTS_QUANTITY: usize = 9;
+    const TICK_ARRAYS_QUANTITY: usize = 3;
+    // https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/instructions/swap.rs
+    pub fn create_swap_instruction(
+        program_id: &Pubkey,
+        token_program: &Pubkey,
+        token_authority: &Pubkey,
+        whirlpool: &Pubkey,
+        token_owner_account_a: &Pubkey,
+        token_vault_a: &Pubkey,
+        token_owner_account_b: &Pubkey,
+        token_vault_b: &Pubkey,
+        tick_array_0: &Pubkey,
+        tick_array_1: &Pubkey,
+        tick_array_2: &Pubkey,
+        oracle: &Pubkey,
+        amount: u64,
+        other_amount_threshold: u64,
+        sqrt_price_limit: u128,
+        amount_specified_is_input: bool,
+        a_to_b: bool,
+    ) -> Instruction {
+        const DISCRIMINATOR: [u8; 8] = [
+            248,
+            198,
+            158,
+            145,
+            225,
+            117,
+            135,
+            200,
+        ];
+        let mut data = Vec::<u8>::with_capacity(42);
+        data.extend(DISCRIMINATOR);
+        data.extend(amount.to_le_bytes());
+        data.extend(other_amount_threshold.to_le_bytes());
+        data.extend(sqrt_price_limit.to_le_bytes());
+        data.push(amount_specified_is_input as u8);
+        data.push(a_to_b as u8);
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*token_program, false),
+                AccountMeta::new_readonly(*token_authority, true),
+                AccountMeta::new(*whirlpool, false),
+                AccountMeta::new(*token_owner_account_a, false),
+                AccountMeta::new(*token_vault_a, false),
+                AccountMeta::new(*token_owner_account_b, false),
+                AccountMeta::new(*token_vault_b, false),
+                AccountMeta::new(*tick_array_0, false),
+                AccountMeta::new(*tick_array_1, false),
+                AccountMeta::new(*tick_array_2, false),
+                AccountMeta::new(*oracle, false),
+            ],
+            data,
+        }
+    }
+    // Returns None if the pool is not available for swap.
+    fn load_pool_state<'a, 'b, 'c>(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<PoolState>, ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let whirlpool_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let whirlpool = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_vault_a = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_vault_b = solana_program::account_info::next_account_info(account_info_iter)?;
+        let tick_array_0 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let tick_array_1 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let tick_array_2 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let oracle = solana_program::account_info::next_account_info(account_info_iter)?;
+        if *whirlpool_program.key != ORCA_WHIRLPOOL_PROGRAM_ID {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if base_data.with_checks
+            && (!whirlpool.is_writable
+                || !token_vault_a.is_writable
+                || !token_vault_b.is_writable
+                || !tick_array_0.is_writable
+                || !tick_array_1.is_writable
+                || !tick_array_2.is_writable
+                || !oracle.is_writable)
+        {
+            return Err(Error::InvalidAccountConfigurationFlags.into());
+        }
+        // This is a slightly modified selective code from https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/instructions/swap.rs
+        // that loads the whirlpool and the tick arrays for swap calculation.
+        let pool_state = {
+            if whirlpool.owner != whirlpool_program.key {
+                return Err(Error::InvalidAmmAccountOwner.into());
+            }
+            let whirlpool_data = whirlpool.data.borrow();
+            if whirlpool_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR
+                || whirlpool_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] != Whirlpool::DISCRIMINATOR
+            {
+                return Err(Error::InvalidAccountData.into());
+            }
+            let whirlpool_ = Box::new(<Whirlpool as borsh::de::BorshDeserialize>::deserialize(
+                &mut &whirlpool_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..],
+            )?);
+            if *token_vault_a.key != whirlpool_.token_vault_a || *token_vault_b.key != whirlpool_.token_vault_b {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            // The adaptive fee requires the oracle state processing, and the Token-2022 vaults and token program require the swap v2 instruction.
+            if whirlpool_.is_initialized_with_adaptive_fee()
+                || *token_program.key != spl_token::ID
+                || *token_vault_a.owner != spl_token::ID
+                || *token_vault_b.owner != spl_token::ID
+            {
+                return Ok(None);
+            }
+            let (in_mint, out_mint) = if base_data.is_from_quote_to_token {
+                (base_data.quote_mint, base_data.token_mint)
+            } else {
+                (base_data.token_mint, base_data.quote_mint)
+            };
+            let a_to_b = if *in_mint == whirlpool_.token_mint_a && *out_mint == whirlpool_.token_mint_b {
+                true
+            } else if *in_mint == whirlpool_.token_mint_b && *out_mint == whirlpool_.token_mint_a {
+                false
+            } else {
+                return Err(Error::InvalidTokenMint.into());
+            };
+            let mut tick_arrays_ = Vec::<TickArray>::with_capacity(Self::TICK_ARRAYS_QUANTITY);
+            '_a: for (index, tick_array) in [
+                tick_array_0,
+                tick_array_1,
+                tick_array_2,
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                // The same tick array can be passed several times, and the not initialized tick array can not be used for calculation.
+                if [
+                    tick_array_0,
+                    tick_array_1,
+                ][..index]
+                    .iter()
+                    .any(|tick_array_| tick_array_.key == tick_array.key)
+                    || *tick_array.owner == solana_program::system_program::ID
+                {
+                    continue '_a;
+                }
+                if tick_array.owner != whirlpool_program.key {
+                    return Err(Error::InvalidAmmAccountOwner.into());
+                }
+                let tick_array_data = tick_array.data.borrow();
+                if tick_array_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR {
+                    return Err(Error::InvalidAccountData.into());
+                }
+                let tick_array_ = if tick_array_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] == TickArray::DISCRIMINATOR {
+                    TickArray::unpack(&tick_array_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..])
+                } else if tick_array_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] == TickArray::DYNAMIC_DISCRIMINATOR {
+                    TickArray::unpack_dynamic(&tick_array_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..])
+                } else {
+                    None
+                }
+                .ok_or(Error::InvalidAccountData)?;
+                if tick_array_.whirlpool != *whirlpool.key {
+                    return Err(Error::InvalidAccountData.into());
+                }
+                tick_arrays_.push(tick_array_);
+            }
+            // This is a slightly modified selective code from https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/util/sparse_swap.rs
+            // The tick arrays are ordered in the swap direction, and the sequence ends on the first missing tick array.
+            let ticks_in_array = TICK_ARRAY_SIZE * whirlpool_.tick_spacing as i32;
+            let shifted_tick_current_index = if a_to_b {
+                whirlpool_.tick_current_index
+            } else {
+                whirlpool_.tick_current_index + whirlpool_.tick_spacing as i32
+            };
+            let start_tick_index = shifted_tick_current_index.div_euclid(ticks_in_array) * ticks_in_array;
+            let mut tick_arrays = Vec::<TickArray>::with_capacity(Self::TICK_ARRAYS_QUANTITY);
+            '_a: for index in 0..Self::TICK_ARRAYS_QUANTITY as i32 {
+                let expected_start_tick_index = if a_to_b {
+                    start_tick_index - index * ticks_in_array
+                } else {
+                    start_tick_index + index * ticks_in_array
+                };
+                match tick_arrays_.iter().position(|tick_array| tick_array.start_tick_index == expected_start_tick_index) {
+                    Some(position) => tick_arrays.push(tick_arrays_.swap_remove(position)),
+                    None => break '_a,
+                }
+            }
+            if tick_arrays.is_empty() {
+                return Ok(None);
+            }
+            PoolState {
+                pool: *whirlpool.key,
+                fee_rate: whirlpool_.fee_rate,
+                tick_spacing: whirlpool_.tick_spacing,
+                liquidity: whirlpool_.liquidity,
+                sqrt_price: whirlpool_.sqrt_price,
+                tick_current_index: whirlpool_.tick_current_index,
+                a_to_b,
+                tick_arrays,
+            }
+        };
+        Ok(Some(pool_state))
+    }
+    // Returns the index of the tick array and the next initialized tick index, or the boundary tick index
+    // of the last tick array. Returns None if the tick index is out of the tick arrays sequence.
+    //
+    // This is a slightly modified selective code from https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/state/tick.rs
+    fn get_next_initialized_tick_index(pool_state: &PoolState, tick_index: i32, start_array_index: usize) -> Option<(usize, i32)> {
+        let mut search_index = tick_index;
+        let mut array_index = start_array_index;
+        loop {
+            let tick_array = pool_state.tick_arrays.get(array_index)?;
+            // The crossed boundary tick of the tick array belongs to the search range of the next one.
+            if !tick_array.in_search_range(search_index, pool_state.tick_spacing, !pool_state.a_to_b) {
+                array_index += 1;
+                continue;
+            }
+            if let Some(next_tick_index) = tick_array.get_next_init_tick_index(search_index, pool_state.tick_spacing, pool_state.a_to_b) {
+                return Some((array_index, next_tick_index));
+            }
+            let ticks_in_array = TICK_ARRAY_SIZE * pool_state.tick_spacing as i32;
+            // If we are at the last tick array, return the boundary tick index.
+            if array_index + 1 == pool_state.tick_arrays.len() {
+                if pool_state.a_to_b {
+                    return Some((array_index, tick_array.start_tick_index));
+                }
+                return Some((array_index, tick_array.start_tick_index + ticks_in_array - pool_state.tick_spacing as i32));
+            }
+            search_index = if pool_state.a_to_b {
+                tick_array.start_tick_index - 1
+            } else {
+                tick_array.start_tick_index + ticks_in_array - 1
+            };
+            array_index += 1;
+        }
+    }
+    // Returns the amount in, the amount out and the trade fee. Returns None if the passed tick arrays
+    // do not cover the amount.
+    //
+    // This is a slightly modified selective code from https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/manager/swap_manager.rs
+    fn calculate_swap(pool_state: &PoolState, amount: u64, amount_specified_is_input: bool) -> Result<Option<(u64, u64, u64)>, ProgramError> {
+        let a_to_b = pool_state.a_to_b;
+        // The default limit of the Orca Whirlpool program.
+        let sqrt_price_limit = if a_to_b {
+            MIN_SQRT_PRICE_X64
+        } else {
+            MAX_SQRT_PRICE_X64
+        };
+        let mut amount_remaining = amount;
+        let mut amount_calculated: u64 = 0;
+        let mut fee_amount: u64 = 0;
+        let mut curr_sqrt_price = pool_state.sqrt_price;
+        let mut curr_tick_index = pool_state.tick_current_index;
+        let mut curr_liquidity = pool_state.liquidity;
+        let mut curr_array_index: usize = 0;
+        while amount_remaining > 0 && sqrt_price_limit != curr_sqrt_price {
+            let (next_array_index, next_tick_index) = match Self::get_next_initialized_tick_index(pool_state, curr_tick_index, curr_array_index) {
+                Some(next_) => next_,
+                None => return Ok(None),
+            };
+            let next_tick_sqrt_price = crate::extern_source::orca_whirlpool::sqrt_price_from_tick_index(next_tick_index).ok_or(ProgramError::ArithmeticOverflow)?;
+            let sqrt_price_target = if a_to_b {
+                next_tick_sqrt_price.max(sqrt_price_limit)
+            } else {
+                next_tick_sqrt_price.min(sqrt_price_limit)
+            };
+            let swap_computation = crate::extern_source::orca_whirlpool::compute_swap(
+                amount_remaining,
+                pool_state.fee_rate,
+                curr_liquidity,
+                curr_sqrt_price,
+                sqrt_price_target,
+                amount_specified_is_input,
+                a_to_b,
+            )
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+            if amount_specified_is_input {
+                amount_remaining = amount_remaining
+                    .checked_sub(swap_computation.amount_in.checked_add(swap_computation.fee_amount).ok_or(ProgramError::ArithmeticOverflow)?)
+                    .ok_or(ProgramError::ArithmeticOverflow)?;
+                amount_calculated = amount_calculated.checked_add(swap_computation.amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+            } else {
+                amount_remaining = amount_remaining.checked_sub(swap_computation.amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+                amount_calculated = amount_calculated
+                    .checked_add(swap_computation.amount_in.checked_add(swap_computation.fee_amount).ok_or(ProgramError::ArithmeticOverflow)?)
+                    .ok_or(ProgramError::ArithmeticOverflow)?;
+            }
+            fee_amount = fee_amount.checked_add(swap_computation.fee_amount).ok_or(ProgramError::ArithmeticOverflow)?;
+            if swap_computation.next_price == next_tick_sqrt_price {
+                // The tick is crossed.
+                let next_tick = pool_state.tick_arrays[next_array_index].get_tick(next_tick_index, pool_state.tick_spacing).ok_or(Error::InvalidAccountData)?;
+                if next_tick.initialized {
+                    let liquidity_net = if a_to_b {
+                        next_tick.liquidity_net.checked_neg().ok_or(ProgramError::ArithmeticOverflow)?
+                    } else {
+                        next_tick.liquidity_net
+                    };
+                    curr_liquidity = curr_liquidity.checked_add_signed(liquidity_net).ok_or(ProgramError::ArithmeticOverflow)?;
+                }
+                curr_tick_index = if a_to_b {
+                    next_tick_index - 1
+                } else {
+                    next_tick_index
+                };
+            } else if swap_computation.next_price != curr_sqrt_price {
+                // The price stopped between the ticks, so the amount is exhausted or the price limit is reached.
+                break;
+            }
+            curr_sqrt_price = swap_computation.next_price;
+            curr_array_index = next_array_index;
+        }
+        if amount_remaining != 0 || amount_calculated == 0 {
+            return Ok(None);
+        }
+        if amount_specified_is_input {
+            Ok(Some((amount, amount_calculated, fee_amount)))
+        } else {
+            Ok(Some((amount_calculated, amount, fee_amount)))
+        }
+    }
+    fn invoke_swap<'a, 'b, 'c>(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        amount_specified_is_input: bool,
+        amount: u64,
+        other_amount_threshold: u64,
+    ) -> Result<(), ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let whirlpool_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let whirlpool = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_vault_a = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_vault_b = solana_program::account_info::next_account_info(account_info_iter)?;
+        let tick_array_0 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let tick_array_1 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let tick_array_2 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let oracle = solana_program::account_info::next_account_info(account_info_iter)?;
+        let (token_owner_account_a, token_owner_account_b, a_to_b) = {
+            let whirlpool_data = whirlpool.data.borrow();
+            let whirlpool_ = Box::new(<Whirlpool as borsh::de::BorshDeserialize>::deserialize(
+                &mut &whirlpool_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..],
+            )?);
+            let in_mint = if base_data.is_from_quote_to_token {
+                base_data.quote_mint
+            } else {
+                base_data.token_mint
+            };
+            if *in_mint == whirlpool_.token_mint_a {
+                if base_data.is_from_quote_to_token {
+                    (base_data.quote_token_account, base_data.token_account, true)
+                } else {
+                    (base_data.token_account, base_data.quote_token_account, true)
+                }
+            } else if base_data.is_from_quote_to_token {
+                (base_data.token_account, base_data.quote_token_account, false)
+            } else {
+                (base_data.quote_token_account, base_data.token_account, false)
+            }
+        };
+        let instruction = Self::create_swap_instruction(
+            whirlpool_program.key,
+            token_program.key,
+            base_data.self_authority.key,
+            whirlpool.key,
+            token_owner_account_a.key,
+            token_vault_a.key,
+            token_owner_account_b.key,
+            token_vault_b.key,
+            tick_array_0.key,
+            tick_array_1.key,
+            tick_array_2.key,
+            oracle.key,
+            amount,
+            other_amount_threshold,
+            // The default limit of the Orca Whirlpool program.
+            0,
+            amount_specified_is_input,
+            a_to_b,
+        );
+        solana_program::program::invoke_signed(
+            &instruction,
+            [
+                token_program.clone(),
+                base_data.self_authority.clone(),
+                whirlpool.clone(),
+                token_owner_account_a.clone(),
+                token_vault_a.clone(),
+                token_owner_account_b.clone(),
+                token_vault_b.clone(),
+                tick_array_0.clone(),
+                tick_array_1.clone(),
+                tick_array_2.clone(),
+                oracle.clone(),
+            ]
+            .as_slice(),
+            [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
+        )
+    }
+}
+impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for OrcaWhirlpool {
+    fn get_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_max_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_pool_account_index(&'a self) -> usize {
+        2
+    }
+    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        if base_data.amount_in == 0 {
+            return Ok(None);
+        }
+        let (_, amount_out, trade_fee) = match Self::calculate_swap(&pool_state, base_data.amount_in, true)? {
+            Some(swap_) => swap_,
+            None => return Ok(None),
+        };
+        let (in_reserve, out_reserve) = get_virtual_reserves(pool_state.sqrt_price, pool_state.a_to_b);
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.pool,
+            amount_in: base_data.amount_in,
+            amount_in_fee: trade_fee,
+            amount_out,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, true, base_data.amount_in, base_data.min_amount_out)
+    }
+    fn do_swap_calculation_base_out(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        if base_data.min_amount_out == 0 {
+            return Ok(None);
+        }
+        let (amount_in, _, trade_fee) = match Self::calculate_swap(&pool_state, base_data.min_amount_out, false)? {
+            Some(swap_) => swap_,
+            None => return Ok(None),
+        };
+        let (in_reserve, out_reserve) = get_virtual_reserves(pool_state.sqrt_price, pool_state.a_to_b);
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.pool,
+            amount_in,
+            amount_in_fee: trade_fee,
+            amount_out: base_data.min_amount_out,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    fn do_swap_base_out(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        _swap_calculation_result: &SwapCalculationResult,
+    ) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, false, base_data.min_amount_out, base_data.amount_in)
+    }
+}
@@ -0,0 +1,445 @@
+use {
+    super::{
+        BaseData,
+        Dex,
+        SwapCalculationResult,
+    },
+    crate::{
+        PHOENIX_PROGRAM_ID,
+        error::Error,
+        extern_source::phoenix::{
+            FifoMarket,
+            MarketHeader,
+        },
+        state::PdaResolver,
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        instruction::{
+            AccountMeta,
+            Instruction,
+        },
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+// The immediate-or-cancel taker order matches no more than this quantity of the resting orders.
+// Limits the simulation as well, so the orders are copied onto the heap within the limit.
+const MATCH_LIMIT: u64 = 32;
+pub struct Phoenix;
+struct PoolState {
+    market: Pubkey,
+    market_: FifoMarket,
+    // The taker buys the base token for the quote token, so the asks are matched.
+    is_bid: bool,
+    base_lot_size: u64,
+    quote_lot_size: u64,
+    // The price in ticks and the base lots of the not expired resting orders of the matched side, the best price first.
+    orders: Vec<(u64, u64)>,
+}
+impl PoolState {
+    // Returns the fee in the input token and the amount out.
+    //
+    // This is a slightly modified selective code from https://github.com/Ellipsis-Labs/phoenix-v1/blob/master/src/state/markets/fifo.rs
+    fn calculate_amount_out(&self, amount_in: u64) -> Option<(u64, u64)> {
+        if self.is_bid {
+            let quote_lots_budget = amount_in / self.quote_lot_size;
+            // The fee is charged above the matched quote lots and is rounded up by 1 at most.
+            let mut quote_lots_remaining =
+                u64::try_from((quote_lots_budget.saturating_sub(1) as u128).checked_mul(10_000)?.checked_div(self.market_.taker_fee_bps.checked_add(10_000)? as u128)?).ok()?;
+            let mut base_lots = 0u64;
+            let mut quote_lots = 0u64;
+            '_a: for (price_in_ticks, num_base_lots) in self.orders.iter() {
+                let base_lots_ = (*num_base_lots).min(self.market_.get_base_lots(*price_in_ticks, quote_lots_remaining)?);
+                if base_lots_ == 0 {
+                    break '_a;
+                }
+                let quote_lots_ = self.market_.get_quote_lots(*price_in_ticks, base_lots_)?;
+                quote_lots_remaining = quote_lots_remaining.checked_sub(quote_lots_)?;
+                base_lots = base_lots.checked_add(base_lots_)?;
+                quote_lots = quote_lots.checked_add(quote_lots_)?;
+            }
+            let fee = self.market_.compute_fee(quote_lots)?.checked_mul(self.quote_lot_size)?;
+            Some((fee, base_lots.checked_mul(self.base_lot_size)?))
+        } else {
+            let mut base_lots_remaining = amount_in / self.base_lot_size;
+            let mut quote_lots = 0u64;
+            '_a: for (price_in_ticks, num_base_lots) in self.orders.iter() {
+                if base_lots_remaining == 0 {
+                    break '_a;
+                }
+                let base_lots_ = (*num_base_lots).min(base_lots_remaining);
+                quote_lots = quote_lots.checked_add(self.market_.get_quote_lots(*price_in_ticks, base_lots_)?)?;
+                base_lots_remaining -= base_lots_;
+            }
+            if quote_lots == 0 {
+                return Some((0, 0));
+            }
+            let fee_quote_lots = self.market_.compute_fee(quote_lots)?;
+            // The fee is charged in the output token, so its input token equivalent is taken.
+            let fee = u64::try_from((fee_quote_lots as u128).checked_mul(amount_in as u128)?.checked_div(quote_lots as u128)?).ok()?;
+            Some((fee, quote_lots.saturating_sub(fee_quote_lots).checked_mul(self.quote_lot_size)?))
+        }
+    }
+    // Returns the fee in the input token and the amount in.
+    fn calculate_amount_in(&self, amount_out: u64) -> Option<(u64, u64)> {
+        if self.is_bid {
+            let mut base_lots_remaining = amount_out.checked_add(self.base_lot_size - 1)? / self.base_lot_size;
+            let mut quote_lots = 0u64;
+            '_a: for (price_in_ticks, num_base_lots) in self.orders.iter() {
+                if base_lots_remaining == 0 {
+                    break '_a;
+                }
+                let base_lots_ = (*num_base_lots).min(base_lots_remaining);
+                quote_lots = quote_lots.checked_add(self.market_.get_quote_lots(*price_in_ticks, base_lots_)?)?;
+                base_lots_remaining -= base_lots_;
+            }
+            if base_lots_remaining != 0 {
+                return None;
+            }
+            let fee_quote_lots = self.market_.compute_fee(quote_lots)?;
+            Some((
+                fee_quote_lots.checked_mul(self.quote_lot_size)?,
+                quote_lots.checked_add(fee_quote_lots)?.checked_mul(self.quote_lot_size)?,
+            ))
+        } else {
+            let target_quote_lots = amount_out.checked_add(self.quote_lot_size - 1)? / self.quote_lot_size;
+            // quote_lots * (1 - taker_fee_bps / 10_000) = target_quote_lots
+            let mut quote_lots_needed = u64::try_from(
+                (target_quote_lots as u128)
+                    .checked_mul(10_000)?
+                    .checked_add(10_000u64.checked_sub(self.market_.taker_fee_bps)?.checked_sub(1)? as u128)?
+                    .checked_div(10_000u64.checked_sub(self.market_.taker_fee_bps)? as u128)?,
+            )
+            .ok()?;
+            // The fee is rounded up by 1 at most.
+            if quote_lots_needed.checked_sub(self.market_.compute_fee(quote_lots_needed)?)? < target_quote_lots {
+                quote_lots_needed = quote_lots_needed.checked_add(1)?;
+            }
+            let mut base_lots = 0u64;
+            let mut quote_lots = 0u64;
+            '_a: for (price_in_ticks, num_base_lots) in self.orders.iter() {
+                if quote_lots >= quote_lots_needed {
+                    break '_a;
+                }
+                let base_lots_ = (*num_base_lots).min(self.market_.get_base_lots_rounding_up(*price_in_ticks, quote_lots_needed - quote_lots)?);
+                base_lots = base_lots.checked_add(base_lots_)?;
+                quote_lots = quote_lots.checked_add(self.market_.get_quote_lots(*price_in_ticks, base_lots_)?)?;
+            }
+            if quote_lots < quote_lots_needed {
+                return None;
+            }
+            let fee_quote_lots = self.market_.compute_fee(quote_lots)?;
+            let amount_in = base_lots.checked_mul(self.base_lot_size)?;
+            // The fee is charged in the output token, so its input token equivalent is taken.
+            let fee = u64::try_from((fee_quote_lots as u128).checked_mul(amount_in as u128)?.checked_div(quote_lots as u128)?).ok()?;
+            Some((fee, amount_in))
+        }
+    }
+    // The quote and the base amounts of the base unit at the best price define the spot price.
+    // Are returned in the order of the swap direction.
+    fn get_reserves(&self) -> Option<(u64, u64)> {
+        let (price_in_ticks, _) = self.orders.first()?;
+        let base_amount = self.market_.base_lots_per_base_unit.checked_mul(self.base_lot_size)?;
+        let quote_amount = self.market_.get_quote_lots(*price_in_ticks, self.market_.base_lots_per_base_unit)?.checked_mul(self.quote_lot_size)?;
+        if self.is_bid {
+            Some((quote_amount, base_amount))
+        } else {
+            Some((base_amount, quote_amount))
+        }
+    }
+}
+impl Phoenix {
+    const SWAP_ACCOUNTS_QUANTITY: usize = 6;
+    // https://github.com/Ellipsis-Labs/phoenix-v1/blob/master/src/program/instruction.rs
+    //
+    // The 'Swap' instruction with the 'OrderPacket::ImmediateOrCancel' at the market price.
+    pub fn create_swap_instruction(
+        program_id: &Pubkey,
+        log_authority: &Pubkey,
+        market: &Pubkey,
+        trader: &Pubkey,
+        base_account: &Pubkey,
+        quote_account: &Pubkey,
+        base_vault: &Pubkey,
+        quote_vault: &Pubkey,
+        token_program: &Pubkey,
+        is_bid: bool,
+        num_base_lots: u64,
+        num_quote_lots: u64,
+        min_base_lots_to_fill: u64,
+        min_quote_lots_to_fill: u64,
+    ) -> Instruction {
+        const SWAP_TAG: u8 = 0;
+        const IMMEDIATE_OR_CANCEL_TAG: u8 = 2;
+        const SELF_TRADE_BEHAVIOR_CANCEL_PROVIDE: u8 = 1;
+        let mut data = Vec::<u8>::with_capacity(74);
+        data.push(SWAP_TAG);
+        data.push(IMMEDIATE_OR_CANCEL_TAG);
+        // side
+        data.push(
+            if is_bid {
+                0
+            } else {
+                1
+            },
+        );
+        // price_in_ticks
+        data.push(0);
+        data.extend(num_base_lots.to_le_bytes());
+        data.extend(num_quote_lots.to_le_bytes());
+        data.extend(min_base_lots_to_fill.to_le_bytes());
+        data.extend(min_quote_lots_to_fill.to_le_bytes());
+        data.push(SELF_TRADE_BEHAVIOR_CANCEL_PROVIDE);
+        // match_limit
+        data.push(1);
+        data.extend(MATCH_LIMIT.to_le_bytes());
+        // client_order_id
+        data.extend(0u128.to_le_bytes());
+        // use_only_deposited_funds, last_valid_slot and last_valid_unix_timestamp_in_seconds
+        data.extend([
+            0,
+            0,
+            0,
+        ]);
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*program_id, false),
+                AccountMeta::new_readonly(*log_authority, false),
+                AccountMeta::new(*market, false),
+                AccountMeta::new_readonly(*trader, true),
+                AccountMeta::new(*base_account, false),
+                AccountMeta::new(*quote_account, false),
+                AccountMeta::new(*base_vault, false),
+                AccountMeta::new(*quote_vault, false),
+                AccountMeta::new_readonly(*token_program, false),
+            ],
+            data,
+        }
+    }
+    // Returns None if the pool is not available for swap.
+    fn load_pool_state<'a, 'b, 'c>(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<PoolState>, ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let phoenix_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _log_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let market = solana_program::account_info::next_account_info(account_info_iter)?;
+        let base_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        if *phoenix_program.key != PHOENIX_PROGRAM_ID {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if base_data.with_checks && (!market.is_writable || !base_vault.is_writable || !quote_vault.is_writable) {
+            return Err(Error::InvalidAccountConfigurationFlags.into());
+        }
+        let pool_state = {
+            if market.owner != phoenix_program.key {
+                return Err(Error::InvalidAmmAccountOwner.into());
+            }
+            let market_data = market.data.borrow();
+            let market_header = MarketHeader::unpack(&market_data).ok_or(Error::InvalidAccountData)?;
+            let (market_, bids, asks) = FifoMarket::unpack(&market_data[MarketHeader::LENGTH..], &market_header).ok_or(Error::InvalidAccountData)?;
+            if *base_vault.key != market_header.base_vault || *quote_vault.key != market_header.quote_vault || *token_program.key != spl_token::ID {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            let (in_mint, out_mint) = if base_data.is_from_quote_to_token {
+                (base_data.quote_mint, base_data.token_mint)
+            } else {
+                (base_data.token_mint, base_data.quote_mint)
+            };
+            let is_bid = if *in_mint == market_header.quote_mint && *out_mint == market_header.base_mint {
+                true
+            } else if *in_mint == market_header.base_mint && *out_mint == market_header.quote_mint {
+                false
+            } else {
+                return Err(Error::InvalidTokenMint.into());
+            };
+            if market_header.status != MarketHeader::STATUS_ACTIVE
+                || market_header.base_lot_size == 0
+                || market_header.quote_lot_size == 0
+                || market_.base_lots_per_base_unit == 0
+                || market_.tick_size_in_quote_lots_per_base_unit == 0
+            {
+                return Ok(None);
+            }
+            let clock = Clock::get()?;
+            let current_unix_timestamp = u64::try_from(clock.unix_timestamp).map_err(|_| ProgramError::ArithmeticOverflow)?;
+            // The expired orders are removed by the matching, so are skipped.
+            let order_tree = if is_bid {
+                asks
+            } else {
+                bids
+            };
+            let mut orders = Vec::<(u64, u64)>::with_capacity(MATCH_LIMIT as usize);
+            '_a: for resting_order in order_tree.iter() {
+                if orders.len() == MATCH_LIMIT as usize {
+                    break '_a;
+                }
+                if resting_order.is_expired(clock.slot, current_unix_timestamp) || resting_order.num_base_lots == 0 || resting_order.price_in_ticks == 0 {
+                    continue '_a;
+                }
+                orders.push((resting_order.price_in_ticks, resting_order.num_base_lots));
+            }
+            if orders.is_empty() {
+                return Ok(None);
+            }
+            PoolState {
+                market: *market.key,
+                market_,
+                is_bid,
+                base_lot_size: market_header.base_lot_size,
+                quote_lot_size: market_header.quote_lot_size,
+                orders,
+            }
+        };
+        Ok(Some(pool_state))
+    }
+    fn invoke_swap<'a, 'b, 'c>(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        is_bid: bool,
+        num_base_lots: u64,
+        num_quote_lots: u64,
+        min_base_lots_to_fill: u64,
+        min_quote_lots_to_fill: u64,
+    ) -> Result<(), ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let phoenix_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let log_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let market = solana_program::account_info::next_account_info(account_info_iter)?;
+        let base_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let (in_token_account, out_token_account) = if base_data.is_from_quote_to_token {
+            (base_data.quote_token_account, base_data.token_account)
+        } else {
+            (base_data.token_account, base_data.quote_token_account)
+        };
+        let (base_account, quote_account) = if is_bid {
+            (out_token_account, in_token_account)
+        } else {
+            (in_token_account, out_token_account)
+        };
+        let instruction = Self::create_swap_instruction(
+            phoenix_program.key,
+            log_authority.key,
+            market.key,
+            base_data.self_authority.key,
+            base_account.key,
+            quote_account.key,
+            base_vault.key,
+            quote_vault.key,
+            token_program.key,
+            is_bid,
+            num_base_lots,
+            num_quote_lots,
+            min_base_lots_to_fill,
+            min_quote_lots_to_fill,
+        );
+        solana_program::program::invoke_signed(
+            &instruction,
+            vec![
+                phoenix_program.clone(),
+                log_authority.clone(),
+                market.clone(),
+                base_data.self_authority.clone(),
+                base_account.clone(),
+                quote_account.clone(),
+                base_vault.clone(),
+                quote_vault.clone(),
+                token_program.clone(),
+            ]
+            .as_slice(),
+            [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
+        )
+    }
+}
+impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for Phoenix {
+    fn get_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_max_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_pool_account_index(&'a self) -> usize {
+        2
+    }
+    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        let (fee, amount_out) = pool_state.calculate_amount_out(base_data.amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
+        if amount_out == 0 {
+            return Ok(None);
+        }
+        let (in_reserve, out_reserve) = pool_state.get_reserves().ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.market,
+            amount_in: base_data.amount_in,
+            amount_in_fee: fee,
+            amount_out,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<(), ProgramError> {
+        let pool_state = self.load_pool_state(base_data, dex_accounts)?.ok_or(Error::InvalidSwapConditions)?;
+        if pool_state.is_bid {
+            let min_base_lots_to_fill = base_data.min_amount_out.div_ceil(pool_state.base_lot_size);
+            self.invoke_swap(base_data, dex_accounts, true, 0, base_data.amount_in / pool_state.quote_lot_size, min_base_lots_to_fill, 0)
+        } else {
+            let min_quote_lots_to_fill = base_data.min_amount_out.div_ceil(pool_state.quote_lot_size);
+            self.invoke_swap(base_data, dex_accounts, false, base_data.amount_in / pool_state.base_lot_size, 0, 0, min_quote_lots_to_fill)
+        }
+    }
+    fn do_swap_calculation_base_out(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        if base_data.min_amount_out == 0 {
+            return Ok(None);
+        }
+        let (fee, amount_in) = match pool_state.calculate_amount_in(base_data.min_amount_out) {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let (in_reserve, out_reserve) = pool_state.get_reserves().ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.market,
+            amount_in,
+            amount_in_fee: fee,
+            amount_out: base_data.min_amount_out,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    fn do_swap_base_out(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        swap_calculation_result: &SwapCalculationResult,
+    ) -> Result<(), ProgramError> {
+        let pool_state = self.load_pool_state(base_data, dex_accounts)?.ok_or(Error::InvalidSwapConditions)?;
+        if pool_state.is_bid {
+            let base_lots = base_data.min_amount_out.div_ceil(pool_state.base_lot_size);
+            self.invoke_swap(base_data, dex_accounts, true, base_lots, base_data.amount_in / pool_state.quote_lot_size, base_lots, 0)
+        } else {
+            let min_quote_lots_to_fill = base_data.min_amount_out.div_ceil(pool_state.quote_lot_size);
+            self.invoke_swap(
+                base_data,
+                dex_accounts,
+                false,
+                swap_calculation_result.amount_in / pool_state.base_lot_size,
+                0,
+                0,
+                min_quote_lots_to_fill,
+            )
+        }
+    }
+}
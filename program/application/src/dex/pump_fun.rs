@@ -0,0 +1,646 @@
+use {
+    super::{
+        BaseData,
+        Dex,
+        SwapCalculationResult,
+    },
+    crate::{
+        PUMP_FUN_PROGRAM_ID,
+        error::Error,
+        extern_source::pump_fun::{
+            BondingCurve,
+            Global,
+        },
+        state::PdaResolver,
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        instruction::{
+            AccountMeta,
+            Instruction,
+        },
+        program_error::ProgramError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        rent::Rent,
+        sysvar::Sysvar,
+    },
+    spl_token::state::Account,
+};
+const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
+const BONDING_CURVE_SEED: &str = "bonding-curve";
+// The bonding curve trades the native Sol, so the WSol is unwrapped through the temporary WSol token account
+// to the 'self_authority' before the buy, and the received Sol is wrapped back to the WSol token account after the swap.
+pub struct PumpFun;
+struct PoolState {
+    bonding_curve: Pubkey,
+    bonding_curve_: BondingCurve,
+    // Buys the token for the Sol.
+    is_buy: bool,
+    // The protocol and the creator fees are charged in the Sol.
+    fees_basis_points: [u64; 2],
+}
+impl PoolState {
+    fn get_fee(&self, sol_amount: u64) -> Option<u64> {
+        let mut fee: u64 = 0;
+        '_a: for fee_basis_points in self.fees_basis_points.iter() {
+            fee = fee.checked_add(crate::extern_source::pump_fun::compute_fee(sol_amount, *fee_basis_points)?)?;
+        }
+        Some(fee)
+    }
+    fn get_fees_basis_points(&self) -> u64 {
+        self.fees_basis_points.iter().sum()
+    }
+    // Returns the fee in the input token and the amount out.
+    //
+    // This is a slightly modified selective code from https://github.com/pump-fun/pump-sdk/blob/main/src/bondingCurve.ts
+    fn calculate_amount_out(&self, amount_in: u64) -> Option<(u64, u64)> {
+        if self.is_buy {
+            // Each fee is rounded up by 1 at most.
+            let sol_amount_without_fees = (amount_in.checked_sub(self.fees_basis_points.len() as u64)? as u128)
+                .checked_mul(10_000)?
+                .checked_div(self.get_fees_basis_points().checked_add(10_000)? as u128)?;
+            let token_amount = self.bonding_curve_.get_buy_token_amount(u64::try_from(sol_amount_without_fees).ok()?)?;
+            if token_amount == 0 {
+                return Some((0, 0));
+            }
+            let sol_amount = self.bonding_curve_.get_buy_sol_cost(token_amount)?;
+            Some((self.get_fee(sol_amount)?, token_amount))
+        } else {
+            let sol_amount = self.bonding_curve_.get_sell_sol_amount(amount_in)?;
+            if sol_amount == 0 {
+                return Some((0, 0));
+            }
+            let fee = self.get_fee(sol_amount)?;
+            // The fee is charged in the output token, so its input token equivalent is taken.
+            let fee_ = u64::try_from((fee as u128).checked_mul(amount_in as u128)?.checked_div(sol_amount as u128)?).ok()?;
+            Some((fee_, sol_amount.saturating_sub(fee)))
+        }
+    }
+    // Returns the fee in the input token and the amount in.
+    fn calculate_amount_in(&self, amount_out: u64) -> Option<(u64, u64)> {
+        if self.is_buy {
+            if amount_out > self.bonding_curve_.real_token_reserves {
+                return None;
+            }
+            let sol_amount = self.bonding_curve_.get_buy_sol_cost(amount_out)?;
+            let fee = self.get_fee(sol_amount)?;
+            Some((fee, sol_amount.checked_add(fee)?))
+        } else {
+            // sol_amount * (1 - fees_basis_points / 10_000) = amount_out
+            let fees_basis_points = self.get_fees_basis_points();
+            let mut sol_amount = u64::try_from(
+                (amount_out as u128)
+                    .checked_mul(10_000)?
+                    .checked_add(10_000u64.checked_sub(fees_basis_points)?.checked_sub(1)? as u128)?
+                    .checked_div(10_000 - fees_basis_points as u128)?,
+            )
+            .ok()?;
+            // Each fee is rounded up by 1 at most.
+            '_a: for _ in 0..=self.fees_basis_points.len() {
+                if sol_amount.checked_sub(self.get_fee(sol_amount)?)? >= amount_out {
+                    break '_a;
+                }
+                sol_amount = sol_amount.checked_add(1)?;
+            }
+            let fee = self.get_fee(sol_amount)?;
+            if sol_amount.checked_sub(fee)? < amount_out {
+                return None;
+            }
+            let token_amount = self.bonding_curve_.get_sell_token_amount(sol_amount)?;
+            // The fee is charged in the output token, so its input token equivalent is taken.
+            let fee_ = u64::try_from((fee as u128).checked_mul(token_amount as u128)?.checked_div(sol_amount as u128)?).ok()?;
+            Some((fee_, token_amount))
+        }
+    }
+    // Are returned in the order of the swap direction.
+    fn get_reserves(&self) -> (u64, u64) {
+        if self.is_buy {
+            (self.bonding_curve_.virtual_sol_reserves, self.bonding_curve_.virtual_token_reserves)
+        } else {
+            (self.bonding_curve_.virtual_token_reserves, self.bonding_curve_.virtual_sol_reserves)
+        }
+    }
+}
+impl PumpFun {
+    const SWAP_ACCOUNTS_QUANTITY: usize = 18;
+    // https://github.com/pump-fun/pump-public-docs/blob/main/idl/pump.json
+    pub fn create_buy_instruction(
+        program_id: &Pubkey,
+        global: &Pubkey,
+        fee_recipient: &Pubkey,
+        mint: &Pubkey,
+        bonding_curve: &Pubkey,
+        associated_bonding_curve: &Pubkey,
+        associated_user: &Pubkey,
+        user: &Pubkey,
+        system_program: &Pubkey,
+        token_program: &Pubkey,
+        creator_vault: &Pubkey,
+        event_authority: &Pubkey,
+        global_volume_accumulator: &Pubkey,
+        user_volume_accumulator: &Pubkey,
+        fee_config: &Pubkey,
+        fee_program: &Pubkey,
+        amount: u64,
+        max_sol_cost: u64,
+    ) -> Instruction {
+        const DISCRIMINATOR: [u8; 8] = [
+            102,
+            6,
+            61,
+            18,
+            1,
+            218,
+            235,
+            234,
+        ];
+        let mut data = Vec::<u8>::with_capacity(25);
+        data.extend(DISCRIMINATOR);
+        data.extend(amount.to_le_bytes());
+        data.extend(max_sol_cost.to_le_bytes());
+        // The 'track_volume' is false.
+        data.push(0);
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*global, false),
+                AccountMeta::new(*fee_recipient, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(*bonding_curve, false),
+                AccountMeta::new(*associated_bonding_curve, false),
+                AccountMeta::new(*associated_user, false),
+                AccountMeta::new(*user, true),
+                AccountMeta::new_readonly(*system_program, false),
+                AccountMeta::new_readonly(*token_program, false),
+                AccountMeta::new(*creator_vault, false),
+                AccountMeta::new_readonly(*event_authority, false),
+                AccountMeta::new_readonly(*program_id, false),
+                AccountMeta::new(*global_volume_accumulator, false),
+                AccountMeta::new(*user_volume_accumulator, false),
+                AccountMeta::new_readonly(*fee_config, false),
+                AccountMeta::new_readonly(*fee_program, false),
+            ],
+            data,
+        }
+    }
+    pub fn create_sell_instruction(
+        program_id: &Pubkey,
+        global: &Pubkey,
+        fee_recipient: &Pubkey,
+        mint: &Pubkey,
+        bonding_curve: &Pubkey,
+        associated_bonding_curve: &Pubkey,
+        associated_user: &Pubkey,
+        user: &Pubkey,
+        system_program: &Pubkey,
+        creator_vault: &Pubkey,
+        token_program: &Pubkey,
+        event_authority: &Pubkey,
+        fee_config: &Pubkey,
+        fee_program: &Pubkey,
+        amount: u64,
+        min_sol_output: u64,
+    ) -> Instruction {
+        const DISCRIMINATOR: [u8; 8] = [
+            51,
+            230,
+            133,
+            164,
+            1,
+            127,
+            131,
+            173,
+        ];
+        let mut data = Vec::<u8>::with_capacity(24);
+        data.extend(DISCRIMINATOR);
+        data.extend(amount.to_le_bytes());
+        data.extend(min_sol_output.to_le_bytes());
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*global, false),
+                AccountMeta::new(*fee_recipient, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(*bonding_curve, false),
+                AccountMeta::new(*associated_bonding_curve, false),
+                AccountMeta::new(*associated_user, false),
+                AccountMeta::new(*user, true),
+                AccountMeta::new_readonly(*system_program, false),
+                AccountMeta::new(*creator_vault, false),
+                AccountMeta::new_readonly(*token_program, false),
+                AccountMeta::new_readonly(*event_authority, false),
+                AccountMeta::new_readonly(*program_id, false),
+                AccountMeta::new_readonly(*fee_config, false),
+                AccountMeta::new_readonly(*fee_program, false),
+            ],
+            data,
+        }
+    }
+    // Returns None if the pool is not available for swap.
+    fn load_pool_state<'a, 'b, 'c>(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<PoolState>, ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let pump_fun_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let global = solana_program::account_info::next_account_info(account_info_iter)?;
+        let fee_recipient = solana_program::account_info::next_account_info(account_info_iter)?;
+        let mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let bonding_curve = solana_program::account_info::next_account_info(account_info_iter)?;
+        let associated_bonding_curve = solana_program::account_info::next_account_info(account_info_iter)?;
+        let creator_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _event_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let global_volume_accumulator = solana_program::account_info::next_account_info(account_info_iter)?;
+        let user_volume_accumulator = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _fee_config = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _fee_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let system_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let w_sol_token_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let temporary_w_sol_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let rent_payer = solana_program::account_info::next_account_info(account_info_iter)?;
+        let self_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        if *pump_fun_program.key != PUMP_FUN_PROGRAM_ID
+            || *system_program.key != solana_program::system_program::ID
+            || *token_program.key != spl_token::ID
+            || *w_sol_token_mint.key != spl_token::native_mint::ID
+        {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if base_data.with_checks {
+            if !fee_recipient.is_writable
+                || !bonding_curve.is_writable
+                || !associated_bonding_curve.is_writable
+                || !creator_vault.is_writable
+                || !global_volume_accumulator.is_writable
+                || !user_volume_accumulator.is_writable
+                || !temporary_w_sol_token_account.is_writable
+                || !rent_payer.is_writable
+                || !rent_payer.is_signer
+                || !self_authority.is_writable
+            {
+                return Err(Error::InvalidAccountConfigurationFlags.into());
+            }
+            if *temporary_w_sol_token_account.key != base_data.intermediary_.temporary_w_sol_token_account {
+                return Err(Error::IntermediaryInvalidTemporaryWSolTokenAccount.into());
+            }
+            if *self_authority.key != *base_data.self_authority.key {
+                return Err(Error::IntermediaryInvalidAuthority.into());
+            }
+            if *bonding_curve.key
+                != Pubkey::find_program_address(
+                    [
+                        BONDING_CURVE_SEED.as_bytes(),
+                        mint.key.as_ref(),
+                    ]
+                    .as_slice(),
+                    pump_fun_program.key,
+                )
+                .0
+            {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+        }
+        let pool_state = {
+            if global.owner != pump_fun_program.key || bonding_curve.owner != pump_fun_program.key {
+                return Err(Error::InvalidAmmAccountOwner.into());
+            }
+            // The Token-2022 mints are not supported.
+            if *mint.owner != spl_token::ID {
+                return Ok(None);
+            }
+            let global_data = global.data.borrow();
+            if global_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR
+                || global_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] != Global::DISCRIMINATOR
+            {
+                return Err(Error::InvalidAccountData.into());
+            }
+            let global_ = <Global as borsh::de::BorshDeserialize>::deserialize(&mut &global_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..])?;
+            let bonding_curve_data = bonding_curve.data.borrow();
+            if bonding_curve_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR
+                || bonding_curve_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] != BondingCurve::DISCRIMINATOR
+            {
+                return Err(Error::InvalidAccountData.into());
+            }
+            let bonding_curve_ =
+                <BondingCurve as borsh::de::BorshDeserialize>::deserialize(&mut &bonding_curve_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..])?;
+            let (in_mint, out_mint) = if base_data.is_from_quote_to_token {
+                (base_data.quote_mint, base_data.token_mint)
+            } else {
+                (base_data.token_mint, base_data.quote_mint)
+            };
+            let is_buy = if *in_mint == spl_token::native_mint::ID && *out_mint == *mint.key {
+                true
+            } else if *in_mint == *mint.key && *out_mint == spl_token::native_mint::ID {
+                false
+            } else {
+                return Err(Error::InvalidTokenMint.into());
+            };
+            // The completed bonding curve is migrated and does not trade.
+            if !global_.initialized || bonding_curve_.complete || bonding_curve_.virtual_token_reserves == 0 || bonding_curve_.virtual_sol_reserves == 0 {
+                return Ok(None);
+            }
+            // The creator fee is charged only if the creator is set.
+            let creator_fee_basis_points = if bonding_curve_.creator == Pubkey::default() {
+                0
+            } else {
+                global_.creator_fee_basis_points
+            };
+            PoolState {
+                bonding_curve: *bonding_curve.key,
+                bonding_curve_,
+                is_buy,
+                fees_basis_points: [
+                    global_.fee_basis_points,
+                    creator_fee_basis_points,
+                ],
+            }
+        };
+        Ok(Some(pool_state))
+    }
+    // For buy the 'sol_amount' is the maximum Sol cost, for sell it is the minimum Sol output.
+    fn invoke_swap<'a, 'b, 'c>(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        token_amount: u64,
+        sol_amount: u64,
+        is_buy: bool,
+    ) -> Result<(), ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let pump_fun_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let global = solana_program::account_info::next_account_info(account_info_iter)?;
+        let fee_recipient = solana_program::account_info::next_account_info(account_info_iter)?;
+        let mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let bonding_curve = solana_program::account_info::next_account_info(account_info_iter)?;
+        let associated_bonding_curve = solana_program::account_info::next_account_info(account_info_iter)?;
+        let creator_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let event_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let global_volume_accumulator = solana_program::account_info::next_account_info(account_info_iter)?;
+        let user_volume_accumulator = solana_program::account_info::next_account_info(account_info_iter)?;
+        let fee_config = solana_program::account_info::next_account_info(account_info_iter)?;
+        let fee_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let system_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let w_sol_token_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let temporary_w_sol_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let rent_payer = solana_program::account_info::next_account_info(account_info_iter)?;
+        let self_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let (w_sol_token_account, token_account) = if *base_data.quote_mint == spl_token::native_mint::ID {
+            (base_data.quote_token_account, base_data.token_account)
+        } else {
+            (base_data.token_account, base_data.quote_token_account)
+        };
+        let self_authority_pubkey_bump_seed = [base_data.intermediary_.self_authority_pubkey_bump_seed];
+        let self_authority_seeds = PdaResolver::self_authority_get_seeds(base_data.intermediary.key, self_authority_pubkey_bump_seed.as_slice());
+        let self_authority_lamports = self_authority.lamports();
+        if is_buy {
+            let token_account_rent_exemption_balance = Rent::get()?.minimum_balance(<Account as Pack>::LEN);
+            solana_program::program::invoke_signed(
+                &solana_program::system_instruction::create_account(
+                    rent_payer.key,
+                    temporary_w_sol_token_account.key,
+                    token_account_rent_exemption_balance,
+                    <Account as Pack>::LEN as u64,
+                    token_program.key,
+                ),
+                vec![
+                    rent_payer.clone(),
+                    temporary_w_sol_token_account.clone(),
+                ]
+                .as_slice(),
+                [
+                    PdaResolver::temporary_w_sol_token_account_get_seeds(
+                        base_data.intermediary.key,
+                        [base_data.intermediary_.temporary_w_sol_token_account_pubkey_bump_seed].as_slice(),
+                    )
+                    .as_slice(),
+                ]
+                .as_slice(),
+            )?;
+            solana_program::program::invoke(
+                &spl_token::instruction::initialize_account3(token_program.key, temporary_w_sol_token_account.key, w_sol_token_mint.key, self_authority.key)?,
+                vec![
+                    temporary_w_sol_token_account.clone(),
+                    w_sol_token_mint.clone(),
+                ]
+                .as_slice(),
+            )?;
+            solana_program::program::invoke_signed(
+                &spl_token::instruction::transfer(
+                    token_program.key,
+                    w_sol_token_account.key,
+                    temporary_w_sol_token_account.key,
+                    self_authority.key,
+                    [].as_slice(),
+                    sol_amount,
+                )?,
+                vec![
+                    w_sol_token_account.clone(),
+                    temporary_w_sol_token_account.clone(),
+                    self_authority.clone(),
+                ]
+                .as_slice(),
+                [self_authority_seeds.as_slice()].as_slice(),
+            )?;
+            solana_program::program::invoke_signed(
+                &spl_token::instruction::close_account(token_program.key, temporary_w_sol_token_account.key, self_authority.key, self_authority.key, [].as_slice())?,
+                vec![
+                    temporary_w_sol_token_account.clone(),
+                    self_authority.clone(),
+                    self_authority.clone(),
+                ]
+                .as_slice(),
+                [self_authority_seeds.as_slice()].as_slice(),
+            )?;
+            solana_program::program::invoke_signed(
+                &Self::create_buy_instruction(
+                    pump_fun_program.key,
+                    global.key,
+                    fee_recipient.key,
+                    mint.key,
+                    bonding_curve.key,
+                    associated_bonding_curve.key,
+                    token_account.key,
+                    self_authority.key,
+                    system_program.key,
+                    token_program.key,
+                    creator_vault.key,
+                    event_authority.key,
+                    global_volume_accumulator.key,
+                    user_volume_accumulator.key,
+                    fee_config.key,
+                    fee_program.key,
+                    token_amount,
+                    sol_amount,
+                ),
+                vec![
+                    global.clone(),
+                    fee_recipient.clone(),
+                    mint.clone(),
+                    bonding_curve.clone(),
+                    associated_bonding_curve.clone(),
+                    token_account.clone(),
+                    self_authority.clone(),
+                    system_program.clone(),
+                    token_program.clone(),
+                    creator_vault.clone(),
+                    event_authority.clone(),
+                    pump_fun_program.clone(),
+                    global_volume_accumulator.clone(),
+                    user_volume_accumulator.clone(),
+                    fee_config.clone(),
+                    fee_program.clone(),
+                ]
+                .as_slice(),
+                [self_authority_seeds.as_slice()].as_slice(),
+            )?;
+            // The temporary WSol token account rent is returned.
+            solana_program::program::invoke_signed(
+                &solana_program::system_instruction::transfer(self_authority.key, rent_payer.key, token_account_rent_exemption_balance),
+                vec![
+                    self_authority.clone(),
+                    rent_payer.clone(),
+                ]
+                .as_slice(),
+                [self_authority_seeds.as_slice()].as_slice(),
+            )?;
+        } else {
+            solana_program::program::invoke_signed(
+                &Self::create_sell_instruction(
+                    pump_fun_program.key,
+                    global.key,
+                    fee_recipient.key,
+                    mint.key,
+                    bonding_curve.key,
+                    associated_bonding_curve.key,
+                    token_account.key,
+                    self_authority.key,
+                    system_program.key,
+                    creator_vault.key,
+                    token_program.key,
+                    event_authority.key,
+                    fee_config.key,
+                    fee_program.key,
+                    token_amount,
+                    sol_amount,
+                ),
+                vec![
+                    global.clone(),
+                    fee_recipient.clone(),
+                    mint.clone(),
+                    bonding_curve.clone(),
+                    associated_bonding_curve.clone(),
+                    token_account.clone(),
+                    self_authority.clone(),
+                    system_program.clone(),
+                    creator_vault.clone(),
+                    token_program.clone(),
+                    event_authority.clone(),
+                    pump_fun_program.clone(),
+                    fee_config.clone(),
+                    fee_program.clone(),
+                ]
+                .as_slice(),
+                [self_authority_seeds.as_slice()].as_slice(),
+            )?;
+        }
+        // The unspent or the received Sol is wrapped back.
+        let lamports = self_authority.lamports().checked_sub(self_authority_lamports).ok_or(ProgramError::ArithmeticOverflow)?;
+        if lamports > 0 {
+            solana_program::program::invoke_signed(
+                &solana_program::system_instruction::transfer(self_authority.key, w_sol_token_account.key, lamports),
+                vec![
+                    self_authority.clone(),
+                    w_sol_token_account.clone(),
+                ]
+                .as_slice(),
+                [self_authority_seeds.as_slice()].as_slice(),
+            )?;
+            solana_program::program::invoke(
+                &spl_token::instruction::sync_native(token_program.key, w_sol_token_account.key)?,
+                vec![
+                    w_sol_token_account.clone(),
+                ]
+                .as_slice(),
+            )?;
+        }
+        Ok(())
+    }
+}
+impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for PumpFun {
+    fn get_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_max_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_pool_account_index(&'a self) -> usize {
+        4
+    }
+    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        let (fee, amount_out) = pool_state.calculate_amount_out(base_data.amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
+        if amount_out == 0 {
+            return Ok(None);
+        }
+        let (in_reserve, out_reserve) = pool_state.get_reserves();
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.bonding_curve,
+            amount_in: base_data.amount_in,
+            amount_in_fee: fee,
+            amount_out,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    // The buy is done for the calculated token amount with the amount in as the maximum Sol cost.
+    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<(), ProgramError> {
+        let pool_state = self.load_pool_state(base_data, dex_accounts)?.ok_or(Error::InvalidSwapConditions)?;
+        if pool_state.is_buy {
+            let (_, token_amount) = pool_state.calculate_amount_out(base_data.amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
+            if token_amount == 0 || token_amount < base_data.min_amount_out {
+                return Err(Error::InvalidSwapConditions.into());
+            }
+            self.invoke_swap(base_data, dex_accounts, token_amount, base_data.amount_in, true)
+        } else {
+            self.invoke_swap(base_data, dex_accounts, base_data.amount_in, base_data.min_amount_out, false)
+        }
+    }
+    fn do_swap_calculation_base_out(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        if base_data.min_amount_out == 0 {
+            return Ok(None);
+        }
+        let (fee, amount_in) = match pool_state.calculate_amount_in(base_data.min_amount_out) {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let (in_reserve, out_reserve) = pool_state.get_reserves();
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.bonding_curve,
+            amount_in,
+            amount_in_fee: fee,
+            amount_out: base_data.min_amount_out,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    fn do_swap_base_out(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        swap_calculation_result: &SwapCalculationResult,
+    ) -> Result<(), ProgramError> {
+        let pool_state = self.load_pool_state(base_data, dex_accounts)?.ok_or(Error::InvalidSwapConditions)?;
+        if pool_state.is_buy {
+            self.invoke_swap(base_data, dex_accounts, base_data.min_amount_out, base_data.amount_in, true)
+        } else {
+            self.invoke_swap(base_data, dex_accounts, swap_calculation_result.amount_in, base_data.min_amount_out, false)
+        }
+    }
+}
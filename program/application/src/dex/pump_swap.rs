@@ -0,0 +1,493 @@
+use {
+    super::{
+        BaseData,
+        Dex,
+        SwapCalculationResult,
+        get_transfer_fee_config,
+    },
+    crate::{
+        PUMP_SWAP_PROGRAM_ID,
+        error::Error,
+        extern_source::pump_swap::{
+            GlobalConfig,
+            Pool,
+        },
+        state::PdaResolver,
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        instruction::{
+            AccountMeta,
+            Instruction,
+        },
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
+pub struct PumpSwap;
+struct PoolState {
+    pool: Pubkey,
+    // Buys the base token for the quote token.
+    is_buy: bool,
+    base_reserve: u64,
+    quote_reserve: u64,
+    // The LP, the protocol and the coin creator fees are charged in the quote token.
+    fees_basis_points: [u64; 3],
+}
+impl PoolState {
+    fn get_fee(&self, quote_amount: u64) -> Option<u64> {
+        let mut fee: u64 = 0;
+        '_a: for fee_basis_points in self.fees_basis_points.iter() {
+            fee = fee.checked_add(crate::extern_source::pump_swap::compute_fee(quote_amount, *fee_basis_points)?)?;
+        }
+        Some(fee)
+    }
+    fn get_fees_basis_points(&self) -> u64 {
+        self.fees_basis_points.iter().sum()
+    }
+    // Returns the fee in the input token and the amount out.
+    //
+    // This is a slightly modified selective code from https://github.com/pump-fun/pump-swap-sdk/blob/main/src/sdk
+    fn calculate_amount_out(&self, amount_in: u64) -> Option<(u64, u64)> {
+        if self.is_buy {
+            // Each fee is rounded up by 1 at most.
+            let quote_amount_without_fees = (amount_in.checked_sub(self.fees_basis_points.len() as u64)? as u128)
+                .checked_mul(10_000)?
+                .checked_div(self.get_fees_basis_points().checked_add(10_000)? as u128)?;
+            let base_amount = crate::extern_source::pump_swap::get_buy_base_amount(u64::try_from(quote_amount_without_fees).ok()?, self.base_reserve, self.quote_reserve)?;
+            let quote_amount = crate::extern_source::pump_swap::get_buy_quote_amount(base_amount, self.base_reserve, self.quote_reserve)?;
+            Some((self.get_fee(quote_amount)?, base_amount))
+        } else {
+            let quote_amount = crate::extern_source::pump_swap::get_sell_quote_amount(amount_in, self.base_reserve, self.quote_reserve)?;
+            if quote_amount == 0 {
+                return Some((0, 0));
+            }
+            let fee = self.get_fee(quote_amount)?;
+            // The fee is charged in the output token, so its input token equivalent is taken.
+            let fee_ = u64::try_from((fee as u128).checked_mul(amount_in as u128)?.checked_div(quote_amount as u128)?).ok()?;
+            Some((fee_, quote_amount.saturating_sub(fee)))
+        }
+    }
+    // Returns the fee in the input token and the amount in.
+    fn calculate_amount_in(&self, amount_out: u64) -> Option<(u64, u64)> {
+        if self.is_buy {
+            let quote_amount = crate::extern_source::pump_swap::get_buy_quote_amount(amount_out, self.base_reserve, self.quote_reserve)?;
+            let fee = self.get_fee(quote_amount)?;
+            Some((fee, quote_amount.checked_add(fee)?))
+        } else {
+            // quote_amount * (1 - fees_basis_points / 10_000) = amount_out
+            let fees_basis_points = self.get_fees_basis_points();
+            let mut quote_amount = u64::try_from(
+                (amount_out as u128)
+                    .checked_mul(10_000)?
+                    .checked_add(10_000u64.checked_sub(fees_basis_points)?.checked_sub(1)? as u128)?
+                    .checked_div(10_000 - fees_basis_points as u128)?,
+            )
+            .ok()?;
+            // Each fee is rounded up by 1 at most.
+            '_a: for _ in 0..=self.fees_basis_points.len() {
+                if quote_amount.checked_sub(self.get_fee(quote_amount)?)? >= amount_out {
+                    break '_a;
+                }
+                quote_amount = quote_amount.checked_add(1)?;
+            }
+            let fee = self.get_fee(quote_amount)?;
+            if quote_amount.checked_sub(fee)? < amount_out {
+                return None;
+            }
+            let base_amount = crate::extern_source::pump_swap::get_sell_base_amount(quote_amount, self.base_reserve, self.quote_reserve)?;
+            // The fee is charged in the output token, so its input token equivalent is taken.
+            let fee_ = u64::try_from((fee as u128).checked_mul(base_amount as u128)?.checked_div(quote_amount as u128)?).ok()?;
+            Some((fee_, base_amount))
+        }
+    }
+    // Are returned in the order of the swap direction.
+    fn get_reserves(&self) -> (u64, u64) {
+        if self.is_buy {
+            (self.quote_reserve, self.base_reserve)
+        } else {
+            (self.base_reserve, self.quote_reserve)
+        }
+    }
+}
+impl PumpSwap {
+    const SWAP_ACCOUNTS_QUANTITY: usize = 20;
+    // https://github.com/pump-fun/pump-public-docs/blob/main/idl/pump_amm.json
+    //
+    // The volume accumulators are used only for buy.
+    pub fn create_swap_instruction(
+        program_id: &Pubkey,
+        pool: &Pubkey,
+        user: &Pubkey,
+        global_config: &Pubkey,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        user_base_token_account: &Pubkey,
+        user_quote_token_account: &Pubkey,
+        pool_base_token_account: &Pubkey,
+        pool_quote_token_account: &Pubkey,
+        protocol_fee_recipient: &Pubkey,
+        protocol_fee_recipient_token_account: &Pubkey,
+        base_token_program: &Pubkey,
+        quote_token_program: &Pubkey,
+        system_program: &Pubkey,
+        associated_token_program: &Pubkey,
+        event_authority: &Pubkey,
+        coin_creator_vault_ata: &Pubkey,
+        coin_creator_vault_authority: &Pubkey,
+        global_volume_accumulator: &Pubkey,
+        user_volume_accumulator: &Pubkey,
+        fee_config: &Pubkey,
+        fee_program: &Pubkey,
+        // The base amount out and the maximum quote amount in for buy,
+        // the base amount in and the minimum quote amount out for sell.
+        base_amount: u64,
+        quote_amount: u64,
+        is_buy: bool,
+    ) -> Instruction {
+        const BUY_DISCRIMINATOR: [u8; 8] = [
+            102,
+            6,
+            61,
+            18,
+            1,
+            218,
+            235,
+            234,
+        ];
+        const SELL_DISCRIMINATOR: [u8; 8] = [
+            51,
+            230,
+            133,
+            164,
+            1,
+            127,
+            131,
+            173,
+        ];
+        let mut data = Vec::<u8>::with_capacity(24);
+        if is_buy {
+            data.extend(BUY_DISCRIMINATOR);
+        } else {
+            data.extend(SELL_DISCRIMINATOR);
+        }
+        data.extend(base_amount.to_le_bytes());
+        data.extend(quote_amount.to_le_bytes());
+        let mut accounts = vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(*global_config, false),
+            AccountMeta::new_readonly(*base_mint, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new(*user_base_token_account, false),
+            AccountMeta::new(*user_quote_token_account, false),
+            AccountMeta::new(*pool_base_token_account, false),
+            AccountMeta::new(*pool_quote_token_account, false),
+            AccountMeta::new_readonly(*protocol_fee_recipient, false),
+            AccountMeta::new(*protocol_fee_recipient_token_account, false),
+            AccountMeta::new_readonly(*base_token_program, false),
+            AccountMeta::new_readonly(*quote_token_program, false),
+            AccountMeta::new_readonly(*system_program, false),
+            AccountMeta::new_readonly(*associated_token_program, false),
+            AccountMeta::new_readonly(*event_authority, false),
+            AccountMeta::new_readonly(*program_id, false),
+            AccountMeta::new(*coin_creator_vault_ata, false),
+            AccountMeta::new_readonly(*coin_creator_vault_authority, false),
+        ];
+        if is_buy {
+            accounts.push(AccountMeta::new(*global_volume_accumulator, false));
+            accounts.push(AccountMeta::new(*user_volume_accumulator, false));
+        }
+        accounts.push(AccountMeta::new_readonly(*fee_config, false));
+        accounts.push(AccountMeta::new_readonly(*fee_program, false));
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    }
+    // Returns None if the pool is not available for swap.
+    fn load_pool_state<'a, 'b, 'c>(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<PoolState>, ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let pump_swap_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool = solana_program::account_info::next_account_info(account_info_iter)?;
+        let global_config = solana_program::account_info::next_account_info(account_info_iter)?;
+        let base_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool_base_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool_quote_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let protocol_fee_recipient = solana_program::account_info::next_account_info(account_info_iter)?;
+        let protocol_fee_recipient_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let base_token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _system_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _associated_token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _event_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let coin_creator_vault_ata = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _coin_creator_vault_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let global_volume_accumulator = solana_program::account_info::next_account_info(account_info_iter)?;
+        let user_volume_accumulator = solana_program::account_info::next_account_info(account_info_iter)?;
+        if *pump_swap_program.key != PUMP_SWAP_PROGRAM_ID {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if base_data.with_checks
+            && (!pool.is_writable
+                || !pool_base_token_account.is_writable
+                || !pool_quote_token_account.is_writable
+                || !protocol_fee_recipient_token_account.is_writable
+                || !coin_creator_vault_ata.is_writable
+                || !global_volume_accumulator.is_writable
+                || !user_volume_accumulator.is_writable)
+        {
+            return Err(Error::InvalidAccountConfigurationFlags.into());
+        }
+        let pool_state = {
+            if pool.owner != pump_swap_program.key || global_config.owner != pump_swap_program.key {
+                return Err(Error::InvalidAmmAccountOwner.into());
+            }
+            let pool_data = pool.data.borrow();
+            if pool_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR
+                || pool_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] != Pool::DISCRIMINATOR
+            {
+                return Err(Error::InvalidAccountData.into());
+            }
+            let pool_ = <Pool as borsh::de::BorshDeserialize>::deserialize(&mut &pool_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..])?;
+            let global_config_data = global_config.data.borrow();
+            if global_config_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR
+                || global_config_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] != GlobalConfig::DISCRIMINATOR
+            {
+                return Err(Error::InvalidAccountData.into());
+            }
+            let global_config_ =
+                <GlobalConfig as borsh::de::BorshDeserialize>::deserialize(&mut &global_config_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..])?;
+            if *base_mint.key != pool_.base_mint
+                || *quote_mint.key != pool_.quote_mint
+                || *pool_base_token_account.key != pool_.pool_base_token_account
+                || *pool_quote_token_account.key != pool_.pool_quote_token_account
+                || *base_token_program.key != *base_mint.owner
+                || *quote_token_program.key != *quote_mint.owner
+                || !global_config_.protocol_fee_recipients.contains(protocol_fee_recipient.key)
+            {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            // The Token-2022 transfer fees are not supported.
+            if get_transfer_fee_config(base_mint).is_some() || get_transfer_fee_config(quote_mint).is_some() {
+                return Ok(None);
+            }
+            let (in_mint, out_mint) = if base_data.is_from_quote_to_token {
+                (base_data.quote_mint, base_data.token_mint)
+            } else {
+                (base_data.token_mint, base_data.quote_mint)
+            };
+            let is_buy = if *in_mint == pool_.quote_mint && *out_mint == pool_.base_mint {
+                true
+            } else if *in_mint == pool_.base_mint && *out_mint == pool_.quote_mint {
+                false
+            } else {
+                return Err(Error::InvalidTokenMint.into());
+            };
+            let disable_flag = if is_buy {
+                GlobalConfig::DISABLE_BUY_FLAG
+            } else {
+                GlobalConfig::DISABLE_SELL_FLAG
+            };
+            if global_config_.disable_flags & disable_flag != 0 {
+                return Ok(None);
+            }
+            let base_reserve = crate::extern_source::spl_token_2022::unpack_account_amount(&pool_base_token_account.data.borrow()).ok_or(Error::InvalidAccountData)?;
+            let quote_reserve = crate::extern_source::spl_token_2022::unpack_account_amount(&pool_quote_token_account.data.borrow()).ok_or(Error::InvalidAccountData)?;
+            if base_reserve == 0 || quote_reserve == 0 {
+                return Ok(None);
+            }
+            // The coin creator fee is charged only if the coin creator is set.
+            let coin_creator_fee_basis_points = if pool_.coin_creator == Pubkey::default() {
+                0
+            } else {
+                global_config_.coin_creator_fee_basis_points
+            };
+            PoolState {
+                pool: *pool.key,
+                is_buy,
+                base_reserve,
+                quote_reserve,
+                fees_basis_points: [
+                    global_config_.lp_fee_basis_points,
+                    global_config_.protocol_fee_basis_points,
+                    coin_creator_fee_basis_points,
+                ],
+            }
+        };
+        Ok(Some(pool_state))
+    }
+    fn invoke_swap<'a, 'b, 'c>(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>], base_amount: u64, quote_amount: u64) -> Result<(), ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let pump_swap_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool = solana_program::account_info::next_account_info(account_info_iter)?;
+        let global_config = solana_program::account_info::next_account_info(account_info_iter)?;
+        let base_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool_base_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool_quote_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let protocol_fee_recipient = solana_program::account_info::next_account_info(account_info_iter)?;
+        let protocol_fee_recipient_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let base_token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let system_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let associated_token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let event_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let coin_creator_vault_ata = solana_program::account_info::next_account_info(account_info_iter)?;
+        let coin_creator_vault_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let global_volume_accumulator = solana_program::account_info::next_account_info(account_info_iter)?;
+        let user_volume_accumulator = solana_program::account_info::next_account_info(account_info_iter)?;
+        let fee_config = solana_program::account_info::next_account_info(account_info_iter)?;
+        let fee_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let (in_token_account, out_token_account, in_mint) = if base_data.is_from_quote_to_token {
+            (base_data.quote_token_account, base_data.token_account, base_data.quote_mint)
+        } else {
+            (base_data.token_account, base_data.quote_token_account, base_data.token_mint)
+        };
+        let is_buy = *in_mint == *quote_mint.key;
+        let (user_base_token_account, user_quote_token_account) = if is_buy {
+            (out_token_account, in_token_account)
+        } else {
+            (in_token_account, out_token_account)
+        };
+        let instruction = Self::create_swap_instruction(
+            pump_swap_program.key,
+            pool.key,
+            base_data.self_authority.key,
+            global_config.key,
+            base_mint.key,
+            quote_mint.key,
+            user_base_token_account.key,
+            user_quote_token_account.key,
+            pool_base_token_account.key,
+            pool_quote_token_account.key,
+            protocol_fee_recipient.key,
+            protocol_fee_recipient_token_account.key,
+            base_token_program.key,
+            quote_token_program.key,
+            system_program.key,
+            associated_token_program.key,
+            event_authority.key,
+            coin_creator_vault_ata.key,
+            coin_creator_vault_authority.key,
+            global_volume_accumulator.key,
+            user_volume_accumulator.key,
+            fee_config.key,
+            fee_program.key,
+            base_amount,
+            quote_amount,
+            is_buy,
+        );
+        solana_program::program::invoke_signed(
+            &instruction,
+            vec![
+                pool.clone(),
+                base_data.self_authority.clone(),
+                global_config.clone(),
+                base_mint.clone(),
+                quote_mint.clone(),
+                user_base_token_account.clone(),
+                user_quote_token_account.clone(),
+                pool_base_token_account.clone(),
+                pool_quote_token_account.clone(),
+                protocol_fee_recipient.clone(),
+                protocol_fee_recipient_token_account.clone(),
+                base_token_program.clone(),
+                quote_token_program.clone(),
+                system_program.clone(),
+                associated_token_program.clone(),
+                event_authority.clone(),
+                pump_swap_program.clone(),
+                coin_creator_vault_ata.clone(),
+                coin_creator_vault_authority.clone(),
+                global_volume_accumulator.clone(),
+                user_volume_accumulator.clone(),
+                fee_config.clone(),
+                fee_program.clone(),
+            ]
+            .as_slice(),
+            [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
+        )
+    }
+}
+impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for PumpSwap {
+    fn get_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_max_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_pool_account_index(&'a self) -> usize {
+        1
+    }
+    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        let (fee, amount_out) = pool_state.calculate_amount_out(base_data.amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
+        if amount_out == 0 {
+            return Ok(None);
+        }
+        let (in_reserve, out_reserve) = pool_state.get_reserves();
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.pool,
+            amount_in: base_data.amount_in,
+            amount_in_fee: fee,
+            amount_out,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    // The buy is done for the calculated base amount with the amount in as the maximum quote amount.
+    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<(), ProgramError> {
+        let pool_state = self.load_pool_state(base_data, dex_accounts)?.ok_or(Error::InvalidSwapConditions)?;
+        if pool_state.is_buy {
+            let (_, base_amount) = pool_state.calculate_amount_out(base_data.amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
+            if base_amount < base_data.min_amount_out {
+                return Err(Error::InvalidSwapConditions.into());
+            }
+            self.invoke_swap(base_data, dex_accounts, base_amount, base_data.amount_in)
+        } else {
+            self.invoke_swap(base_data, dex_accounts, base_data.amount_in, base_data.min_amount_out)
+        }
+    }
+    fn do_swap_calculation_base_out(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        if base_data.min_amount_out == 0 {
+            return Ok(None);
+        }
+        let (fee, amount_in) = match pool_state.calculate_amount_in(base_data.min_amount_out) {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let (in_reserve, out_reserve) = pool_state.get_reserves();
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.pool,
+            amount_in,
+            amount_in_fee: fee,
+            amount_out: base_data.min_amount_out,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    fn do_swap_base_out(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        swap_calculation_result: &SwapCalculationResult,
+    ) -> Result<(), ProgramError> {
+        let pool_state = self.load_pool_state(base_data, dex_accounts)?.ok_or(Error::InvalidSwapConditions)?;
+        if pool_state.is_buy {
+            self.invoke_swap(base_data, dex_accounts, base_data.min_amount_out, base_data.amount_in)
+        } else {
+            self.invoke_swap(base_data, dex_accounts, swap_calculation_result.amount_in, base_data.min_amount_out)
+        }
+    }
+}
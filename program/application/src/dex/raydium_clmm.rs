@@ -0,0 +1,527 @@
+use {
+    super::{
+        BaseData,
+        Dex,
+        SwapCalculationResult,
+        get_transfer_fee_config,
+        get_virtual_reserves,
+    },
+    crate::{
+        RAYDIUM_CLMM_PROGRAM_ID,
+        error::Error,
+        extern_source::{
+            raydium_clmm::{
+                AmmConfig,
+                MAX_SQRT_PRICE_X64,
+                MAX_TICK,
+                MEMO_PROGRAM_ID,
+                MIN_SQRT_PRICE_X64,
+                MIN_TICK,
+                PoolState as PoolState_,
+                PoolStatusBitIndex,
+                TickArrayBitmapExtension,
+                TickArrayState,
+            },
+            spl_token_2022::TransferFeeConfig,
+        },
+        state::PdaResolver,
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        instruction::{
+            AccountMeta,
+            Instruction,
+        },
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
+pub struct RaydiumClmm;
+struct PoolState {
+    pool: Pubkey,
+    trade_fee_rate: u32,
+    tick_spacing: u16,
+    liquidity: u128,
+    sqrt_price_x64: u128,
+    tick_current: i32,
+    zero_for_one: bool,
+    // In the order of passing.
+    tick_arrays: Vec<TickArrayState>,
+    // Token-2022 transfer fee configs of the mints.
+    in_transfer_fee_config: Option<TransferFeeConfig>,
+    out_transfer_fee_config: Option<TransferFeeConfig>,
+    epoch: u64,
+}
+impl RaydiumClmm {
+    // The accounts before the remaining ones: the optional tick array bitmap extension and the tick arrays.
+    const FIXED_SWAP_ACCOUNTS_QUANTITY: usize = 12;
+    const TICK_ARRAYS_MAX_QUANTITY: usize = 10;
+    // https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/instructions/swap_v2.rs
+    pub fn create_swap_v2_instruction(
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        amm_config: &Pubkey,
+        pool_state: &Pubkey,
+        input_token_account: &Pubkey,
+        output_token_account: &Pubkey,
+        input_vault: &Pubkey,
+        output_vault: &Pubkey,
+        observation_state: &Pubkey,
+        token_program: &Pubkey,
+        token_program_2022: &Pubkey,
+        memo_program: &Pubkey,
+        input_vault_mint: &Pubkey,
+        output_vault_mint: &Pubkey,
+        // The tick array bitmap extension if it is needed and the tick arrays.
+        remaining_accounts: &[&Pubkey],
+        amount: u64,
+        other_amount_threshold: u64,
+        sqrt_price_limit_x64: u128,
+        is_base_input: bool,
+    ) -> Instruction {
+        const DISCRIMINATOR: [u8; 8] = [
+            43,
+            4,
+            237,
+            11,
+            26,
+            201,
+            30,
+            98,
+        ];
+        let mut data = Vec::<u8>::with_capacity(41);
+        data.extend(DISCRIMINATOR);
+        data.extend(amount.to_le_bytes());
+        data.extend(other_amount_threshold.to_le_bytes());
+        data.extend(sqrt_price_limit_x64.to_le_bytes());
+        data.push(is_base_input as u8);
+        let mut accounts = vec![
+            AccountMeta::new_readonly(*payer, true),
+            AccountMeta::new_readonly(*amm_config, false),
+            AccountMeta::new(*pool_state, false),
+            AccountMeta::new(*input_token_account, false),
+            AccountMeta::new(*output_token_account, false),
+            AccountMeta::new(*input_vault, false),
+            AccountMeta::new(*output_vault, false),
+            AccountMeta::new(*observation_state, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(*token_program_2022, false),
+            AccountMeta::new_readonly(*memo_program, false),
+            AccountMeta::new_readonly(*input_vault_mint, false),
+            AccountMeta::new_readonly(*output_vault_mint, false),
+        ];
+        '_a: for remaining_account in remaining_accounts.iter() {
+            accounts.push(AccountMeta::new(**remaining_account, false));
+        }
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    }
+    // Returns None if the pool is not available for swap.
+    fn load_pool_state<'a, 'b, 'c>(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<PoolState>, ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let raydium_clmm_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let clock = solana_program::account_info::next_account_info(account_info_iter)?;
+        let amm_config = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool_state = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_vault_0 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_vault_1 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let observation_state = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program_2022 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let memo_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_mint_0 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_mint_1 = solana_program::account_info::next_account_info(account_info_iter)?;
+        if *raydium_clmm_program.key != RAYDIUM_CLMM_PROGRAM_ID
+            || *token_program.key != spl_token::ID
+            || *token_program_2022.key != crate::extern_source::spl_token_2022::ID
+            || *memo_program.key != MEMO_PROGRAM_ID
+        {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if base_data.with_checks && (!pool_state.is_writable || !token_vault_0.is_writable || !token_vault_1.is_writable || !observation_state.is_writable) {
+            return Err(Error::InvalidAccountConfigurationFlags.into());
+        }
+        // This is a slightly modified selective code from https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/instructions/swap.rs
+        // that loads the pool state and the tick arrays for swap calculation.
+        let pool_state_ = {
+            if pool_state.owner != raydium_clmm_program.key || amm_config.owner != raydium_clmm_program.key {
+                return Err(Error::InvalidAmmAccountOwner.into());
+            }
+            let pool_state_data = pool_state.data.borrow();
+            if pool_state_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR
+                || pool_state_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] != PoolState_::DISCRIMINATOR
+            {
+                return Err(Error::InvalidAccountData.into());
+            }
+            let pool_state__ = Box::new(<PoolState_ as borsh::de::BorshDeserialize>::deserialize(
+                &mut &pool_state_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..],
+            )?);
+            let amm_config_data = amm_config.data.borrow();
+            if amm_config_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR
+                || amm_config_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] != AmmConfig::DISCRIMINATOR
+            {
+                return Err(Error::InvalidAccountData.into());
+            }
+            let amm_config_ = <AmmConfig as borsh::de::BorshDeserialize>::deserialize(&mut &amm_config_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..])?;
+            if *amm_config.key != pool_state__.amm_config
+                || *token_vault_0.key != pool_state__.token_vault_0
+                || *token_vault_1.key != pool_state__.token_vault_1
+                || *observation_state.key != pool_state__.observation_key
+                || *token_mint_0.key != pool_state__.token_mint_0
+                || *token_mint_1.key != pool_state__.token_mint_1
+            {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            let clock_ = Clock::from_account_info(clock)?;
+            if !pool_state__.get_status_by_bit(PoolStatusBitIndex::Swap) || (clock_.unix_timestamp as u64) < pool_state__.open_time {
+                return Ok(None);
+            }
+            let (in_mint, out_mint) = if base_data.is_from_quote_to_token {
+                (base_data.quote_mint, base_data.token_mint)
+            } else {
+                (base_data.token_mint, base_data.quote_mint)
+            };
+            let zero_for_one = if *in_mint == pool_state__.token_mint_0 && *out_mint == pool_state__.token_mint_1 {
+                true
+            } else if *in_mint == pool_state__.token_mint_1 && *out_mint == pool_state__.token_mint_0 {
+                false
+            } else {
+                return Err(Error::InvalidTokenMint.into());
+            };
+            let mut tick_arrays = Vec::<TickArrayState>::with_capacity(dex_accounts.len() - Self::FIXED_SWAP_ACCOUNTS_QUANTITY);
+            '_a: for remaining_account in account_info_iter {
+                if remaining_account.owner != raydium_clmm_program.key {
+                    return Err(Error::InvalidAmmAccountOwner.into());
+                }
+                if base_data.with_checks && !remaining_account.is_writable {
+                    return Err(Error::InvalidAccountConfigurationFlags.into());
+                }
+                let remaining_account_data = remaining_account.data.borrow();
+                if remaining_account_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR {
+                    return Err(Error::InvalidAccountData.into());
+                }
+                // The bitmap extension is needed only for the CPI to find the next initialized tick array.
+                if remaining_account_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] == TickArrayBitmapExtension::DISCRIMINATOR {
+                    continue '_a;
+                }
+                if remaining_account_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] != TickArrayState::DISCRIMINATOR {
+                    return Err(Error::InvalidAccountData.into());
+                }
+                let tick_array = TickArrayState::unpack(&remaining_account_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..]).ok_or(Error::InvalidAccountData)?;
+                if tick_array.pool_id != *pool_state.key {
+                    return Err(Error::InvalidAccountData.into());
+                }
+                tick_arrays.push(tick_array);
+            }
+            let token_0_transfer_fee_config = get_transfer_fee_config(token_mint_0);
+            let token_1_transfer_fee_config = get_transfer_fee_config(token_mint_1);
+            let (in_transfer_fee_config, out_transfer_fee_config) = if zero_for_one {
+                (token_0_transfer_fee_config, token_1_transfer_fee_config)
+            } else {
+                (token_1_transfer_fee_config, token_0_transfer_fee_config)
+            };
+            PoolState {
+                pool: *pool_state.key,
+                trade_fee_rate: amm_config_.trade_fee_rate,
+                tick_spacing: pool_state__.tick_spacing,
+                liquidity: pool_state__.liquidity,
+                sqrt_price_x64: pool_state__.sqrt_price_x64,
+                tick_current: pool_state__.tick_current,
+                zero_for_one,
+                tick_arrays,
+                in_transfer_fee_config,
+                out_transfer_fee_config,
+                epoch: clock_.epoch,
+            }
+        };
+        Ok(Some(pool_state_))
+    }
+    // Returns the amount in, the amount out and the trade fee. Returns None if the passed tick arrays
+    // do not cover the amount.
+    //
+    // This is a slightly modified selective code from https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/instructions/swap.rs
+    // where the passed tick arrays are traversed instead of the tick array bitmap. The tick arrays should be passed
+    // in the swap direction, as the CPI requires the same.
+    fn calculate_swap(pool_state: &PoolState, amount_specified: u64, is_base_input: bool) -> Result<Option<(u64, u64, u64)>, ProgramError> {
+        let zero_for_one = pool_state.zero_for_one;
+        let sqrt_price_limit_x64 = if zero_for_one {
+            MIN_SQRT_PRICE_X64 + 1
+        } else {
+            MAX_SQRT_PRICE_X64 - 1
+        };
+        let mut amount_specified_remaining = amount_specified;
+        let mut amount_calculated: u64 = 0;
+        let mut fee_amount: u64 = 0;
+        let mut sqrt_price_x64 = pool_state.sqrt_price_x64;
+        let mut tick = pool_state.tick_current;
+        let mut liquidity = pool_state.liquidity;
+        let current_tick_array_start_index = TickArrayState::get_array_start_index(tick, pool_state.tick_spacing);
+        // The tick arrays behind the current tick in the swap direction are skipped.
+        let mut tick_arrays = pool_state.tick_arrays.iter().skip_while(|tick_array| {
+            if zero_for_one {
+                tick_array.start_tick_index > current_tick_array_start_index
+            } else {
+                tick_array.start_tick_index < current_tick_array_start_index
+            }
+        });
+        let mut tick_array_current = match tick_arrays.next() {
+            Some(tick_array) => tick_array,
+            None => return Ok(None),
+        };
+        let mut is_match_pool_current_tick_array = tick_array_current.start_tick_index == current_tick_array_start_index;
+        while amount_specified_remaining != 0 && sqrt_price_x64 != sqrt_price_limit_x64 {
+            let sqrt_price_start_x64 = sqrt_price_x64;
+            let mut next_initialized_tick = tick_array_current.next_initialized_tick(tick, pool_state.tick_spacing, zero_for_one).copied();
+            if next_initialized_tick.is_none() && !is_match_pool_current_tick_array {
+                is_match_pool_current_tick_array = true;
+                next_initialized_tick = tick_array_current.first_initialized_tick(zero_for_one).copied();
+            }
+            let next_initialized_tick = match next_initialized_tick {
+                Some(next_initialized_tick_) => next_initialized_tick_,
+                None => {
+                    let next_tick_array = match tick_arrays.next() {
+                        Some(tick_array) => tick_array,
+                        None => return Ok(None),
+                    };
+                    if (zero_for_one && next_tick_array.start_tick_index >= tick_array_current.start_tick_index)
+                        || (!zero_for_one && next_tick_array.start_tick_index <= tick_array_current.start_tick_index)
+                    {
+                        return Err(Error::InvalidAccountData.into());
+                    }
+                    tick_array_current = next_tick_array;
+                    *tick_array_current.first_initialized_tick(zero_for_one).ok_or(Error::InvalidAccountData)?
+                }
+            };
+            let tick_next = next_initialized_tick.tick.clamp(MIN_TICK, MAX_TICK);
+            let sqrt_price_next_x64 = crate::extern_source::raydium_clmm::get_sqrt_price_at_tick(tick_next).ok_or(ProgramError::ArithmeticOverflow)?;
+            let target_price = if (zero_for_one && sqrt_price_next_x64 < sqrt_price_limit_x64) || (!zero_for_one && sqrt_price_next_x64 > sqrt_price_limit_x64) {
+                sqrt_price_limit_x64
+            } else {
+                sqrt_price_next_x64
+            };
+            let swap_step = crate::extern_source::raydium_clmm::compute_swap_step(
+                sqrt_price_x64,
+                target_price,
+                liquidity,
+                amount_specified_remaining,
+                pool_state.trade_fee_rate,
+                is_base_input,
+                zero_for_one,
+            )
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+            sqrt_price_x64 = swap_step.sqrt_price_next_x64;
+            if is_base_input {
+                amount_specified_remaining = amount_specified_remaining
+                    .checked_sub(swap_step.amount_in.checked_add(swap_step.fee_amount).ok_or(ProgramError::ArithmeticOverflow)?)
+                    .ok_or(ProgramError::ArithmeticOverflow)?;
+                amount_calculated = amount_calculated.checked_add(swap_step.amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+            } else {
+                amount_specified_remaining = amount_specified_remaining.checked_sub(swap_step.amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+                amount_calculated = amount_calculated
+                    .checked_add(swap_step.amount_in.checked_add(swap_step.fee_amount).ok_or(ProgramError::ArithmeticOverflow)?)
+                    .ok_or(ProgramError::ArithmeticOverflow)?;
+            }
+            fee_amount = fee_amount.checked_add(swap_step.fee_amount).ok_or(ProgramError::ArithmeticOverflow)?;
+            if sqrt_price_x64 == sqrt_price_next_x64 {
+                // The tick is crossed.
+                let liquidity_net = if zero_for_one {
+                    next_initialized_tick.liquidity_net.checked_neg().ok_or(ProgramError::ArithmeticOverflow)?
+                } else {
+                    next_initialized_tick.liquidity_net
+                };
+                liquidity = crate::extern_source::raydium_clmm::add_delta(liquidity, liquidity_net).ok_or(ProgramError::ArithmeticOverflow)?;
+                tick = if zero_for_one {
+                    tick_next - 1
+                } else {
+                    tick_next
+                };
+            } else if sqrt_price_x64 != sqrt_price_start_x64 {
+                // The price stopped between the ticks, so the amount is exhausted or the price limit is reached.
+                break;
+            }
+        }
+        if amount_specified_remaining != 0 || amount_calculated == 0 {
+            return Ok(None);
+        }
+        if is_base_input {
+            Ok(Some((amount_specified, amount_calculated, fee_amount)))
+        } else {
+            Ok(Some((amount_calculated, amount_specified, fee_amount)))
+        }
+    }
+    fn invoke_swap<'a, 'b, 'c>(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        is_base_input: bool,
+        amount: u64,
+        other_amount_threshold: u64,
+    ) -> Result<(), ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let raydium_clmm_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _clock = solana_program::account_info::next_account_info(account_info_iter)?;
+        let amm_config = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool_state = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_vault_0 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_vault_1 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let observation_state = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program_2022 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let memo_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_mint_0 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_mint_1 = solana_program::account_info::next_account_info(account_info_iter)?;
+        let remaining_accounts = &dex_accounts[Self::FIXED_SWAP_ACCOUNTS_QUANTITY..];
+        let (input_token_account, output_token_account, input_mint) = if base_data.is_from_quote_to_token {
+            (base_data.quote_token_account, base_data.token_account, base_data.quote_mint)
+        } else {
+            (base_data.token_account, base_data.quote_token_account, base_data.token_mint)
+        };
+        let (input_vault, output_vault, input_vault_mint, output_vault_mint) = if *input_mint == *token_mint_0.key {
+            (token_vault_0, token_vault_1, token_mint_0, token_mint_1)
+        } else {
+            (token_vault_1, token_vault_0, token_mint_1, token_mint_0)
+        };
+        let instruction = Self::create_swap_v2_instruction(
+            raydium_clmm_program.key,
+            base_data.self_authority.key,
+            amm_config.key,
+            pool_state.key,
+            input_token_account.key,
+            output_token_account.key,
+            input_vault.key,
+            output_vault.key,
+            observation_state.key,
+            token_program.key,
+            token_program_2022.key,
+            memo_program.key,
+            input_vault_mint.key,
+            output_vault_mint.key,
+            remaining_accounts.iter().map(|remaining_account| remaining_account.key).collect::<Vec<&Pubkey>>().as_slice(),
+            amount,
+            other_amount_threshold,
+            // The default limit of the Raydium CLMM program.
+            0,
+            is_base_input,
+        );
+        let mut account_infos = vec![
+            base_data.self_authority.clone(),
+            amm_config.clone(),
+            pool_state.clone(),
+            input_token_account.clone(),
+            output_token_account.clone(),
+            input_vault.clone(),
+            output_vault.clone(),
+            observation_state.clone(),
+            token_program.clone(),
+            token_program_2022.clone(),
+            memo_program.clone(),
+            input_vault_mint.clone(),
+            output_vault_mint.clone(),
+        ];
+        account_infos.extend(remaining_accounts.iter().cloned());
+        solana_program::program::invoke_signed(
+            &instruction,
+            account_infos.as_slice(),
+            [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
+        )
+    }
+}
+impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for RaydiumClmm {
+    // At least one tick array is required.
+    fn get_swap_accounts_quantity(&'a self) -> usize {
+        Self::FIXED_SWAP_ACCOUNTS_QUANTITY + 1
+    }
+    // The tick array bitmap extension and the tick arrays.
+    fn get_max_swap_accounts_quantity(&'a self) -> usize {
+        Self::FIXED_SWAP_ACCOUNTS_QUANTITY + 1 + Self::TICK_ARRAYS_MAX_QUANTITY
+    }
+    fn get_pool_account_index(&'a self) -> usize {
+        3
+    }
+    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        // This is a slightly modified selective code from https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/instructions/swap_v2.rs
+        // Take transfer fees into account for actual amount transferred in.
+        let in_transfer_fee = match pool_state.in_transfer_fee_config {
+            Some(ref transfer_fee_config) => transfer_fee_config.calculate_epoch_fee(pool_state.epoch, base_data.amount_in).ok_or(ProgramError::ArithmeticOverflow)?,
+            None => 0,
+        };
+        let actual_amount_in = base_data.amount_in.checked_sub(in_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        if actual_amount_in == 0 {
+            return Ok(None);
+        }
+        let (_, amount_out, trade_fee) = match Self::calculate_swap(&pool_state, actual_amount_in, true)? {
+            Some(swap_) => swap_,
+            None => return Ok(None),
+        };
+        let out_transfer_fee = match pool_state.out_transfer_fee_config {
+            Some(ref transfer_fee_config) => transfer_fee_config.calculate_epoch_fee(pool_state.epoch, amount_out).ok_or(ProgramError::ArithmeticOverflow)?,
+            None => 0,
+        };
+        let amount_out_ = amount_out.checked_sub(out_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        if amount_out_ == 0 {
+            return Ok(None);
+        }
+        let (in_reserve, out_reserve) = get_virtual_reserves(pool_state.sqrt_price_x64, pool_state.zero_for_one);
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.pool,
+            amount_in: base_data.amount_in,
+            amount_in_fee: trade_fee.checked_add(in_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?,
+            amount_out: amount_out_,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, true, base_data.amount_in, base_data.min_amount_out)
+    }
+    fn do_swap_calculation_base_out(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        // This is a slightly modified selective code from https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/instructions/swap_v2.rs
+        if base_data.min_amount_out == 0 {
+            return Ok(None);
+        }
+        let out_transfer_fee = match pool_state.out_transfer_fee_config {
+            Some(ref transfer_fee_config) => transfer_fee_config.calculate_inverse_epoch_fee(pool_state.epoch, base_data.min_amount_out).ok_or(ProgramError::ArithmeticOverflow)?,
+            None => 0,
+        };
+        let actual_amount_out = base_data.min_amount_out.checked_add(out_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        let (amount_in, _, trade_fee) = match Self::calculate_swap(&pool_state, actual_amount_out, false)? {
+            Some(swap_) => swap_,
+            None => return Ok(None),
+        };
+        let in_transfer_fee = match pool_state.in_transfer_fee_config {
+            Some(ref transfer_fee_config) => transfer_fee_config.calculate_inverse_epoch_fee(pool_state.epoch, amount_in).ok_or(ProgramError::ArithmeticOverflow)?,
+            None => 0,
+        };
+        let (in_reserve, out_reserve) = get_virtual_reserves(pool_state.sqrt_price_x64, pool_state.zero_for_one);
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.pool,
+            amount_in: amount_in.checked_add(in_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?,
+            amount_in_fee: trade_fee.checked_add(in_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?,
+            amount_out: base_data.min_amount_out,
+            in_reserve,
+            out_reserve,
+        }))
+    }
+    fn do_swap_base_out(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        _swap_calculation_result: &SwapCalculationResult,
+    ) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, false, base_data.min_amount_out, base_data.amount_in)
+    }
+}
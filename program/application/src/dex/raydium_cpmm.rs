@@ -0,0 +1,471 @@
+use {
+    super::{
+        BaseData,
+        Dex,
+        SwapCalculationResult,
+        get_transfer_fee_config,
+    },
+    crate::{
+        RAYDIUM_CPMM_PROGRAM_ID,
+        error::Error,
+        extern_source::{
+            raydium_cpmm::{
+                AmmConfig,
+                PoolState as PoolState_,
+                PoolStatusBitIndex,
+            },
+            spl_token_2022::TransferFeeConfig,
+        },
+        state::PdaResolver,
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        instruction::{
+            AccountMeta,
+            Instruction,
+        },
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+const FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR: usize = 8;
+pub struct RaydiumCpmm;
+struct PoolState {
+    pool: Pubkey,
+    trade_fee_rate: u64,
+    in_reserve: u64,
+    out_reserve: u64,
+    // Token-2022 transfer fee configs of the mints.
+    in_transfer_fee_config: Option<TransferFeeConfig>,
+    out_transfer_fee_config: Option<TransferFeeConfig>,
+    epoch: u64,
+}
+impl RaydiumCpmm {
+    const SWAP_ACCOUNTS_QUANTITY: usize = 12;
+    // https://github.com/raydium-io/raydium-cp-swap/blob/master/programs/cp-swap/src/instructions/swap_base_input.rs
+    pub fn create_swap_base_input_instruction(
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        authority: &Pubkey,
+        amm_config: &Pubkey,
+        pool_state: &Pubkey,
+        input_token_account: &Pubkey,
+        output_token_account: &Pubkey,
+        input_vault: &Pubkey,
+        output_vault: &Pubkey,
+        input_token_program: &Pubkey,
+        output_token_program: &Pubkey,
+        input_token_mint: &Pubkey,
+        output_token_mint: &Pubkey,
+        observation_state: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+    ) -> Instruction {
+        const DISCRIMINATOR: [u8; 8] = [
+            143,
+            190,
+            90,
+            218,
+            196,
+            30,
+            51,
+            222,
+        ];
+        Self::create_instruction(
+            program_id,
+            payer,
+            authority,
+            amm_config,
+            pool_state,
+            input_token_account,
+            output_token_account,
+            input_vault,
+            output_vault,
+            input_token_program,
+            output_token_program,
+            input_token_mint,
+            output_token_mint,
+            observation_state,
+            DISCRIMINATOR,
+            amount_in,
+            minimum_amount_out,
+        )
+    }
+    // https://github.com/raydium-io/raydium-cp-swap/blob/master/programs/cp-swap/src/instructions/swap_base_output.rs
+    pub fn create_swap_base_output_instruction(
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        authority: &Pubkey,
+        amm_config: &Pubkey,
+        pool_state: &Pubkey,
+        input_token_account: &Pubkey,
+        output_token_account: &Pubkey,
+        input_vault: &Pubkey,
+        output_vault: &Pubkey,
+        input_token_program: &Pubkey,
+        output_token_program: &Pubkey,
+        input_token_mint: &Pubkey,
+        output_token_mint: &Pubkey,
+        observation_state: &Pubkey,
+        max_amount_in: u64,
+        amount_out: u64,
+    ) -> Instruction {
+        const DISCRIMINATOR: [u8; 8] = [
+            55,
+            217,
+            98,
+            86,
+            163,
+            74,
+            180,
+            173,
+        ];
+        Self::create_instruction(
+            program_id,
+            payer,
+            authority,
+            amm_config,
+            pool_state,
+            input_token_account,
+            output_token_account,
+            input_vault,
+            output_vault,
+            input_token_program,
+            output_token_program,
+            input_token_mint,
+            output_token_mint,
+            observation_state,
+            DISCRIMINATOR,
+            max_amount_in,
+            amount_out,
+        )
+    }
+    fn create_instruction(
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        authority: &Pubkey,
+        amm_config: &Pubkey,
+        pool_state: &Pubkey,
+        input_token_account: &Pubkey,
+        output_token_account: &Pubkey,
+        input_vault: &Pubkey,
+        output_vault: &Pubkey,
+        input_token_program: &Pubkey,
+        output_token_program: &Pubkey,
+        input_token_mint: &Pubkey,
+        output_token_mint: &Pubkey,
+        observation_state: &Pubkey,
+        discriminator: [u8; 8],
+        amount: u64,
+        other_amount: u64,
+    ) -> Instruction {
+        let mut data = Vec::<u8>::with_capacity(24);
+        data.extend(discriminator);
+        data.extend(amount.to_le_bytes());
+        data.extend(other_amount.to_le_bytes());
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*payer, true),
+                AccountMeta::new_readonly(*authority, false),
+                AccountMeta::new_readonly(*amm_config, false),
+                AccountMeta::new(*pool_state, false),
+                AccountMeta::new(*input_token_account, false),
+                AccountMeta::new(*output_token_account, false),
+                AccountMeta::new(*input_vault, false),
+                AccountMeta::new(*output_vault, false),
+                AccountMeta::new_readonly(*input_token_program, false),
+                AccountMeta::new_readonly(*output_token_program, false),
+                AccountMeta::new_readonly(*input_token_mint, false),
+                AccountMeta::new_readonly(*output_token_mint, false),
+                AccountMeta::new(*observation_state, false),
+            ],
+            data,
+        }
+    }
+    // Returns None if the pool is not available for swap.
+    fn load_pool_state<'a, 'b, 'c>(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<PoolState>, ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let raydium_cpmm_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let clock = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let amm_config = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool_state = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_0_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_1_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_0_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_1_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_0_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_1_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let observation_state = solana_program::account_info::next_account_info(account_info_iter)?;
+        if *raydium_cpmm_program.key != RAYDIUM_CPMM_PROGRAM_ID {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if base_data.with_checks && (!pool_state.is_writable || !token_0_vault.is_writable || !token_1_vault.is_writable || !observation_state.is_writable) {
+            return Err(Error::InvalidAccountConfigurationFlags.into());
+        }
+        // This is a slightly modified selective code from https://github.com/raydium-io/raydium-cp-swap/blob/master/programs/cp-swap/src/instructions/swap_base_input.rs
+        // that loads the pool state for swap calculation.
+        let pool_state_ = {
+            if pool_state.owner != raydium_cpmm_program.key || amm_config.owner != raydium_cpmm_program.key {
+                return Err(Error::InvalidAmmAccountOwner.into());
+            }
+            let pool_state_data = pool_state.data.borrow();
+            if pool_state_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR
+                || pool_state_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] != PoolState_::DISCRIMINATOR
+            {
+                return Err(Error::InvalidAccountData.into());
+            }
+            let pool_state__ = <PoolState_ as borsh::de::BorshDeserialize>::deserialize(&mut &pool_state_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..])?;
+            let amm_config_data = amm_config.data.borrow();
+            if amm_config_data.len() < FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR
+                || amm_config_data[..FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR] != AmmConfig::DISCRIMINATOR
+            {
+                return Err(Error::InvalidAccountData.into());
+            }
+            let amm_config_ = <AmmConfig as borsh::de::BorshDeserialize>::deserialize(&mut &amm_config_data[FIRST_BYTE_INDEX_AFTER_ANCHOR_DEFAULT_LENGTH_DISCRIMINATOR..])?;
+            if *amm_config.key != pool_state__.amm_config
+                || *token_0_vault.key != pool_state__.token_0_vault
+                || *token_1_vault.key != pool_state__.token_1_vault
+                || *token_0_program.key != pool_state__.token_0_program
+                || *token_1_program.key != pool_state__.token_1_program
+                || *token_0_mint.key != pool_state__.token_0_mint
+                || *token_1_mint.key != pool_state__.token_1_mint
+                || *observation_state.key != pool_state__.observation_key
+            {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            let clock_ = Clock::from_account_info(clock)?;
+            if !pool_state__.get_status_by_bit(PoolStatusBitIndex::Swap) || (clock_.unix_timestamp as u64) < pool_state__.open_time {
+                return Ok(None);
+            }
+            let (in_mint, out_mint) = if base_data.is_from_quote_to_token {
+                (base_data.quote_mint, base_data.token_mint)
+            } else {
+                (base_data.token_mint, base_data.quote_mint)
+            };
+            let is_zero_for_one = if *in_mint == pool_state__.token_0_mint && *out_mint == pool_state__.token_1_mint {
+                true
+            } else if *in_mint == pool_state__.token_1_mint && *out_mint == pool_state__.token_0_mint {
+                false
+            } else {
+                return Err(Error::InvalidTokenMint.into());
+            };
+            let token_0_vault_amount = crate::extern_source::spl_token_2022::unpack_account_amount(&token_0_vault.data.borrow()).ok_or(Error::InvalidAccountData)?;
+            let token_1_vault_amount = crate::extern_source::spl_token_2022::unpack_account_amount(&token_1_vault.data.borrow()).ok_or(Error::InvalidAccountData)?;
+            let (token_0_reserve, token_1_reserve) = pool_state__.vault_amount_without_fee(token_0_vault_amount, token_1_vault_amount).ok_or(ProgramError::ArithmeticOverflow)?;
+            let token_0_transfer_fee_config = get_transfer_fee_config(token_0_mint);
+            let token_1_transfer_fee_config = get_transfer_fee_config(token_1_mint);
+            let (in_reserve, out_reserve, in_transfer_fee_config, out_transfer_fee_config) = if is_zero_for_one {
+                (token_0_reserve, token_1_reserve, token_0_transfer_fee_config, token_1_transfer_fee_config)
+            } else {
+                (token_1_reserve, token_0_reserve, token_1_transfer_fee_config, token_0_transfer_fee_config)
+            };
+            PoolState {
+                pool: *pool_state.key,
+                trade_fee_rate: amm_config_.trade_fee_rate,
+                in_reserve,
+                out_reserve,
+                in_transfer_fee_config,
+                out_transfer_fee_config,
+                epoch: clock_.epoch,
+            }
+        };
+        Ok(Some(pool_state_))
+    }
+    fn invoke_swap<'a, 'b, 'c>(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        is_base_out: bool,
+        amount: u64,
+        other_amount: u64,
+    ) -> Result<(), ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let raydium_cpmm_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _clock = solana_program::account_info::next_account_info(account_info_iter)?;
+        let authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let amm_config = solana_program::account_info::next_account_info(account_info_iter)?;
+        let pool_state = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_0_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_1_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_0_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_1_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_0_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_1_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let observation_state = solana_program::account_info::next_account_info(account_info_iter)?;
+        let (input_token_account, output_token_account, input_mint) = if base_data.is_from_quote_to_token {
+            (base_data.quote_token_account, base_data.token_account, base_data.quote_mint)
+        } else {
+            (base_data.token_account, base_data.quote_token_account, base_data.token_mint)
+        };
+        let (input_vault, output_vault, input_token_program, output_token_program, input_token_mint, output_token_mint) = if *input_mint == *token_0_mint.key {
+            (token_0_vault, token_1_vault, token_0_program, token_1_program, token_0_mint, token_1_mint)
+        } else {
+            (token_1_vault, token_0_vault, token_1_program, token_0_program, token_1_mint, token_0_mint)
+        };
+        let instruction = if is_base_out {
+            Self::create_swap_base_output_instruction(
+                raydium_cpmm_program.key,
+                base_data.self_authority.key,
+                authority.key,
+                amm_config.key,
+                pool_state.key,
+                input_token_account.key,
+                output_token_account.key,
+                input_vault.key,
+                output_vault.key,
+                input_token_program.key,
+                output_token_program.key,
+                input_token_mint.key,
+                output_token_mint.key,
+                observation_state.key,
+                amount,
+                other_amount,
+            )
+        } else {
+            Self::create_swap_base_input_instruction(
+                raydium_cpmm_program.key,
+                base_data.self_authority.key,
+                authority.key,
+                amm_config.key,
+                pool_state.key,
+                input_token_account.key,
+                output_token_account.key,
+                input_vault.key,
+                output_vault.key,
+                input_token_program.key,
+                output_token_program.key,
+                input_token_mint.key,
+                output_token_mint.key,
+                observation_state.key,
+                amount,
+                other_amount,
+            )
+        };
+        solana_program::program::invoke_signed(
+            &instruction,
+            vec![
+                base_data.self_authority.clone(),
+                authority.clone(),
+                amm_config.clone(),
+                pool_state.clone(),
+                input_token_account.clone(),
+                output_token_account.clone(),
+                input_vault.clone(),
+                output_vault.clone(),
+                input_token_program.clone(),
+                output_token_program.clone(),
+                input_token_mint.clone(),
+                output_token_mint.clone(),
+                observation_state.clone(),
+            ]
+            .as_slice(),
+            [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
+        )
+    }
+}
+impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for RaydiumCpmm {
+    fn get_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_max_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_pool_account_index(&'a self) -> usize {
+        4
+    }
+    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        // This is a slightly modified selective code from https://github.com/raydium-io/raydium-cp-swap/blob/master/programs/cp-swap/src/instructions/swap_base_input.rs
+        // Take transfer fees into account for actual amount transferred in.
+        let in_transfer_fee = match pool_state.in_transfer_fee_config {
+            Some(ref transfer_fee_config) => transfer_fee_config.calculate_epoch_fee(pool_state.epoch, base_data.amount_in).ok_or(ProgramError::ArithmeticOverflow)?,
+            None => 0,
+        };
+        let actual_amount_in = base_data.amount_in.checked_sub(in_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        if actual_amount_in == 0 {
+            return Ok(None);
+        }
+        let trade_fee = crate::extern_source::raydium_cpmm::trading_fee(actual_amount_in as u128, pool_state.trade_fee_rate).ok_or(ProgramError::ArithmeticOverflow)?;
+        let source_amount_less_fees = (actual_amount_in as u128).checked_sub(trade_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        let destination_amount_swapped =
+            crate::extern_source::raydium_cpmm::swap_base_input_without_fees(source_amount_less_fees, pool_state.in_reserve as u128, pool_state.out_reserve as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+        let destination_amount_swapped: u64 = destination_amount_swapped.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?;
+        if destination_amount_swapped == 0 || destination_amount_swapped >= pool_state.out_reserve {
+            return Ok(None);
+        }
+        let out_transfer_fee = match pool_state.out_transfer_fee_config {
+            Some(ref transfer_fee_config) => transfer_fee_config.calculate_epoch_fee(pool_state.epoch, destination_amount_swapped).ok_or(ProgramError::ArithmeticOverflow)?,
+            None => 0,
+        };
+        let amount_out_ = destination_amount_swapped.checked_sub(out_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        if amount_out_ == 0 {
+            return Ok(None);
+        }
+        let trade_fee: u64 = trade_fee.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?;
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.pool,
+            amount_in: base_data.amount_in,
+            amount_in_fee: trade_fee.checked_add(in_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?,
+            amount_out: amount_out_,
+            in_reserve: pool_state.in_reserve,
+            out_reserve: pool_state.out_reserve,
+        }))
+    }
+    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, false, base_data.amount_in, base_data.min_amount_out)
+    }
+    fn do_swap_calculation_base_out(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        // This is a slightly modified selective code from https://github.com/raydium-io/raydium-cp-swap/blob/master/programs/cp-swap/src/instructions/swap_base_output.rs
+        if base_data.min_amount_out == 0 {
+            return Ok(None);
+        }
+        let out_transfer_fee = match pool_state.out_transfer_fee_config {
+            Some(ref transfer_fee_config) => transfer_fee_config.calculate_inverse_epoch_fee(pool_state.epoch, base_data.min_amount_out).ok_or(ProgramError::ArithmeticOverflow)?,
+            None => 0,
+        };
+        let actual_amount_out = base_data.min_amount_out.checked_add(out_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        if actual_amount_out >= pool_state.out_reserve {
+            return Ok(None);
+        }
+        let source_amount_swapped =
+            crate::extern_source::raydium_cpmm::swap_base_output_without_fees(actual_amount_out as u128, pool_state.in_reserve as u128, pool_state.out_reserve as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+        let source_amount =
+            crate::extern_source::raydium_cpmm::calculate_pre_fee_amount(source_amount_swapped, pool_state.trade_fee_rate).ok_or(ProgramError::ArithmeticOverflow)?;
+        let trade_fee = crate::extern_source::raydium_cpmm::trading_fee(source_amount, pool_state.trade_fee_rate).ok_or(ProgramError::ArithmeticOverflow)?;
+        let source_amount: u64 = source_amount.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?;
+        let in_transfer_fee = match pool_state.in_transfer_fee_config {
+            Some(ref transfer_fee_config) => transfer_fee_config.calculate_inverse_epoch_fee(pool_state.epoch, source_amount).ok_or(ProgramError::ArithmeticOverflow)?,
+            None => 0,
+        };
+        let trade_fee: u64 = trade_fee.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?;
+        Ok(Some(SwapCalculationResult {
+            pool: pool_state.pool,
+            amount_in: source_amount.checked_add(in_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?,
+            amount_in_fee: trade_fee.checked_add(in_transfer_fee).ok_or(ProgramError::ArithmeticOverflow)?,
+            amount_out: base_data.min_amount_out,
+            in_reserve: pool_state.in_reserve,
+            out_reserve: pool_state.out_reserve,
+        }))
+    }
+    fn do_swap_base_out(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        _swap_calculation_result: &SwapCalculationResult,
+    ) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, true, base_data.amount_in, base_data.min_amount_out)
+    }
+}
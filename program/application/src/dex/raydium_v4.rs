@@ -19,6 +19,7 @@ use {
         state::PdaResolver,
     },
     solana_program::{
+        account_info::AccountInfo,
         clock::Clock,
         instruction::{
             AccountMeta,
@@ -30,7 +31,92 @@ use {
     },
 };
 pub struct RaydiumV4;
+// The pool without the orderbook ('SwapOnly' status) is swapped without the OpenBook market accounts.
+pub struct RaydiumV4SwapOnly;
+struct PoolState {
+    pool: Pubkey,
+    swap_fee_numerator: u64,
+    swap_fee_denominator: u64,
+    total_pc_without_take_pnl: u64,
+    total_coin_without_take_pnl: u64,
+    swap_direction: SwapDirection,
+}
+// The market accounts of the pool with the orderbook.
+struct OrderBookAccounts<'a, 'b> {
+    amm_authority: &'a AccountInfo<'b>,
+    amm_open_orders: &'a AccountInfo<'b>,
+    market: &'a AccountInfo<'b>,
+    market_event_queue: &'a AccountInfo<'b>,
+}
+impl PoolState {
+    fn calculate_swap(&self, amount_in: u64) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        // This is a slightly modified selective code from https://github.com/raydium-io/raydium-amm/blob/2748852a7981c2b6909e07e10b1325669fbb9195/program/src/processor.rs#L2268
+        let swap_fee = U128::from(amount_in)
+            .checked_mul(self.swap_fee_numerator.into())
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_ceil_div(self.swap_fee_denominator.into())
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .0;
+        let swap_in_after_deduct_fee = U128::from(amount_in).checked_sub(swap_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        let amount_out_ = crate::extern_source::raydium_v4::swap_token_amount_base_in(
+            swap_in_after_deduct_fee,
+            self.total_pc_without_take_pnl.into(),
+            self.total_coin_without_take_pnl.into(),
+            self.swap_direction,
+        )
+        .as_u64();
+        let (total_in_without_take_pnl, total_out_without_take_pnl) = match self.swap_direction {
+            SwapDirection::Coin2PC => (self.total_coin_without_take_pnl, self.total_pc_without_take_pnl),
+            SwapDirection::PC2Coin => (self.total_pc_without_take_pnl, self.total_coin_without_take_pnl),
+        };
+        if amount_out_ >= total_out_without_take_pnl {
+            return Ok(None);
+        }
+        Ok(Some(SwapCalculationResult {
+            pool: self.pool,
+            amount_in,
+            amount_in_fee: swap_fee.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?,
+            amount_out: amount_out_,
+            in_reserve: total_in_without_take_pnl,
+            out_reserve: total_out_without_take_pnl,
+        }))
+    }
+    fn calculate_swap_base_out(&self, amount_out: u64) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let (total_in_without_take_pnl, total_out_without_take_pnl) = match self.swap_direction {
+            SwapDirection::Coin2PC => (self.total_coin_without_take_pnl, self.total_pc_without_take_pnl),
+            SwapDirection::PC2Coin => (self.total_pc_without_take_pnl, self.total_coin_without_take_pnl),
+        };
+        if amount_out == 0 || amount_out >= total_out_without_take_pnl {
+            return Ok(None);
+        }
+        // This is a slightly modified selective code from https://github.com/raydium-io/raydium-amm/blob/2748852a7981c2b6909e07e10b1325669fbb9195/program/src/processor.rs#L2368
+        let swap_in_before_add_fee = crate::extern_source::raydium_v4::swap_token_amount_base_out(
+            amount_out.into(),
+            self.total_pc_without_take_pnl.into(),
+            self.total_coin_without_take_pnl.into(),
+            self.swap_direction,
+        );
+        // swap_in_after_add_fee * (1 - 0.0025) = swap_in_before_add_fee
+        // swap_in_after_add_fee = swap_in_before_add_fee / (1 - 0.0025)
+        let swap_in_after_add_fee = swap_in_before_add_fee
+            .checked_mul(self.swap_fee_denominator.into())
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_ceil_div(self.swap_fee_denominator.checked_sub(self.swap_fee_numerator).ok_or(ProgramError::ArithmeticOverflow)?.into())
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .0;
+        let swap_fee = swap_in_after_add_fee.checked_sub(swap_in_before_add_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(Some(SwapCalculationResult {
+            pool: self.pool,
+            amount_in: swap_in_after_add_fee.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?,
+            amount_in_fee: swap_fee.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?,
+            amount_out,
+            in_reserve: total_in_without_take_pnl,
+            out_reserve: total_out_without_take_pnl,
+        }))
+    }
+}
 impl RaydiumV4 {
+    const SWAP_ACCOUNTS_QUANTITY: usize = 16;
     // https://github.com/raydium-io/raydium-amm/blob/2748852a7981c2b6909e07e10b1325669fbb9195/program/src/instruction.rs#L1045
     pub fn create_swap_instruction(
         program_id: &Pubkey,
@@ -59,6 +145,99 @@ impl RaydiumV4 {
         data.push(9);
         data.extend(amount_in.to_le_bytes());
         data.extend(minimum_amount_out.to_le_bytes());
+        Self::create_instruction(
+            program_id,
+            token_program,
+            amm_pool,
+            amm_authority,
+            amm_open_orders,
+            amm_coin_vault,
+            amm_pc_vault,
+            market_program,
+            market,
+            market_bids,
+            market_asks,
+            market_event_queue,
+            market_coin_vault,
+            market_pc_vault,
+            market_vault_signer,
+            user_token_source,
+            user_token_destination,
+            user_source_owner,
+            data,
+        )
+    }
+    // https://github.com/raydium-io/raydium-amm/blob/2748852a7981c2b6909e07e10b1325669fbb9195/program/src/instruction.rs#L1101
+    pub fn create_swap_base_out_instruction(
+        program_id: &Pubkey,
+        token_program: &Pubkey,
+        amm_pool: &Pubkey,
+        amm_authority: &Pubkey,
+        amm_open_orders: &Pubkey,
+        amm_coin_vault: &Pubkey,
+        amm_pc_vault: &Pubkey,
+        market_program: &Pubkey,
+        market: &Pubkey,
+        market_bids: &Pubkey,
+        market_asks: &Pubkey,
+        market_event_queue: &Pubkey,
+        market_coin_vault: &Pubkey,
+        market_pc_vault: &Pubkey,
+        market_vault_signer: &Pubkey,
+        user_token_source: &Pubkey,
+        user_token_destination: &Pubkey,
+        user_source_owner: &Pubkey,
+        max_amount_in: u64,
+        amount_out: u64,
+    ) -> Instruction {
+        let mut data = Vec::<u8>::with_capacity(17);
+        // https://github.com/raydium-io/raydium-amm/blob/2748852a7981c2b6909e07e10b1325669fbb9195/program/src/instruction.rs#L765
+        data.push(11);
+        data.extend(max_amount_in.to_le_bytes());
+        data.extend(amount_out.to_le_bytes());
+        Self::create_instruction(
+            program_id,
+            token_program,
+            amm_pool,
+            amm_authority,
+            amm_open_orders,
+            amm_coin_vault,
+            amm_pc_vault,
+            market_program,
+            market,
+            market_bids,
+            market_asks,
+            market_event_queue,
+            market_coin_vault,
+            market_pc_vault,
+            market_vault_signer,
+            user_token_source,
+            user_token_destination,
+            user_source_owner,
+            data,
+        )
+    }
+    fn create_instruction(
+        program_id: &Pubkey,
+        token_program: &Pubkey,
+        amm_pool: &Pubkey,
+        amm_authority: &Pubkey,
+        amm_open_orders: &Pubkey,
+        amm_coin_vault: &Pubkey,
+        amm_pc_vault: &Pubkey,
+        market_program: &Pubkey,
+        market: &Pubkey,
+        market_bids: &Pubkey,
+        market_asks: &Pubkey,
+        market_event_queue: &Pubkey,
+        market_coin_vault: &Pubkey,
+        market_pc_vault: &Pubkey,
+        market_vault_signer: &Pubkey,
+        user_token_source: &Pubkey,
+        user_token_destination: &Pubkey,
+        user_source_owner: &Pubkey,
+        data: Vec<u8>,
+    ) -> Instruction {
         Instruction {
             program_id: *program_id,
             accounts: vec![
@@ -83,13 +262,9 @@ impl RaydiumV4 {
             data,
         }
     }
-}
-impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for RaydiumV4 {
-    fn get_swap_accounts_quantity(&'a self) -> usize {
-        16
-    }
-    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, first_account_index: usize) -> Result<Option<SwapCalculationResult>, ProgramError> {
-        let account_info_iter = &mut base_data.accounts.iter().skip(first_account_index).take(self.get_swap_accounts_quantity());
+    // Returns None if the pool is not available for swap.
+    fn load_pool_state<'a, 'b, 'c>(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<PoolState>, ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
         let raydium_v4_program_id = solana_program::account_info::next_account_info(account_info_iter)?;
         let clock = solana_program::account_info::next_account_info(account_info_iter)?;
         let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
@@ -123,107 +298,31 @@ impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for RaydiumV4 {
         {
             return Err(Error::InvalidAccountConfigurationFlags.into());
         }
-        // This is a slightly modified selective code from https://github.com/raydium-io/raydium-amm/blob/2748852a7981c2b6909e07e10b1325669fbb9195/program/src/processor.rs#L2210
-        // that calculates the swap possibility.
-        //
-        // In ideal case all structures here should be deserealized from accounts in zero-copy context.
-        let swap_calculation_result = {
-            if *token_program.key != spl_token::ID {
-                return Err(Error::InvalidSplTokenProgram.into());
-            }
-            let mut amm_info = match AmmInfo::load_mut_checked(amm_pool, raydium_v4_program_id.key) {
-                Ok(amm_info_) => amm_info_,
-                Err(error) => {
-                    match error {
-                        ProgramError::Custom(code) => {
-                            if code == Error::InvalidStatus as u32 {
-                                return Ok(None);
-                            } else {
-                                return Err(error);
-                            }
-                        }
-                        _ => return Err(error),
-                    }
-                }
-            };
-            let amm_coin_vault = crate::extern_source::raydium_v4::unpack_token_account(amm_coin_vault, token_program.key)?;
-            let amm_pc_vault = crate::extern_source::raydium_v4::unpack_token_account(amm_pc_vault, token_program.key)?;
-            if !AmmStatus::from_u64(amm_info.status).swap_permission() {
-                let clock_ = Clock::from_account_info(clock)?;
-                if amm_info.status == AmmStatus::OrderBookOnly.into_u64() && (clock_.unix_timestamp as u64) >= amm_info.state_data.orderbook_to_init_time {
-                    amm_info.status = AmmStatus::Initialized.into_u64();
-                } else {
-                    return Ok(None);
-                }
-            } else if amm_info.status == AmmStatus::WaitingTrade.into_u64() {
-                let clock_ = Clock::from_account_info(clock)?;
-                if (clock_.unix_timestamp as u64) < amm_info.state_data.pool_open_time {
-                    return Ok(None);
-                } else {
-                    amm_info.status = AmmStatus::SwapOnly.into_u64();
-                }
-            }
-            let (total_pc_without_take_pnl, total_coin_without_take_pnl) = if AmmStatus::from_u64(amm_info.status).orderbook_permission() {
-                let (market_state, open_orders) = crate::extern_source::raydium_v4::load_serum_market_order(market, amm_open_orders, amm_authority, &amm_info, false)?;
-                // Calculator::calc_total_without_take_pnl() writes logs.
-                // We don't need that, so we use a method that does the same thing but doesn't write logs.
-                crate::extern_source::raydium_v4::calc_total_without_take_pnl(
-                    amm_pc_vault.amount,
-                    amm_coin_vault.amount,
-                    &open_orders,
-                    &amm_info,
-                    &market_state,
-                    market_event_queue,
-                    amm_open_orders,
-                )?
-            } else {
-                crate::extern_source::raydium_v4::calc_total_without_take_pnl_no_orderbook(amm_pc_vault.amount, amm_coin_vault.amount, &amm_info)?
-            };
-            let user_source = crate::extern_source::raydium_v4::unpack_token_account(base_data.quote_token_account, token_program.key)?;
-            let user_destination = crate::extern_source::raydium_v4::unpack_token_account(base_data.token_account, token_program.key)?;
-            let swap_direction = if user_source.mint == amm_coin_vault.mint && user_destination.mint == amm_pc_vault.mint {
-                SwapDirection::Coin2PC
-            } else if user_source.mint == amm_pc_vault.mint && user_destination.mint == amm_coin_vault.mint {
-                SwapDirection::PC2Coin
-            } else {
-                return Err(Error::InvalidUserToken.into());
-            };
-            let swap_fee = U128::from(base_data.amount_in)
-                .checked_mul(amm_info.fees.swap_fee_numerator.into())
-                .ok_or(ProgramError::ArithmeticOverflow)?
-                .checked_ceil_div(amm_info.fees.swap_fee_denominator.into())
-                .ok_or(ProgramError::ArithmeticOverflow)?
-                .0;
-            let swap_in_after_deduct_fee = U128::from(base_data.amount_in).checked_sub(swap_fee).ok_or(ProgramError::ArithmeticOverflow)?;
-            let amount_out_ = crate::extern_source::raydium_v4::swap_token_amount_base_in(
-                swap_in_after_deduct_fee,
-                total_pc_without_take_pnl.into(),
-                total_coin_without_take_pnl.into(),
-                swap_direction,
-            )
-            .as_u64();
-            match swap_direction {
-                SwapDirection::Coin2PC => {
-                    if amount_out_ >= total_pc_without_take_pnl {
-                        return Ok(None);
-                    }
-                }
-                SwapDirection::PC2Coin => {
-                    if amount_out_ >= total_coin_without_take_pnl {
-                        return Ok(None);
-                    }
-                }
-            };
-            SwapCalculationResult {
-                pool: *amm_pool.key,
-                amount_in_fee: swap_fee.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?,
-                amount_out: amount_out_,
-            }
-        };
-        Ok(Some(swap_calculation_result))
+        load_pool_state(
+            base_data,
+            raydium_v4_program_id,
+            clock,
+            token_program,
+            amm_pool,
+            amm_coin_vault,
+            amm_pc_vault,
+            Some(OrderBookAccounts {
+                amm_authority,
+                amm_open_orders,
+                market,
+                market_event_queue,
+            }),
+        )
     }
-    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, first_account_index: usize) -> Result<(), ProgramError> {
-        let account_info_iter = &mut base_data.accounts.iter().skip(first_account_index).take(self.get_swap_accounts_quantity());
+    fn invoke_swap<'a, 'b, 'c>(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        is_base_out: bool,
+        amount: u64,
+        other_amount: u64,
+    ) -> Result<(), ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
         let raydium_v4_program_id = solana_program::account_info::next_account_info(account_info_iter)?;
         let _clock = solana_program::account_info::next_account_info(account_info_iter)?;
         let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
@@ -240,98 +339,434 @@ impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for RaydiumV4 {
         let market_coin_vault = solana_program::account_info::next_account_info(account_info_iter)?;
         let market_pc_vault = solana_program::account_info::next_account_info(account_info_iter)?;
         let market_vault_signer = solana_program::account_info::next_account_info(account_info_iter)?;
-        if base_data.is_from_quote_to_token {
-            solana_program::program::invoke_signed(
-                &Self::create_swap_instruction(
-                    raydium_v4_program_id.key,
-                    token_program.key,
-                    amm_pool.key,
-                    amm_authority.key,
-                    amm_open_orders.key,
-                    amm_coin_vault.key,
-                    amm_pc_vault.key,
-                    market_program.key,
-                    market.key,
-                    market_bids.key,
-                    market_asks.key,
-                    market_event_queue.key,
-                    market_coin_vault.key,
-                    market_pc_vault.key,
-                    market_vault_signer.key,
-                    base_data.quote_token_account.key,
-                    base_data.token_account.key,
-                    base_data.self_authority.key,
-                    base_data.amount_in,
-                    base_data.min_amount_out,
-                ),
-                vec![
-                    token_program.clone(),
-                    amm_pool.clone(),
-                    amm_authority.clone(),
-                    amm_open_orders.clone(),
-                    amm_coin_vault.clone(),
-                    amm_pc_vault.clone(),
-                    market_program.clone(),
-                    market.clone(),
-                    market_bids.clone(),
-                    market_asks.clone(),
-                    market_event_queue.clone(),
-                    market_coin_vault.clone(),
-                    market_pc_vault.clone(),
-                    market_vault_signer.clone(),
-                    base_data.quote_token_account.clone(),
-                    base_data.token_account.clone(),
-                    base_data.self_authority.clone(),
-                ]
-                .as_slice(),
-                [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
+        let (user_source, user_destination) = if base_data.is_from_quote_to_token {
+            (base_data.quote_token_account, base_data.token_account)
+        } else {
+            (base_data.token_account, base_data.quote_token_account)
+        };
+        let instruction = if is_base_out {
+            Self::create_swap_base_out_instruction(
+                raydium_v4_program_id.key,
+                token_program.key,
+                amm_pool.key,
+                amm_authority.key,
+                amm_open_orders.key,
+                amm_coin_vault.key,
+                amm_pc_vault.key,
+                market_program.key,
+                market.key,
+                market_bids.key,
+                market_asks.key,
+                market_event_queue.key,
+                market_coin_vault.key,
+                market_pc_vault.key,
+                market_vault_signer.key,
+                user_source.key,
+                user_destination.key,
+                base_data.self_authority.key,
+                amount,
+                other_amount,
             )
         } else {
-            solana_program::program::invoke_signed(
-                &Self::create_swap_instruction(
-                    raydium_v4_program_id.key,
-                    token_program.key,
-                    amm_pool.key,
-                    amm_authority.key,
-                    amm_open_orders.key,
-                    amm_coin_vault.key,
-                    amm_pc_vault.key,
-                    market_program.key,
-                    market.key,
-                    market_bids.key,
-                    market_asks.key,
-                    market_event_queue.key,
-                    market_coin_vault.key,
-                    market_pc_vault.key,
-                    market_vault_signer.key,
-                    base_data.token_account.key,
-                    base_data.quote_token_account.key,
-                    base_data.self_authority.key,
-                    base_data.amount_in,
-                    base_data.min_amount_out,
-                ),
-                vec![
-                    token_program.clone(),
-                    amm_pool.clone(),
-                    amm_authority.clone(),
-                    amm_open_orders.clone(),
-                    amm_coin_vault.clone(),
-                    amm_pc_vault.clone(),
-                    market_program.clone(),
-                    market.clone(),
-                    market_bids.clone(),
-                    market_asks.clone(),
-                    market_event_queue.clone(),
-                    market_coin_vault.clone(),
-                    market_pc_vault.clone(),
-                    market_vault_signer.clone(),
-                    base_data.token_account.clone(),
-                    base_data.quote_token_account.clone(),
-                    base_data.self_authority.clone(),
-                ]
-                .as_slice(),
-                [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
+            Self::create_swap_instruction(
+                raydium_v4_program_id.key,
+                token_program.key,
+                amm_pool.key,
+                amm_authority.key,
+                amm_open_orders.key,
+                amm_coin_vault.key,
+                amm_pc_vault.key,
+                market_program.key,
+                market.key,
+                market_bids.key,
+                market_asks.key,
+                market_event_queue.key,
+                market_coin_vault.key,
+                market_pc_vault.key,
+                market_vault_signer.key,
+                user_source.key,
+                user_destination.key,
+                base_data.self_authority.key,
+                amount,
+                other_amount,
             )
+        };
+        solana_program::program::invoke_signed(
+            &instruction,
+            vec![
+                token_program.clone(),
+                amm_pool.clone(),
+                amm_authority.clone(),
+                amm_open_orders.clone(),
+                amm_coin_vault.clone(),
+                amm_pc_vault.clone(),
+                market_program.clone(),
+                market.clone(),
+                market_bids.clone(),
+                market_asks.clone(),
+                market_event_queue.clone(),
+                market_coin_vault.clone(),
+                market_pc_vault.clone(),
+                market_vault_signer.clone(),
+                user_source.clone(),
+                user_destination.clone(),
+                base_data.self_authority.clone(),
+            ]
+            .as_slice(),
+            [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
+        )
+    }
+}
+impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for RaydiumV4 {
+    fn get_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_max_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_pool_account_index(&'a self) -> usize {
+        3
+    }
+    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        pool_state.calculate_swap(base_data.amount_in)
+    }
+    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, false, base_data.amount_in, base_data.min_amount_out)
+    }
+    fn do_swap_calculation_base_out(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        pool_state.calculate_swap_base_out(base_data.min_amount_out)
+    }
+    fn do_swap_base_out(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        _swap_calculation_result: &SwapCalculationResult,
+    ) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, true, base_data.amount_in, base_data.min_amount_out)
+    }
+}
+impl RaydiumV4SwapOnly {
+    const SWAP_ACCOUNTS_QUANTITY: usize = 7;
+    // https://github.com/raydium-io/raydium-amm/blob/master/program/src/instruction.rs
+    //
+    // The 'SwapBaseInV2' instruction.
+    pub fn create_swap_instruction(
+        program_id: &Pubkey,
+        token_program: &Pubkey,
+        amm_pool: &Pubkey,
+        amm_authority: &Pubkey,
+        amm_coin_vault: &Pubkey,
+        amm_pc_vault: &Pubkey,
+        user_token_source: &Pubkey,
+        user_token_destination: &Pubkey,
+        user_source_owner: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+    ) -> Instruction {
+        let mut data = Vec::<u8>::with_capacity(17);
+        data.push(16);
+        data.extend(amount_in.to_le_bytes());
+        data.extend(minimum_amount_out.to_le_bytes());
+        Self::create_instruction(
+            program_id,
+            token_program,
+            amm_pool,
+            amm_authority,
+            amm_coin_vault,
+            amm_pc_vault,
+            user_token_source,
+            user_token_destination,
+            user_source_owner,
+            data,
+        )
+    }
+    // https://github.com/raydium-io/raydium-amm/blob/master/program/src/instruction.rs
+    //
+    // The 'SwapBaseOutV2' instruction.
+    pub fn create_swap_base_out_instruction(
+        program_id: &Pubkey,
+        token_program: &Pubkey,
+        amm_pool: &Pubkey,
+        amm_authority: &Pubkey,
+        amm_coin_vault: &Pubkey,
+        amm_pc_vault: &Pubkey,
+        user_token_source: &Pubkey,
+        user_token_destination: &Pubkey,
+        user_source_owner: &Pubkey,
+        max_amount_in: u64,
+        amount_out: u64,
+    ) -> Instruction {
+        let mut data = Vec::<u8>::with_capacity(17);
+        data.push(17);
+        data.extend(max_amount_in.to_le_bytes());
+        data.extend(amount_out.to_le_bytes());
+        Self::create_instruction(
+            program_id,
+            token_program,
+            amm_pool,
+            amm_authority,
+            amm_coin_vault,
+            amm_pc_vault,
+            user_token_source,
+            user_token_destination,
+            user_source_owner,
+            data,
+        )
+    }
+    fn create_instruction(
+        program_id: &Pubkey,
+        token_program: &Pubkey,
+        amm_pool: &Pubkey,
+        amm_authority: &Pubkey,
+        amm_coin_vault: &Pubkey,
+        amm_pc_vault: &Pubkey,
+        user_token_source: &Pubkey,
+        user_token_destination: &Pubkey,
+        user_source_owner: &Pubkey,
+        data: Vec<u8>,
+    ) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*token_program, false),
+                AccountMeta::new(*amm_pool, false),
+                AccountMeta::new_readonly(*amm_authority, false),
+                AccountMeta::new(*amm_coin_vault, false),
+                AccountMeta::new(*amm_pc_vault, false),
+                AccountMeta::new(*user_token_source, false),
+                AccountMeta::new(*user_token_destination, false),
+                AccountMeta::new_readonly(*user_source_owner, true),
+            ],
+            data,
+        }
+    }
+    // Returns None if the pool is not available for swap or has the orderbook.
+    fn load_pool_state<'a, 'b, 'c>(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<PoolState>, ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let raydium_v4_program_id = solana_program::account_info::next_account_info(account_info_iter)?;
+        let clock = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let amm_pool = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _amm_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let amm_coin_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let amm_pc_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        if *raydium_v4_program_id.key != RAYDIUM_V4_PROGRAM_ID {
+            return Err(Error::InvalidAccountPubkey.into());
         }
+        if base_data.with_checks && (!amm_pool.is_writable || !amm_coin_vault.is_writable || !amm_pc_vault.is_writable) {
+            return Err(Error::InvalidAccountConfigurationFlags.into());
+        }
+        load_pool_state(base_data, raydium_v4_program_id, clock, token_program, amm_pool, amm_coin_vault, amm_pc_vault, None)
+    }
+    fn invoke_swap<'a, 'b, 'c>(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        is_base_out: bool,
+        amount: u64,
+        other_amount: u64,
+    ) -> Result<(), ProgramError> {
+        let account_info_iter = &mut dex_accounts.iter();
+        let raydium_v4_program_id = solana_program::account_info::next_account_info(account_info_iter)?;
+        let _clock = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let amm_pool = solana_program::account_info::next_account_info(account_info_iter)?;
+        let amm_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let amm_coin_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let amm_pc_vault = solana_program::account_info::next_account_info(account_info_iter)?;
+        let (user_source, user_destination) = if base_data.is_from_quote_to_token {
+            (base_data.quote_token_account, base_data.token_account)
+        } else {
+            (base_data.token_account, base_data.quote_token_account)
+        };
+        let instruction = if is_base_out {
+            Self::create_swap_base_out_instruction(
+                raydium_v4_program_id.key,
+                token_program.key,
+                amm_pool.key,
+                amm_authority.key,
+                amm_coin_vault.key,
+                amm_pc_vault.key,
+                user_source.key,
+                user_destination.key,
+                base_data.self_authority.key,
+                amount,
+                other_amount,
+            )
+        } else {
+            Self::create_swap_instruction(
+                raydium_v4_program_id.key,
+                token_program.key,
+                amm_pool.key,
+                amm_authority.key,
+                amm_coin_vault.key,
+                amm_pc_vault.key,
+                user_source.key,
+                user_destination.key,
+                base_data.self_authority.key,
+                amount,
+                other_amount,
+            )
+        };
+        solana_program::program::invoke_signed(
+            &instruction,
+            vec![
+                token_program.clone(),
+                amm_pool.clone(),
+                amm_authority.clone(),
+                amm_coin_vault.clone(),
+                amm_pc_vault.clone(),
+                user_source.clone(),
+                user_destination.clone(),
+                base_data.self_authority.clone(),
+            ]
+            .as_slice(),
+            [PdaResolver::self_authority_get_seeds(base_data.intermediary.key, [base_data.intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
+        )
     }
 }
+impl<'a, 'b, 'c> Dex<'a, 'b, 'c> for RaydiumV4SwapOnly {
+    fn get_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_max_swap_accounts_quantity(&'a self) -> usize {
+        Self::SWAP_ACCOUNTS_QUANTITY
+    }
+    fn get_pool_account_index(&'a self) -> usize {
+        3
+    }
+    fn do_swap_calculation(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        pool_state.calculate_swap(base_data.amount_in)
+    }
+    fn do_swap(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, false, base_data.amount_in, base_data.min_amount_out)
+    }
+    fn do_swap_calculation_base_out(&'a self, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        let pool_state = match self.load_pool_state(base_data, dex_accounts)? {
+            Some(pool_state_) => pool_state_,
+            None => return Ok(None),
+        };
+        pool_state.calculate_swap_base_out(base_data.min_amount_out)
+    }
+    fn do_swap_base_out(
+        &'a self,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        _swap_calculation_result: &SwapCalculationResult,
+    ) -> Result<(), ProgramError> {
+        self.invoke_swap(base_data, dex_accounts, true, base_data.amount_in, base_data.min_amount_out)
+    }
+}
+// The pool with the orderbook needs the market accounts to calculate its total amounts.
+// Returns None if the pool is not available for swap or needs the absent market accounts.
+fn load_pool_state<'a, 'b>(
+    base_data: &BaseData,
+    raydium_v4_program_id: &'a AccountInfo<'b>,
+    clock: &'a AccountInfo<'b>,
+    token_program: &'a AccountInfo<'b>,
+    amm_pool: &'a AccountInfo<'b>,
+    amm_coin_vault: &'a AccountInfo<'b>,
+    amm_pc_vault: &'a AccountInfo<'b>,
+    order_book_accounts: Option<OrderBookAccounts<'a, 'b>>,
+) -> Result<Option<PoolState>, ProgramError> {
+    // This is a slightly modified selective code from https://github.com/raydium-io/raydium-amm/blob/2748852a7981c2b6909e07e10b1325669fbb9195/program/src/processor.rs#L2210
+    // that loads the pool state for swap calculation.
+    //
+    // In ideal case all structures here should be deserealized from accounts in zero-copy context.
+    let pool_state = {
+        if *token_program.key != spl_token::ID {
+            return Err(Error::InvalidSplTokenProgram.into());
+        }
+        let mut amm_info = match AmmInfo::load_mut_checked(amm_pool, raydium_v4_program_id.key) {
+            Ok(amm_info_) => amm_info_,
+            Err(error) => {
+                match error {
+                    ProgramError::Custom(code) => {
+                        if code == Error::InvalidStatus as u32 {
+                            return Ok(None);
+                        } else {
+                            return Err(error);
+                        }
+                    }
+                    _ => return Err(error),
+                }
+            }
+        };
+        let amm_coin_vault = crate::extern_source::raydium_v4::unpack_token_account(amm_coin_vault, token_program.key)?;
+        let amm_pc_vault = crate::extern_source::raydium_v4::unpack_token_account(amm_pc_vault, token_program.key)?;
+        if !AmmStatus::from_u64(amm_info.status).swap_permission() {
+            let clock_ = Clock::from_account_info(clock)?;
+            if amm_info.status == AmmStatus::OrderBookOnly.into_u64() && (clock_.unix_timestamp as u64) >= amm_info.state_data.orderbook_to_init_time {
+                amm_info.status = AmmStatus::Initialized.into_u64();
+            } else {
+                return Ok(None);
+            }
+        } else if amm_info.status == AmmStatus::WaitingTrade.into_u64() {
+            let clock_ = Clock::from_account_info(clock)?;
+            if (clock_.unix_timestamp as u64) < amm_info.state_data.pool_open_time {
+                return Ok(None);
+            } else {
+                amm_info.status = AmmStatus::SwapOnly.into_u64();
+            }
+        }
+        let (total_pc_without_take_pnl, total_coin_without_take_pnl) = if AmmStatus::from_u64(amm_info.status).orderbook_permission() {
+            let OrderBookAccounts {
+                amm_authority,
+                amm_open_orders,
+                market,
+                market_event_queue,
+            } = match order_book_accounts {
+                Some(order_book_accounts_) => order_book_accounts_,
+                None => return Ok(None),
+            };
+            let (market_state, open_orders) = crate::extern_source::raydium_v4::load_serum_market_order(market, amm_open_orders, amm_authority, &amm_info, false)?;
+            // Calculator::calc_total_without_take_pnl() writes logs.
+            // We don't need that, so we use a method that does the same thing but doesn't write logs.
+            crate::extern_source::raydium_v4::calc_total_without_take_pnl(
+                amm_pc_vault.amount,
+                amm_coin_vault.amount,
+                &open_orders,
+                &amm_info,
+                &market_state,
+                market_event_queue,
+                amm_open_orders,
+            )?
+        } else {
+            crate::extern_source::raydium_v4::calc_total_without_take_pnl_no_orderbook(amm_pc_vault.amount, amm_coin_vault.amount, &amm_info)?
+        };
+        // The direction is determined by mints, so the user token accounts are not required to exist.
+        let (in_mint, out_mint) = if base_data.is_from_quote_to_token {
+            (base_data.quote_mint, base_data.token_mint)
+        } else {
+            (base_data.token_mint, base_data.quote_mint)
+        };
+        let swap_direction = if *in_mint == amm_coin_vault.mint && *out_mint == amm_pc_vault.mint {
+            SwapDirection::Coin2PC
+        } else if *in_mint == amm_pc_vault.mint && *out_mint == amm_coin_vault.mint {
+            SwapDirection::PC2Coin
+        } else {
+            return Err(Error::InvalidUserToken.into());
+        };
+        PoolState {
+            pool: *amm_pool.key,
+            swap_fee_numerator: amm_info.fees.swap_fee_numerator,
+            swap_fee_denominator: amm_info.fees.swap_fee_denominator,
+            total_pc_without_take_pnl,
+            total_coin_without_take_pnl,
+            swap_direction,
+        }
+    };
+    Ok(Some(pool_state))
+}
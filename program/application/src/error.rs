@@ -49,6 +49,14 @@ pub enum Error {
     InvalidUserToken,
     InvalidFee,
     WrongEventQueueAccount,
+    InvalidSwapSplit,
+    ZeroHopsPresented,
+    InvalidDexAccountsRange,
+    SwapExpired,
+    PriceDeviationExceeded,
+    IntermediaryInvalidQuoteTokenAccount,
+    QuoteMintIsNotRegistered,
+    ZeroTokenMintsPresented,
 }
 impl Display for Error {
     fn fmt(&self, _: &mut Formatter<'_>) -> FmtResult {
@@ -105,6 +113,14 @@ impl ToStr for Error {
             Self::InvalidUserToken => "InvalidUserToken",
             Self::InvalidFee => "InvalidFee",
             Self::WrongEventQueueAccount => "WrongEventQueueAccount",
+            Self::InvalidSwapSplit => "InvalidSwapSplit",
+            Self::ZeroHopsPresented => "ZeroHopsPresented",
+            Self::InvalidDexAccountsRange => "InvalidDexAccountsRange",
+            Self::SwapExpired => "SwapExpired",
+            Self::PriceDeviationExceeded => "PriceDeviationExceeded",
+            Self::IntermediaryInvalidQuoteTokenAccount => "IntermediaryInvalidQuoteTokenAccount",
+            Self::QuoteMintIsNotRegistered => "QuoteMintIsNotRegistered",
+            Self::ZeroTokenMintsPresented => "ZeroTokenMintsPresented",
         }
     }
 }
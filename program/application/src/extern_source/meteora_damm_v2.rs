@@ -0,0 +1,456 @@
+#![allow(clippy::manual_div_ceil)]
+use {
+    solana_program::pubkey::Pubkey,
+    uint::construct_uint,
+};
+// https://github.com/MeteoraAg/damm-v2/blob/main/programs/cp-amm/src/constants.rs
+pub const RESOLUTION: u8 = 64;
+pub const ONE_Q64: u128 = 1u128 << RESOLUTION;
+pub const BASIS_POINT_MAX: u64 = 10_000;
+pub const FEE_DENOMINATOR: u64 = 1_000_000_000;
+pub const MAX_FEE_NUMERATOR: u64 = 500_000_000;
+pub const MAX_EXPONENTIAL: u32 = 0x80000;
+construct_uint! {
+    pub struct U256(4);
+}
+// https://github.com/MeteoraAg/damm-v2/blob/main/programs/cp-amm/src/state/fee.rs
+#[derive(borsh::BorshDeserialize)]
+pub struct BaseFeeStruct {
+    pub cliff_fee_numerator: u64,
+    pub fee_scheduler_mode: u8,
+    pub padding_0: [u8; 5],
+    pub number_of_period: u16,
+    pub period_frequency: u64,
+    pub reduction_factor: u64,
+    pub padding_1: u64,
+}
+impl BaseFeeStruct {
+    pub fn get_current_base_fee_numerator(&self, current_point: u64, activation_point: u64) -> Option<u64> {
+        if self.period_frequency == 0 {
+            return Some(self.cliff_fee_numerator);
+        }
+        // can trade before activation point, so it is alpha-vault, we use min fee
+        let period = if current_point < activation_point {
+            self.number_of_period.into()
+        } else {
+            let period = current_point.checked_sub(activation_point)?.checked_div(self.period_frequency)?;
+            period.min(self.number_of_period.into())
+        };
+        match FeeSchedulerMode::try_from(self.fee_scheduler_mode).ok()? {
+            FeeSchedulerMode::Linear => {
+                let fee_numerator = self.cliff_fee_numerator.checked_sub(self.reduction_factor.checked_mul(period)?)?;
+                Some(fee_numerator)
+            }
+            FeeSchedulerMode::Exponential => {
+                let period = u16::try_from(period).ok()?;
+                get_fee_in_period(self.cliff_fee_numerator, self.reduction_factor, period)
+            }
+        }
+    }
+}
+// https://github.com/MeteoraAg/damm-v2/blob/main/programs/cp-amm/src/base_fee/fee_scheduler.rs
+pub enum FeeSchedulerMode {
+    // fee = cliff_fee_numerator - passed_period * reduction_factor
+    Linear,
+    // fee = cliff_fee_numerator * (1-reduction_factor/10_000)^passed_period
+    Exponential,
+}
+impl TryFrom<u8> for FeeSchedulerMode {
+    type Error = String;
+    fn try_from(s: u8) -> std::result::Result<FeeSchedulerMode, String> {
+        match s {
+            0 => Ok(FeeSchedulerMode::Linear),
+            1 => Ok(FeeSchedulerMode::Exponential),
+            _ => Err("Invalid value".to_string()),
+        }
+    }
+}
+pub fn get_fee_in_period(cliff_fee_numerator: u64, reduction_factor: u64, passed_period: u16) -> Option<u64> {
+    if reduction_factor == 0 {
+        return Some(cliff_fee_numerator);
+    }
+    // Make bin_step into Q64x64, and divided by BASIS_POINT_MAX. If bin_step = 1, we get 0.0001 in Q64x64
+    let bps = u128::from(reduction_factor).checked_shl(RESOLUTION.into())?.checked_div(BASIS_POINT_MAX.into())?;
+    let base = ONE_Q64.checked_sub(bps)?;
+    let result = pow(base, passed_period.into())?;
+    let (fee, _) = result.checked_mul(cliff_fee_numerator.into())?.overflowing_shr(RESOLUTION.into());
+    u64::try_from(fee).ok()
+}
+#[derive(borsh::BorshDeserialize)]
+pub struct DynamicFeeStruct {
+    pub initialized: u8,
+    pub padding: [u8; 7],
+    pub max_volatility_accumulator: u32,
+    pub variable_fee_control: u32,
+    pub bin_step: u16,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub last_update_timestamp: u64,
+    pub bin_step_u128: u128,
+    pub sqrt_price_reference: u128,
+    pub volatility_accumulator: u128,
+    pub volatility_reference: u128,
+}
+impl DynamicFeeStruct {
+    pub fn is_dynamic_fee_enable(&self) -> bool {
+        self.initialized != 0
+    }
+    pub fn get_variable_fee(&self) -> Option<u128> {
+        if self.is_dynamic_fee_enable() {
+            let square_vfa_bin: u128 = self.volatility_accumulator.checked_mul(self.bin_step.into())?.checked_pow(2)?;
+            // Variable fee control, volatility accumulator, bin step are in basis point unit (10_000)
+            // This is 1e20. Which > 1e9. Scale down it to 1e9 unit and ceiling the remaining.
+            let v_fee = square_vfa_bin.checked_mul(self.variable_fee_control.into())?;
+            let scaled_v_fee = v_fee.checked_add(99_999_999_999)?.checked_div(100_000_000_000)?;
+            Some(scaled_v_fee)
+        } else {
+            Some(0)
+        }
+    }
+}
+#[derive(borsh::BorshDeserialize)]
+pub struct PoolFeesStruct {
+    pub base_fee: BaseFeeStruct,
+    pub protocol_fee_percent: u8,
+    pub partner_fee_percent: u8,
+    pub referral_fee_percent: u8,
+    pub padding_0: [u8; 5],
+    pub dynamic_fee: DynamicFeeStruct,
+    pub padding_1: [u64; 2],
+}
+impl PoolFeesStruct {
+    pub fn get_total_trading_fee(&self, current_point: u64, activation_point: u64) -> Option<u128> {
+        let base_fee_numerator = self.base_fee.get_current_base_fee_numerator(current_point, activation_point)?;
+        let total_fee_numerator = self.dynamic_fee.get_variable_fee()?.checked_add(base_fee_numerator.into())?;
+        Some(total_fee_numerator)
+    }
+    pub fn get_trade_fee_numerator(&self, current_point: u64, activation_point: u64) -> Option<u64> {
+        let trade_fee_numerator = self.get_total_trading_fee(current_point, activation_point)?;
+        if trade_fee_numerator > MAX_FEE_NUMERATOR.into() {
+            Some(MAX_FEE_NUMERATOR)
+        } else {
+            trade_fee_numerator.try_into().ok()
+        }
+    }
+    // Returns the amount after the trading fee and the trading fee.
+    pub fn get_fee_on_amount(&self, amount: u64, current_point: u64, activation_point: u64) -> Option<(u64, u64)> {
+        let trade_fee_numerator = self.get_trade_fee_numerator(current_point, activation_point)?;
+        let lp_fee = mul_div_u64(amount, trade_fee_numerator, FEE_DENOMINATOR, Rounding::Up)?;
+        // update amount
+        let amount = amount.checked_sub(lp_fee)?;
+        Some((amount, lp_fee))
+    }
+}
+// https://github.com/MeteoraAg/damm-v2/blob/main/programs/cp-amm/src/state/pool.rs
+#[derive(borsh::BorshDeserialize)]
+pub struct Pool {
+    /// Pool fee
+    pub pool_fees: PoolFeesStruct,
+    /// token a mint
+    pub token_a_mint: Pubkey,
+    /// token b mint
+    pub token_b_mint: Pubkey,
+    /// token a vault
+    pub token_a_vault: Pubkey,
+    /// token b vault
+    pub token_b_vault: Pubkey,
+    /// Whitelisted vault to be able to buy pool before activation_point
+    pub whitelisted_vault: Pubkey,
+    /// partner
+    pub partner: Pubkey,
+    /// liquidity share
+    pub liquidity: u128,
+    /// padding, previous reserve amount, be careful to use that field
+    pub _padding: u128,
+    /// protocol a fee
+    pub protocol_a_fee: u64,
+    /// protocol b fee
+    pub protocol_b_fee: u64,
+    /// partner a fee
+    pub partner_a_fee: u64,
+    /// partner b fee
+    pub partner_b_fee: u64,
+    /// min price
+    pub sqrt_min_price: u128,
+    /// max price
+    pub sqrt_max_price: u128,
+    /// current price
+    pub sqrt_price: u128,
+    /// Activation point, can be slot or timestamp
+    pub activation_point: u64,
+    /// Activation type, 0 means by slot, 1 means by timestamp
+    pub activation_type: u8,
+    /// pool status, 0: enable, 1 disable
+    pub pool_status: u8,
+    /// token a flag
+    pub token_a_flag: u8,
+    /// token b flag
+    pub token_b_flag: u8,
+    /// 0 is collect fee in both token, 1 only collect fee in token b
+    pub collect_fee_mode: u8,
+    /// pool type
+    pub pool_type: u8,
+}
+impl Pool {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        241,
+        154,
+        109,
+        4,
+        17,
+        177,
+        109,
+        188,
+    ];
+    // Returns the output amount and the next square root price.
+    pub fn get_swap_result_from_a_to_b(&self, amount_in: u64) -> Option<(u64, u128)> {
+        // finding new target price
+        let next_sqrt_price = get_next_sqrt_price_from_input(self.sqrt_price, self.liquidity, amount_in, true)?;
+        if next_sqrt_price < self.sqrt_min_price {
+            return None;
+        }
+        // finding output amount
+        let output_amount = get_delta_amount_b_unsigned(next_sqrt_price, self.sqrt_price, self.liquidity, Rounding::Down)?;
+        Some((output_amount, next_sqrt_price))
+    }
+    pub fn get_swap_result_from_b_to_a(&self, amount_in: u64) -> Option<(u64, u128)> {
+        // finding new target price
+        let next_sqrt_price = get_next_sqrt_price_from_input(self.sqrt_price, self.liquidity, amount_in, false)?;
+        if next_sqrt_price > self.sqrt_max_price {
+            return None;
+        }
+        // finding output amount
+        let output_amount = get_delta_amount_a_unsigned(self.sqrt_price, next_sqrt_price, self.liquidity, Rounding::Down)?;
+        Some((output_amount, next_sqrt_price))
+    }
+}
+pub enum CollectFeeMode {
+    BothToken,
+    OnlyB,
+}
+impl TryFrom<u8> for CollectFeeMode {
+    type Error = String;
+    fn try_from(s: u8) -> std::result::Result<CollectFeeMode, String> {
+        match s {
+            0 => Ok(CollectFeeMode::BothToken),
+            1 => Ok(CollectFeeMode::OnlyB),
+            _ => Err("Invalid value".to_string()),
+        }
+    }
+}
+pub enum PoolStatus {
+    Enable,
+    Disable,
+}
+impl TryFrom<u8> for PoolStatus {
+    type Error = String;
+    fn try_from(s: u8) -> std::result::Result<PoolStatus, String> {
+        match s {
+            0 => Ok(PoolStatus::Enable),
+            1 => Ok(PoolStatus::Disable),
+            _ => Err("Invalid value".to_string()),
+        }
+    }
+}
+pub enum ActivationType {
+    Slot,
+    Timestamp,
+}
+impl TryFrom<u8> for ActivationType {
+    type Error = String;
+    fn try_from(s: u8) -> std::result::Result<ActivationType, String> {
+        match s {
+            0 => Ok(ActivationType::Slot),
+            1 => Ok(ActivationType::Timestamp),
+            _ => Err("Invalid value".to_string()),
+        }
+    }
+}
+// https://github.com/MeteoraAg/damm-v2/blob/main/programs/cp-amm/src/state/fee.rs
+//
+// Returns if the fee is charged on the input token.
+pub fn is_fees_on_input(collect_fee_mode: &CollectFeeMode, a_to_b: bool) -> bool {
+    match (collect_fee_mode, a_to_b) {
+        // When collecting fees on output token
+        (CollectFeeMode::BothToken, _) => false,
+        // When collecting fees on tokenB
+        (CollectFeeMode::OnlyB, true) => false,
+        (CollectFeeMode::OnlyB, false) => true,
+    }
+}
+// https://github.com/MeteoraAg/damm-v2/blob/main/programs/cp-amm/src/curve.rs
+/// Gets the delta amount_a for given liquidity and price range
+///
+/// # Formula
+///
+/// * `Δa = L * (1 / √P_lower - 1 / √P_upper)`
+/// * i.e. `L * (√P_upper - √P_lower) / (√P_upper * √P_lower)`
+pub fn get_delta_amount_a_unsigned(lower_sqrt_price: u128, upper_sqrt_price: u128, liquidity: u128, round: Rounding) -> Option<u64> {
+    let numerator_1 = U256::from(liquidity);
+    let numerator_2 = U256::from(upper_sqrt_price.checked_sub(lower_sqrt_price)?);
+    let denominator = U256::from(lower_sqrt_price).checked_mul(U256::from(upper_sqrt_price))?;
+    let result = mul_div_u256(numerator_1, numerator_2, denominator, round)?;
+    if result > U256::from(u64::MAX) {
+        return None;
+    }
+    Some(result.low_u64())
+}
+/// Gets the delta amount_b for given liquidity and price range
+/// * `Δb = L (√P_upper - √P_lower)`
+pub fn get_delta_amount_b_unsigned(lower_sqrt_price: u128, upper_sqrt_price: u128, liquidity: u128, round: Rounding) -> Option<u64> {
+    let liquidity = U256::from(liquidity);
+    let delta_sqrt_price = U256::from(upper_sqrt_price.checked_sub(lower_sqrt_price)?);
+    let prod = liquidity.checked_mul(delta_sqrt_price)?;
+    let result = match round {
+        Rounding::Up => {
+            let denominator = U256::one() << ((RESOLUTION as usize) * 2);
+            (prod + denominator - U256::one()) / denominator
+        }
+        Rounding::Down => prod >> ((RESOLUTION as usize) * 2),
+    };
+    if result > U256::from(u64::MAX) {
+        return None;
+    }
+    Some(result.low_u64())
+}
+/// Gets the next sqrt price given an input amount of token_a or token_b
+/// Throws if price or liquidity are 0, or if the next price is out of bounds
+pub fn get_next_sqrt_price_from_input(sqrt_price: u128, liquidity: u128, amount_in: u64, a_for_b: bool) -> Option<u128> {
+    if sqrt_price == 0 || liquidity == 0 {
+        return None;
+    }
+    // round to make sure that we don't pass the target price
+    if a_for_b {
+        get_next_sqrt_price_from_amount_a_rounding_up(sqrt_price, liquidity, amount_in)
+    } else {
+        get_next_sqrt_price_from_amount_b_rounding_down(sqrt_price, liquidity, amount_in)
+    }
+}
+/// Gets the next sqrt price given an output amount of token_a or token_b
+/// Throws if price or liquidity are 0, or if the output amount exceeds the liquidity
+pub fn get_next_sqrt_price_from_output(sqrt_price: u128, liquidity: u128, amount_out: u64, a_for_b: bool) -> Option<u128> {
+    if sqrt_price == 0 || liquidity == 0 {
+        return None;
+    }
+    // round to make sure that we pass the target price
+    if a_for_b {
+        get_next_sqrt_price_from_amount_b_out_rounding_down(sqrt_price, liquidity, amount_out)
+    } else {
+        get_next_sqrt_price_from_amount_a_out_rounding_up(sqrt_price, liquidity, amount_out)
+    }
+}
+/// Gets the next sqrt price √P' given a removed delta of token_a
+///
+/// `√P' = √P * L / (L - Δa * √P)`
+pub fn get_next_sqrt_price_from_amount_a_out_rounding_up(sqrt_price: u128, liquidity: u128, amount: u64) -> Option<u128> {
+    if amount == 0 {
+        return Some(sqrt_price);
+    }
+    let sqrt_price = U256::from(sqrt_price);
+    let liquidity = U256::from(liquidity);
+    let product = U256::from(amount).checked_mul(sqrt_price)?;
+    let denominator = liquidity.checked_sub(product)?;
+    let result = mul_div_u256(liquidity, sqrt_price, denominator, Rounding::Up)?;
+    u128::try_from(result).ok()
+}
+/// Gets the next sqrt price given a removed delta of token_b
+///
+/// `√P' = √P - Δb / L`
+pub fn get_next_sqrt_price_from_amount_b_out_rounding_down(sqrt_price: u128, liquidity: u128, amount: u64) -> Option<u128> {
+    let quotient = mul_div_u256(U256::from(amount), U256::one() << ((RESOLUTION * 2) as usize), U256::from(liquidity), Rounding::Up)?;
+    let result = U256::from(sqrt_price).checked_sub(quotient)?;
+    u128::try_from(result).ok()
+}
+/// Gets the next sqrt price √P' given a delta of token_a
+///
+/// `√P' = √P * L / (L + Δa * √P)`
+pub fn get_next_sqrt_price_from_amount_a_rounding_up(sqrt_price: u128, liquidity: u128, amount: u64) -> Option<u128> {
+    if amount == 0 {
+        return Some(sqrt_price);
+    }
+    let sqrt_price = U256::from(sqrt_price);
+    let liquidity = U256::from(liquidity);
+    let product = U256::from(amount).checked_mul(sqrt_price)?;
+    let denominator = liquidity.checked_add(product)?;
+    let result = mul_div_u256(liquidity, sqrt_price, denominator, Rounding::Up)?;
+    u128::try_from(result).ok()
+}
+/// Gets the next sqrt price given a delta of token_b
+///
+/// `√P' = √P + Δb / L`
+pub fn get_next_sqrt_price_from_amount_b_rounding_down(sqrt_price: u128, liquidity: u128, amount: u64) -> Option<u128> {
+    let quotient = (U256::from(amount) << ((RESOLUTION * 2) as usize)).checked_div(U256::from(liquidity))?;
+    let result = U256::from(sqrt_price).checked_add(quotient)?;
+    u128::try_from(result).ok()
+}
+// https://github.com/MeteoraAg/damm-v2/blob/main/programs/cp-amm/src/math/u64x64_math.rs
+pub fn pow(base: u128, exp: i32) -> Option<u128> {
+    // If exponent is negative. We will invert the result later by 1 / base^exp.abs()
+    let mut invert = exp.is_negative();
+    // When exponential is 0, result will always be 1
+    if exp == 0 {
+        return Some(1u128 << 64);
+    }
+    // Make the exponential positive. Which will compute the result later by 1 / base^exp
+    let exp: u32 = if invert {
+        exp.unsigned_abs()
+    } else {
+        exp as u32
+    };
+    // No point to continue the calculation as it will overflow the maximum value Q64.64 can support
+    if exp >= MAX_EXPONENTIAL {
+        return None;
+    }
+    let mut squared_base = base;
+    let mut result = ONE_Q64;
+    // When multiply the base twice, the number of bits double from 128 -> 256, which overflow.
+    // The trick here is to inverse the calculation, which make the upper 64 bits (number bits) to be 0s.
+    if squared_base >= result {
+        // This inverse the base: 1 / base
+        squared_base = u128::MAX.checked_div(squared_base)?;
+        // If exponent is negative, the above already inverted the result. Therefore, at the end of the function, we do not need to invert again.
+        invert = !invert;
+    }
+    '_a: for bit in 0..19 {
+        if exp & (1 << bit) > 0 {
+            result = (result.checked_mul(squared_base)?) >> RESOLUTION;
+        }
+        squared_base = (squared_base.checked_mul(squared_base)?) >> RESOLUTION;
+    }
+    // Stop here as the next is 20th bit, which > MAX_EXPONENTIAL
+    if result == 0 {
+        return None;
+    }
+    if invert {
+        result = u128::MAX.checked_div(result)?;
+    }
+    Some(result)
+}
+// https://github.com/MeteoraAg/damm-v2/blob/main/programs/cp-amm/src/math/utils_math.rs
+#[derive(Clone, Copy, PartialEq)]
+pub enum Rounding {
+    Up,
+    Down,
+}
+pub fn mul_div_u256(x: U256, y: U256, denominator: U256, rounding: Rounding) -> Option<U256> {
+    if denominator.is_zero() {
+        return None;
+    }
+    let prod = x.checked_mul(y)?;
+    match rounding {
+        Rounding::Up => Some((prod + denominator - U256::one()) / denominator),
+        Rounding::Down => Some(prod / denominator),
+    }
+}
+pub fn mul_div_u64(x: u64, y: u64, denominator: u64, rounding: Rounding) -> Option<u64> {
+    if denominator == 0 {
+        return None;
+    }
+    let prod = u128::from(x).checked_mul(u128::from(y))?;
+    let result = match rounding {
+        Rounding::Up => prod.div_ceil(u128::from(denominator)),
+        Rounding::Down => prod / u128::from(denominator),
+    };
+    u64::try_from(result).ok()
+}
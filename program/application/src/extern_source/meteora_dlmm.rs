@@ -0,0 +1,510 @@
+#![allow(clippy::manual_div_ceil)]
+use {
+    solana_program::pubkey::Pubkey,
+    uint::construct_uint,
+};
+// https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/constants.rs
+pub const MAX_BIN_PER_ARRAY: usize = 70;
+pub const MAX_BIN_ID: i32 = 443636;
+pub const MIN_BIN_ID: i32 = -443636;
+pub const BASIS_POINT_MAX: i32 = 10000;
+pub const FEE_PRECISION: u64 = 1_000_000_000;
+pub const MAX_FEE_RATE: u64 = 100_000_000;
+// https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/math/u64x64_math.rs
+pub const SCALE_OFFSET: u8 = 64;
+pub const ONE: u128 = 1u128 << SCALE_OFFSET;
+pub const MAX_EXPONENTIAL: u32 = 0x80000;
+construct_uint! {
+    pub struct U256(4);
+}
+// https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/state/parameters.rs
+#[derive(borsh::BorshDeserialize, Clone, Copy)]
+pub struct StaticParameters {
+    /// Used for base fee calculation. base_fee_rate = base_factor * bin_step * 10 * 10^base_fee_power_factor
+    pub base_factor: u16,
+    /// Filter period determine high frequency trading time window.
+    pub filter_period: u16,
+    /// Decay period determine when the volatile fee start decay / decrease.
+    pub decay_period: u16,
+    /// Reduction factor controls the volatile fee rate decrement rate.
+    pub reduction_factor: u16,
+    /// Used to scale the variable fee component depending on the dynamic of the market
+    pub variable_fee_control: u32,
+    /// Maximum number of bin crossed can be accumulated. Used to cap volatile fee rate.
+    pub max_volatility_accumulator: u32,
+    /// Min bin id supported by the pool based on the configured bin step.
+    pub min_bin_id: i32,
+    /// Max bin id supported by the pool based on the configured bin step.
+    pub max_bin_id: i32,
+    /// Portion of swap fees retained by the protocol by controlling protocol_share parameter. protocol_swap_fee = protocol_share * total_swap_fee
+    pub protocol_share: u16,
+    /// Base fee power factor
+    pub base_fee_power_factor: u8,
+    /// Padding for bytemuck safe alignment
+    pub _padding: [u8; 5],
+}
+#[derive(borsh::BorshDeserialize, Clone, Copy)]
+pub struct VariableParameters {
+    /// Volatility accumulator measure the number of bin crossed since reference bin ID. Normally (without filter period taken into consideration), reference bin ID is the active bin of last swap.
+    /// It affects the variable fee rate
+    pub volatility_accumulator: u32,
+    /// Volatility reference is decayed volatility accumulator. It is always <= volatility_accumulator
+    pub volatility_reference: u32,
+    /// Active bin id of last swap.
+    pub index_reference: i32,
+    /// Padding for bytemuck safe alignment
+    pub _padding: [u8; 4],
+    /// Last timestamp the variable parameters was updated
+    pub last_update_timestamp: i64,
+    /// Padding for bytemuck safe alignment
+    pub _padding_1: [u8; 8],
+}
+// https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/state/lb_pair/mod.rs
+#[derive(borsh::BorshDeserialize, Clone)]
+pub struct ProtocolFee {
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+#[derive(borsh::BorshDeserialize, Clone)]
+pub struct RewardInfo {
+    /// Reward token mint.
+    pub mint: Pubkey,
+    /// Reward vault token account.
+    pub vault: Pubkey,
+    /// Authority account that allows to fund rewards
+    pub funder: Pubkey,
+    /// TODO check whether we need to store it in pool
+    pub reward_duration: u64,
+    /// TODO check whether we need to store it in pool
+    pub reward_duration_end: u64,
+    /// TODO check whether we need to store it in pool
+    pub reward_rate: u128,
+    /// The last time reward states were updated.
+    pub last_update_time: u64,
+    /// Accumulated seconds where when farm distribute rewards, but the bin is empty. The reward will be accumulated for next reward time window.
+    pub cumulative_seconds_with_empty_liquidity_reward: u64,
+}
+#[derive(borsh::BorshDeserialize, Clone)]
+pub struct LbPair {
+    pub parameters: StaticParameters,
+    pub v_parameters: VariableParameters,
+    pub bump_seed: [u8; 1],
+    /// Bin step signer seed
+    pub bin_step_seed: [u8; 2],
+    /// Type of the pair
+    pub pair_type: u8,
+    /// Active bin id
+    pub active_id: i32,
+    /// Bin step. Represent the price increment / decrement.
+    pub bin_step: u16,
+    /// Status of the pair. Check PairStatus enum.
+    pub status: u8,
+    /// Require base factor seed
+    pub require_base_factor_seed: u8,
+    /// Base factor seed
+    pub base_factor_seed: [u8; 2],
+    /// Activation type
+    pub activation_type: u8,
+    /// Allow pool creator to enable/disable pool with restricted validation. Only applicable for customizable permissionless pair type.
+    pub creator_pool_on_off_control: u8,
+    /// Token X mint
+    pub token_x_mint: Pubkey,
+    /// Token Y mint
+    pub token_y_mint: Pubkey,
+    /// LB token X vault
+    pub reserve_x: Pubkey,
+    /// LB token Y vault
+    pub reserve_y: Pubkey,
+    /// Uncollected protocol fee
+    pub protocol_fee: ProtocolFee,
+    /// _padding_1, previous Fee owner, BE CAREFUL FOR TOMBSTONE WHEN REUSE !!
+    pub _padding_1: [u8; 32],
+    /// Farming reward information
+    pub reward_infos: [RewardInfo; 2],
+    /// Oracle pubkey
+    pub oracle: Pubkey,
+    /// Packed initialized bin array state
+    pub bin_array_bitmap: [u64; 16],
+    /// Last time the pool fee parameter was updated
+    pub last_updated_at: i64,
+    /// _padding_2, previous whitelisted_wallet, BE CAREFUL FOR TOMBSTONE WHEN REUSE !!
+    pub _padding_2: [u8; 32],
+    /// Address allowed to swap when the current point is greater than or equal to the pre-activation point. The pre-activation point is calculated as `activation_point - pre_activation_duration`.
+    pub pre_activation_swap_address: Pubkey,
+    /// Base keypair. Only required for permission pair
+    pub base_key: Pubkey,
+    /// Time point to enable the pair. Only applicable for permission pair.
+    pub activation_point: u64,
+    /// Duration before activation activation_point. Used to calculate pre-activation time point for pre_activation_swap_address
+    pub pre_activation_duration: u64,
+}
+impl LbPair {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        33,
+        11,
+        49,
+        98,
+        181,
+        101,
+        177,
+        13,
+    ];
+    pub fn update_references(&mut self, current_timestamp: i64) -> Option<()> {
+        let v_params = &mut self.v_parameters;
+        let s_params = &self.parameters;
+        let elapsed = current_timestamp.checked_sub(v_params.last_update_timestamp)?;
+        // Not high frequency trade
+        if elapsed >= s_params.filter_period as i64 {
+            // Update volatility reference
+            v_params.index_reference = self.active_id;
+            // filter period < t < decay_period. Decay time window.
+            if elapsed < s_params.decay_period as i64 {
+                let volatility_reference = v_params.volatility_accumulator.checked_mul(s_params.reduction_factor as u32)?.checked_div(BASIS_POINT_MAX as u32)?;
+                v_params.volatility_reference = volatility_reference;
+            }
+            // Out of decay time window
+            else {
+                v_params.volatility_reference = 0;
+            }
+        }
+        Some(())
+    }
+    pub fn update_volatility_accumulator(&mut self) -> Option<()> {
+        let v_params = &mut self.v_parameters;
+        let s_params = &self.parameters;
+        let delta_id = i64::from(v_params.index_reference).checked_sub(self.active_id.into())?.unsigned_abs();
+        let volatility_accumulator = u64::from(v_params.volatility_reference).checked_add(delta_id.checked_mul(BASIS_POINT_MAX as u64)?)?;
+        v_params.volatility_accumulator = u32::try_from(std::cmp::min(volatility_accumulator, s_params.max_volatility_accumulator.into())).ok()?;
+        Some(())
+    }
+    pub fn advance_active_bin(&mut self, swap_for_y: bool) -> Option<()> {
+        let next_active_bin_id = if swap_for_y {
+            self.active_id.checked_sub(1)?
+        } else {
+            self.active_id.checked_add(1)?
+        };
+        if !(MIN_BIN_ID..=MAX_BIN_ID).contains(&next_active_bin_id) {
+            return None;
+        }
+        self.active_id = next_active_bin_id;
+        Some(())
+    }
+    pub fn get_base_fee(&self) -> Option<u128> {
+        u128::from(self.parameters.base_factor)
+            .checked_mul(self.bin_step.into())?
+            .checked_mul(10u128)?
+            .checked_mul(10u128.checked_pow(self.parameters.base_fee_power_factor.into())?)
+    }
+    pub fn compute_variable_fee(&self, volatility_accumulator: u32) -> Option<u128> {
+        if self.parameters.variable_fee_control > 0 {
+            let volatility_accumulator: u128 = volatility_accumulator.into();
+            let bin_step: u128 = self.bin_step.into();
+            let variable_fee_control: u128 = self.parameters.variable_fee_control.into();
+            let square_vfa_bin = volatility_accumulator.checked_mul(bin_step)?.checked_pow(2)?;
+            // Variable fee control, volatility accumulator, bin step are in basis point unit (10_000)
+            // This is 1e20. Which > 1e9. Scale down it to 1e9 unit and ceiling the remaining.
+            let v_fee = variable_fee_control.checked_mul(square_vfa_bin)?;
+            let scaled_v_fee = v_fee.checked_add(99_999_999_999)?.checked_div(100_000_000_000)?;
+            return Some(scaled_v_fee);
+        }
+        Some(0)
+    }
+    pub fn get_variable_fee(&self) -> Option<u128> {
+        self.compute_variable_fee(self.v_parameters.volatility_accumulator)
+    }
+    pub fn get_total_fee(&self) -> Option<u128> {
+        let total_fee_rate = self.get_base_fee()?.checked_add(self.get_variable_fee()?)?;
+        let total_fee_rate_cap = std::cmp::min(total_fee_rate, MAX_FEE_RATE.into());
+        Some(total_fee_rate_cap)
+    }
+    // Compute fee from amount, where fee is part of the amount. The result is ceil-ed.
+    pub fn compute_fee_from_amount(&self, amount_with_fees: u64) -> Option<u64> {
+        // total_fee_rate 1e9 unit
+        let total_fee_rate = self.get_total_fee()?;
+        // Ceil division
+        let fee_amount = u128::from(amount_with_fees).checked_mul(total_fee_rate)?.checked_add((FEE_PRECISION - 1).into())?;
+        let scaled_down_fee = fee_amount.checked_div(FEE_PRECISION.into())?;
+        u64::try_from(scaled_down_fee).ok()
+    }
+    // Compute fee for the amount. The fee is not part of the amount. This function is used when you do not know the amount_with_fees
+    // Solve for fee_amount, equation: (amount + fee_amount) * total_fee_rate / 1e9 = fee_amount
+    // fee_amount = (amount * total_fee_rate) / (1e9 - total_fee_rate)
+    // The result is ceil-ed.
+    pub fn compute_fee(&self, amount: u64) -> Option<u64> {
+        let total_fee_rate = self.get_total_fee()?;
+        let denominator = u128::from(FEE_PRECISION).checked_sub(total_fee_rate)?;
+        // Ceil division
+        let fee = u128::from(amount).checked_mul(total_fee_rate)?.checked_add(denominator)?.checked_sub(1)?;
+        let scaled_down_fee = fee.checked_div(denominator)?;
+        u64::try_from(scaled_down_fee).ok()
+    }
+}
+// https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/state/lb_pair/mod.rs
+pub enum PairStatus {
+    // Fully enabled.
+    // Condition:
+    // Permissionless: PairStatus::Enabled
+    // Permission: PairStatus::Enabled and current_point > activation_point
+    Enabled,
+    // Similar as emergency mode. User can only withdraw (Only outflow). Except whitelisted wallet still have full privileges.
+    Disabled,
+}
+impl TryFrom<u8> for PairStatus {
+    type Error = String;
+    fn try_from(s: u8) -> std::result::Result<PairStatus, String> {
+        match s {
+            0 => Ok(PairStatus::Enabled),
+            1 => Ok(PairStatus::Disabled),
+            _ => Err("Invalid value".to_string()),
+        }
+    }
+}
+/// Type of the activation
+pub enum ActivationType {
+    Slot,
+    Timestamp,
+}
+impl TryFrom<u8> for ActivationType {
+    type Error = String;
+    fn try_from(s: u8) -> std::result::Result<ActivationType, String> {
+        match s {
+            0 => Ok(ActivationType::Slot),
+            1 => Ok(ActivationType::Timestamp),
+            _ => Err("Invalid value".to_string()),
+        }
+    }
+}
+// https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/state/bin.rs
+//
+// Only the fields required for swap calculation are read from the zero-copy packed layout.
+#[derive(Clone, Copy)]
+pub struct Bin {
+    /// Amount of token X in the bin. This already excluded protocol fees.
+    pub amount_x: u64,
+    /// Amount of token Y in the bin. This already excluded protocol fees.
+    pub amount_y: u64,
+    /// Bin price
+    pub price: u128,
+}
+impl Bin {
+    // amount_x, amount_y, price, liquidity_supply, reward_per_token_stored, fee_amount_x_per_token_stored,
+    // fee_amount_y_per_token_stored, amount_x_in and amount_y_in.
+    const LENGTH: usize = 8 + 8 + 16 + 16 + 16 * 2 + 16 + 16 + 16 + 16;
+    fn unpack(data: &[u8]) -> Self {
+        Self {
+            amount_x: u64::from_le_bytes(*arrayref::array_ref![data, 0, 8]),
+            amount_y: u64::from_le_bytes(*arrayref::array_ref![data, 8, 8]),
+            price: u128::from_le_bytes(*arrayref::array_ref![data, 16, 16]),
+        }
+    }
+    pub fn is_empty(&self, is_x: bool) -> bool {
+        if is_x {
+            self.amount_x == 0
+        } else {
+            self.amount_y == 0
+        }
+    }
+    pub fn get_or_compute_bin_price(&self, id: i32, bin_step: u16) -> Option<u128> {
+        if self.price == 0 {
+            return get_price_from_id(id, bin_step);
+        }
+        Some(self.price)
+    }
+    pub fn get_max_amount_out(&self, swap_for_y: bool) -> u64 {
+        if swap_for_y {
+            self.amount_y
+        } else {
+            self.amount_x
+        }
+    }
+    pub fn get_max_amount_in(&self, price: u128, swap_for_y: bool) -> Option<u64> {
+        if swap_for_y {
+            // y = x * price
+            // x = y / price
+            shl_div(self.amount_y.into(), price, SCALE_OFFSET, Rounding::Up)
+        } else {
+            // x = y / price
+            // y = x * price
+            mul_shr(self.amount_x.into(), price, SCALE_OFFSET, Rounding::Up)
+        }
+    }
+    pub fn get_amount_out(amount_in: u64, price: u128, swap_for_y: bool) -> Option<u64> {
+        if swap_for_y {
+            // (Q64x0 * Q64x64) >> SCALE_OFFSET = (Q128x64 >> SCALE_OFFSET) = Q64x0
+            mul_shr(price, amount_in.into(), SCALE_OFFSET, Rounding::Down)
+        } else {
+            // (Q64x0 << SCALE_OFFSET) / Q64x64 = (Q64x64 / Q64x64) = Q64x0
+            shl_div(amount_in.into(), price, SCALE_OFFSET, Rounding::Down)
+        }
+    }
+    pub fn get_amount_in(amount_out: u64, price: u128, swap_for_y: bool) -> Option<u64> {
+        if swap_for_y {
+            shl_div(amount_out.into(), price, SCALE_OFFSET, Rounding::Up)
+        } else {
+            mul_shr(amount_out.into(), price, SCALE_OFFSET, Rounding::Up)
+        }
+    }
+    // Returns the amount in with fees, the amount out and the fee.
+    pub fn swap(&self, amount_in: u64, price: u128, swap_for_y: bool, lb_pair: &LbPair) -> Option<(u64, u64, u64)> {
+        let max_amount_out = self.get_max_amount_out(swap_for_y);
+        let mut max_amount_in = self.get_max_amount_in(price, swap_for_y)?;
+        let max_fee = lb_pair.compute_fee(max_amount_in)?;
+        max_amount_in = max_amount_in.checked_add(max_fee)?;
+        if amount_in > max_amount_in {
+            return Some((max_amount_in, max_amount_out, max_fee));
+        }
+        let fee = lb_pair.compute_fee_from_amount(amount_in)?;
+        let amount_in_after_fee = amount_in.checked_sub(fee)?;
+        let amount_out = Bin::get_amount_out(amount_in_after_fee, price, swap_for_y)?;
+        Some((amount_in, std::cmp::min(amount_out, max_amount_out), fee))
+    }
+}
+// https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/state/bin.rs
+pub struct BinArray {
+    pub index: i64,
+    pub lb_pair: Pubkey,
+    pub bins: Vec<Bin>,
+}
+impl BinArray {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        92,
+        142,
+        92,
+        220,
+        5,
+        148,
+        70,
+        181,
+    ];
+    // index, version, _padding and lb_pair.
+    const BINS_OFFSET: usize = 8 + 1 + 7 + 32;
+    // Unpacks the data after the discriminator.
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::BINS_OFFSET + Bin::LENGTH * MAX_BIN_PER_ARRAY {
+            return None;
+        }
+        let mut bins = Vec::<Bin>::with_capacity(MAX_BIN_PER_ARRAY);
+        '_a: for index in 0..MAX_BIN_PER_ARRAY {
+            bins.push(Bin::unpack(&data[Self::BINS_OFFSET + index * Bin::LENGTH..]));
+        }
+        Some(Self {
+            index: i64::from_le_bytes(*arrayref::array_ref![data, 0, 8]),
+            lb_pair: Pubkey::new_from_array(*arrayref::array_ref![data, 16, 32]),
+            bins,
+        })
+    }
+    pub fn bin_id_to_bin_array_index(bin_id: i32) -> i32 {
+        bin_id.div_euclid(MAX_BIN_PER_ARRAY as i32)
+    }
+    /// Get bin array lower, upper bin id
+    pub fn get_bin_array_lower_upper_bin_id(index: i32) -> (i32, i32) {
+        let lower_bin_id = index * MAX_BIN_PER_ARRAY as i32;
+        let upper_bin_id = lower_bin_id + MAX_BIN_PER_ARRAY as i32 - 1;
+        (lower_bin_id, upper_bin_id)
+    }
+    pub fn is_bin_id_within_range(&self, bin_id: i32) -> bool {
+        let (lower_bin_id, upper_bin_id) = Self::get_bin_array_lower_upper_bin_id(self.index as i32);
+        bin_id >= lower_bin_id && bin_id <= upper_bin_id
+    }
+    pub fn get_bin(&self, bin_id: i32) -> Option<&Bin> {
+        if !self.is_bin_id_within_range(bin_id) {
+            return None;
+        }
+        let (lower_bin_id, _) = Self::get_bin_array_lower_upper_bin_id(self.index as i32);
+        self.bins.get((bin_id - lower_bin_id) as usize)
+    }
+}
+// https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/math/price_math.rs
+/// Calculate price based on the given bin id. Eg: 1.0001 ^ 5555. The returned value is in Q64.64
+pub fn get_price_from_id(active_id: i32, bin_step: u16) -> Option<u128> {
+    // Make bin_step into Q64x64, and divided by BASIS_POINT_MAX. If bin_step = 1, we get 0.0001 in Q64x64
+    let bps = u128::from(bin_step).checked_shl(SCALE_OFFSET.into())?.checked_div(BASIS_POINT_MAX as u128)?;
+    // Add 1 to bps, we get 1.0001 in Q64.64
+    let base = ONE.checked_add(bps)?;
+    pow(base, active_id)
+}
+// https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/math/u64x64_math.rs
+pub fn pow(base: u128, exp: i32) -> Option<u128> {
+    // If exponent is negative. We will invert the result later by 1 / base^exp.abs()
+    let mut invert = exp.is_negative();
+    // When exponential is 0, result will always be 1
+    if exp == 0 {
+        return Some(1u128 << 64);
+    }
+    // Make the exponential positive. Which will compute the result later by 1 / base^exp
+    let exp: u32 = if invert {
+        exp.unsigned_abs()
+    } else {
+        exp as u32
+    };
+    // No point to continue the calculation as it will overflow the maximum value Q64.64 can support
+    if exp >= MAX_EXPONENTIAL {
+        return None;
+    }
+    let mut squared_base = base;
+    let mut result = ONE;
+    // When multiply the base twice, the number of bits double from 128 -> 256, which overflow.
+    // The trick here is to inverse the calculation, which make the upper 64 bits (number bits) to be 0s.
+    // For example:
+    // let base = 1.001, exp = 5
+    // let neg = 1 / (1.001 ^ 5)
+    // Inverse the neg: 1 / neg
+    // By using a calculator, you will find out that 1.001^5 == 1 / (1 / 1.001^5)
+    if squared_base >= result {
+        // This inverse the base: 1 / base
+        squared_base = u128::MAX.checked_div(squared_base)?;
+        // If exponent is negative, the above already inverted the result. Therefore, at the end of the function, we do not need to invert again.
+        invert = !invert;
+    }
+    // The following code is equivalent to looping through each binary value of the exponential.
+    // As explained in MAX_EXPONENTIAL, 19 exponential bits are enough to covert the full bin price.
+    // Therefore, there will be 19 if statements, which similar to the following pseudo code.
+    '_a: for bit in 0..19 {
+        if exp & (1 << bit) > 0 {
+            result = (result.checked_mul(squared_base)?) >> SCALE_OFFSET;
+        }
+        squared_base = (squared_base.checked_mul(squared_base)?) >> SCALE_OFFSET;
+    }
+    // Stop here as the next is 20th bit, which > MAX_EXPONENTIAL
+    if result == 0 {
+        return None;
+    }
+    if invert {
+        result = u128::MAX.checked_div(result)?;
+    }
+    Some(result)
+}
+// https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/math/u128x128_math.rs
+#[derive(Clone, Copy, PartialEq)]
+pub enum Rounding {
+    Up,
+    Down,
+}
+/// (x * y) >> offset
+pub fn mul_shr(x: u128, y: u128, offset: u8, rounding: Rounding) -> Option<u64> {
+    let denominator = 1u128.checked_shl(offset.into())?;
+    mul_div(x, y, denominator, rounding)
+}
+/// (x << offset) / y
+pub fn shl_div(x: u128, y: u128, offset: u8, rounding: Rounding) -> Option<u64> {
+    let scale = 1u128.checked_shl(offset.into())?;
+    mul_div(x, scale, y, rounding)
+}
+/// (x * y) / denominator
+pub fn mul_div(x: u128, y: u128, denominator: u128, rounding: Rounding) -> Option<u64> {
+    if denominator == 0 {
+        return None;
+    }
+    let x = U256::from(x);
+    let y = U256::from(y);
+    let denominator = U256::from(denominator);
+    let prod = x.checked_mul(y)?;
+    let result = match rounding {
+        Rounding::Up => (prod + denominator - U256::one()) / denominator,
+        Rounding::Down => prod / denominator,
+    };
+    if result > U256::from(u64::MAX) {
+        return None;
+    }
+    Some(result.low_u64())
+}
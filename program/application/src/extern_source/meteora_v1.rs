@@ -1,7 +1,12 @@
+#![allow(clippy::manual_div_ceil)]
 use {
     super::CheckedCeilDiv,
     solana_program::pubkey::Pubkey,
+    uint::construct_uint,
 };
+construct_uint! {
+    pub struct U256(4);
+}
 // https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/programs/dynamic-amm/src/state.rs#L61
 #[derive(borsh::BorshSchema, borsh::BorshDeserialize)]
 pub struct Pool {
@@ -193,7 +198,7 @@ impl DepegType {
     }
 }
 // https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/programs/dynamic-vault/src/state.rs#L16
-#[derive(borsh::BorshSchema, borsh::BorshDeserialize)]
+#[derive(borsh::BorshSchema, borsh::BorshDeserialize, Clone)]
 pub struct Vault {
     /// The flag, if admin set enable = false, then the user can only withdraw and cannot deposit in the vault.
     pub enabled: u8,
@@ -240,7 +245,7 @@ impl Vault {
 // https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/programs/dynamic-vault/src/state.rs#L7
 pub const MAX_STRATEGY: usize = 30;
 // https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/programs/dynamic-vault/src/state.rs#L143
-#[derive(borsh::BorshSchema, borsh::BorshDeserialize)]
+#[derive(borsh::BorshSchema, borsh::BorshDeserialize, Clone)]
 pub struct VaultBumps {
     /// vault_bump
     pub vault_bump: u8,
@@ -248,7 +253,7 @@ pub struct VaultBumps {
     pub token_vault_bump: u8,
 }
 // https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/programs/dynamic-vault/src/state.rs#L86
-#[derive(borsh::BorshSchema, borsh::BorshDeserialize)]
+#[derive(borsh::BorshSchema, borsh::BorshDeserialize, Clone)]
 pub struct LockedProfitTracker {
     /// The total locked profit from the last report
     pub last_updated_locked_profit: u64,
@@ -292,6 +297,7 @@ impl TryFrom<u8> for ActivationType {
         }
     }
 }
+#[derive(Clone, Copy)]
 pub enum TradeDirection {
     /// Input token A, output token B
     AtoB,
@@ -360,6 +366,206 @@ pub struct SwapWithoutFeesResult {
     /// Amount of destination token swapped
     pub destination_amount_swapped: u128,
 }
+// https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/dynamic-amm-quote/src/math/stable_swap.rs
+pub struct StableSwap {
+    /// Amplification coefficient
+    pub amp: u64,
+    /// Multiplier for the pool token. Used to normalized token with different decimal into the same precision.
+    pub token_multiplier: TokenMultiplier,
+    /// Depeg pool information. The virtual price should be updated before the swap.
+    pub depeg: Depeg,
+}
+impl StableSwap {
+    fn upscale_token_a(&self, token_amount: u128) -> Option<u128> {
+        let normalized_token_amount = token_amount.checked_mul(self.token_multiplier.token_a_multiplier.into())?;
+        if !self.depeg.depeg_type.is_none() {
+            normalized_token_amount.checked_mul(DEPEG_PRECISION.into())
+        } else {
+            Some(normalized_token_amount)
+        }
+    }
+    fn downscale_token_a(&self, token_amount: u128) -> Option<u128> {
+        let denormalized_token_amount = token_amount.checked_div(self.token_multiplier.token_a_multiplier.into())?;
+        if !self.depeg.depeg_type.is_none() {
+            denormalized_token_amount.checked_div(DEPEG_PRECISION.into())
+        } else {
+            Some(denormalized_token_amount)
+        }
+    }
+    fn upscale_token_b(&self, token_amount: u128) -> Option<u128> {
+        let normalized_token_amount = token_amount.checked_mul(self.token_multiplier.token_b_multiplier.into())?;
+        if !self.depeg.depeg_type.is_none() {
+            normalized_token_amount.checked_mul(self.depeg.base_virtual_price.into())
+        } else {
+            Some(normalized_token_amount)
+        }
+    }
+    fn downscale_token_b(&self, token_amount: u128) -> Option<u128> {
+        let denormalized_token_amount = token_amount.checked_div(self.token_multiplier.token_b_multiplier.into())?;
+        if !self.depeg.depeg_type.is_none() {
+            denormalized_token_amount.checked_div(self.depeg.base_virtual_price.into())
+        } else {
+            Some(denormalized_token_amount)
+        }
+    }
+    fn upscale(&self, source_amount: u64, swap_source_amount: u64, swap_destination_amount: u64, trade_direction: TradeDirection) -> Option<(u128, u128, u128)> {
+        match trade_direction {
+            TradeDirection::AtoB => {
+                Some((
+                    self.upscale_token_a(source_amount.into())?,
+                    self.upscale_token_a(swap_source_amount.into())?,
+                    self.upscale_token_b(swap_destination_amount.into())?,
+                ))
+            }
+            TradeDirection::BtoA => {
+                Some((
+                    self.upscale_token_b(source_amount.into())?,
+                    self.upscale_token_b(swap_source_amount.into())?,
+                    self.upscale_token_a(swap_destination_amount.into())?,
+                ))
+            }
+        }
+    }
+    fn downscale_destination(&self, token_amount: u128, trade_direction: TradeDirection) -> Option<u128> {
+        match trade_direction {
+            TradeDirection::AtoB => self.downscale_token_b(token_amount),
+            TradeDirection::BtoA => self.downscale_token_a(token_amount),
+        }
+    }
+    fn downscale_source(&self, token_amount: u128, trade_direction: TradeDirection) -> Option<u128> {
+        match trade_direction {
+            TradeDirection::AtoB => self.downscale_token_a(token_amount),
+            TradeDirection::BtoA => self.downscale_token_b(token_amount),
+        }
+    }
+    // Returns the source amount without fees which is enough to receive the destination amount.
+    // The downscaling rounds down, so one token is added to the result.
+    pub fn get_source_amount(&self, destination_amount: u64, swap_source_amount: u64, swap_destination_amount: u64, trade_direction: TradeDirection) -> Option<u64> {
+        let (upscaled_destination_amount, upscaled_swap_destination_amount, upscaled_swap_source_amount) =
+            self.upscale(destination_amount, swap_destination_amount, swap_source_amount, reverse_trade_direction(trade_direction))?;
+        let invariant = compute_d(self.amp, upscaled_swap_source_amount, upscaled_swap_destination_amount)?;
+        let new_swap_source_amount = compute_y(self.amp, upscaled_swap_destination_amount.checked_sub(upscaled_destination_amount)?, invariant)?;
+        let source_amount = self.downscale_source(new_swap_source_amount.checked_sub(upscaled_swap_source_amount)?, trade_direction)?;
+        u64::try_from(source_amount.checked_add(1)?).ok()
+    }
+}
+impl SwapCurve for StableSwap {
+    fn swap(&self, source_amount: u64, swap_source_amount: u64, swap_destination_amount: u64, trade_direction: TradeDirection) -> Option<SwapResult> {
+        let (upscaled_source_amount, upscaled_swap_source_amount, upscaled_swap_destination_amount) =
+            self.upscale(source_amount, swap_source_amount, swap_destination_amount, trade_direction)?;
+        let invariant = compute_d(self.amp, upscaled_swap_source_amount, upscaled_swap_destination_amount)?;
+        let new_swap_destination_amount = compute_y(self.amp, upscaled_swap_source_amount.checked_add(upscaled_source_amount)?, invariant)?;
+        let upscaled_destination_amount_swapped = upscaled_swap_destination_amount.checked_sub(new_swap_destination_amount)?;
+        let destination_amount_swapped = map_zero_to_none(self.downscale_destination(upscaled_destination_amount_swapped, trade_direction)?)?;
+        let source_amount: u128 = source_amount.into();
+        Some(SwapResult {
+            new_swap_source_amount: u128::from(swap_source_amount).checked_add(source_amount)?,
+            new_swap_destination_amount: u128::from(swap_destination_amount).checked_sub(destination_amount_swapped)?,
+            source_amount_swapped: source_amount,
+            destination_amount_swapped,
+        })
+    }
+}
+fn reverse_trade_direction(trade_direction: TradeDirection) -> TradeDirection {
+    match trade_direction {
+        TradeDirection::AtoB => TradeDirection::BtoA,
+        TradeDirection::BtoA => TradeDirection::AtoB,
+    }
+}
+// https://github.com/saber-hq/stable-swap/blob/master/stable-swap-math/src/curve.rs
+//
+// The amounts are upscaled by the token multipliers and the depeg virtual price,
+// so they are 'u128' instead of 'u64' and the intermediate values are 'U256'.
+const N_COINS: u8 = 2;
+const MAX_ITERATIONS_QUANTITY: usize = 256;
+// Computes the stable swap invariant.
+fn compute_d(amp: u64, amount_a: u128, amount_b: u128) -> Option<u128> {
+    let sum_x = amount_a.checked_add(amount_b)?;
+    if sum_x == 0 {
+        return Some(0);
+    }
+    let amount_a_times_coins = U256::from(amount_a).checked_mul(N_COINS.into())?;
+    let amount_b_times_coins = U256::from(amount_b).checked_mul(N_COINS.into())?;
+    let ann = U256::from(amp).checked_mul(N_COINS.into())?;
+    let leverage = U256::from(sum_x).checked_mul(ann)?;
+    let mut d = U256::from(sum_x);
+    '_a: for _ in 0..MAX_ITERATIONS_QUANTITY {
+        let d_prod = d.checked_mul(d)?.checked_div(amount_a_times_coins)?.checked_mul(d)?.checked_div(amount_b_times_coins)?;
+        let d_previous = d;
+        // d = (ann * sum_x + d_prod * n_coins) * d / ((ann - 1) * d + (n_coins + 1) * d_prod)
+        let numerator = d.checked_mul(d_prod.checked_mul(N_COINS.into())?.checked_add(leverage)?)?;
+        let denominator = d.checked_mul(ann.checked_sub(U256::one())?)?.checked_add(d_prod.checked_mul((N_COINS + 1).into())?)?;
+        d = numerator.checked_div(denominator)?;
+        if (d > d_previous && d - d_previous <= U256::one()) || (d <= d_previous && d_previous - d <= U256::one()) {
+            break;
+        }
+    }
+    u256_to_u128(d)
+}
+// Computes the amount of the other token for the amount of the token and the invariant.
+fn compute_y(amp: u64, x: u128, d: u128) -> Option<u128> {
+    let ann = U256::from(amp).checked_mul(N_COINS.into())?;
+    let x = U256::from(x);
+    let d = U256::from(d);
+    // c =  D ** (n + 1) / (n ** (2 * n) * prod' * A)
+    let c = d.checked_mul(d)?.checked_div(x.checked_mul(N_COINS.into())?)?.checked_mul(d)?.checked_div(ann.checked_mul(N_COINS.into())?)?;
+    // b = sum' - (A*n**n - 1) * D / (A * n**n), where 'd' is subtracted below.
+    let b = d.checked_div(ann)?.checked_add(x)?;
+    // Solve for y by approximating: y**2 + b*y = c
+    let mut y = d;
+    '_a: for _ in 0..MAX_ITERATIONS_QUANTITY {
+        let y_previous = y;
+        // y = (y * y + c) / (2 * y + b - d)
+        y = y.checked_mul(y)?.checked_add(c)?.checked_div(y.checked_mul(N_COINS.into())?.checked_add(b)?.checked_sub(d)?)?;
+        if (y > y_previous && y - y_previous <= U256::one()) || (y <= y_previous && y_previous - y <= U256::one()) {
+            break;
+        }
+    }
+    u256_to_u128(y)
+}
+fn u256_to_u128(value: U256) -> Option<u128> {
+    if value > U256::from(u128::MAX) {
+        return None;
+    }
+    Some(value.as_u128())
+}
+// https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/programs/dynamic-amm/src/constants.rs
+//
+// The precision of the depeg virtual price.
+pub const DEPEG_PRECISION: u64 = 1_000_000;
+// The base virtual price is cached for 10 minutes.
+pub const BASE_CACHE_EXPIRES: u64 = 60 * 10;
+// https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/dynamic-amm-quote/src/depeg/marinade.rs
+pub const MARINADE_STATE: Pubkey = Pubkey::from_str_const("8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC");
+// Reads the 'msol_price' from the Marinade 'State' account with the Anchor discriminator.
+pub fn get_marinade_virtual_price(data: &[u8]) -> Option<u64> {
+    const MSOL_PRICE_OFFSET: usize = 512;
+    const PRICE_DENOMINATOR: u128 = 0x1_0000_0000;
+    let msol_price = u64::from_le_bytes(*arrayref::array_ref![data.get(MSOL_PRICE_OFFSET..MSOL_PRICE_OFFSET + 8)?, 0, 8]);
+    u64::try_from((msol_price as u128).checked_mul(DEPEG_PRECISION as u128)?.checked_div(PRICE_DENOMINATOR)?).ok()
+}
+// https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/dynamic-amm-quote/src/depeg/solido.rs
+pub const SOLIDO_STATE: Pubkey = Pubkey::from_str_const("49Yi1TKkNyYjPAFdR9LBvoHcUjuPX4Df5T5yv39w2XTn");
+// Reads the 'exchange_rate' from the Solido 'Lido' account:
+// account_type, lido_version, manager, st_sol_mint and exchange_rate (computed_in_epoch, st_sol_supply, sol_balance).
+pub fn get_solido_virtual_price(data: &[u8]) -> Option<u64> {
+    const ST_SOL_SUPPLY_OFFSET: usize = 1 + 1 + 32 * 2 + 8;
+    let exchange_rate = data.get(ST_SOL_SUPPLY_OFFSET..ST_SOL_SUPPLY_OFFSET + 16)?;
+    let st_sol_supply = u64::from_le_bytes(*arrayref::array_ref![exchange_rate, 0, 8]);
+    let sol_balance = u64::from_le_bytes(*arrayref::array_ref![exchange_rate, 8, 8]);
+    u64::try_from((sol_balance as u128).checked_mul(DEPEG_PRECISION as u128)?.checked_div(st_sol_supply as u128)?).ok()
+}
+// https://github.com/MeteoraAg/damm-v1-sdk/blob/b21e2efb3680c17a68149ed2e22465aeef9b3784/dynamic-amm-quote/src/depeg/spl_stake.rs
+// Reads the 'total_lamports' and the 'pool_token_supply' from the SPL 'StakePool' account:
+// account_type, manager, staker, stake_deposit_authority, stake_withdraw_bump_seed, validator_list, reserve_stake,
+// pool_mint, manager_fee_account and token_program_id.
+pub fn get_spl_stake_virtual_price(data: &[u8]) -> Option<u64> {
+    const TOTAL_LAMPORTS_OFFSET: usize = 1 + 32 * 3 + 1 + 32 * 5;
+    let stake_pool = data.get(TOTAL_LAMPORTS_OFFSET..TOTAL_LAMPORTS_OFFSET + 16)?;
+    let total_lamports = u64::from_le_bytes(*arrayref::array_ref![stake_pool, 0, 8]);
+    let pool_token_supply = u64::from_le_bytes(*arrayref::array_ref![stake_pool, 8, 8]);
+    u64::try_from((total_lamports as u128).checked_mul(DEPEG_PRECISION as u128)?.checked_div(pool_token_supply as u128)?).ok()
+}
 impl CheckedCeilDiv for u128 {
     fn checked_ceil_div(&self, mut rhs: Self) -> Option<(Self, Self)> {
         let mut quotient = self.checked_div(rhs)?;
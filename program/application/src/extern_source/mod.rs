@@ -1,6 +1,15 @@
 // All code here anf in this modules copied from source and slightly refactored in context of Error type.
+pub mod meteora_damm_v2;
+pub mod meteora_dlmm;
 pub mod meteora_v1;
+pub mod orca_whirlpool;
+pub mod phoenix;
+pub mod pump_fun;
+pub mod pump_swap;
+pub mod raydium_clmm;
+pub mod raydium_cpmm;
 pub mod raydium_v4;
+pub mod spl_token_2022;
 pub trait CheckedCeilDiv: Sized {
     fn checked_ceil_div(&self, rhs: Self) -> Option<(Self, Self)>;
 }
@@ -0,0 +1,523 @@
+#![allow(clippy::manual_div_ceil)]
+use {
+    solana_program::pubkey::Pubkey,
+    uint::construct_uint,
+};
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/math/tick_math.rs
+pub const MAX_TICK_INDEX: i32 = 443636;
+pub const MIN_TICK_INDEX: i32 = -443636;
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673515401279992447579055;
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/math/swap_math.rs
+pub const FEE_RATE_MUL_VALUE: u128 = 1_000_000;
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/math/mod.rs
+pub const Q64_RESOLUTION: u8 = 64;
+pub const Q64_MASK: u128 = 0xFFFF_FFFF_FFFF_FFFF;
+construct_uint! {
+    pub struct U256(4);
+}
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/state/whirlpool.rs
+#[derive(borsh::BorshDeserialize)]
+pub struct Whirlpool {
+    pub whirlpools_config: Pubkey,
+    pub whirlpool_bump: [u8; 1],
+    pub tick_spacing: u16,
+    pub fee_tier_index_seed: [u8; 2],
+    // Stored as hundredths of a basis point
+    // u16::MAX corresponds to ~6.5%
+    pub fee_rate: u16,
+    // Portion of fee rate taken stored as basis points
+    pub protocol_fee_rate: u16,
+    // Maximum amount that can be held by Solana account
+    pub liquidity: u128,
+    // MAX/MIN at Q32.64, but using Q64.64 for rounder bytes
+    // Q64.64
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub protocol_fee_owed_a: u64,
+    pub protocol_fee_owed_b: u64,
+    pub token_mint_a: Pubkey,
+    pub token_vault_a: Pubkey,
+    // Q64.64
+    pub fee_growth_global_a: u128,
+    pub token_mint_b: Pubkey,
+    pub token_vault_b: Pubkey,
+    // Q64.64
+    pub fee_growth_global_b: u128,
+    pub reward_last_updated_timestamp: u64,
+    pub reward_infos: [WhirlpoolRewardInfo; 3],
+}
+impl Whirlpool {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        63,
+        149,
+        209,
+        12,
+        225,
+        128,
+        99,
+        9,
+    ];
+    pub fn fee_tier_index(&self) -> u16 {
+        u16::from_le_bytes(self.fee_tier_index_seed)
+    }
+    pub fn is_initialized_with_adaptive_fee(&self) -> bool {
+        self.fee_tier_index() != self.tick_spacing
+    }
+}
+#[derive(borsh::BorshDeserialize)]
+pub struct WhirlpoolRewardInfo {
+    /// Reward token mint.
+    pub mint: Pubkey,
+    /// Reward vault token account.
+    pub vault: Pubkey,
+    /// reward_infos[0]: Authority account that has permission to initialize the reward and set emissions.
+    /// reward_infos[1]: used for a struct that contains fields for extension. (not used)
+    /// reward_infos[2]: used for a struct that contains fields for extension. (not used)
+    pub extension: [u8; 32],
+    /// Q64.64 number that indicates how many tokens per second are earned per unit of liquidity.
+    pub emissions_per_second_x64: u128,
+    /// Q64.64 number that tracks the total tokens earned per unit of liquidity since the reward
+    /// emissions were turned on.
+    pub growth_global_x64: u128,
+}
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/state/fixed_tick_array.rs
+//
+// Only the fields required for swap calculation are read from the zero-copy packed layout.
+#[derive(Clone, Copy, Default)]
+pub struct Tick {
+    pub initialized: bool,
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+}
+impl Tick {
+    // initialized, liquidity_net, liquidity_gross, fee_growth_outside_a, fee_growth_outside_b and reward_growths_outside.
+    const LENGTH: usize = 1 + 16 + 16 + 16 + 16 + 16 * 3;
+    fn unpack(data: &[u8]) -> Self {
+        Self {
+            initialized: data[0] != 0,
+            liquidity_net: i128::from_le_bytes(*arrayref::array_ref![data, 1, 16]),
+            liquidity_gross: u128::from_le_bytes(*arrayref::array_ref![data, 17, 16]),
+        }
+    }
+}
+pub const TICK_ARRAY_SIZE: i32 = 88;
+pub const TICK_ARRAY_SIZE_USIZE: usize = 88;
+pub struct TickArray {
+    pub start_tick_index: i32,
+    pub ticks: Vec<Tick>,
+    pub whirlpool: Pubkey,
+}
+impl TickArray {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        69,
+        97,
+        189,
+        190,
+        110,
+        7,
+        66,
+        187,
+    ];
+    pub const DYNAMIC_DISCRIMINATOR: [u8; 8] = [
+        17,
+        216,
+        246,
+        142,
+        225,
+        199,
+        218,
+        56,
+    ];
+    const TICKS_OFFSET: usize = 4;
+    const WHIRLPOOL_OFFSET: usize = Self::TICKS_OFFSET + Tick::LENGTH * TICK_ARRAY_SIZE_USIZE;
+    // Unpacks the data after the discriminator.
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::WHIRLPOOL_OFFSET + 32 {
+            return None;
+        }
+        let mut ticks = Vec::<Tick>::with_capacity(TICK_ARRAY_SIZE_USIZE);
+        '_a: for index in 0..TICK_ARRAY_SIZE_USIZE {
+            ticks.push(Tick::unpack(&data[Self::TICKS_OFFSET + index * Tick::LENGTH..]));
+        }
+        Some(Self {
+            start_tick_index: i32::from_le_bytes(*arrayref::array_ref![data, 0, 4]),
+            ticks,
+            whirlpool: Pubkey::new_from_array(*arrayref::array_ref![data, Self::WHIRLPOOL_OFFSET, 32]),
+        })
+    }
+    // Unpacks the data after the discriminator of the variable length tick array
+    // from https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/state/dynamic_tick_array.rs
+    pub fn unpack_dynamic(data: &[u8]) -> Option<Self> {
+        // start_tick_index, whirlpool and tick_bitmap.
+        const TICKS_OFFSET: usize = 4 + 32 + 16;
+        if data.len() < TICKS_OFFSET {
+            return None;
+        }
+        let mut ticks = Vec::<Tick>::with_capacity(TICK_ARRAY_SIZE_USIZE);
+        let mut offset = TICKS_OFFSET;
+        '_a: for _ in 0..TICK_ARRAY_SIZE_USIZE {
+            match *data.get(offset)? {
+                0 => {
+                    ticks.push(Tick::default());
+                    offset += 1;
+                }
+                1 => {
+                    // The enum tag is followed by the same data as the fixed tick has after the 'initialized' flag.
+                    if data.len() < offset + Tick::LENGTH {
+                        return None;
+                    }
+                    ticks.push(Tick::unpack(&data[offset..]));
+                    offset += Tick::LENGTH;
+                }
+                _ => return None,
+            }
+        }
+        Some(Self {
+            start_tick_index: i32::from_le_bytes(*arrayref::array_ref![data, 0, 4]),
+            ticks,
+            whirlpool: Pubkey::new_from_array(*arrayref::array_ref![data, 4, 32]),
+        })
+    }
+    /// Search for the next initialized tick in this array.
+    ///
+    /// # Returns
+    /// - `Some(i32)`: The next initialized tick index of this array
+    /// - `None`: An initialized tick index was not found in this array
+    ///   or the tick index is out of the search range of this array.
+    pub fn get_next_init_tick_index(&self, tick_index: i32, tick_spacing: u16, a_to_b: bool) -> Option<i32> {
+        if !self.in_search_range(tick_index, tick_spacing, !a_to_b) {
+            return None;
+        }
+        let mut curr_offset = self.tick_offset(tick_index, tick_spacing);
+        // For a_to_b searches, the search moves to the left. The next possible init-tick can be the 1st tick in the current offset
+        // For b_to_a searches, the search moves to the right. The next possible init-tick cannot be within the current offset
+        if !a_to_b {
+            curr_offset += 1;
+        }
+        while (0..TICK_ARRAY_SIZE).contains(&curr_offset) {
+            let curr_tick = self.ticks[curr_offset as usize];
+            if curr_tick.initialized {
+                return Some(curr_offset * tick_spacing as i32 + self.start_tick_index);
+            }
+            curr_offset = if a_to_b {
+                curr_offset - 1
+            } else {
+                curr_offset + 1
+            };
+        }
+        None
+    }
+    /// Checks whether the tick index is within the array range. If shifted, the range is moved to the left
+    /// by the tick spacing.
+    pub fn in_search_range(&self, tick_index: i32, tick_spacing: u16, shifted: bool) -> bool {
+        let mut lower = self.start_tick_index;
+        let mut upper = self.start_tick_index + TICK_ARRAY_SIZE * tick_spacing as i32;
+        if shifted {
+            lower -= tick_spacing as i32;
+            upper -= tick_spacing as i32;
+        }
+        tick_index >= lower && tick_index < upper
+    }
+    /// Returns the tick for the tick index, if it is placed in this array.
+    pub fn get_tick(&self, tick_index: i32, tick_spacing: u16) -> Option<&Tick> {
+        if !self.in_search_range(tick_index, tick_spacing, false) || tick_index % tick_spacing as i32 != 0 {
+            return None;
+        }
+        self.ticks.get(self.tick_offset(tick_index, tick_spacing) as usize)
+    }
+    fn tick_offset(&self, tick_index: i32, tick_spacing: u16) -> i32 {
+        (tick_index - self.start_tick_index).div_euclid(tick_spacing as i32)
+    }
+}
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/math/tick_math.rs
+pub fn sqrt_price_from_tick_index(tick: i32) -> Option<u128> {
+    if !(MIN_TICK_INDEX..=MAX_TICK_INDEX).contains(&tick) {
+        return None;
+    }
+    if tick >= 0 {
+        get_sqrt_price_positive_tick(tick)
+    } else {
+        Some(get_sqrt_price_negative_tick(tick))
+    }
+}
+fn mul_shift_96(n0: u128, n1: u128) -> Option<u128> {
+    u128::try_from((U256::from(n0) * U256::from(n1)) >> 96).ok()
+}
+// Performs the exponential conversion with Q64.64 precision
+fn get_sqrt_price_positive_tick(tick: i32) -> Option<u128> {
+    let mut ratio: u128 = if tick & 1 != 0 {
+        79232123823359799118286999567
+    } else {
+        79228162514264337593543950336
+    };
+    const RATIOS: [(i32, u128); 18] = [
+        (2, 79236085330515764027303304731),
+        (4, 79244008939048815603706035061),
+        (8, 79259858533276714757314932305),
+        (16, 79291567232598584799939703904),
+        (32, 79355022692464371645785046466),
+        (64, 79482085999252804386437311141),
+        (128, 79736823300114093921829183326),
+        (256, 80248749790819932309965073892),
+        (512, 81282483887344747381513967011),
+        (1024, 83390072131320151908154831281),
+        (2048, 87770609709833776024991924138),
+        (4096, 97234110755111693312479820773),
+        (8192, 119332217159966728226237229890),
+        (16384, 179736315981702064433883588727),
+        (32768, 407748233172238350107850275304),
+        (65536, 2098478828474011932436660412517),
+        (131072, 55581415166113811149459800483533),
+        (262144, 38992368544603139932233054999993551),
+    ];
+    '_a: for (bit, ratio_) in RATIOS {
+        if tick & bit != 0 {
+            ratio = mul_shift_96(ratio, ratio_)?;
+        }
+    }
+    Some(ratio >> 32)
+}
+fn get_sqrt_price_negative_tick(tick: i32) -> u128 {
+    let abs_tick = tick.abs();
+    let mut ratio: u128 = if abs_tick & 1 != 0 {
+        18445821805675392311
+    } else {
+        18446744073709551616
+    };
+    const RATIOS: [(i32, u128); 18] = [
+        (2, 18444899583751176498),
+        (4, 18443055278223354162),
+        (8, 18439367220385604838),
+        (16, 18431993317065449817),
+        (32, 18417254355718160513),
+        (64, 18387811781193591352),
+        (128, 18329067761203520168),
+        (256, 18212142134806087854),
+        (512, 17980523815641551639),
+        (1024, 17526086738831147013),
+        (2048, 16651378430235024244),
+        (4096, 15030750278693429944),
+        (8192, 12247334978882834399),
+        (16384, 8131365268884726200),
+        (32768, 3584323654723342297),
+        (65536, 696457651847595233),
+        (131072, 26294789957452057),
+        (262144, 37481735321082),
+    ];
+    '_a: for (bit, ratio_) in RATIOS {
+        if abs_tick & bit != 0 {
+            ratio = (ratio * ratio_) >> 64;
+        }
+    }
+    ratio
+}
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/math/token_math.rs
+//
+// The 'None' stands for the amount exceeding 'u64'.
+fn increasing_price_order(sqrt_price_0: u128, sqrt_price_1: u128) -> (u128, u128) {
+    if sqrt_price_0 > sqrt_price_1 {
+        (sqrt_price_1, sqrt_price_0)
+    } else {
+        (sqrt_price_0, sqrt_price_1)
+    }
+}
+// Shifts the value to the left by 64 bits. Returns None on overflow.
+fn checked_shift_word_left(value: U256) -> Option<U256> {
+    if value.bits() > 256 - Q64_RESOLUTION as usize {
+        return None;
+    }
+    Some(value << Q64_RESOLUTION)
+}
+// Returns the outer None on the multiplication overflow.
+//
+// delta_a = (liquidity * (sqrt_price_upper - sqrt_price_lower) << 64) / (sqrt_price_upper * sqrt_price_lower)
+pub fn try_get_amount_delta_a(sqrt_price_0: u128, sqrt_price_1: u128, liquidity: u128, round_up: bool) -> Option<Option<u64>> {
+    let (sqrt_price_lower, sqrt_price_upper) = increasing_price_order(sqrt_price_0, sqrt_price_1);
+    let sqrt_price_diff = sqrt_price_upper - sqrt_price_lower;
+    let numerator = checked_shift_word_left(U256::from(liquidity) * U256::from(sqrt_price_diff))?;
+    let denominator = U256::from(sqrt_price_upper) * U256::from(sqrt_price_lower);
+    if denominator.is_zero() {
+        return None;
+    }
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    let result = if round_up && !remainder.is_zero() {
+        quotient + U256::one()
+    } else {
+        quotient
+    };
+    Some(u64::try_from(result).ok())
+}
+// delta_b = liquidity * (sqrt_price_upper - sqrt_price_lower) >> 64
+pub fn try_get_amount_delta_b(sqrt_price_0: u128, sqrt_price_1: u128, liquidity: u128, round_up: bool) -> Option<u64> {
+    let (sqrt_price_lower, sqrt_price_upper) = increasing_price_order(sqrt_price_0, sqrt_price_1);
+    // customized checked_mul_shift_right_round_up_if
+    let n0 = liquidity;
+    let n1 = sqrt_price_upper - sqrt_price_lower;
+    if n0 == 0 || n1 == 0 {
+        return Some(0);
+    }
+    let p = n0.checked_mul(n1)?;
+    let result = (p >> Q64_RESOLUTION) as u64;
+    let should_round = round_up && (p & Q64_MASK > 0);
+    if should_round && result == u64::MAX {
+        return None;
+    }
+    Some(
+        if should_round {
+            result + 1
+        } else {
+            result
+        },
+    )
+}
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/math/sqrt_price_math.rs
+pub fn get_next_sqrt_price(sqrt_price: u128, liquidity: u128, amount: u64, amount_specified_is_input: bool, a_to_b: bool) -> Option<u128> {
+    if amount_specified_is_input == a_to_b {
+        // We are fixing A
+        // Case 1. amount_specified_is_input = true, a_to_b = true
+        // We are exchanging A to B with at most _amount_ of A (input)
+        //
+        // Case 2. amount_specified_is_input = false, a_to_b = false
+        // We are exchanging B to A wanting to guarantee at least _amount_ of A (output)
+        //
+        // In either case we want the sqrt_price to be rounded up.
+        get_next_sqrt_price_from_a_round_up(sqrt_price, liquidity, amount, amount_specified_is_input)
+    } else {
+        // We are fixing B
+        // Case 1. amount_specified_is_input = true, a_to_b = false
+        // We are exchanging B to A using at most _amount_ of B (input)
+        //
+        // Case 2. amount_specified_is_input = false, a_to_b = true
+        // We are exchanging A to B wanting to guarantee at least _amount_ of B (output)
+        //
+        // In either case we want the sqrt_price to be rounded down.
+        get_next_sqrt_price_from_b_round_down(sqrt_price, liquidity, amount, amount_specified_is_input)
+    }
+}
+// sqrt_price_new = (sqrt_price * liquidity) / (liquidity + amount * sqrt_price)
+pub fn get_next_sqrt_price_from_a_round_up(sqrt_price: u128, liquidity: u128, amount: u64, amount_specified_is_input: bool) -> Option<u128> {
+    if amount == 0 {
+        return Some(sqrt_price);
+    }
+    let product = U256::from(sqrt_price) * U256::from(amount);
+    let numerator = checked_shift_word_left(U256::from(liquidity) * U256::from(sqrt_price))?;
+    // In this scenario the denominator will end up being < 0
+    let liquidity_shift_left = U256::from(liquidity) << Q64_RESOLUTION;
+    if !amount_specified_is_input && liquidity_shift_left <= product {
+        return None;
+    }
+    let denominator = if amount_specified_is_input {
+        liquidity_shift_left + product
+    } else {
+        liquidity_shift_left - product
+    };
+    if denominator.is_zero() {
+        return None;
+    }
+    let quotient = numerator / denominator;
+    let price = if (numerator % denominator).is_zero() {
+        quotient
+    } else {
+        quotient + U256::one()
+    };
+    let price = u128::try_from(price).ok()?;
+    if !(MIN_SQRT_PRICE_X64..=MAX_SQRT_PRICE_X64).contains(&price) {
+        return None;
+    }
+    Some(price)
+}
+// sqrt_price_new = sqrt_price + (delta / liquidity)
+pub fn get_next_sqrt_price_from_b_round_down(sqrt_price: u128, liquidity: u128, amount: u64, amount_specified_is_input: bool) -> Option<u128> {
+    if liquidity == 0 {
+        return None;
+    }
+    let amount_x64 = (amount as u128) << Q64_RESOLUTION;
+    let delta = if amount_specified_is_input {
+        amount_x64 / liquidity
+    } else {
+        amount_x64.div_ceil(liquidity)
+    };
+    let price = if amount_specified_is_input {
+        sqrt_price.checked_add(delta)?
+    } else {
+        sqrt_price.checked_sub(delta)?
+    };
+    if !(MIN_SQRT_PRICE_X64..=MAX_SQRT_PRICE_X64).contains(&price) {
+        return None;
+    }
+    Some(price)
+}
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/math/swap_math.rs
+#[derive(Default)]
+pub struct SwapStepComputation {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub next_price: u128,
+    pub fee_amount: u64,
+}
+pub fn compute_swap(
+    amount_remaining: u64,
+    fee_rate: u16,
+    liquidity: u128,
+    sqrt_price_current: u128,
+    sqrt_price_target: u128,
+    amount_specified_is_input: bool,
+    a_to_b: bool,
+) -> Option<SwapStepComputation> {
+    // Since SplashPool (aka FullRange only pool) has only 2 initialized ticks at both ends,
+    // the possibility of exceeding u64 when calculating "delta amount" is higher than concentrated pools.
+    // The inner None is the amount exceeding 'u64'.
+    let mut amount_fixed_delta = get_amount_fixed_delta(sqrt_price_current, sqrt_price_target, liquidity, amount_specified_is_input, a_to_b)?;
+    let mut amount_calc = amount_remaining;
+    if amount_specified_is_input {
+        amount_calc = u64::try_from((amount_remaining as u128) * (FEE_RATE_MUL_VALUE - fee_rate as u128) / FEE_RATE_MUL_VALUE).ok()?;
+    }
+    let next_sqrt_price = if amount_fixed_delta.is_some_and(|amount_fixed_delta_| amount_fixed_delta_ <= amount_calc) {
+        sqrt_price_target
+    } else {
+        get_next_sqrt_price(sqrt_price_current, liquidity, amount_calc, amount_specified_is_input, a_to_b)?
+    };
+    let is_max_swap = next_sqrt_price == sqrt_price_target;
+    let amount_unfixed_delta = get_amount_unfixed_delta(sqrt_price_current, next_sqrt_price, liquidity, amount_specified_is_input, a_to_b)?;
+    // If the swap is not at the max, we need to readjust the amount of the fixed token we are using
+    if !is_max_swap {
+        amount_fixed_delta = get_amount_fixed_delta(sqrt_price_current, next_sqrt_price, liquidity, amount_specified_is_input, a_to_b)?;
+    }
+    let amount_fixed_delta = amount_fixed_delta?;
+    let (amount_in, mut amount_out) = if amount_specified_is_input {
+        (amount_fixed_delta, amount_unfixed_delta)
+    } else {
+        (amount_unfixed_delta, amount_fixed_delta)
+    };
+    // Cap output amount if using output
+    if !amount_specified_is_input && amount_out > amount_remaining {
+        amount_out = amount_remaining;
+    }
+    let fee_amount = if amount_specified_is_input && !is_max_swap {
+        amount_remaining.checked_sub(amount_in)?
+    } else {
+        u64::try_from(((amount_in as u128) * (fee_rate as u128)).div_ceil(FEE_RATE_MUL_VALUE - fee_rate as u128)).ok()?
+    };
+    Some(SwapStepComputation {
+        amount_in,
+        amount_out,
+        next_price: next_sqrt_price,
+        fee_amount,
+    })
+}
+fn get_amount_fixed_delta(sqrt_price_current: u128, sqrt_price_target: u128, liquidity: u128, amount_specified_is_input: bool, a_to_b: bool) -> Option<Option<u64>> {
+    if a_to_b == amount_specified_is_input {
+        try_get_amount_delta_a(sqrt_price_current, sqrt_price_target, liquidity, amount_specified_is_input)
+    } else {
+        Some(try_get_amount_delta_b(sqrt_price_current, sqrt_price_target, liquidity, amount_specified_is_input))
+    }
+}
+fn get_amount_unfixed_delta(sqrt_price_current: u128, sqrt_price_target: u128, liquidity: u128, amount_specified_is_input: bool, a_to_b: bool) -> Option<u64> {
+    if a_to_b == amount_specified_is_input {
+        try_get_amount_delta_b(sqrt_price_current, sqrt_price_target, liquidity, !amount_specified_is_input)
+    } else {
+        try_get_amount_delta_a(sqrt_price_current, sqrt_price_target, liquidity, !amount_specified_is_input)?
+    }
+}
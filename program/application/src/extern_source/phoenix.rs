@@ -0,0 +1,187 @@
+use solana_program::pubkey::Pubkey;
+// https://github.com/Ellipsis-Labs/phoenix-v1/blob/master/src/program/accounts.rs
+//
+// Only the fields required for swap calculation are read from the zero-copy layout.
+pub struct MarketHeader {
+    pub status: u64,
+    pub bids_size: u64,
+    pub asks_size: u64,
+    pub num_seats: u64,
+    pub base_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub base_lot_size: u64,
+    pub quote_mint: Pubkey,
+    pub quote_vault: Pubkey,
+    pub quote_lot_size: u64,
+}
+impl MarketHeader {
+    pub const LENGTH: usize = 576;
+    pub const STATUS_ACTIVE: u64 = 1;
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::LENGTH {
+            return None;
+        }
+        // discriminant, status, market_size_params, base_params (decimals, vault_bump, mint_key, vault_key), base_lot_size,
+        // quote_params and quote_lot_size.
+        Some(Self {
+            status: u64::from_le_bytes(*arrayref::array_ref![data, 8, 8]),
+            bids_size: u64::from_le_bytes(*arrayref::array_ref![data, 16, 8]),
+            asks_size: u64::from_le_bytes(*arrayref::array_ref![data, 24, 8]),
+            num_seats: u64::from_le_bytes(*arrayref::array_ref![data, 32, 8]),
+            base_mint: Pubkey::new_from_array(*arrayref::array_ref![data, 48, 32]),
+            base_vault: Pubkey::new_from_array(*arrayref::array_ref![data, 80, 32]),
+            base_lot_size: u64::from_le_bytes(*arrayref::array_ref![data, 112, 8]),
+            quote_mint: Pubkey::new_from_array(*arrayref::array_ref![data, 128, 32]),
+            quote_vault: Pubkey::new_from_array(*arrayref::array_ref![data, 160, 32]),
+            quote_lot_size: u64::from_le_bytes(*arrayref::array_ref![data, 192, 8]),
+        })
+    }
+}
+// https://github.com/Ellipsis-Labs/phoenix-v1/blob/master/src/state/markets/fifo.rs
+#[derive(Clone, Copy)]
+pub struct FifoMarket {
+    pub base_lots_per_base_unit: u64,
+    pub tick_size_in_quote_lots_per_base_unit: u64,
+    pub taker_fee_bps: u64,
+}
+impl FifoMarket {
+    // _padding, base_lots_per_base_unit, tick_size_in_quote_lots_per_base_unit, order_sequence_number, taker_fee_bps,
+    // collected_quote_lot_fees and unclaimed_quote_lot_fees.
+    const BIDS_OFFSET: usize = 8 * 32 + 8 * 6;
+    // Unpacks the data after the market header. Returns the market with the bids and the asks order trees.
+    pub fn unpack<'a>(data: &'a [u8], market_header: &MarketHeader) -> Option<(Self, OrderTree<'a>, OrderTree<'a>)> {
+        let bids_length = OrderTree::get_length(market_header.bids_size)?;
+        let asks_length = OrderTree::get_length(market_header.asks_size)?;
+        let asks_offset = Self::BIDS_OFFSET.checked_add(bids_length)?;
+        let traders_offset = asks_offset.checked_add(asks_length)?;
+        if data.len() < traders_offset {
+            return None;
+        }
+        Some((
+            Self {
+                base_lots_per_base_unit: u64::from_le_bytes(*arrayref::array_ref![data, 256, 8]),
+                tick_size_in_quote_lots_per_base_unit: u64::from_le_bytes(*arrayref::array_ref![data, 264, 8]),
+                taker_fee_bps: u64::from_le_bytes(*arrayref::array_ref![data, 280, 8]),
+            },
+            OrderTree {
+                data: &data[Self::BIDS_OFFSET..asks_offset],
+                max_size: market_header.bids_size,
+            },
+            OrderTree {
+                data: &data[asks_offset..traders_offset],
+                max_size: market_header.asks_size,
+            },
+        ))
+    }
+    // Rounds up.
+    pub fn compute_fee(&self, size_in_quote_lots: u64) -> Option<u64> {
+        u64::try_from((size_in_quote_lots as u128).checked_mul(self.taker_fee_bps as u128)?.checked_add(9_999)?.checked_div(10_000)?).ok()
+    }
+    // Returns the quote lots for the base lots at the price.
+    pub fn get_quote_lots(&self, price_in_ticks: u64, base_lots: u64) -> Option<u64> {
+        u64::try_from(
+            (price_in_ticks as u128)
+                .checked_mul(self.tick_size_in_quote_lots_per_base_unit as u128)?
+                .checked_mul(base_lots as u128)?
+                .checked_div(self.base_lots_per_base_unit as u128)?,
+        )
+        .ok()
+    }
+    // Returns the base lots which quote lots do not exceed the quote lots at the price.
+    pub fn get_base_lots(&self, price_in_ticks: u64, quote_lots: u64) -> Option<u64> {
+        u64::try_from(
+            (quote_lots as u128)
+                .checked_mul(self.base_lots_per_base_unit as u128)?
+                .checked_div((price_in_ticks as u128).checked_mul(self.tick_size_in_quote_lots_per_base_unit as u128)?)?,
+        )
+        .ok()
+    }
+    // Returns the base lots which quote lots are not less than the quote lots at the price.
+    pub fn get_base_lots_rounding_up(&self, price_in_ticks: u64, quote_lots: u64) -> Option<u64> {
+        let denominator = (price_in_ticks as u128).checked_mul(self.tick_size_in_quote_lots_per_base_unit as u128)?;
+        u64::try_from((quote_lots as u128).checked_mul(self.base_lots_per_base_unit as u128)?.checked_add(denominator.checked_sub(1)?)?.checked_div(denominator)?).ok()
+    }
+}
+#[derive(Clone, Copy)]
+pub struct RestingOrder {
+    pub price_in_ticks: u64,
+    pub num_base_lots: u64,
+    pub last_valid_slot: u64,
+    pub last_valid_unix_timestamp_in_seconds: u64,
+}
+impl RestingOrder {
+    pub fn is_expired(&self, current_slot: u64, current_unix_timestamp: u64) -> bool {
+        (self.last_valid_slot != 0 && self.last_valid_slot < current_slot)
+            || (self.last_valid_unix_timestamp_in_seconds != 0 && self.last_valid_unix_timestamp_in_seconds < current_unix_timestamp)
+    }
+}
+// https://github.com/Ellipsis-Labs/sokoban/blob/master/src/red_black_tree.rs
+//
+// The 'RedBlackTree<FIFOOrderId, FIFORestingOrder, SIZE>'. The key ordering puts the best price first on both sides.
+pub struct OrderTree<'a> {
+    data: &'a [u8],
+    max_size: u64,
+}
+impl<'a> OrderTree<'a> {
+    // root, _padding, and the node allocator size, bump_index and free_list_head.
+    const NODES_OFFSET: usize = 4 + 4 * 3 + 8 + 4 + 4;
+    // registers (left, right, parent, color), key (price_in_ticks, order_sequence_number)
+    // and value (trader_index, num_base_lots, last_valid_slot, last_valid_unix_timestamp_in_seconds).
+    const NODE_LENGTH: usize = 4 * 4 + 8 * 2 + 8 * 4;
+    const SENTINEL: u32 = 0;
+    fn get_length(max_size: u64) -> Option<usize> {
+        Self::NODE_LENGTH.checked_mul(usize::try_from(max_size).ok()?)?.checked_add(Self::NODES_OFFSET)
+    }
+    // Node indexes start from 1.
+    fn get_node(&self, index: u32) -> Option<&'a [u8]> {
+        if index == Self::SENTINEL || index as u64 > self.max_size {
+            return None;
+        }
+        let offset = Self::NODES_OFFSET + (index as usize - 1) * Self::NODE_LENGTH;
+        Some(&self.data[offset..offset + Self::NODE_LENGTH])
+    }
+    fn get_register(node: &[u8], register: usize) -> u32 {
+        u32::from_le_bytes(*arrayref::array_ref![node, register * 4, 4])
+    }
+    // Iterates the orders in order. Stops on the invalid node index.
+    pub fn iter(&self) -> OrderTreeIterator<'_, 'a> {
+        OrderTreeIterator {
+            order_tree: self,
+            stack: Vec::new(),
+            node_index: u32::from_le_bytes(*arrayref::array_ref![self.data, 0, 4]),
+            steps_quantity: 0,
+        }
+    }
+}
+pub struct OrderTreeIterator<'b, 'a> {
+    order_tree: &'b OrderTree<'a>,
+    stack: Vec<u32>,
+    node_index: u32,
+    // Protects from the cycle in the corrupted tree.
+    steps_quantity: u64,
+}
+impl Iterator for OrderTreeIterator<'_, '_> {
+    type Item = RestingOrder;
+    fn next(&mut self) -> Option<Self::Item> {
+        const LEFT: usize = 0;
+        const RIGHT: usize = 1;
+        '_a: while self.node_index != OrderTree::SENTINEL {
+            self.steps_quantity += 1;
+            if self.steps_quantity > self.order_tree.max_size {
+                return None;
+            }
+            let node = self.order_tree.get_node(self.node_index)?;
+            self.stack.push(self.node_index);
+            self.node_index = OrderTree::get_register(node, LEFT);
+        }
+        let node_index = self.stack.pop()?;
+        let node = self.order_tree.get_node(node_index)?;
+        self.node_index = OrderTree::get_register(node, RIGHT);
+        Some(RestingOrder {
+            price_in_ticks: u64::from_le_bytes(*arrayref::array_ref![node, 16, 8]),
+            num_base_lots: u64::from_le_bytes(*arrayref::array_ref![node, 40, 8]),
+            last_valid_slot: u64::from_le_bytes(*arrayref::array_ref![node, 48, 8]),
+            last_valid_unix_timestamp_in_seconds: u64::from_le_bytes(*arrayref::array_ref![node, 56, 8]),
+        })
+    }
+}
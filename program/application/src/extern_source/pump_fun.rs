@@ -0,0 +1,94 @@
+use solana_program::pubkey::Pubkey;
+// https://github.com/pump-fun/pump-public-docs/blob/main/idl/pump.json
+//
+// The fields after the used ones are not deserialized.
+#[derive(borsh::BorshDeserialize)]
+pub struct Global {
+    pub initialized: bool,
+    pub authority: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub initial_virtual_token_reserves: u64,
+    pub initial_virtual_sol_reserves: u64,
+    pub initial_real_token_reserves: u64,
+    pub token_total_supply: u64,
+    pub fee_basis_points: u64,
+    pub withdraw_authority: Pubkey,
+    pub enable_migrate: bool,
+    pub pool_migration_fee: u64,
+    pub creator_fee_basis_points: u64,
+}
+impl Global {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        167,
+        232,
+        232,
+        177,
+        200,
+        108,
+        114,
+        127,
+    ];
+}
+#[derive(borsh::BorshDeserialize)]
+pub struct BondingCurve {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+    pub creator: Pubkey,
+}
+impl BondingCurve {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        23,
+        183,
+        248,
+        55,
+        96,
+        216,
+        172,
+        96,
+    ];
+    // https://github.com/pump-fun/pump-sdk/blob/main/src/bondingCurve.ts
+    //
+    // Returns the sol cost without fees for the token amount.
+    pub fn get_buy_sol_cost(&self, amount: u64) -> Option<u64> {
+        if amount >= self.virtual_token_reserves {
+            return None;
+        }
+        let sol_cost = (amount as u128).checked_mul(self.virtual_sol_reserves as u128)?.checked_div((self.virtual_token_reserves - amount) as u128)?.checked_add(1)?;
+        u64::try_from(sol_cost).ok()
+    }
+    // Returns the token amount which sol cost without fees does not exceed the sol amount.
+    pub fn get_buy_token_amount(&self, sol_amount: u64) -> Option<u64> {
+        if sol_amount < 1 {
+            return Some(0);
+        }
+        // The sol cost is rounded up by 1.
+        let sol_amount_ = (sol_amount - 1) as u128;
+        let token_amount = sol_amount_.checked_mul(self.virtual_token_reserves as u128)?.checked_div((self.virtual_sol_reserves as u128).checked_add(sol_amount_)?)?;
+        Some(u64::try_from(token_amount).ok()?.min(self.real_token_reserves))
+    }
+    // Returns the sol amount without fees for the token amount.
+    pub fn get_sell_sol_amount(&self, amount: u64) -> Option<u64> {
+        let sol_amount = (amount as u128).checked_mul(self.virtual_sol_reserves as u128)?.checked_div((self.virtual_token_reserves as u128).checked_add(amount as u128)?)?;
+        u64::try_from(sol_amount).ok()
+    }
+    // Returns the token amount which sol amount without fees is not less than the sol amount.
+    pub fn get_sell_token_amount(&self, sol_amount: u64) -> Option<u64> {
+        if sol_amount >= self.virtual_sol_reserves {
+            return None;
+        }
+        let numerator = (sol_amount as u128).checked_mul(self.virtual_token_reserves as u128)?;
+        let denominator = (self.virtual_sol_reserves - sol_amount) as u128;
+        let token_amount = numerator.checked_add(denominator - 1)?.checked_div(denominator)?;
+        u64::try_from(token_amount).ok()
+    }
+}
+pub fn compute_fee(amount: u64, fee_basis_points: u64) -> Option<u64> {
+    ceil_div((amount as u128).checked_mul(fee_basis_points as u128)?, 10_000)
+}
+fn ceil_div(a: u128, b: u128) -> Option<u64> {
+    u64::try_from(a.checked_add(b.checked_sub(1)?)?.checked_div(b)?).ok()
+}
@@ -0,0 +1,90 @@
+use solana_program::pubkey::Pubkey;
+// https://github.com/pump-fun/pump-public-docs/blob/main/idl/pump_amm.json
+#[derive(borsh::BorshDeserialize)]
+pub struct Pool {
+    pub pool_bump: u8,
+    pub index: u16,
+    pub creator: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub pool_base_token_account: Pubkey,
+    pub pool_quote_token_account: Pubkey,
+    pub lp_supply: u64,
+    pub coin_creator: Pubkey,
+}
+impl Pool {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        241,
+        154,
+        109,
+        4,
+        17,
+        177,
+        109,
+        188,
+    ];
+}
+#[derive(borsh::BorshDeserialize)]
+pub struct GlobalConfig {
+    pub admin: Pubkey,
+    pub lp_fee_basis_points: u64,
+    pub protocol_fee_basis_points: u64,
+    /// Flags to disable certain functionality
+    /// bit 0 - Disable create pool
+    /// bit 1 - Disable deposit
+    /// bit 2 - Disable withdraw
+    /// bit 3 - Disable buy
+    /// bit 4 - Disable sell
+    pub disable_flags: u8,
+    pub protocol_fee_recipients: [Pubkey; 8],
+    pub coin_creator_fee_basis_points: u64,
+}
+impl GlobalConfig {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        149,
+        8,
+        156,
+        202,
+        160,
+        252,
+        176,
+        217,
+    ];
+    pub const DISABLE_BUY_FLAG: u8 = 1 << 3;
+    pub const DISABLE_SELL_FLAG: u8 = 1 << 4;
+}
+// https://github.com/pump-fun/pump-swap-sdk/blob/main/src/sdk/buy.ts
+//
+// Returns the quote amount without fees for the base amount.
+pub fn get_buy_quote_amount(base_amount: u64, base_reserve: u64, quote_reserve: u64) -> Option<u64> {
+    if base_amount >= base_reserve {
+        return None;
+    }
+    ceil_div((quote_reserve as u128).checked_mul(base_amount as u128)?, (base_reserve - base_amount) as u128)
+}
+// Returns the base amount which quote amount without fees does not exceed the quote amount.
+pub fn get_buy_base_amount(quote_amount: u64, base_reserve: u64, quote_reserve: u64) -> Option<u64> {
+    let base_amount = (base_reserve as u128).checked_mul(quote_amount as u128)?.checked_div((quote_reserve as u128).checked_add(quote_amount as u128)?)?;
+    u64::try_from(base_amount).ok()
+}
+// https://github.com/pump-fun/pump-swap-sdk/blob/main/src/sdk/sell.ts
+//
+// Returns the quote amount without fees for the base amount.
+pub fn get_sell_quote_amount(base_amount: u64, base_reserve: u64, quote_reserve: u64) -> Option<u64> {
+    let quote_amount = (quote_reserve as u128).checked_mul(base_amount as u128)?.checked_div((base_reserve as u128).checked_add(base_amount as u128)?)?;
+    u64::try_from(quote_amount).ok()
+}
+// Returns the base amount which quote amount without fees is not less than the quote amount.
+pub fn get_sell_base_amount(quote_amount: u64, base_reserve: u64, quote_reserve: u64) -> Option<u64> {
+    if quote_amount >= quote_reserve {
+        return None;
+    }
+    ceil_div((base_reserve as u128).checked_mul(quote_amount as u128)?, (quote_reserve - quote_amount) as u128)
+}
+pub fn compute_fee(amount: u64, basis_points: u64) -> Option<u64> {
+    ceil_div((amount as u128).checked_mul(basis_points as u128)?, 10_000)
+}
+fn ceil_div(a: u128, b: u128) -> Option<u64> {
+    u64::try_from(a.checked_add(b.checked_sub(1)?)?.checked_div(b)?).ok()
+}
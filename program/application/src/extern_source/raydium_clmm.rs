@@ -0,0 +1,605 @@
+#![allow(clippy::manual_div_ceil)]
+#![allow(clippy::nonminimal_bool)]
+use {
+    solana_program::pubkey::Pubkey,
+    uint::construct_uint,
+};
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/instructions/swap_v2.rs
+pub const MEMO_PROGRAM_ID: Pubkey = Pubkey::from_str_const("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/libraries/fixed_point_64.rs
+pub const RESOLUTION: u8 = 64;
+pub const Q64: u128 = (u64::MAX as u128) + 1;
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/libraries/tick_math.rs
+pub const MIN_TICK: i32 = -443636;
+pub const MAX_TICK: i32 = -MIN_TICK;
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673521066979257578248091;
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/states/config.rs
+pub const FEE_RATE_DENOMINATOR_VALUE: u32 = 1_000_000;
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/libraries/big_num.rs
+construct_uint! {
+    pub struct U256(4);
+}
+construct_uint! {
+    pub struct U512(8);
+}
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/libraries/full_math.rs
+//
+// The multiplication result is kept in 'U512' to not overflow.
+pub trait MulDiv: Sized {
+    fn mul_div_floor(self, num: Self, denom: Self) -> Option<Self>;
+    fn mul_div_ceil(self, num: Self, denom: Self) -> Option<Self>;
+}
+impl MulDiv for u64 {
+    fn mul_div_floor(self, num: Self, denom: Self) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+        let r = (self as u128) * (num as u128) / (denom as u128);
+        u64::try_from(r).ok()
+    }
+    fn mul_div_ceil(self, num: Self, denom: Self) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+        let r = ((self as u128) * (num as u128) + (denom as u128 - 1)) / (denom as u128);
+        u64::try_from(r).ok()
+    }
+}
+impl MulDiv for U256 {
+    fn mul_div_floor(self, num: Self, denom: Self) -> Option<Self> {
+        if denom.is_zero() {
+            return None;
+        }
+        let r = (u256_to_u512(self) * u256_to_u512(num)) / u256_to_u512(denom);
+        u512_to_u256(r)
+    }
+    fn mul_div_ceil(self, num: Self, denom: Self) -> Option<Self> {
+        if denom.is_zero() {
+            return None;
+        }
+        let denom_ = u256_to_u512(denom);
+        let r = (u256_to_u512(self) * u256_to_u512(num) + (denom_ - U512::one())) / denom_;
+        u512_to_u256(r)
+    }
+}
+fn u256_to_u512(value: U256) -> U512 {
+    U512([
+        value.0[0],
+        value.0[1],
+        value.0[2],
+        value.0[3],
+        0,
+        0,
+        0,
+        0,
+    ])
+}
+fn u512_to_u256(value: U512) -> Option<U256> {
+    if value.0[4..].iter().any(|word| *word != 0) {
+        return None;
+    }
+    Some(U256([
+        value.0[0],
+        value.0[1],
+        value.0[2],
+        value.0[3],
+    ]))
+}
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/libraries/unsafe_math.rs
+fn div_rounding_up(x: U256, y: U256) -> U256 {
+    let quotient = x / y;
+    if (x % y).is_zero() {
+        quotient
+    } else {
+        quotient + U256::one()
+    }
+}
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/states/pool.rs
+//
+// In the source code the structure is zero-copy and packed, so it has the same layout as with 'borsh'.
+// Only the fields before the paddings are presented.
+#[derive(borsh::BorshDeserialize)]
+pub struct PoolState {
+    /// Bump to identify PDA
+    pub bump: [u8; 1],
+    // Which config the pool belongs
+    pub amm_config: Pubkey,
+    // Pool creator
+    pub owner: Pubkey,
+    /// Token pair of the pool, where token_mint_0 address < token_mint_1 address
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    /// Token pair vault
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    /// observation account key
+    pub observation_key: Pubkey,
+    /// mint0 and mint1 decimals
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
+    /// The minimum number of ticks between initialized ticks
+    pub tick_spacing: u16,
+    /// The currently in range liquidity available to the pool.
+    pub liquidity: u128,
+    /// The current price of the pool as a sqrt(token_1/token_0) Q64.64 value
+    pub sqrt_price_x64: u128,
+    /// The current tick of the pool, i.e. according to the last tick transition that was run.
+    pub tick_current: i32,
+    pub padding3: u16,
+    pub padding4: u16,
+    /// The fee growth as a Q64.64 number, i.e. fees of token_0 and token_1 collected per
+    /// unit of liquidity for the entire life of the pool.
+    pub fee_growth_global_0_x64: u128,
+    pub fee_growth_global_1_x64: u128,
+    /// The amounts of token_0 and token_1 that are owed to the protocol.
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    /// The amounts in and out of swap token_0 and token_1
+    pub swap_in_amount_token_0: u128,
+    pub swap_out_amount_token_1: u128,
+    pub swap_in_amount_token_1: u128,
+    pub swap_out_amount_token_0: u128,
+    /// Bitwise representation of the state of the pool
+    /// bit0, 1: disable open position and increase liquidity, 0: normal
+    /// bit1, 1: disable decrease liquidity, 0: normal
+    /// bit2, 1: disable collect fee, 0: normal
+    /// bit3, 1: disable collect reward, 0: normal
+    /// bit4, 1: disable swap, 0: normal
+    pub status: u8,
+    /// Leave blank for future use
+    pub padding: [u8; 7],
+    pub reward_infos: [RewardInfo; 3],
+    /// Packed initialized tick array state
+    pub tick_array_bitmap: [u64; 16],
+    /// except protocol_fee and fund_fee
+    pub total_fees_token_0: u64,
+    /// except protocol_fee and fund_fee
+    pub total_fees_claimed_token_0: u64,
+    pub total_fees_token_1: u64,
+    pub total_fees_claimed_token_1: u64,
+    pub fund_fees_token_0: u64,
+    pub fund_fees_token_1: u64,
+    // The timestamp allowed for swap in the pool.
+    pub open_time: u64,
+    // account recent update epoch
+    pub recent_epoch: u64,
+}
+impl PoolState {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        247,
+        237,
+        227,
+        245,
+        215,
+        195,
+        222,
+        70,
+    ];
+    pub fn get_status_by_bit(&self, bit: PoolStatusBitIndex) -> bool {
+        let status = 1u8 << (bit as u8);
+        self.status & status == 0
+    }
+}
+#[derive(borsh::BorshDeserialize)]
+pub struct RewardInfo {
+    /// Reward state
+    pub reward_state: u8,
+    /// Reward open time
+    pub open_time: u64,
+    /// Reward end time
+    pub end_time: u64,
+    /// Reward last update time
+    pub last_update_time: u64,
+    /// Q64.64 number indicates how many tokens per second are earned per unit of liquidity.
+    pub emissions_per_second_x64: u128,
+    /// The total amount of reward emissioned
+    pub reward_total_emissioned: u64,
+    /// The total amount of claimed reward
+    pub reward_claimed: u64,
+    /// Reward token mint.
+    pub token_mint: Pubkey,
+    /// Reward vault token account.
+    pub token_vault: Pubkey,
+    /// The owner that has permission to set reward param
+    pub authority: Pubkey,
+    /// Q64.64 number that tracks the total tokens earned per unit of liquidity since the reward
+    /// emissions were turned on.
+    pub reward_growth_global_x64: u128,
+}
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/states/pool.rs
+pub enum PoolStatusBitIndex {
+    OpenPositionOrIncreaseLiquidity,
+    DecreaseLiquidity,
+    CollectFee,
+    CollectReward,
+    Swap,
+}
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/states/config.rs
+#[derive(borsh::BorshDeserialize)]
+pub struct AmmConfig {
+    /// Bump to identify PDA
+    pub bump: u8,
+    pub index: u16,
+    /// Address of the protocol owner
+    pub owner: Pubkey,
+    /// The protocol fee
+    pub protocol_fee_rate: u32,
+    /// The trade fee, denominated in hundredths of a bip (10^-6)
+    pub trade_fee_rate: u32,
+    /// The tick spacing
+    pub tick_spacing: u16,
+    /// The fund fee, denominated in hundredths of a bip (10^-6)
+    pub fund_fee_rate: u32,
+    // padding space for upgrade
+    pub padding_u32: u32,
+    pub fund_owner: Pubkey,
+    pub padding: [u64; 3],
+}
+impl AmmConfig {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        218,
+        244,
+        33,
+        104,
+        203,
+        203,
+        43,
+        111,
+    ];
+}
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/states/tickarray_bitmap_extension.rs
+pub struct TickArrayBitmapExtension;
+impl TickArrayBitmapExtension {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        60,
+        150,
+        36,
+        219,
+        97,
+        128,
+        139,
+        153,
+    ];
+}
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/states/tick_array.rs
+//
+// Only the fields required for swap calculation are read from the zero-copy packed layout.
+#[derive(Clone, Copy, Default)]
+pub struct TickState {
+    pub tick: i32,
+    /// Amount of net liquidity added (subtracted) when tick is crossed from left to right (right to left)
+    pub liquidity_net: i128,
+    /// The total position liquidity that references this tick
+    pub liquidity_gross: u128,
+}
+impl TickState {
+    // tick, liquidity_net, liquidity_gross, fee_growth_outside_0_x64, fee_growth_outside_1_x64,
+    // reward_growths_outside_x64 and padding.
+    const LENGTH: usize = 4 + 16 + 16 + 16 + 16 + 16 * 3 + 4 * 13;
+    fn unpack(data: &[u8]) -> Self {
+        Self {
+            tick: i32::from_le_bytes(*arrayref::array_ref![data, 0, 4]),
+            liquidity_net: i128::from_le_bytes(*arrayref::array_ref![data, 4, 16]),
+            liquidity_gross: u128::from_le_bytes(*arrayref::array_ref![data, 20, 16]),
+        }
+    }
+    pub fn is_initialized(&self) -> bool {
+        self.liquidity_gross != 0
+    }
+}
+pub const TICK_ARRAY_SIZE_USIZE: usize = 60;
+pub const TICK_ARRAY_SIZE: i32 = 60;
+pub struct TickArrayState {
+    pub pool_id: Pubkey,
+    pub start_tick_index: i32,
+    pub ticks: Vec<TickState>,
+}
+impl TickArrayState {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        192,
+        155,
+        85,
+        205,
+        49,
+        249,
+        129,
+        42,
+    ];
+    const TICKS_OFFSET: usize = 32 + 4;
+    // Unpacks the data after the discriminator.
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::TICKS_OFFSET + TickState::LENGTH * TICK_ARRAY_SIZE_USIZE {
+            return None;
+        }
+        let mut ticks = Vec::<TickState>::with_capacity(TICK_ARRAY_SIZE_USIZE);
+        '_a: for index in 0..TICK_ARRAY_SIZE_USIZE {
+            ticks.push(TickState::unpack(&data[Self::TICKS_OFFSET + index * TickState::LENGTH..]));
+        }
+        Some(Self {
+            pool_id: Pubkey::new_from_array(*arrayref::array_ref![data, 0, 32]),
+            start_tick_index: i32::from_le_bytes(*arrayref::array_ref![data, 32, 4]),
+            ticks,
+        })
+    }
+    /// Input an arbitrary tick_index, output the start_index of the tick_array it sits on
+    pub fn get_array_start_index(tick_index: i32, tick_spacing: u16) -> i32 {
+        let ticks_in_array = TICK_ARRAY_SIZE * i32::from(tick_spacing);
+        let mut start = tick_index / ticks_in_array;
+        if tick_index < 0 && tick_index % ticks_in_array != 0 {
+            start -= 1
+        }
+        start * ticks_in_array
+    }
+    /// Base on swap directioin, return the first initialized tick in the tick array.
+    pub fn first_initialized_tick(&self, zero_for_one: bool) -> Option<&TickState> {
+        if zero_for_one {
+            self.ticks.iter().rev().find(|tick_state| tick_state.is_initialized())
+        } else {
+            self.ticks.iter().find(|tick_state| tick_state.is_initialized())
+        }
+    }
+    /// Get next initialized tick in tick array, `current_tick_index` can be any tick index, in other words, `current_tick_index` not exactly a point in the tickarray,
+    /// and current_tick_index % tick_spacing maybe not equal zero.
+    /// If price move to left tick <= current_tick_index, or to right tick > current_tick_index
+    pub fn next_initialized_tick(&self, current_tick_index: i32, tick_spacing: u16, zero_for_one: bool) -> Option<&TickState> {
+        let current_tick_array_start_index = Self::get_array_start_index(current_tick_index, tick_spacing);
+        if current_tick_array_start_index != self.start_tick_index {
+            return None;
+        }
+        let mut offset_in_array = (current_tick_index - self.start_tick_index) / i32::from(tick_spacing);
+        if zero_for_one {
+            while offset_in_array >= 0 {
+                if self.ticks[offset_in_array as usize].is_initialized() {
+                    return Some(&self.ticks[offset_in_array as usize]);
+                }
+                offset_in_array -= 1;
+            }
+        } else {
+            offset_in_array += 1;
+            while offset_in_array < TICK_ARRAY_SIZE {
+                if self.ticks[offset_in_array as usize].is_initialized() {
+                    return Some(&self.ticks[offset_in_array as usize]);
+                }
+                offset_in_array += 1;
+            }
+        }
+        None
+    }
+}
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/libraries/tick_math.rs
+/// Calculates 1.0001^(tick/2) as a U64.64 number representing
+/// the square root of the ratio of the two assets (token_1/token_0)
+pub fn get_sqrt_price_at_tick(tick: i32) -> Option<u128> {
+    let abs_tick = tick.unsigned_abs();
+    if abs_tick > MAX_TICK as u32 {
+        return None;
+    }
+    // i = 0
+    let mut ratio = if abs_tick & 0x1 != 0 {
+        0xfffcb933bd6fb800
+    } else {
+        // 2^64
+        Q64
+    };
+    const RATIOS: [(u32, u128); 18] = [
+        (0x2, 0xfff97272373d4000),
+        (0x4, 0xfff2e50f5f657000),
+        (0x8, 0xffe5caca7e10f000),
+        (0x10, 0xffcb9843d60f7000),
+        (0x20, 0xff973b41fa98e800),
+        (0x40, 0xff2ea16466c9b000),
+        (0x80, 0xfe5dee046a9a3800),
+        (0x100, 0xfcbe86c7900bb000),
+        (0x200, 0xf987a7253ac65800),
+        (0x400, 0xf3392b0822bb6000),
+        (0x800, 0xe7159475a2caf000),
+        (0x1000, 0xd097f3bdfd2f2000),
+        (0x2000, 0xa9f746462d9f8000),
+        (0x4000, 0x70d869a156f31c00),
+        (0x8000, 0x31be135f97ed3200),
+        (0x10000, 0x9aa508b5b85a500),
+        (0x20000, 0x5d6af8dedc582c),
+        (0x40000, 0x2216e584f5fa),
+    ];
+    '_a: for (bit, ratio_) in RATIOS {
+        if abs_tick & bit != 0 {
+            ratio = (ratio * ratio_) >> RESOLUTION;
+        }
+    }
+    // Divide to obtain 1.0001^(2^(i - 1)) * 2^32 in numerator
+    if tick > 0 {
+        ratio = u128::MAX / ratio;
+    }
+    Some(ratio)
+}
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/libraries/liquidity_math.rs
+/// Add a signed liquidity delta to liquidity and revert if it overflows or underflows
+pub fn add_delta(x: u128, y: i128) -> Option<u128> {
+    if y < 0 {
+        x.checked_sub(y.unsigned_abs())
+    } else {
+        x.checked_add(y as u128)
+    }
+}
+/// Gets the delta amount_0 for given liquidity and price range.
+/// Returns None if the amount does not fit into 'u64'.
+pub fn get_delta_amount_0_unsigned(mut sqrt_ratio_a_x64: u128, mut sqrt_ratio_b_x64: u128, liquidity: u128, round_up: bool) -> Option<u64> {
+    // sqrt_ratio_a_x64 should hold the smaller value
+    if sqrt_ratio_a_x64 > sqrt_ratio_b_x64 {
+        std::mem::swap(&mut sqrt_ratio_a_x64, &mut sqrt_ratio_b_x64);
+    };
+    if sqrt_ratio_a_x64 == 0 {
+        return None;
+    }
+    let numerator_1 = U256::from(liquidity) << RESOLUTION;
+    let numerator_2 = U256::from(sqrt_ratio_b_x64 - sqrt_ratio_a_x64);
+    let result = if round_up {
+        div_rounding_up(numerator_1.mul_div_ceil(numerator_2, U256::from(sqrt_ratio_b_x64))?, U256::from(sqrt_ratio_a_x64))
+    } else {
+        numerator_1.mul_div_floor(numerator_2, U256::from(sqrt_ratio_b_x64))? / U256::from(sqrt_ratio_a_x64)
+    };
+    u64::try_from(result).ok()
+}
+/// Gets the delta amount_1 for given liquidity and price range.
+/// Returns None if the amount does not fit into 'u64'.
+pub fn get_delta_amount_1_unsigned(mut sqrt_ratio_a_x64: u128, mut sqrt_ratio_b_x64: u128, liquidity: u128, round_up: bool) -> Option<u64> {
+    // sqrt_ratio_a_x64 should hold the smaller value
+    if sqrt_ratio_a_x64 > sqrt_ratio_b_x64 {
+        std::mem::swap(&mut sqrt_ratio_a_x64, &mut sqrt_ratio_b_x64);
+    };
+    let result = if round_up {
+        U256::from(liquidity).mul_div_ceil(U256::from(sqrt_ratio_b_x64 - sqrt_ratio_a_x64), U256::from(Q64))?
+    } else {
+        U256::from(liquidity).mul_div_floor(U256::from(sqrt_ratio_b_x64 - sqrt_ratio_a_x64), U256::from(Q64))?
+    };
+    u64::try_from(result).ok()
+}
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/libraries/sqrt_price_math.rs
+/// Gets the next sqrt price √P' given a delta of token_0
+pub fn get_next_sqrt_price_from_amount_0_rounding_up(sqrt_price_x64: u128, liquidity: u128, amount: u64, add: bool) -> Option<u128> {
+    if amount == 0 {
+        return Some(sqrt_price_x64);
+    };
+    let numerator_1 = U256::from(liquidity) << RESOLUTION;
+    let product = U256::from(amount) * U256::from(sqrt_price_x64);
+    let result = if add {
+        let denominator = numerator_1 + product;
+        numerator_1.mul_div_ceil(U256::from(sqrt_price_x64), denominator)?
+    } else {
+        if numerator_1 <= product {
+            return None;
+        }
+        let denominator = numerator_1 - product;
+        numerator_1.mul_div_ceil(U256::from(sqrt_price_x64), denominator)?
+    };
+    u128::try_from(result).ok()
+}
+/// Gets the next sqrt price given a delta of token_1
+pub fn get_next_sqrt_price_from_amount_1_rounding_down(sqrt_price_x64: u128, liquidity: u128, amount: u64, add: bool) -> Option<u128> {
+    if liquidity == 0 {
+        return None;
+    }
+    if add {
+        let quotient = (U256::from(amount) << RESOLUTION) / U256::from(liquidity);
+        sqrt_price_x64.checked_add(u128::try_from(quotient).ok()?)
+    } else {
+        let quotient = div_rounding_up(U256::from(amount) << RESOLUTION, U256::from(liquidity));
+        sqrt_price_x64.checked_sub(u128::try_from(quotient).ok()?).filter(|sqrt_price_x64_| *sqrt_price_x64_ > 0)
+    }
+}
+/// Gets the next sqrt price given an input amount of token_0 or token_1
+pub fn get_next_sqrt_price_from_input(sqrt_price_x64: u128, liquidity: u128, amount_in: u64, zero_for_one: bool) -> Option<u128> {
+    if sqrt_price_x64 == 0 || liquidity == 0 {
+        return None;
+    }
+    // round to make sure that we don't pass the target price
+    if zero_for_one {
+        get_next_sqrt_price_from_amount_0_rounding_up(sqrt_price_x64, liquidity, amount_in, true)
+    } else {
+        get_next_sqrt_price_from_amount_1_rounding_down(sqrt_price_x64, liquidity, amount_in, true)
+    }
+}
+/// Gets the next sqrt price given an output amount of token0 or token1
+pub fn get_next_sqrt_price_from_output(sqrt_price_x64: u128, liquidity: u128, amount_out: u64, zero_for_one: bool) -> Option<u128> {
+    if sqrt_price_x64 == 0 || liquidity == 0 {
+        return None;
+    }
+    if zero_for_one {
+        get_next_sqrt_price_from_amount_1_rounding_down(sqrt_price_x64, liquidity, amount_out, false)
+    } else {
+        get_next_sqrt_price_from_amount_0_rounding_up(sqrt_price_x64, liquidity, amount_out, false)
+    }
+}
+// https://github.com/raydium-io/raydium-clmm/blob/master/programs/amm/src/libraries/swap_math.rs
+/// Result of a swap step
+#[derive(Default)]
+pub struct SwapStep {
+    /// The price after swapping the amount in/out, not to exceed the price target
+    pub sqrt_price_next_x64: u128,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+/// Computes the result of swapping some amount in, or amount out, given the parameters of the swap
+pub fn compute_swap_step(
+    sqrt_price_current_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee_rate: u32,
+    is_base_input: bool,
+    zero_for_one: bool,
+) -> Option<SwapStep> {
+    // let exact_in = amount_remaining >= 0;
+    let mut swap_step = SwapStep::default();
+    if is_base_input {
+        // round up amount_in
+        // In exact input case, amount_remaining is positive
+        let amount_remaining_less_fee = amount_remaining.mul_div_floor(u64::from(FEE_RATE_DENOMINATOR_VALUE.checked_sub(fee_rate)?), u64::from(FEE_RATE_DENOMINATOR_VALUE))?;
+        // None if the amount in to reach the target price does not fit into 'u64'.
+        let amount_in = if zero_for_one {
+            get_delta_amount_0_unsigned(sqrt_price_target_x64, sqrt_price_current_x64, liquidity, true)
+        } else {
+            get_delta_amount_1_unsigned(sqrt_price_current_x64, sqrt_price_target_x64, liquidity, true)
+        };
+        if let Some(amount_in_) = amount_in {
+            swap_step.amount_in = amount_in_;
+        }
+        swap_step.sqrt_price_next_x64 = if amount_in.is_some() && amount_remaining_less_fee >= swap_step.amount_in {
+            sqrt_price_target_x64
+        } else {
+            get_next_sqrt_price_from_input(sqrt_price_current_x64, liquidity, amount_remaining_less_fee, zero_for_one)?
+        };
+    } else {
+        // round down amount_out
+        // None if the amount out to reach the target price does not fit into 'u64'.
+        let amount_out = if zero_for_one {
+            get_delta_amount_1_unsigned(sqrt_price_target_x64, sqrt_price_current_x64, liquidity, false)
+        } else {
+            get_delta_amount_0_unsigned(sqrt_price_current_x64, sqrt_price_target_x64, liquidity, false)
+        };
+        if let Some(amount_out_) = amount_out {
+            swap_step.amount_out = amount_out_;
+        }
+        // In exact output case, amount_remaining is negative
+        swap_step.sqrt_price_next_x64 = if amount_out.is_some() && amount_remaining >= swap_step.amount_out {
+            sqrt_price_target_x64
+        } else {
+            get_next_sqrt_price_from_output(sqrt_price_current_x64, liquidity, amount_remaining, zero_for_one)?
+        }
+    }
+    // whether we reached the max possible price for the given ticks
+    let max = sqrt_price_target_x64 == swap_step.sqrt_price_next_x64;
+    // get the input / output amounts when target price is not reached
+    if zero_for_one {
+        // if max is reached for exact input case, entire amount_in is needed
+        if !(max && is_base_input) {
+            swap_step.amount_in = get_delta_amount_0_unsigned(swap_step.sqrt_price_next_x64, sqrt_price_current_x64, liquidity, true)?
+        };
+        // if max is reached for exact output case, entire amount_out is needed
+        if !(max && !is_base_input) {
+            swap_step.amount_out = get_delta_amount_1_unsigned(swap_step.sqrt_price_next_x64, sqrt_price_current_x64, liquidity, false)?;
+        };
+    } else {
+        if !(max && is_base_input) {
+            swap_step.amount_in = get_delta_amount_1_unsigned(sqrt_price_current_x64, swap_step.sqrt_price_next_x64, liquidity, true)?
+        };
+        if !(max && !is_base_input) {
+            swap_step.amount_out = get_delta_amount_0_unsigned(sqrt_price_current_x64, swap_step.sqrt_price_next_x64, liquidity, false)?
+        };
+    }
+    // For exact output case, cap the output amount to not exceed the remaining output amount
+    if !is_base_input && swap_step.amount_out > amount_remaining {
+        swap_step.amount_out = amount_remaining;
+    }
+    swap_step.fee_amount = if is_base_input && swap_step.sqrt_price_next_x64 != sqrt_price_target_x64 {
+        // we didn't reach the target, so take the remainder of the maximum input as fee
+        // swap dust is granted as fee
+        amount_remaining.checked_sub(swap_step.amount_in)?
+    } else {
+        // take pip percentage as fee
+        swap_step.amount_in.mul_div_ceil(u64::from(fee_rate), u64::from(FEE_RATE_DENOMINATOR_VALUE.checked_sub(fee_rate)?))?
+    };
+    Some(swap_step)
+}
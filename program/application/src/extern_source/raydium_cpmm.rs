@@ -0,0 +1,147 @@
+use {
+    super::CheckedCeilDiv,
+    solana_program::pubkey::Pubkey,
+};
+// https://github.com/raydium-io/raydium-cp-swap/blob/master/programs/cp-swap/src/states/pool.rs
+//
+// In the source code the structure is zero-copy and packed, so it has the same layout as with 'borsh'.
+#[derive(borsh::BorshDeserialize)]
+pub struct PoolState {
+    /// Which config the pool belongs
+    pub amm_config: Pubkey,
+    /// pool creator
+    pub pool_creator: Pubkey,
+    /// Token A
+    pub token_0_vault: Pubkey,
+    /// Token B
+    pub token_1_vault: Pubkey,
+    /// Pool tokens are issued when A or B tokens are deposited.
+    /// Pool tokens can be withdrawn back to the original A or B token.
+    pub lp_mint: Pubkey,
+    /// Mint information for token A
+    pub token_0_mint: Pubkey,
+    /// Mint information for token B
+    pub token_1_mint: Pubkey,
+    /// token_0 program
+    pub token_0_program: Pubkey,
+    /// token_1 program
+    pub token_1_program: Pubkey,
+    /// observation account to store oracle data
+    pub observation_key: Pubkey,
+    pub auth_bump: u8,
+    /// Bitwise representation of the state of the pool
+    /// bit0, 1: disable deposit(vaule is 1), 0: normal
+    /// bit1, 1: disable withdraw(vaule is 2), 0: normal
+    /// bit2, 1: disable swap(vaule is 4), 0: normal
+    pub status: u8,
+    pub lp_mint_decimals: u8,
+    /// mint0 and mint1 decimals
+    pub mint_0_decimals: u8,
+    pub mint_1_decimals: u8,
+    /// True circulating supply without burns and lock ups
+    pub lp_supply: u64,
+    /// The amounts of token_0 and token_1 that are owed to the liquidity provider.
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    pub fund_fees_token_0: u64,
+    pub fund_fees_token_1: u64,
+    /// The timestamp allowed for swap in the pool.
+    pub open_time: u64,
+    /// recent epoch
+    pub recent_epoch: u64,
+}
+impl PoolState {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        247,
+        237,
+        227,
+        245,
+        215,
+        195,
+        222,
+        70,
+    ];
+    pub fn get_status_by_bit(&self, bit: PoolStatusBitIndex) -> bool {
+        let status = 1u8 << (bit as u8);
+        self.status & status == 0
+    }
+    pub fn vault_amount_without_fee(&self, vault_0: u64, vault_1: u64) -> Option<(u64, u64)> {
+        Some((
+            vault_0.checked_sub(self.protocol_fees_token_0.checked_add(self.fund_fees_token_0)?)?,
+            vault_1.checked_sub(self.protocol_fees_token_1.checked_add(self.fund_fees_token_1)?)?,
+        ))
+    }
+}
+// https://github.com/raydium-io/raydium-cp-swap/blob/master/programs/cp-swap/src/states/pool.rs
+pub enum PoolStatusBitIndex {
+    Deposit,
+    Withdraw,
+    Swap,
+}
+// https://github.com/raydium-io/raydium-cp-swap/blob/master/programs/cp-swap/src/states/config.rs
+#[derive(borsh::BorshDeserialize)]
+pub struct AmmConfig {
+    /// Bump to identify PDA
+    pub bump: u8,
+    /// Status to control if new pool can be create
+    pub disable_create_pool: bool,
+    /// Config index
+    pub index: u16,
+    /// The trade fee, denominated in hundredths of a bip (10^-6)
+    pub trade_fee_rate: u64,
+    /// The protocol fee
+    pub protocol_fee_rate: u64,
+    /// The fund fee, denominated in hundredths of a bip (10^-6)
+    pub fund_fee_rate: u64,
+    /// Fee for create a new pool
+    pub create_pool_fee: u64,
+    /// Address of the protocol fee owner
+    pub protocol_owner: Pubkey,
+    /// Address of the fund fee owner
+    pub fund_owner: Pubkey,
+}
+impl AmmConfig {
+    pub const DISCRIMINATOR: [u8; 8] = [
+        218,
+        244,
+        33,
+        104,
+        203,
+        203,
+        43,
+        111,
+    ];
+}
+// https://github.com/raydium-io/raydium-cp-swap/blob/master/programs/cp-swap/src/curve/fees.rs
+pub const FEE_RATE_DENOMINATOR_VALUE: u64 = 1_000_000;
+pub fn ceil_div(token_amount: u128, fee_numerator: u128, fee_denominator: u128) -> Option<u128> {
+    token_amount.checked_mul(fee_numerator)?.checked_add(fee_denominator)?.checked_sub(1)?.checked_div(fee_denominator)
+}
+/// Calculate the trading fee in trading tokens
+pub fn trading_fee(amount: u128, trade_fee_rate: u64) -> Option<u128> {
+    ceil_div(amount, u128::from(trade_fee_rate), u128::from(FEE_RATE_DENOMINATOR_VALUE))
+}
+pub fn calculate_pre_fee_amount(post_fee_amount: u128, trade_fee_rate: u64) -> Option<u128> {
+    if trade_fee_rate == 0 {
+        Some(post_fee_amount)
+    } else {
+        let numerator = post_fee_amount.checked_mul(u128::from(FEE_RATE_DENOMINATOR_VALUE))?;
+        let denominator = u128::from(FEE_RATE_DENOMINATOR_VALUE).checked_sub(u128::from(trade_fee_rate))?;
+        numerator.checked_add(denominator)?.checked_sub(1)?.checked_div(denominator)
+    }
+}
+// https://github.com/raydium-io/raydium-cp-swap/blob/master/programs/cp-swap/src/curve/constant_product.rs
+pub fn swap_base_input_without_fees(source_amount: u128, swap_source_amount: u128, swap_destination_amount: u128) -> Option<u128> {
+    // (x + delta_x) * (y - delta_y) = x * y
+    // delta_y = (delta_x * y) / (x + delta_x)
+    let numerator = source_amount.checked_mul(swap_destination_amount)?;
+    let denominator = swap_source_amount.checked_add(source_amount)?;
+    numerator.checked_div(denominator)
+}
+pub fn swap_base_output_without_fees(destination_amount: u128, swap_source_amount: u128, swap_destination_amount: u128) -> Option<u128> {
+    // (x + delta_x) * (y - delta_y) = x * y
+    // delta_x = (x * delta_y) / (y - delta_y)
+    let numerator = swap_source_amount.checked_mul(destination_amount)?;
+    let denominator = swap_destination_amount.checked_sub(destination_amount)?;
+    Some(numerator.checked_ceil_div(denominator)?.0)
+}
@@ -714,10 +714,15 @@ impl Pack for Fees {
     }
 }
 pub fn unpack_token_account(account_info: &AccountInfo, token_program_id: &Pubkey) -> Result<Account, Error> {
-    if account_info.owner != token_program_id {
+    // The Token-2022 account has the same base state, the extensions are placed after it.
+    if account_info.owner != token_program_id && *account_info.owner != crate::extern_source::spl_token_2022::ID {
         Err(Error::InvalidSplTokenProgram)
     } else {
-        Account::unpack(&account_info.data.borrow()).map_err(|_| Error::ExpectedAccount)
+        let data = account_info.data.borrow();
+        if data.len() < Account::LEN {
+            return Err(Error::ExpectedAccount);
+        }
+        Account::unpack(&data[..Account::LEN]).map_err(|_| Error::ExpectedAccount)
     }
 }
 #[derive(Copy, Clone, Debug)]
@@ -1356,3 +1361,25 @@ pub fn swap_token_amount_base_in(amount_in: U128, total_pc_without_take_pnl: U12
         }
     }
 }
+pub fn swap_token_amount_base_out(amount_out: U128, total_pc_without_take_pnl: U128, total_coin_without_take_pnl: U128, swap_direction: SwapDirection) -> U128 {
+    match swap_direction {
+        SwapDirection::Coin2PC => {
+            // (x + delta_x) * (y + delta_y) = x * y
+            // (coin + amount_in) * (pc - amount_out) = coin * pc
+            // => amount_in = coin * pc / (pc - amount_out) - coin
+            // => amount_in = (coin * pc - pc * coin + amount_out * coin) / (pc - amount_out)
+            // => amount_in = (amount_out * coin) / (pc - amount_out)
+            let denominator = total_pc_without_take_pnl.checked_sub(amount_out).unwrap();
+            total_coin_without_take_pnl.checked_mul(amount_out).unwrap().checked_ceil_div(denominator).unwrap().0
+        }
+        SwapDirection::PC2Coin => {
+            // (x + delta_x) * (y + delta_y) = x * y
+            // (pc + amount_in) * (coin - amount_out) = coin * pc
+            // => amount_out = coin - coin * pc / (pc + amount_in)
+            // => amount_out = (coin * pc + coin * amount_in - coin * pc) / (pc + amount_in)
+            // => amount_in = (amount_out * pc) / (coin - amount_out)
+            let denominator = total_coin_without_take_pnl.checked_sub(amount_out).unwrap();
+            total_pc_without_take_pnl.checked_mul(amount_out).unwrap().checked_ceil_div(denominator).unwrap().0
+        }
+    }
+}
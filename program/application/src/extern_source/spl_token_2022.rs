@@ -0,0 +1,214 @@
+use solana_program::{
+    instruction::{
+        AccountMeta,
+        Instruction,
+    },
+    pubkey::Pubkey,
+};
+pub const ID: Pubkey = Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+// https://github.com/solana-program/token-2022/blob/main/program/src/state.rs
+//
+// The base state is the same as for 'spl_token', the extensions are placed after the 'spl_token::state::Account::LEN' bytes.
+const BASE_ACCOUNT_LENGTH: usize = 165;
+const ACCOUNT_TYPE_LENGTH: usize = 1;
+const ACCOUNT_AMOUNT_OFFSET: usize = 64;
+const MINT_LENGTH: usize = 82;
+const MINT_DECIMALS_OFFSET: usize = 44;
+// https://github.com/solana-program/token-2022/blob/main/program/src/extension/mod.rs
+const EXTENSION_TYPE_TRANSFER_FEE_CONFIG: u16 = 1;
+const TYPE_LENGTH: usize = 2;
+const LENGTH_LENGTH: usize = 2;
+// https://github.com/solana-program/token-2022/blob/main/program/src/extension/transfer_fee/mod.rs
+pub const MAX_FEE_BASIS_POINTS: u16 = 10_000;
+const ONE_IN_BASIS_POINTS: u128 = MAX_FEE_BASIS_POINTS as u128;
+pub struct TransferFee {
+    /// First epoch where the transfer fee takes effect
+    pub epoch: u64,
+    /// Maximum fee assessed on transfers, expressed as an amount of tokens
+    pub maximum_fee: u64,
+    /// Amount of transfer collected as fees, expressed as basis points of the
+    /// transfer amount, ie. increments of 0.01%
+    pub transfer_fee_basis_points: u16,
+}
+impl TransferFee {
+    const LENGTH: usize = 18;
+    fn unpack(data: &[u8]) -> Self {
+        Self {
+            epoch: u64::from_le_bytes(*arrayref::array_ref![data, 0, 8]),
+            maximum_fee: u64::from_le_bytes(*arrayref::array_ref![data, 8, 8]),
+            transfer_fee_basis_points: u16::from_le_bytes(*arrayref::array_ref![data, 16, 2]),
+        }
+    }
+    /// Calculate the transfer fee
+    pub fn calculate_fee(&self, pre_fee_amount: u64) -> Option<u64> {
+        let transfer_fee_basis_points = self.transfer_fee_basis_points as u128;
+        if transfer_fee_basis_points == 0 || pre_fee_amount == 0 {
+            Some(0)
+        } else {
+            let numerator = (pre_fee_amount as u128).checked_mul(transfer_fee_basis_points)?;
+            let raw_fee = numerator.checked_add(ONE_IN_BASIS_POINTS)?.checked_sub(1)?.checked_div(ONE_IN_BASIS_POINTS)?;
+            let fee: u64 = raw_fee.try_into().ok()?;
+            Some(fee.min(self.maximum_fee))
+        }
+    }
+    /// Calculate the pre-fee amount that will result in a given post-fee amount
+    pub fn calculate_pre_fee_amount(&self, post_fee_amount: u64) -> Option<u64> {
+        let maximum_fee = self.maximum_fee;
+        let transfer_fee_basis_points = self.transfer_fee_basis_points as u128;
+        match (transfer_fee_basis_points, post_fee_amount) {
+            // no fee, same amount
+            (0, _) => Some(post_fee_amount),
+            // 0 zero out, 0 in
+            (_, 0) => Some(0),
+            // 100%, cap at max fee
+            (ONE_IN_BASIS_POINTS, _) => maximum_fee.checked_add(post_fee_amount),
+            _ => {
+                let numerator = (post_fee_amount as u128).checked_mul(ONE_IN_BASIS_POINTS)?;
+                let denominator = ONE_IN_BASIS_POINTS.checked_sub(transfer_fee_basis_points)?;
+                let raw_pre_fee_amount = numerator.checked_add(denominator)?.checked_sub(1)?.checked_div(denominator)?;
+                if raw_pre_fee_amount.checked_sub(post_fee_amount as u128)? >= maximum_fee as u128 {
+                    post_fee_amount.checked_add(maximum_fee)
+                } else {
+                    // should return `None` if `pre_fee_amount` overflows
+                    u64::try_from(raw_pre_fee_amount).ok()
+                }
+            }
+        }
+    }
+    /// Calculate the fee that would produce the given output
+    pub fn calculate_inverse_fee(&self, post_fee_amount: u64) -> Option<u64> {
+        let pre_fee_amount = self.calculate_pre_fee_amount(post_fee_amount)?;
+        self.calculate_fee(pre_fee_amount)
+    }
+}
+pub struct TransferFeeConfig {
+    /// Withheld transfer fee tokens that have been moved to the mint for withdrawal
+    pub withheld_amount: u64,
+    /// Older transfer fee, used if the current epoch < new_transfer_fee.epoch
+    pub older_transfer_fee: TransferFee,
+    /// Newer transfer fee, used if the current epoch >= new_transfer_fee.epoch
+    pub newer_transfer_fee: TransferFee,
+}
+impl TransferFeeConfig {
+    // Transfer fee config authority and withdraw withheld authority.
+    const AUTHORITIES_LENGTH: usize = 64;
+    const LENGTH: usize = Self::AUTHORITIES_LENGTH + 8 + TransferFee::LENGTH * 2;
+    /// Get the fee for the given epoch
+    pub fn get_epoch_fee(&self, epoch: u64) -> &TransferFee {
+        if epoch >= self.newer_transfer_fee.epoch {
+            &self.newer_transfer_fee
+        } else {
+            &self.older_transfer_fee
+        }
+    }
+    /// Calculate the fee for the given epoch and input amount
+    pub fn calculate_epoch_fee(&self, epoch: u64, pre_fee_amount: u64) -> Option<u64> {
+        self.get_epoch_fee(epoch).calculate_fee(pre_fee_amount)
+    }
+    /// Calculate the fee for the given epoch and output amount
+    pub fn calculate_inverse_epoch_fee(&self, epoch: u64, post_fee_amount: u64) -> Option<u64> {
+        self.get_epoch_fee(epoch).calculate_inverse_fee(post_fee_amount)
+    }
+    // Returns None if the mint has no the extension.
+    pub fn unpack_from_mint(mint_data: &[u8]) -> Option<Self> {
+        let mut index = BASE_ACCOUNT_LENGTH + ACCOUNT_TYPE_LENGTH;
+        while index + TYPE_LENGTH + LENGTH_LENGTH <= mint_data.len() {
+            let extension_type = u16::from_le_bytes(*arrayref::array_ref![mint_data, index, TYPE_LENGTH]);
+            let length = u16::from_le_bytes(*arrayref::array_ref![mint_data, index + TYPE_LENGTH, LENGTH_LENGTH]) as usize;
+            let value_index = index + TYPE_LENGTH + LENGTH_LENGTH;
+            if value_index + length > mint_data.len() {
+                return None;
+            }
+            if extension_type == EXTENSION_TYPE_TRANSFER_FEE_CONFIG {
+                if length != Self::LENGTH {
+                    return None;
+                }
+                let value = &mint_data[value_index..value_index + length];
+                return Some(Self {
+                    withheld_amount: u64::from_le_bytes(*arrayref::array_ref![value, Self::AUTHORITIES_LENGTH, 8]),
+                    older_transfer_fee: TransferFee::unpack(&value[Self::AUTHORITIES_LENGTH + 8..]),
+                    newer_transfer_fee: TransferFee::unpack(&value[Self::AUTHORITIES_LENGTH + 8 + TransferFee::LENGTH..]),
+                });
+            }
+            index = value_index + length;
+        }
+        None
+    }
+}
+// Reads the decimals of the mint of both 'spl_token' and 'spl_token_2022' programs.
+pub fn unpack_mint_decimals(mint_data: &[u8]) -> Option<u8> {
+    if mint_data.len() < MINT_LENGTH {
+        return None;
+    }
+    Some(mint_data[MINT_DECIMALS_OFFSET])
+}
+// Reads the amount of the token account of both 'spl_token' and 'spl_token_2022' programs.
+pub fn unpack_account_amount(account_data: &[u8]) -> Option<u64> {
+    if account_data.len() < BASE_ACCOUNT_LENGTH {
+        return None;
+    }
+    Some(u64::from_le_bytes(*arrayref::array_ref![account_data, ACCOUNT_AMOUNT_OFFSET, 8]))
+}
+// https://github.com/solana-program/token-2022/blob/main/program/src/instruction.rs
+//
+// Returns the size of the token account with the extensions required by the mint through the return data.
+pub fn get_account_data_size(token_program_id: &Pubkey, mint: &Pubkey) -> Instruction {
+    const GET_ACCOUNT_DATA_SIZE_INSTRUCTION_TAG: u8 = 21;
+    Instruction {
+        program_id: *token_program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*mint, false),
+        ],
+        data: vec![
+            GET_ACCOUNT_DATA_SIZE_INSTRUCTION_TAG,
+        ],
+    }
+}
+// The same as 'initialize_account', but the owner is passed through the data and the rent sysvar is not required.
+pub fn initialize_account3(token_program_id: &Pubkey, account: &Pubkey, mint: &Pubkey, owner: &Pubkey) -> Instruction {
+    const INITIALIZE_ACCOUNT3_INSTRUCTION_TAG: u8 = 18;
+    let mut data = Vec::<u8>::with_capacity(1 + 32);
+    data.push(INITIALIZE_ACCOUNT3_INSTRUCTION_TAG);
+    data.extend_from_slice(owner.as_ref());
+    Instruction {
+        program_id: *token_program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*mint, false),
+        ],
+        data,
+    }
+}
+// The instruction has the same layout in the 'spl_token' program, so it is used for both programs.
+pub fn transfer_checked(token_program_id: &Pubkey, source: &Pubkey, mint: &Pubkey, destination: &Pubkey, authority: &Pubkey, amount: u64, decimals: u8) -> Instruction {
+    const TRANSFER_CHECKED_INSTRUCTION_TAG: u8 = 12;
+    let mut data = Vec::<u8>::with_capacity(1 + 8 + 1);
+    data.push(TRANSFER_CHECKED_INSTRUCTION_TAG);
+    data.extend_from_slice(amount.to_le_bytes().as_slice());
+    data.push(decimals);
+    Instruction {
+        program_id: *token_program_id,
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data,
+    }
+}
+// The instruction has the same layout in the 'spl_token' program, so it is used for both programs.
+pub fn close_account(token_program_id: &Pubkey, account: &Pubkey, destination: &Pubkey, owner: &Pubkey) -> Instruction {
+    const CLOSE_ACCOUNT_INSTRUCTION_TAG: u8 = 9;
+    Instruction {
+        program_id: *token_program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: vec![
+            CLOSE_ACCOUNT_INSTRUCTION_TAG,
+        ],
+    }
+}
@@ -1,5 +1,11 @@
 use {
-    crate::state::Dex,
+    crate::state::{
+        Dex,
+        DexAccountsRange,
+        Hop,
+        SwapMode,
+        TokenAccountToClose,
+    },
     solana_program::{
         instruction::{
             AccountMeta,
@@ -29,15 +35,85 @@ pub enum Instruction {
         lamports_from_treasury: u64,
     },
     Swap {
-        dexes: Vec<Dex>,
+        dexes: Vec<DexAccountsRange>,
         token_mint: Pubkey,
         quote_mint: Pubkey,
         amount_in: u64,
         min_amount_out: u64,
         token_account_pubkey_bump_seed: u8,
+        quote_token_account_pubkey_bump_seed: u8,
+        is_from_quote_to_token: bool,
+        with_checks: bool,
+        swap_mode: SwapMode,
+        // The swap is rejected after this slot.
+        valid_until_slot: Option<u64>,
+        // The swap is rejected after this unix timestamp.
+        valid_until_unix_timestamp: Option<i64>,
+        // The maximum deviation of the execution price from the pre-trade spot price without fees.
+        max_price_impact_bps: Option<u16>,
+        // The maximum deviation of the received amount from the pre-trade spot price amount with fees.
+        max_slippage_bps: Option<u16>,
+    },
+    // The in token account of the first hop and the out token account of the last hop may be the treasury
+    // of the registered non-WSol quote mint. The bump seed is then the bump seed of the treasury.
+    Route {
+        hops: Vec<Hop>,
+        in_token_mint: Pubkey,
+        amount_in: u64,
+        min_amount_out: u64,
+        in_token_account_pubkey_bump_seed: u8,
+        with_checks: bool,
+    },
+    // Buys the token for WSol on the one Dex and sells the received token amount on the another Dex.
+    // Only WSol is accepted as the quote mint, as the profit is measured in lamports.
+    // The accounts of the sell Dex follow the accounts of the buy Dex.
+    Arbitrage {
+        buy_dex: Dex,
+        buy_dex_accounts_quantity: u8,
+        sell_dex: Dex,
+        sell_dex_accounts_quantity: u8,
+        token_mint: Pubkey,
+        amount_in: u64,
+        min_profit_lamports: u64,
+        token_account_pubkey_bump_seed: u8,
+        with_checks: bool,
+    },
+    // Does the swap calculation on every Dex and returns Vec<SwapCalculationResult> through the return data.
+    Quote {
+        dexes: Vec<DexAccountsRange>,
+        token_mint: Pubkey,
+        quote_mint: Pubkey,
+        amount_in: u64,
+        token_account_pubkey_bump_seed: u8,
+        quote_token_account_pubkey_bump_seed: u8,
         is_from_quote_to_token: bool,
         with_checks: bool,
     },
+    // Creates the treasury token account for the quote mint. Only the quote mint with the treasury is accepted by the swap.
+    RegisterQuoteMint {
+        quote_token_account_pubkey_bump_seed: u8,
+    },
+    DepositQuoteFunds {
+        amount_to_treasury: u64,
+        quote_token_account_pubkey_bump_seed: u8,
+    },
+    WithdrawQuoteFunds {
+        amount_from_treasury: u64,
+        quote_token_account_pubkey_bump_seed: u8,
+    },
+    // Transfers the token from the PDA token account of the mint. The emptied account may be closed with the rent to the manager.
+    // The WSol and the quote mint treasuries are not accepted.
+    WithdrawToken {
+        mint: Pubkey,
+        amount: u64,
+        token_account_pubkey_bump_seed: u8,
+        close_token_account: bool,
+    },
+    // Closes the empty PDA token accounts of the mints with the rent to the trader or the manager.
+    // The WSol and the quote mint treasuries are not accepted.
+    CloseTokenAccounts {
+        token_accounts: Vec<TokenAccountToClose>,
+    },
 }
 impl Instruction {
     pub fn initialize(
@@ -139,6 +215,157 @@ impl Instruction {
             })?,
         })
     }
+    pub fn register_quote_mint(
+        program_id: &Pubkey,
+        intermediary: &Pubkey,
+        intermediary_manager: &Pubkey,
+        quote_token_account: &Pubkey,
+        self_authority: &Pubkey,
+        quote_token_mint: &Pubkey,
+        system_program_id: &Pubkey,
+        rent_program_id: &Pubkey,
+        token_program_id: &Pubkey,
+        quote_token_account_pubkey_bump_seed: u8,
+    ) -> Result<Instruction_, Error> {
+        Ok(Instruction_ {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*intermediary, false),
+                AccountMeta::new(*intermediary_manager, true),
+                AccountMeta::new(*quote_token_account, false),
+                AccountMeta::new_readonly(*self_authority, false),
+                AccountMeta::new_readonly(*quote_token_mint, false),
+                AccountMeta::new_readonly(*system_program_id, false),
+                AccountMeta::new_readonly(*rent_program_id, false),
+                AccountMeta::new_readonly(*token_program_id, false),
+            ],
+            data: borsh::to_vec(&Self::RegisterQuoteMint {
+                quote_token_account_pubkey_bump_seed,
+            })?,
+        })
+    }
+    pub fn deposit_quote_funds(
+        program_id: &Pubkey,
+        intermediary: &Pubkey,
+        intermediary_manager: &Pubkey,
+        intermediary_manager_token_account: &Pubkey,
+        quote_token_account: &Pubkey,
+        quote_token_mint: &Pubkey,
+        token_program_id: &Pubkey,
+        amount_to_treasury: u64,
+        quote_token_account_pubkey_bump_seed: u8,
+    ) -> Result<Instruction_, Error> {
+        Ok(Instruction_ {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*intermediary, false),
+                AccountMeta::new_readonly(*intermediary_manager, true),
+                AccountMeta::new(*intermediary_manager_token_account, false),
+                AccountMeta::new(*quote_token_account, false),
+                AccountMeta::new_readonly(*quote_token_mint, false),
+                AccountMeta::new_readonly(*token_program_id, false),
+            ],
+            data: borsh::to_vec(&Self::DepositQuoteFunds {
+                amount_to_treasury,
+                quote_token_account_pubkey_bump_seed,
+            })?,
+        })
+    }
+    pub fn withdraw_quote_funds(
+        program_id: &Pubkey,
+        intermediary: &Pubkey,
+        intermediary_manager: &Pubkey,
+        quote_token_account: &Pubkey,
+        intermediary_manager_token_account: &Pubkey,
+        self_authority: &Pubkey,
+        quote_token_mint: &Pubkey,
+        token_program_id: &Pubkey,
+        amount_from_treasury: u64,
+        quote_token_account_pubkey_bump_seed: u8,
+    ) -> Result<Instruction_, Error> {
+        Ok(Instruction_ {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*intermediary, false),
+                AccountMeta::new_readonly(*intermediary_manager, true),
+                AccountMeta::new(*quote_token_account, false),
+                AccountMeta::new(*intermediary_manager_token_account, false),
+                AccountMeta::new_readonly(*self_authority, false),
+                AccountMeta::new_readonly(*quote_token_mint, false),
+                AccountMeta::new_readonly(*token_program_id, false),
+            ],
+            data: borsh::to_vec(&Self::WithdrawQuoteFunds {
+                amount_from_treasury,
+                quote_token_account_pubkey_bump_seed,
+            })?,
+        })
+    }
+    pub fn withdraw_token(
+        program_id: &Pubkey,
+        intermediary: &Pubkey,
+        intermediary_manager: &Pubkey,
+        token_account: &Pubkey,
+        destination_token_account: &Pubkey,
+        self_authority: &Pubkey,
+        token_mint: &Pubkey,
+        token_program_id: &Pubkey,
+        amount: u64,
+        token_account_pubkey_bump_seed: u8,
+        close_token_account: bool,
+    ) -> Result<Instruction_, Error> {
+        Ok(Instruction_ {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*intermediary, false),
+                AccountMeta::new(*intermediary_manager, true),
+                AccountMeta::new(*token_account, false),
+                AccountMeta::new(*destination_token_account, false),
+                AccountMeta::new_readonly(*self_authority, false),
+                AccountMeta::new_readonly(*token_mint, false),
+                AccountMeta::new_readonly(*token_program_id, false),
+            ],
+            data: borsh::to_vec(&Self::WithdrawToken {
+                mint: *token_mint,
+                amount,
+                token_account_pubkey_bump_seed,
+                close_token_account,
+            })?,
+        })
+    }
+    // The receiver is the trader or the manager. The token accounts are presented in the same order as the mints.
+    pub fn close_token_accounts(
+        program_id: &Pubkey,
+        intermediary: &Pubkey,
+        receiver: &Pubkey,
+        self_authority: &Pubkey,
+        token_program_id: &Pubkey,
+        token_2022_program_id: &Pubkey,
+        token_accounts: Vec<(&Pubkey, TokenAccountToClose)>,
+    ) -> Result<Instruction_, Error> {
+        if token_accounts.is_empty() {
+            return Err(Error::other("Zero token mints."));
+        }
+        let mut accounts = vec![
+            // For Intermediary
+            AccountMeta::new_readonly(*intermediary, false),
+            AccountMeta::new(*receiver, true),
+            AccountMeta::new_readonly(*self_authority, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(*token_2022_program_id, false),
+        ];
+        let mut token_accounts_ = Vec::<TokenAccountToClose>::with_capacity(token_accounts.len());
+        '_a: for (token_account, token_account_to_close) in token_accounts.into_iter() {
+            accounts.push(AccountMeta::new(*token_account, false));
+            token_accounts_.push(token_account_to_close);
+        }
+        Ok(Instruction_ {
+            program_id: *program_id,
+            accounts,
+            data: borsh::to_vec(&Self::CloseTokenAccounts {
+                token_accounts: token_accounts_,
+            })?,
+        })
+    }
     pub fn swap(
         program_id: &Pubkey,
         intermediary: &Pubkey,
@@ -151,12 +378,19 @@ impl Instruction {
         system_program_id: &Pubkey,
         rent_program_id: &Pubkey,
         token_program_id: &Pubkey,
+        token_2022_program_id: &Pubkey,
         dexes: Vec<Dex_<'_>>,
         amount_in: u64,
         min_amount_out: u64,
         token_account_pubkey_bump_seed: u8,
+        quote_token_account_pubkey_bump_seed: u8,
         is_from_quote_to_token: bool,
         with_checks: bool,
+        swap_mode: SwapMode,
+        valid_until_slot: Option<u64>,
+        valid_until_unix_timestamp: Option<i64>,
+        max_price_impact_bps: Option<u16>,
+        max_slippage_bps: Option<u16>,
     ) -> Result<Instruction_, Error> {
         if dexes.is_empty() {
             return Err(Error::other("Zero dexes."));
@@ -173,85 +407,9 @@ impl Instruction {
             AccountMeta::new_readonly(*system_program_id, false),
             AccountMeta::new_readonly(*rent_program_id, false),
             AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(*token_2022_program_id, false),
         ];
-        let mut dexes_ = vec![];
-        let mut dexes_btree_set = BTreeSet::<Dex>::new();
-        '_a: for dex in dexes {
-            let dex_ = match dex {
-                Dex_::RaydiumV4 {
-                    raydium_v4_program_id,
-                    clock,
-                    token_program_id: token_program_id_,
-                    amm_pool,
-                    amm_authority,
-                    amm_open_orders,
-                    amm_coin_vault,
-                    amm_pc_vault,
-                    market_program_id,
-                    market,
-                    market_bids,
-                    market_asks,
-                    market_event_queue,
-                    market_coin_vault,
-                    market_pc_vault,
-                    market_vault_signer,
-                } => {
-                    accounts.push(AccountMeta::new_readonly(*raydium_v4_program_id, false));
-                    accounts.push(AccountMeta::new_readonly(*clock, false));
-                    accounts.push(AccountMeta::new_readonly(*token_program_id_, false));
-                    accounts.push(AccountMeta::new(*amm_pool, false));
-                    accounts.push(AccountMeta::new_readonly(*amm_authority, false));
-                    accounts.push(AccountMeta::new(*amm_open_orders, false));
-                    accounts.push(AccountMeta::new(*amm_coin_vault, false));
-                    accounts.push(AccountMeta::new(*amm_pc_vault, false));
-                    accounts.push(AccountMeta::new_readonly(*market_program_id, false));
-                    accounts.push(AccountMeta::new(*market, false));
-                    accounts.push(AccountMeta::new(*market_bids, false));
-                    accounts.push(AccountMeta::new(*market_asks, false));
-                    accounts.push(AccountMeta::new(*market_event_queue, false));
-                    accounts.push(AccountMeta::new(*market_coin_vault, false));
-                    accounts.push(AccountMeta::new(*market_pc_vault, false));
-                    accounts.push(AccountMeta::new_readonly(*market_vault_signer, false));
-                    Dex::RaydiumV4
-                }
-                Dex_::MeteoraV1 {
-                    meteora_v1_program,
-                    clock,
-                    pool,
-                    a_vault,
-                    b_vault,
-                    a_token_vault,
-                    b_token_vault,
-                    a_vault_lp_mint,
-                    b_vault_lp_mint,
-                    a_vault_lp,
-                    b_vault_lp,
-                    protocol_token_fee,
-                    vault_program,
-                    token_program,
-                } => {
-                    accounts.push(AccountMeta::new_readonly(*meteora_v1_program, false));
-                    accounts.push(AccountMeta::new_readonly(*clock, false));
-                    accounts.push(AccountMeta::new(*pool, false));
-                    accounts.push(AccountMeta::new(*a_vault, false));
-                    accounts.push(AccountMeta::new(*b_vault, false));
-                    accounts.push(AccountMeta::new(*a_token_vault, false));
-                    accounts.push(AccountMeta::new(*b_token_vault, false));
-                    accounts.push(AccountMeta::new(*a_vault_lp_mint, false));
-                    accounts.push(AccountMeta::new(*b_vault_lp_mint, false));
-                    accounts.push(AccountMeta::new(*a_vault_lp, false));
-                    accounts.push(AccountMeta::new(*b_vault_lp, false));
-                    accounts.push(AccountMeta::new(*protocol_token_fee, false));
-                    accounts.push(AccountMeta::new_readonly(*vault_program, false));
-                    accounts.push(AccountMeta::new_readonly(*token_program, false));
-                    Dex::MeteoraV1
-                }
-            };
-            if !dexes_btree_set.insert(dex_) {
-                return Err(Error::other("Repeatable dexes."));
-            }
-            dexes_.push(dex_);
-        }
+        let dexes_ = Self::push_dexes_accounts(dexes.as_slice(), &mut accounts)?;
         Ok(Instruction_ {
             program_id: *program_id,
             accounts,
@@ -262,12 +420,249 @@ impl Instruction {
                 amount_in,
                 min_amount_out,
                 token_account_pubkey_bump_seed,
+                quote_token_account_pubkey_bump_seed,
                 is_from_quote_to_token,
                 with_checks,
+                swap_mode,
+                valid_until_slot,
+                valid_until_unix_timestamp,
+                max_price_impact_bps,
+                max_slippage_bps,
             })?,
         })
     }
+    // The exact output swap is the swap in 'SwapMode::ExactOut'.
+    pub fn swap_exact_out(
+        program_id: &Pubkey,
+        intermediary: &Pubkey,
+        intermediary_trader: &Pubkey,
+        quote_token_account: &Pubkey,
+        self_authority: &Pubkey,
+        token_account: &Pubkey,
+        quote_token_mint: &Pubkey,
+        token_mint: &Pubkey,
+        system_program_id: &Pubkey,
+        rent_program_id: &Pubkey,
+        token_program_id: &Pubkey,
+        token_2022_program_id: &Pubkey,
+        dexes: Vec<Dex_<'_>>,
+        amount_out: u64,
+        max_amount_in: u64,
+        token_account_pubkey_bump_seed: u8,
+        quote_token_account_pubkey_bump_seed: u8,
+        is_from_quote_to_token: bool,
+        with_checks: bool,
+        valid_until_slot: Option<u64>,
+        valid_until_unix_timestamp: Option<i64>,
+        max_price_impact_bps: Option<u16>,
+        max_slippage_bps: Option<u16>,
+    ) -> Result<Instruction_, Error> {
+        Self::swap(
+            program_id,
+            intermediary,
+            intermediary_trader,
+            quote_token_account,
+            self_authority,
+            token_account,
+            quote_token_mint,
+            token_mint,
+            system_program_id,
+            rent_program_id,
+            token_program_id,
+            token_2022_program_id,
+            dexes,
+            max_amount_in,
+            amount_out,
+            token_account_pubkey_bump_seed,
+            quote_token_account_pubkey_bump_seed,
+            is_from_quote_to_token,
+            with_checks,
+            SwapMode::ExactOut,
+            valid_until_slot,
+            valid_until_unix_timestamp,
+            max_price_impact_bps,
+            max_slippage_bps,
+        )
+    }
+    pub fn route(
+        program_id: &Pubkey,
+        intermediary: &Pubkey,
+        intermediary_trader: &Pubkey,
+        self_authority: &Pubkey,
+        in_token_account: &Pubkey,
+        in_token_mint: &Pubkey,
+        system_program_id: &Pubkey,
+        rent_program_id: &Pubkey,
+        token_program_id: &Pubkey,
+        token_2022_program_id: &Pubkey,
+        hops: Vec<Hop_<'_>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        in_token_account_pubkey_bump_seed: u8,
+        with_checks: bool,
+    ) -> Result<Instruction_, Error> {
+        if hops.is_empty() {
+            return Err(Error::other("Zero hops."));
+        }
+        let mut accounts = vec![
+            // For Intermediary
+            AccountMeta::new_readonly(*intermediary, false),
+            AccountMeta::new(*intermediary_trader, true),
+            AccountMeta::new_readonly(*self_authority, false),
+            AccountMeta::new(*in_token_account, false),
+            AccountMeta::new_readonly(*in_token_mint, false),
+            AccountMeta::new_readonly(*system_program_id, false),
+            AccountMeta::new_readonly(*rent_program_id, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(*token_2022_program_id, false),
+        ];
+        let mut hops_ = vec![];
+        '_a: for hop in hops.iter() {
+            accounts.push(AccountMeta::new_readonly(*hop.out_token_mint, false));
+            accounts.push(AccountMeta::new(*hop.out_token_account, false));
+            let first_account_index = accounts.len();
+            let dex = hop.dex.push_accounts(&mut accounts);
+            hops_.push(Hop {
+                dex,
+                accounts_quantity: u8::try_from(accounts.len() - first_account_index).map_err(|_| Error::other("Too many accounts."))?,
+                pool: *hop.dex.get_pool(),
+                out_token_mint: *hop.out_token_mint,
+                out_token_account_pubkey_bump_seed: hop.out_token_account_pubkey_bump_seed,
+            });
+        }
+        Ok(Instruction_ {
+            program_id: *program_id,
+            accounts,
+            data: borsh::to_vec(&Self::Route {
+                hops: hops_,
+                in_token_mint: *in_token_mint,
+                amount_in,
+                min_amount_out,
+                in_token_account_pubkey_bump_seed,
+                with_checks,
+            })?,
+        })
+    }
+    pub fn arbitrage(
+        program_id: &Pubkey,
+        intermediary: &Pubkey,
+        intermediary_trader: &Pubkey,
+        quote_token_account: &Pubkey,
+        self_authority: &Pubkey,
+        token_account: &Pubkey,
+        quote_token_mint: &Pubkey,
+        token_mint: &Pubkey,
+        system_program_id: &Pubkey,
+        rent_program_id: &Pubkey,
+        token_program_id: &Pubkey,
+        token_2022_program_id: &Pubkey,
+        buy_dex: Dex_<'_>,
+        sell_dex: Dex_<'_>,
+        amount_in: u64,
+        min_profit_lamports: u64,
+        token_account_pubkey_bump_seed: u8,
+        with_checks: bool,
+    ) -> Result<Instruction_, Error> {
+        if buy_dex.get_pool() == sell_dex.get_pool() {
+            return Err(Error::other("Equal pools."));
+        }
+        let mut accounts = vec![
+            // For Intermediary
+            AccountMeta::new_readonly(*intermediary, false),
+            AccountMeta::new(*intermediary_trader, true),
+            AccountMeta::new(*quote_token_account, false),
+            AccountMeta::new_readonly(*self_authority, false),
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new_readonly(*quote_token_mint, false),
+            AccountMeta::new_readonly(*token_mint, false),
+            AccountMeta::new_readonly(*system_program_id, false),
+            AccountMeta::new_readonly(*rent_program_id, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(*token_2022_program_id, false),
+        ];
+        let buy_dex_first_account_index = accounts.len();
+        let buy_dex_ = buy_dex.push_accounts(&mut accounts);
+        let sell_dex_first_account_index = accounts.len();
+        let sell_dex_ = sell_dex.push_accounts(&mut accounts);
+        let buy_dex_accounts_quantity = u8::try_from(sell_dex_first_account_index - buy_dex_first_account_index).map_err(|_| Error::other("Too many accounts."))?;
+        let sell_dex_accounts_quantity = u8::try_from(accounts.len() - sell_dex_first_account_index).map_err(|_| Error::other("Too many accounts."))?;
+        Ok(Instruction_ {
+            program_id: *program_id,
+            accounts,
+            data: borsh::to_vec(&Self::Arbitrage {
+                buy_dex: buy_dex_,
+                buy_dex_accounts_quantity,
+                sell_dex: sell_dex_,
+                sell_dex_accounts_quantity,
+                token_mint: *token_mint,
+                amount_in,
+                min_profit_lamports,
+                token_account_pubkey_bump_seed,
+                with_checks,
+            })?,
+        })
+    }
+    pub fn quote(
+        program_id: &Pubkey,
+        intermediary: &Pubkey,
+        quote_token_account: &Pubkey,
+        self_authority: &Pubkey,
+        token_account: &Pubkey,
+        quote_token_mint: &Pubkey,
+        token_mint: &Pubkey,
+        dexes: Vec<Dex_<'_>>,
+        amount_in: u64,
+        token_account_pubkey_bump_seed: u8,
+        quote_token_account_pubkey_bump_seed: u8,
+        is_from_quote_to_token: bool,
+        with_checks: bool,
+    ) -> Result<Instruction_, Error> {
+        if dexes.is_empty() {
+            return Err(Error::other("Zero dexes."));
+        }
+        let mut accounts = vec![
+            // For Intermediary
+            AccountMeta::new_readonly(*intermediary, false),
+            AccountMeta::new_readonly(*quote_token_account, false),
+            AccountMeta::new_readonly(*self_authority, false),
+            AccountMeta::new_readonly(*token_account, false),
+        ];
+        let dexes_ = Self::push_dexes_accounts(dexes.as_slice(), &mut accounts)?;
+        Ok(Instruction_ {
+            program_id: *program_id,
+            accounts,
+            data: borsh::to_vec(&Self::Quote {
+                dexes: dexes_,
+                token_mint: *token_mint,
+                quote_mint: *quote_token_mint,
+                amount_in,
+                token_account_pubkey_bump_seed,
+                quote_token_account_pubkey_bump_seed,
+                is_from_quote_to_token,
+                with_checks,
+            })?,
+        })
+    }
+    fn push_dexes_accounts(dexes: &[Dex_<'_>], accounts: &mut Vec<AccountMeta>) -> Result<Vec<DexAccountsRange>, Error> {
+        let mut dexes_ = Vec::<DexAccountsRange>::with_capacity(dexes.len());
+        // The same Dex may be presented several times with different pools.
+        let mut pools_btree_set = BTreeSet::<&Pubkey>::new();
+        '_a: for dex in dexes.iter() {
+            if !pools_btree_set.insert(dex.get_pool()) {
+                return Err(Error::other("Repeatable pools."));
+            }
+            let first_account_index = accounts.len();
+            let dex_ = dex.push_accounts(accounts);
+            dexes_.push(DexAccountsRange {
+                dex: dex_,
+                first_account_index: u8::try_from(first_account_index).map_err(|_| Error::other("Too many accounts."))?,
+                accounts_quantity: u8::try_from(accounts.len() - first_account_index).map_err(|_| Error::other("Too many accounts."))?,
+            });
+        }
+        Ok(dexes_)
+    }
 }
+#[derive(Clone, Copy)]
 pub enum Dex_<'a> {
     MeteoraV1 {
         meteora_v1_program: &'a Pubkey,
@@ -284,6 +679,8 @@ pub enum Dex_<'a> {
         protocol_token_fee: &'a Pubkey,
         vault_program: &'a Pubkey,
         token_program: &'a Pubkey,
+        // Should be presented for the depeg stable pool: the Marinade state, the Solido state or the 'stake' SPL stake pool of the pool.
+        depeg_accounts: &'a [Pubkey],
     },
     RaydiumV4 {
         raydium_v4_program_id: &'a Pubkey,
@@ -303,4 +700,531 @@ pub enum Dex_<'a> {
         market_pc_vault: &'a Pubkey,
         market_vault_signer: &'a Pubkey,
     },
+    RaydiumCpmm {
+        raydium_cpmm_program: &'a Pubkey,
+        clock: &'a Pubkey,
+        authority: &'a Pubkey,
+        amm_config: &'a Pubkey,
+        pool_state: &'a Pubkey,
+        token_0_vault: &'a Pubkey,
+        token_1_vault: &'a Pubkey,
+        token_0_program: &'a Pubkey,
+        token_1_program: &'a Pubkey,
+        token_0_mint: &'a Pubkey,
+        token_1_mint: &'a Pubkey,
+        observation_state: &'a Pubkey,
+    },
+    RaydiumClmm {
+        raydium_clmm_program: &'a Pubkey,
+        clock: &'a Pubkey,
+        amm_config: &'a Pubkey,
+        pool_state: &'a Pubkey,
+        token_vault_0: &'a Pubkey,
+        token_vault_1: &'a Pubkey,
+        observation_state: &'a Pubkey,
+        token_program: &'a Pubkey,
+        token_program_2022: &'a Pubkey,
+        memo_program: &'a Pubkey,
+        token_mint_0: &'a Pubkey,
+        token_mint_1: &'a Pubkey,
+        // Should be presented if the swap crosses the tick arrays out of the pool bitmap.
+        tick_array_bitmap_extension: Option<&'a Pubkey>,
+        // In the swap direction, starting from the tick array with the current tick.
+        tick_arrays: &'a [Pubkey],
+    },
+    OrcaWhirlpool {
+        whirlpool_program: &'a Pubkey,
+        token_program: &'a Pubkey,
+        whirlpool: &'a Pubkey,
+        token_vault_a: &'a Pubkey,
+        token_vault_b: &'a Pubkey,
+        // In the swap direction, starting from the tick array with the current tick. May repeat if the swap does not reach the next ones.
+        tick_array_0: &'a Pubkey,
+        tick_array_1: &'a Pubkey,
+        tick_array_2: &'a Pubkey,
+        oracle: &'a Pubkey,
+    },
+    MeteoraDlmm {
+        meteora_dlmm_program: &'a Pubkey,
+        lb_pair: &'a Pubkey,
+        // Should be presented if the swap crosses the bin arrays out of the pair bitmap.
+        bin_array_bitmap_extension: Option<&'a Pubkey>,
+        reserve_x: &'a Pubkey,
+        reserve_y: &'a Pubkey,
+        token_x_mint: &'a Pubkey,
+        token_y_mint: &'a Pubkey,
+        oracle: &'a Pubkey,
+        token_x_program: &'a Pubkey,
+        token_y_program: &'a Pubkey,
+        event_authority: &'a Pubkey,
+        // In the swap direction, starting from the bin array with the active bin.
+        bin_arrays: &'a [Pubkey],
+    },
+    MeteoraDammV2 {
+        meteora_damm_v2_program: &'a Pubkey,
+        pool_authority: &'a Pubkey,
+        pool: &'a Pubkey,
+        token_a_vault: &'a Pubkey,
+        token_b_vault: &'a Pubkey,
+        token_a_mint: &'a Pubkey,
+        token_b_mint: &'a Pubkey,
+        token_a_program: &'a Pubkey,
+        token_b_program: &'a Pubkey,
+        event_authority: &'a Pubkey,
+    },
+    PumpFun {
+        pump_fun_program: &'a Pubkey,
+        global: &'a Pubkey,
+        fee_recipient: &'a Pubkey,
+        mint: &'a Pubkey,
+        bonding_curve: &'a Pubkey,
+        associated_bonding_curve: &'a Pubkey,
+        creator_vault: &'a Pubkey,
+        event_authority: &'a Pubkey,
+        global_volume_accumulator: &'a Pubkey,
+        user_volume_accumulator: &'a Pubkey,
+        fee_config: &'a Pubkey,
+        fee_program: &'a Pubkey,
+        system_program: &'a Pubkey,
+        token_program: &'a Pubkey,
+        w_sol_token_mint: &'a Pubkey,
+        temporary_w_sol_token_account: &'a Pubkey,
+        // The trader. Pays the rent for the temporary WSol token account during the buy.
+        rent_payer: &'a Pubkey,
+        // Receives and spends the native Sol, so is writable.
+        self_authority: &'a Pubkey,
+    },
+    PumpSwap {
+        pump_swap_program: &'a Pubkey,
+        pool: &'a Pubkey,
+        global_config: &'a Pubkey,
+        base_mint: &'a Pubkey,
+        quote_mint: &'a Pubkey,
+        pool_base_token_account: &'a Pubkey,
+        pool_quote_token_account: &'a Pubkey,
+        protocol_fee_recipient: &'a Pubkey,
+        protocol_fee_recipient_token_account: &'a Pubkey,
+        base_token_program: &'a Pubkey,
+        quote_token_program: &'a Pubkey,
+        system_program: &'a Pubkey,
+        associated_token_program: &'a Pubkey,
+        event_authority: &'a Pubkey,
+        coin_creator_vault_ata: &'a Pubkey,
+        coin_creator_vault_authority: &'a Pubkey,
+        global_volume_accumulator: &'a Pubkey,
+        user_volume_accumulator: &'a Pubkey,
+        fee_config: &'a Pubkey,
+        fee_program: &'a Pubkey,
+    },
+    Phoenix {
+        phoenix_program: &'a Pubkey,
+        log_authority: &'a Pubkey,
+        market: &'a Pubkey,
+        base_vault: &'a Pubkey,
+        quote_vault: &'a Pubkey,
+        token_program: &'a Pubkey,
+    },
+    // The 'RaydiumV4' pool with the 'SwapOnly' status, which does not need the market accounts.
+    RaydiumV4SwapOnly {
+        raydium_v4_program_id: &'a Pubkey,
+        clock: &'a Pubkey,
+        token_program_id: &'a Pubkey,
+        amm_pool: &'a Pubkey,
+        amm_authority: &'a Pubkey,
+        amm_coin_vault: &'a Pubkey,
+        amm_pc_vault: &'a Pubkey,
+    },
+}
+impl<'a> Dex_<'a> {
+    // Pushes the accounts required for swap on the Dex.
+    pub fn push_accounts(&self, accounts: &mut Vec<AccountMeta>) -> Dex {
+        match *self {
+            Self::RaydiumV4 {
+                raydium_v4_program_id,
+                clock,
+                token_program_id: token_program_id_,
+                amm_pool,
+                amm_authority,
+                amm_open_orders,
+                amm_coin_vault,
+                amm_pc_vault,
+                market_program_id,
+                market,
+                market_bids,
+                market_asks,
+                market_event_queue,
+                market_coin_vault,
+                market_pc_vault,
+                market_vault_signer,
+            } => {
+                accounts.push(AccountMeta::new_readonly(*raydium_v4_program_id, false));
+                accounts.push(AccountMeta::new_readonly(*clock, false));
+                accounts.push(AccountMeta::new_readonly(*token_program_id_, false));
+                accounts.push(AccountMeta::new(*amm_pool, false));
+                accounts.push(AccountMeta::new_readonly(*amm_authority, false));
+                accounts.push(AccountMeta::new(*amm_open_orders, false));
+                accounts.push(AccountMeta::new(*amm_coin_vault, false));
+                accounts.push(AccountMeta::new(*amm_pc_vault, false));
+                accounts.push(AccountMeta::new_readonly(*market_program_id, false));
+                accounts.push(AccountMeta::new(*market, false));
+                accounts.push(AccountMeta::new(*market_bids, false));
+                accounts.push(AccountMeta::new(*market_asks, false));
+                accounts.push(AccountMeta::new(*market_event_queue, false));
+                accounts.push(AccountMeta::new(*market_coin_vault, false));
+                accounts.push(AccountMeta::new(*market_pc_vault, false));
+                accounts.push(AccountMeta::new_readonly(*market_vault_signer, false));
+                Dex::RaydiumV4
+            }
+            Self::MeteoraV1 {
+                meteora_v1_program,
+                clock,
+                pool,
+                a_vault,
+                b_vault,
+                a_token_vault,
+                b_token_vault,
+                a_vault_lp_mint,
+                b_vault_lp_mint,
+                a_vault_lp,
+                b_vault_lp,
+                protocol_token_fee,
+                vault_program,
+                token_program,
+                depeg_accounts,
+            } => {
+                accounts.push(AccountMeta::new_readonly(*meteora_v1_program, false));
+                accounts.push(AccountMeta::new_readonly(*clock, false));
+                accounts.push(AccountMeta::new(*pool, false));
+                accounts.push(AccountMeta::new(*a_vault, false));
+                accounts.push(AccountMeta::new(*b_vault, false));
+                accounts.push(AccountMeta::new(*a_token_vault, false));
+                accounts.push(AccountMeta::new(*b_token_vault, false));
+                accounts.push(AccountMeta::new(*a_vault_lp_mint, false));
+                accounts.push(AccountMeta::new(*b_vault_lp_mint, false));
+                accounts.push(AccountMeta::new(*a_vault_lp, false));
+                accounts.push(AccountMeta::new(*b_vault_lp, false));
+                accounts.push(AccountMeta::new(*protocol_token_fee, false));
+                accounts.push(AccountMeta::new_readonly(*vault_program, false));
+                accounts.push(AccountMeta::new_readonly(*token_program, false));
+                '_a: for depeg_account in depeg_accounts.iter() {
+                    accounts.push(AccountMeta::new_readonly(*depeg_account, false));
+                }
+                Dex::MeteoraV1
+            }
+            Self::RaydiumCpmm {
+                raydium_cpmm_program,
+                clock,
+                authority,
+                amm_config,
+                pool_state,
+                token_0_vault,
+                token_1_vault,
+                token_0_program,
+                token_1_program,
+                token_0_mint,
+                token_1_mint,
+                observation_state,
+            } => {
+                accounts.push(AccountMeta::new_readonly(*raydium_cpmm_program, false));
+                accounts.push(AccountMeta::new_readonly(*clock, false));
+                accounts.push(AccountMeta::new_readonly(*authority, false));
+                accounts.push(AccountMeta::new_readonly(*amm_config, false));
+                accounts.push(AccountMeta::new(*pool_state, false));
+                accounts.push(AccountMeta::new(*token_0_vault, false));
+                accounts.push(AccountMeta::new(*token_1_vault, false));
+                accounts.push(AccountMeta::new_readonly(*token_0_program, false));
+                accounts.push(AccountMeta::new_readonly(*token_1_program, false));
+                accounts.push(AccountMeta::new_readonly(*token_0_mint, false));
+                accounts.push(AccountMeta::new_readonly(*token_1_mint, false));
+                accounts.push(AccountMeta::new(*observation_state, false));
+                Dex::RaydiumCpmm
+            }
+            Self::RaydiumClmm {
+                raydium_clmm_program,
+                clock,
+                amm_config,
+                pool_state,
+                token_vault_0,
+                token_vault_1,
+                observation_state,
+                token_program,
+                token_program_2022,
+                memo_program,
+                token_mint_0,
+                token_mint_1,
+                tick_array_bitmap_extension,
+                tick_arrays,
+            } => {
+                accounts.push(AccountMeta::new_readonly(*raydium_clmm_program, false));
+                accounts.push(AccountMeta::new_readonly(*clock, false));
+                accounts.push(AccountMeta::new_readonly(*amm_config, false));
+                accounts.push(AccountMeta::new(*pool_state, false));
+                accounts.push(AccountMeta::new(*token_vault_0, false));
+                accounts.push(AccountMeta::new(*token_vault_1, false));
+                accounts.push(AccountMeta::new(*observation_state, false));
+                accounts.push(AccountMeta::new_readonly(*token_program, false));
+                accounts.push(AccountMeta::new_readonly(*token_program_2022, false));
+                accounts.push(AccountMeta::new_readonly(*memo_program, false));
+                accounts.push(AccountMeta::new_readonly(*token_mint_0, false));
+                accounts.push(AccountMeta::new_readonly(*token_mint_1, false));
+                if let Some(tick_array_bitmap_extension_) = tick_array_bitmap_extension {
+                    accounts.push(AccountMeta::new(*tick_array_bitmap_extension_, false));
+                }
+                '_a: for tick_array in tick_arrays.iter() {
+                    accounts.push(AccountMeta::new(*tick_array, false));
+                }
+                Dex::RaydiumClmm
+            }
+            Self::OrcaWhirlpool {
+                whirlpool_program,
+                token_program,
+                whirlpool,
+                token_vault_a,
+                token_vault_b,
+                tick_array_0,
+                tick_array_1,
+                tick_array_2,
+                oracle,
+            } => {
+                accounts.push(AccountMeta::new_readonly(*whirlpool_program, false));
+                accounts.push(AccountMeta::new_readonly(*token_program, false));
+                accounts.push(AccountMeta::new(*whirlpool, false));
+                accounts.push(AccountMeta::new(*token_vault_a, false));
+                accounts.push(AccountMeta::new(*token_vault_b, false));
+                accounts.push(AccountMeta::new(*tick_array_0, false));
+                accounts.push(AccountMeta::new(*tick_array_1, false));
+                accounts.push(AccountMeta::new(*tick_array_2, false));
+                accounts.push(AccountMeta::new(*oracle, false));
+                Dex::OrcaWhirlpool
+            }
+            Self::MeteoraDlmm {
+                meteora_dlmm_program,
+                lb_pair,
+                bin_array_bitmap_extension,
+                reserve_x,
+                reserve_y,
+                token_x_mint,
+                token_y_mint,
+                oracle,
+                token_x_program,
+                token_y_program,
+                event_authority,
+                bin_arrays,
+            } => {
+                accounts.push(AccountMeta::new_readonly(*meteora_dlmm_program, false));
+                accounts.push(AccountMeta::new(*lb_pair, false));
+                // The absent optional account is replaced by the program id.
+                accounts.push(AccountMeta::new_readonly(*bin_array_bitmap_extension.unwrap_or(meteora_dlmm_program), false));
+                accounts.push(AccountMeta::new(*reserve_x, false));
+                accounts.push(AccountMeta::new(*reserve_y, false));
+                accounts.push(AccountMeta::new_readonly(*token_x_mint, false));
+                accounts.push(AccountMeta::new_readonly(*token_y_mint, false));
+                accounts.push(AccountMeta::new(*oracle, false));
+                accounts.push(AccountMeta::new_readonly(*token_x_program, false));
+                accounts.push(AccountMeta::new_readonly(*token_y_program, false));
+                accounts.push(AccountMeta::new_readonly(*event_authority, false));
+                '_a: for bin_array in bin_arrays.iter() {
+                    accounts.push(AccountMeta::new(*bin_array, false));
+                }
+                Dex::MeteoraDlmm
+            }
+            Self::MeteoraDammV2 {
+                meteora_damm_v2_program,
+                pool_authority,
+                pool,
+                token_a_vault,
+                token_b_vault,
+                token_a_mint,
+                token_b_mint,
+                token_a_program,
+                token_b_program,
+                event_authority,
+            } => {
+                accounts.push(AccountMeta::new_readonly(*meteora_damm_v2_program, false));
+                accounts.push(AccountMeta::new_readonly(*pool_authority, false));
+                accounts.push(AccountMeta::new(*pool, false));
+                accounts.push(AccountMeta::new(*token_a_vault, false));
+                accounts.push(AccountMeta::new(*token_b_vault, false));
+                accounts.push(AccountMeta::new_readonly(*token_a_mint, false));
+                accounts.push(AccountMeta::new_readonly(*token_b_mint, false));
+                accounts.push(AccountMeta::new_readonly(*token_a_program, false));
+                accounts.push(AccountMeta::new_readonly(*token_b_program, false));
+                accounts.push(AccountMeta::new_readonly(*event_authority, false));
+                Dex::MeteoraDammV2
+            }
+            Self::PumpFun {
+                pump_fun_program,
+                global,
+                fee_recipient,
+                mint,
+                bonding_curve,
+                associated_bonding_curve,
+                creator_vault,
+                event_authority,
+                global_volume_accumulator,
+                user_volume_accumulator,
+                fee_config,
+                fee_program,
+                system_program,
+                token_program,
+                w_sol_token_mint,
+                temporary_w_sol_token_account,
+                rent_payer,
+                self_authority,
+            } => {
+                accounts.push(AccountMeta::new_readonly(*pump_fun_program, false));
+                accounts.push(AccountMeta::new_readonly(*global, false));
+                accounts.push(AccountMeta::new(*fee_recipient, false));
+                accounts.push(AccountMeta::new_readonly(*mint, false));
+                accounts.push(AccountMeta::new(*bonding_curve, false));
+                accounts.push(AccountMeta::new(*associated_bonding_curve, false));
+                accounts.push(AccountMeta::new(*creator_vault, false));
+                accounts.push(AccountMeta::new_readonly(*event_authority, false));
+                accounts.push(AccountMeta::new(*global_volume_accumulator, false));
+                accounts.push(AccountMeta::new(*user_volume_accumulator, false));
+                accounts.push(AccountMeta::new_readonly(*fee_config, false));
+                accounts.push(AccountMeta::new_readonly(*fee_program, false));
+                accounts.push(AccountMeta::new_readonly(*system_program, false));
+                accounts.push(AccountMeta::new_readonly(*token_program, false));
+                accounts.push(AccountMeta::new_readonly(*w_sol_token_mint, false));
+                accounts.push(AccountMeta::new(*temporary_w_sol_token_account, false));
+                accounts.push(AccountMeta::new(*rent_payer, false));
+                accounts.push(AccountMeta::new(*self_authority, false));
+                Dex::PumpFun
+            }
+            Self::PumpSwap {
+                pump_swap_program,
+                pool,
+                global_config,
+                base_mint,
+                quote_mint,
+                pool_base_token_account,
+                pool_quote_token_account,
+                protocol_fee_recipient,
+                protocol_fee_recipient_token_account,
+                base_token_program,
+                quote_token_program,
+                system_program,
+                associated_token_program,
+                event_authority,
+                coin_creator_vault_ata,
+                coin_creator_vault_authority,
+                global_volume_accumulator,
+                user_volume_accumulator,
+                fee_config,
+                fee_program,
+            } => {
+                accounts.push(AccountMeta::new_readonly(*pump_swap_program, false));
+                accounts.push(AccountMeta::new(*pool, false));
+                accounts.push(AccountMeta::new_readonly(*global_config, false));
+                accounts.push(AccountMeta::new_readonly(*base_mint, false));
+                accounts.push(AccountMeta::new_readonly(*quote_mint, false));
+                accounts.push(AccountMeta::new(*pool_base_token_account, false));
+                accounts.push(AccountMeta::new(*pool_quote_token_account, false));
+                accounts.push(AccountMeta::new_readonly(*protocol_fee_recipient, false));
+                accounts.push(AccountMeta::new(*protocol_fee_recipient_token_account, false));
+                accounts.push(AccountMeta::new_readonly(*base_token_program, false));
+                accounts.push(AccountMeta::new_readonly(*quote_token_program, false));
+                accounts.push(AccountMeta::new_readonly(*system_program, false));
+                accounts.push(AccountMeta::new_readonly(*associated_token_program, false));
+                accounts.push(AccountMeta::new_readonly(*event_authority, false));
+                accounts.push(AccountMeta::new(*coin_creator_vault_ata, false));
+                accounts.push(AccountMeta::new_readonly(*coin_creator_vault_authority, false));
+                accounts.push(AccountMeta::new(*global_volume_accumulator, false));
+                accounts.push(AccountMeta::new(*user_volume_accumulator, false));
+                accounts.push(AccountMeta::new_readonly(*fee_config, false));
+                accounts.push(AccountMeta::new_readonly(*fee_program, false));
+                Dex::PumpSwap
+            }
+            Self::Phoenix {
+                phoenix_program,
+                log_authority,
+                market,
+                base_vault,
+                quote_vault,
+                token_program,
+            } => {
+                accounts.push(AccountMeta::new_readonly(*phoenix_program, false));
+                accounts.push(AccountMeta::new_readonly(*log_authority, false));
+                accounts.push(AccountMeta::new(*market, false));
+                accounts.push(AccountMeta::new(*base_vault, false));
+                accounts.push(AccountMeta::new(*quote_vault, false));
+                accounts.push(AccountMeta::new_readonly(*token_program, false));
+                Dex::Phoenix
+            }
+            Self::RaydiumV4SwapOnly {
+                raydium_v4_program_id,
+                clock,
+                token_program_id: token_program_id_,
+                amm_pool,
+                amm_authority,
+                amm_coin_vault,
+                amm_pc_vault,
+            } => {
+                accounts.push(AccountMeta::new_readonly(*raydium_v4_program_id, false));
+                accounts.push(AccountMeta::new_readonly(*clock, false));
+                accounts.push(AccountMeta::new_readonly(*token_program_id_, false));
+                accounts.push(AccountMeta::new(*amm_pool, false));
+                accounts.push(AccountMeta::new_readonly(*amm_authority, false));
+                accounts.push(AccountMeta::new(*amm_coin_vault, false));
+                accounts.push(AccountMeta::new(*amm_pc_vault, false));
+                Dex::RaydiumV4SwapOnly
+            }
+        }
+    }
+    pub fn get_pool(&self) -> &'a Pubkey {
+        match *self {
+            Self::MeteoraV1 {
+                pool,
+                ..
+            } => pool,
+            Self::RaydiumV4 {
+                amm_pool,
+                ..
+            } => amm_pool,
+            Self::RaydiumCpmm {
+                pool_state,
+                ..
+            } => pool_state,
+            Self::RaydiumClmm {
+                pool_state,
+                ..
+            } => pool_state,
+            Self::OrcaWhirlpool {
+                whirlpool,
+                ..
+            } => whirlpool,
+            Self::MeteoraDlmm {
+                lb_pair,
+                ..
+            } => lb_pair,
+            Self::MeteoraDammV2 {
+                pool,
+                ..
+            } => pool,
+            Self::PumpFun {
+                bonding_curve,
+                ..
+            } => bonding_curve,
+            Self::PumpSwap {
+                pool,
+                ..
+            } => pool,
+            Self::Phoenix {
+                market,
+                ..
+            } => market,
+            Self::RaydiumV4SwapOnly {
+                amm_pool,
+                ..
+            } => amm_pool,
+        }
+    }
+}
+pub struct Hop_<'a> {
+    pub dex: Dex_<'a>,
+    pub out_token_mint: &'a Pubkey,
+    // Should be PDA-derived as the token account for 'out_token_mint'.
+    pub out_token_account: &'a Pubkey,
+    pub out_token_account_pubkey_bump_seed: u8,
 }
@@ -47,3 +47,83 @@ pub const RAYDIUM_V4_PROGRAM_ID: Pubkey = {
         Pubkey::from_str_const("DRaya7Kj3aMWQSy19kSjvmuwq9docCHofyP9kanQGaav")
     }
 };
+pub const RAYDIUM_CPMM_PROGRAM_ID: Pubkey = {
+    #[cfg(not(feature = "devnet"))]
+    {
+        Pubkey::from_str_const("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C")
+    }
+    #[cfg(feature = "devnet")]
+    {
+        Pubkey::from_str_const("DRaycpLY18LhpbydsBWbVJtxpNv9oXPgjRSfpF2bWpYb")
+    }
+};
+pub const RAYDIUM_CLMM_PROGRAM_ID: Pubkey = {
+    #[cfg(not(feature = "devnet"))]
+    {
+        Pubkey::from_str_const("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK")
+    }
+    #[cfg(feature = "devnet")]
+    {
+        Pubkey::from_str_const("devi51mZmdwUJGU9hjN27vEz64Gps7uUefqxg27EAtH")
+    }
+};
+pub const ORCA_WHIRLPOOL_PROGRAM_ID: Pubkey = {
+    #[cfg(not(feature = "devnet"))]
+    {
+        Pubkey::from_str_const("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc")
+    }
+    #[cfg(feature = "devnet")]
+    {
+        Pubkey::from_str_const("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc")
+    }
+};
+pub const METEORA_DLMM_PROGRAM_ID: Pubkey = {
+    #[cfg(not(feature = "devnet"))]
+    {
+        Pubkey::from_str_const("LBUZKhRxPF3XUpBCjbWtzsKG1ZthdZ5B7bGNZCZ9uk4")
+    }
+    #[cfg(feature = "devnet")]
+    {
+        Pubkey::from_str_const("LBUZKhRxPF3XUpBCjbWtzsKG1ZthdZ5B7bGNZCZ9uk4")
+    }
+};
+pub const METEORA_DAMM_V2_PROGRAM_ID: Pubkey = {
+    #[cfg(not(feature = "devnet"))]
+    {
+        Pubkey::from_str_const("cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG")
+    }
+    #[cfg(feature = "devnet")]
+    {
+        Pubkey::from_str_const("cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG")
+    }
+};
+pub const PUMP_FUN_PROGRAM_ID: Pubkey = {
+    #[cfg(not(feature = "devnet"))]
+    {
+        Pubkey::from_str_const("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P")
+    }
+    #[cfg(feature = "devnet")]
+    {
+        Pubkey::from_str_const("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P")
+    }
+};
+pub const PUMP_SWAP_PROGRAM_ID: Pubkey = {
+    #[cfg(not(feature = "devnet"))]
+    {
+        Pubkey::from_str_const("pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA")
+    }
+    #[cfg(feature = "devnet")]
+    {
+        Pubkey::from_str_const("pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA")
+    }
+};
+pub const PHOENIX_PROGRAM_ID: Pubkey = {
+    #[cfg(not(feature = "devnet"))]
+    {
+        Pubkey::from_str_const("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY")
+    }
+    #[cfg(feature = "devnet")]
+    {
+        Pubkey::from_str_const("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY")
+    }
+};
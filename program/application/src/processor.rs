@@ -4,24 +4,44 @@ use {
         dex::{
             BaseData,
             Dex,
+            SwapCalculationResult,
+            get_transfer_fee_config,
+            meteora_damm_v2::MeteoraDammV2,
+            meteora_dlmm::MeteoraDlmm,
             meteora_v1::MeteoraV1,
-            raydium_v4::RaydiumV4,
+            orca_whirlpool::OrcaWhirlpool,
+            phoenix::Phoenix,
+            pump_fun::PumpFun,
+            pump_swap::PumpSwap,
+            raydium_clmm::RaydiumClmm,
+            raydium_cpmm::RaydiumCpmm,
+            raydium_v4::{
+                RaydiumV4,
+                RaydiumV4SwapOnly,
+            },
         },
         error::Error,
+        extern_source::spl_token_2022::TransferFeeConfig,
         instruction::Instruction,
         state::{
             Dex as Dex_,
+            DexAccountsRange,
+            Hop,
             Intermediary,
             MUCH_USED_STATIC_ACCOUNTS,
             PdaResolver,
             QUANTITY_OF_MUCH_USED_DYNAMIC_ACCOUNTS,
+            SwapMode,
+            TokenAccountToClose,
         },
     },
     borsh::BorshDeserialize,
     solana_program::{
         account_info::AccountInfo,
+        clock::Clock,
         entrypoint::ProgramResult,
         msg,
+        program_error::ProgramError,
         program_pack::Pack,
         pubkey::Pubkey,
         rent::Rent,
@@ -66,8 +86,14 @@ impl Processor {
                 amount_in,
                 min_amount_out,
                 token_account_pubkey_bump_seed,
+                quote_token_account_pubkey_bump_seed,
                 is_from_quote_to_token,
                 with_checks,
+                swap_mode,
+                valid_until_slot,
+                valid_until_unix_timestamp,
+                max_price_impact_bps,
+                max_slippage_bps,
             } => {
                 Self::swap(
                     dexes,
@@ -77,10 +103,90 @@ impl Processor {
                     amount_in,
                     min_amount_out,
                     token_account_pubkey_bump_seed,
+                    quote_token_account_pubkey_bump_seed,
                     is_from_quote_to_token,
                     with_checks,
+                    swap_mode,
+                    valid_until_slot,
+                    valid_until_unix_timestamp,
+                    max_price_impact_bps,
+                    max_slippage_bps,
                 )
             }
+            Instruction::Route {
+                hops,
+                in_token_mint,
+                amount_in,
+                min_amount_out,
+                in_token_account_pubkey_bump_seed,
+                with_checks,
+            } => Self::route(hops, accounts, in_token_mint, amount_in, min_amount_out, in_token_account_pubkey_bump_seed, with_checks),
+            Instruction::Arbitrage {
+                buy_dex,
+                buy_dex_accounts_quantity,
+                sell_dex,
+                sell_dex_accounts_quantity,
+                token_mint,
+                amount_in,
+                min_profit_lamports,
+                token_account_pubkey_bump_seed,
+                with_checks,
+            } => {
+                Self::arbitrage(
+                    buy_dex,
+                    buy_dex_accounts_quantity,
+                    sell_dex,
+                    sell_dex_accounts_quantity,
+                    accounts,
+                    token_mint,
+                    amount_in,
+                    min_profit_lamports,
+                    token_account_pubkey_bump_seed,
+                    with_checks,
+                )
+            }
+            Instruction::Quote {
+                dexes,
+                token_mint,
+                quote_mint,
+                amount_in,
+                token_account_pubkey_bump_seed,
+                quote_token_account_pubkey_bump_seed,
+                is_from_quote_to_token,
+                with_checks,
+            } => {
+                Self::quote(
+                    dexes,
+                    accounts,
+                    token_mint,
+                    quote_mint,
+                    amount_in,
+                    token_account_pubkey_bump_seed,
+                    quote_token_account_pubkey_bump_seed,
+                    is_from_quote_to_token,
+                    with_checks,
+                )
+            }
+            Instruction::RegisterQuoteMint {
+                quote_token_account_pubkey_bump_seed,
+            } => Self::register_quote_mint(accounts, quote_token_account_pubkey_bump_seed),
+            Instruction::DepositQuoteFunds {
+                amount_to_treasury,
+                quote_token_account_pubkey_bump_seed,
+            } => Self::deposit_quote_funds(accounts, amount_to_treasury, quote_token_account_pubkey_bump_seed),
+            Instruction::WithdrawQuoteFunds {
+                amount_from_treasury,
+                quote_token_account_pubkey_bump_seed,
+            } => Self::withdraw_quote_funds(accounts, amount_from_treasury, quote_token_account_pubkey_bump_seed),
+            Instruction::WithdrawToken {
+                mint,
+                amount,
+                token_account_pubkey_bump_seed,
+                close_token_account,
+            } => Self::withdraw_token(accounts, mint, amount, token_account_pubkey_bump_seed, close_token_account),
+            Instruction::CloseTokenAccounts {
+                token_accounts,
+            } => Self::close_token_accounts(accounts, token_accounts),
         }
     }
     fn initialize(
@@ -328,7 +434,7 @@ impl Processor {
         {
             return Err(Error::IntermediaryInvalidTemporaryWSolTokenAccount.into());
         }
-        if lamports_from_treasury > Account::unpack_unchecked(&w_sol_token_account.data.borrow())?.amount {
+        if lamports_from_treasury > Self::get_token_account_amount(w_sol_token_account)? {
             return Err(Error::TokenAccountInsufficientAmount.into());
         }
         if *self_authority.key != intermediary_.self_authority
@@ -395,21 +501,327 @@ impl Processor {
         )?;
         Ok(())
     }
+    fn register_quote_mint(accounts: &[AccountInfo], quote_token_account_pubkey_bump_seed: u8) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let intermediary = solana_program::account_info::next_account_info(account_info_iter)?;
+        let intermediary_manager = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let self_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_token_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let system_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let rent = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        if *system_program.key != solana_program::system_program::ID || *rent.key != solana_program::sysvar::rent::ID || *token_program.key != *quote_token_mint.owner {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if *token_program.key != spl_token::ID && *token_program.key != crate::extern_source::spl_token_2022::ID {
+            return Err(Error::InvalidTokenMint.into());
+        }
+        if !intermediary_manager.is_writable || !intermediary_manager.is_signer || !quote_token_account.is_writable {
+            return Err(Error::InvalidAccountConfigurationFlags.into());
+        }
+        let intermediary_data = &intermediary.data.borrow();
+        let intermediary_ = bytemuck::try_from_bytes::<Intermediary>(intermediary_data).map_err(|_| Error::InvalidLogic)?;
+        if !intermediary_.is_initialized() {
+            return Err(Error::IntermediaryIsNotInitialized.into());
+        }
+        if *intermediary_manager.key != intermediary_.manager {
+            return Err(Error::IntermediaryInvalidManager.into());
+        }
+        Self::check_quote_token_account(intermediary, intermediary_, quote_token_account, quote_token_mint.key, quote_token_account_pubkey_bump_seed)?;
+        if !quote_token_account.data_is_empty() {
+            return Err(Error::IntermediaryInvalidQuoteTokenAccount.into());
+        }
+        if *self_authority.key != intermediary_.self_authority
+            || *self_authority.key != PdaResolver::self_authority_create(intermediary.key, intermediary_.self_authority_pubkey_bump_seed)?
+        {
+            return Err(Error::IntermediaryInvalidAuthority.into());
+        }
+        Self::create_token_account(
+            intermediary_manager,
+            quote_token_account,
+            quote_token_mint,
+            self_authority,
+            rent,
+            token_program,
+            PdaResolver::quote_token_account_get_seeds(intermediary.key, quote_token_mint.key, [quote_token_account_pubkey_bump_seed].as_slice()).as_slice(),
+        )
+    }
+    fn deposit_quote_funds(accounts: &[AccountInfo], amount_to_treasury: u64, quote_token_account_pubkey_bump_seed: u8) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let intermediary = solana_program::account_info::next_account_info(account_info_iter)?;
+        let intermediary_manager = solana_program::account_info::next_account_info(account_info_iter)?;
+        let intermediary_manager_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_token_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        if *token_program.key != *quote_token_mint.owner {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if !intermediary_manager.is_signer || !intermediary_manager_token_account.is_writable || !quote_token_account.is_writable {
+            return Err(Error::InvalidAccountConfigurationFlags.into());
+        }
+        let intermediary_data = &intermediary.data.borrow();
+        let intermediary_ = bytemuck::try_from_bytes::<Intermediary>(intermediary_data).map_err(|_| Error::InvalidLogic)?;
+        if !intermediary_.is_initialized() {
+            return Err(Error::IntermediaryIsNotInitialized.into());
+        }
+        if *intermediary_manager.key != intermediary_.manager {
+            return Err(Error::IntermediaryInvalidManager.into());
+        }
+        Self::check_quote_token_account(intermediary, intermediary_, quote_token_account, quote_token_mint.key, quote_token_account_pubkey_bump_seed)?;
+        if quote_token_account.data_is_empty() {
+            return Err(Error::QuoteMintIsNotRegistered.into());
+        }
+        let decimals = crate::extern_source::spl_token_2022::unpack_mint_decimals(&quote_token_mint.data.borrow()).ok_or(Error::InvalidAccountData)?;
+        solana_program::program::invoke(
+            &crate::extern_source::spl_token_2022::transfer_checked(
+                token_program.key,
+                intermediary_manager_token_account.key,
+                quote_token_mint.key,
+                quote_token_account.key,
+                intermediary_manager.key,
+                amount_to_treasury,
+                decimals,
+            ),
+            vec![
+                intermediary_manager_token_account.clone(),
+                quote_token_mint.clone(),
+                quote_token_account.clone(),
+                intermediary_manager.clone(),
+            ]
+            .as_slice(),
+        )?;
+        Ok(())
+    }
+    fn withdraw_quote_funds(accounts: &[AccountInfo], amount_from_treasury: u64, quote_token_account_pubkey_bump_seed: u8) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let intermediary = solana_program::account_info::next_account_info(account_info_iter)?;
+        let intermediary_manager = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let intermediary_manager_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let self_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_token_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        if *token_program.key != *quote_token_mint.owner {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if !intermediary_manager.is_signer || !quote_token_account.is_writable || !intermediary_manager_token_account.is_writable {
+            return Err(Error::InvalidAccountConfigurationFlags.into());
+        }
+        let intermediary_data = &intermediary.data.borrow();
+        let intermediary_ = bytemuck::try_from_bytes::<Intermediary>(intermediary_data).map_err(|_| Error::InvalidLogic)?;
+        if !intermediary_.is_initialized() {
+            return Err(Error::IntermediaryIsNotInitialized.into());
+        }
+        if *intermediary_manager.key != intermediary_.manager {
+            return Err(Error::IntermediaryInvalidManager.into());
+        }
+        Self::check_quote_token_account(intermediary, intermediary_, quote_token_account, quote_token_mint.key, quote_token_account_pubkey_bump_seed)?;
+        if quote_token_account.data_is_empty() {
+            return Err(Error::QuoteMintIsNotRegistered.into());
+        }
+        if amount_from_treasury > Self::get_token_account_amount(quote_token_account)? {
+            return Err(Error::TokenAccountInsufficientAmount.into());
+        }
+        if *self_authority.key != intermediary_.self_authority
+            || *self_authority.key != PdaResolver::self_authority_create(intermediary.key, intermediary_.self_authority_pubkey_bump_seed)?
+        {
+            return Err(Error::IntermediaryInvalidAuthority.into());
+        }
+        let decimals = crate::extern_source::spl_token_2022::unpack_mint_decimals(&quote_token_mint.data.borrow()).ok_or(Error::InvalidAccountData)?;
+        solana_program::program::invoke_signed(
+            &crate::extern_source::spl_token_2022::transfer_checked(
+                token_program.key,
+                quote_token_account.key,
+                quote_token_mint.key,
+                intermediary_manager_token_account.key,
+                self_authority.key,
+                amount_from_treasury,
+                decimals,
+            ),
+            vec![
+                quote_token_account.clone(),
+                quote_token_mint.clone(),
+                intermediary_manager_token_account.clone(),
+                self_authority.clone(),
+            ]
+            .as_slice(),
+            [PdaResolver::self_authority_get_seeds(intermediary.key, [intermediary_.self_authority_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
+        )?;
+        Ok(())
+    }
+    fn withdraw_token(accounts: &[AccountInfo], mint: Pubkey, amount: u64, token_account_pubkey_bump_seed: u8, close_token_account: bool) -> ProgramResult {
+        // The WSol treasury is managed through the 'WithdrawFunds'.
+        if mint == spl_token::native_mint::ID {
+            return Err(Error::InvalidTokenMint.into());
+        }
+        let account_info_iter = &mut accounts.iter();
+        let intermediary = solana_program::account_info::next_account_info(account_info_iter)?;
+        let intermediary_manager = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let destination_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let self_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        if mint != *token_mint.key {
+            return Err(Error::InvalidTokenMint.into());
+        }
+        // The treasury of the registered quote mint is not the token account PDA, so it is managed only through the 'WithdrawQuoteFunds'.
+        if *token_account.key != PdaResolver::token_account_create(intermediary.key, token_mint.key, token_account_pubkey_bump_seed)?
+            || *token_program.key != *token_mint.owner
+            || *token_program.key != *token_account.owner
+            || token_account.key == destination_token_account.key
+        {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if !intermediary_manager.is_writable || !intermediary_manager.is_signer || !token_account.is_writable || !destination_token_account.is_writable {
+            return Err(Error::InvalidAccountConfigurationFlags.into());
+        }
+        let intermediary_data = &intermediary.data.borrow();
+        let intermediary_ = bytemuck::try_from_bytes::<Intermediary>(intermediary_data).map_err(|_| Error::InvalidLogic)?;
+        if !intermediary_.is_initialized() {
+            return Err(Error::IntermediaryIsNotInitialized.into());
+        }
+        if *intermediary_manager.key != intermediary_.manager {
+            return Err(Error::IntermediaryInvalidManager.into());
+        }
+        if *self_authority.key != intermediary_.self_authority
+            || *self_authority.key != PdaResolver::self_authority_create(intermediary.key, intermediary_.self_authority_pubkey_bump_seed)?
+        {
+            return Err(Error::IntermediaryInvalidAuthority.into());
+        }
+        let token_amount = Self::get_token_account_amount(token_account)?;
+        if amount > token_amount {
+            return Err(Error::TokenAccountInsufficientAmount.into());
+        }
+        if close_token_account && amount != token_amount {
+            return Err(Error::TokenAccountInvalidAmount.into());
+        }
+        let self_authority_pubkey_bump_seed = [intermediary_.self_authority_pubkey_bump_seed];
+        let self_authority_seeds = PdaResolver::self_authority_get_seeds(intermediary.key, self_authority_pubkey_bump_seed.as_slice());
+        if amount > 0 {
+            let decimals = crate::extern_source::spl_token_2022::unpack_mint_decimals(&token_mint.data.borrow()).ok_or(Error::InvalidAccountData)?;
+            solana_program::program::invoke_signed(
+                &crate::extern_source::spl_token_2022::transfer_checked(
+                    token_program.key,
+                    token_account.key,
+                    token_mint.key,
+                    destination_token_account.key,
+                    self_authority.key,
+                    amount,
+                    decimals,
+                ),
+                vec![
+                    token_account.clone(),
+                    token_mint.clone(),
+                    destination_token_account.clone(),
+                    self_authority.clone(),
+                ]
+                .as_slice(),
+                [self_authority_seeds.as_slice()].as_slice(),
+            )?;
+        }
+        if close_token_account {
+            solana_program::program::invoke_signed(
+                &crate::extern_source::spl_token_2022::close_account(token_program.key, token_account.key, intermediary_manager.key, self_authority.key),
+                vec![
+                    token_account.clone(),
+                    intermediary_manager.clone(),
+                    self_authority.clone(),
+                ]
+                .as_slice(),
+                [self_authority_seeds.as_slice()].as_slice(),
+            )?;
+        }
+        Ok(())
+    }
+    fn close_token_accounts(accounts: &[AccountInfo], token_accounts: Vec<TokenAccountToClose>) -> ProgramResult {
+        if token_accounts.is_empty() {
+            return Err(Error::ZeroTokenMintsPresented.into());
+        }
+        let account_info_iter = &mut accounts.iter();
+        let intermediary = solana_program::account_info::next_account_info(account_info_iter)?;
+        let receiver = solana_program::account_info::next_account_info(account_info_iter)?;
+        let self_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_2022_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        if *token_program.key != spl_token::ID || *token_2022_program.key != crate::extern_source::spl_token_2022::ID {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if !receiver.is_writable || !receiver.is_signer {
+            return Err(Error::InvalidAccountConfigurationFlags.into());
+        }
+        let intermediary_data = &intermediary.data.borrow();
+        let intermediary_ = bytemuck::try_from_bytes::<Intermediary>(intermediary_data).map_err(|_| Error::InvalidLogic)?;
+        if !intermediary_.is_initialized() {
+            return Err(Error::IntermediaryIsNotInitialized.into());
+        }
+        if *receiver.key != intermediary_.trader && *receiver.key != intermediary_.manager {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        if *self_authority.key != intermediary_.self_authority
+            || *self_authority.key != PdaResolver::self_authority_create(intermediary.key, intermediary_.self_authority_pubkey_bump_seed)?
+        {
+            return Err(Error::IntermediaryInvalidAuthority.into());
+        }
+        let self_authority_pubkey_bump_seed = [intermediary_.self_authority_pubkey_bump_seed];
+        let self_authority_seeds = PdaResolver::self_authority_get_seeds(intermediary.key, self_authority_pubkey_bump_seed.as_slice());
+        '_a: for token_account_to_close in token_accounts.iter() {
+            let token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+            // The WSol treasury is required for the swap.
+            if token_account_to_close.token_mint == spl_token::native_mint::ID {
+                return Err(Error::InvalidTokenMint.into());
+            }
+            // The treasury of the registered quote mint is not the token account PDA, so it is refused here.
+            if *token_account.key != PdaResolver::token_account_create(intermediary.key, &token_account_to_close.token_mint, token_account_to_close.token_account_pubkey_bump_seed)?
+            {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            if !token_account.is_writable {
+                return Err(Error::InvalidAccountConfigurationFlags.into());
+            }
+            let token_account_program = if *token_account.owner == spl_token::ID {
+                token_program
+            } else if *token_account.owner == crate::extern_source::spl_token_2022::ID {
+                token_2022_program
+            } else {
+                return Err(Error::InvalidAccountData.into());
+            };
+            if Self::get_token_account_amount(token_account)? != 0 {
+                msg!("8Fail. Token account is not empty. Mint: {}, token_account: {}.", &token_account_to_close.token_mint, token_account.key);
+                return Err(Error::TokenAccountInvalidAmount.into());
+            }
+            solana_program::program::invoke_signed(
+                &crate::extern_source::spl_token_2022::close_account(token_account_program.key, token_account.key, receiver.key, self_authority.key),
+                vec![
+                    token_account.clone(),
+                    receiver.clone(),
+                    self_authority.clone(),
+                ]
+                .as_slice(),
+                [self_authority_seeds.as_slice()].as_slice(),
+            )?;
+        }
+        Ok(())
+    }
     fn swap(
-        dexes: Vec<Dex_>,
+        dexes: Vec<DexAccountsRange>,
         accounts: &[AccountInfo],
         token_mint: Pubkey,
         quote_mint: Pubkey,
         amount_in: u64,
         min_amount_out: u64,
         token_account_pubkey_bump_seed: u8,
+        quote_token_account_pubkey_bump_seed: u8,
         is_from_quote_to_token: bool,
         with_checks: bool,
+        swap_mode: SwapMode,
+        valid_until_slot: Option<u64>,
+        valid_until_unix_timestamp: Option<i64>,
+        max_price_impact_bps: Option<u16>,
+        max_slippage_bps: Option<u16>,
     ) -> ProgramResult {
-        // Only direction from WSol to AnyMint is valid.
-        if !is_from_quote_to_token || quote_mint != spl_token::native_mint::ID {
-            return Err(Error::NotImplemented.into());
-        }
         if token_mint == quote_mint {
             return Err(Error::EqualMints.into());
         }
@@ -419,6 +831,15 @@ impl Processor {
         if dexes.is_empty() {
             return Err(Error::ZeroDexesPresented.into());
         }
+        if valid_until_slot.is_some() || valid_until_unix_timestamp.is_some() {
+            let clock = Clock::get()?;
+            if valid_until_slot.is_some_and(|valid_until_slot_| clock.slot > valid_until_slot_)
+                || valid_until_unix_timestamp.is_some_and(|valid_until_unix_timestamp_| clock.unix_timestamp > valid_until_unix_timestamp_)
+            {
+                msg!("6Fail. Swap expired. Slot: {}, unix_timestamp: {}.", clock.slot, clock.unix_timestamp);
+                return Err(Error::SwapExpired.into());
+            }
+        }
         let account_info_iter = &mut accounts.iter();
         let intermediary = solana_program::account_info::next_account_info(account_info_iter)?;
         let intermediary_trader = solana_program::account_info::next_account_info(account_info_iter)?;
@@ -430,16 +851,16 @@ impl Processor {
         let system_program = solana_program::account_info::next_account_info(account_info_iter)?;
         let rent = solana_program::account_info::next_account_info(account_info_iter)?;
         let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
-        if token_mint != *token_mint_.key {
+        let token_2022_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        if token_mint != *token_mint_.key || quote_mint != *quote_token_mint.key {
             return Err(Error::InvalidTokenMint.into());
         }
         if with_checks {
             if *token_account.key != PdaResolver::token_account_create(intermediary.key, token_mint_.key, token_account_pubkey_bump_seed)?
-                || quote_token_mint.key == token_mint_.key
-                || *quote_token_mint.key != spl_token::native_mint::ID
                 || *system_program.key != solana_program::system_program::ID
                 || *rent.key != solana_program::sysvar::rent::ID
                 || *token_program.key != spl_token::ID
+                || *token_2022_program.key != crate::extern_source::spl_token_2022::ID
             {
                 return Err(Error::InvalidAccountPubkey.into());
             }
@@ -447,11 +868,6 @@ impl Processor {
                 return Err(Error::InvalidAccountConfigurationFlags.into());
             }
         }
-        let rent_ = Rent::from_account_info(rent)?;
-        let token_account_rent_exemption_balance = rent_.minimum_balance(<Account as Pack>::LEN);
-        if intermediary_trader.lamports() < token_account_rent_exemption_balance {
-            return Err(Error::InvalidAccountLamports.into());
-        }
         let intermediary_data = &intermediary.data.borrow();
         let intermediary_ = bytemuck::try_from_bytes::<Intermediary>(intermediary_data).map_err(|_| Error::InvalidLogic)?;
         if !intermediary_.is_initialized() {
@@ -460,56 +876,41 @@ impl Processor {
         if *intermediary_trader.key != intermediary_.trader {
             return Err(Error::IntermediaryInvalidTrader.into());
         }
-        if *quote_token_account.key != intermediary_.w_sol_token_account
-            || *quote_token_account.key != PdaResolver::token_account_create(intermediary.key, &spl_token::native_mint::ID, intermediary_.w_sol_token_account_pubkey_bump_seed)?
-        {
-            return Err(Error::IntermediaryInvalidWSolTokenAccount.into());
+        Self::check_quote_token_account(intermediary, intermediary_, quote_token_account, &quote_mint, quote_token_account_pubkey_bump_seed)?;
+        if quote_token_account.data_is_empty() {
+            return Err(Error::QuoteMintIsNotRegistered.into());
         }
         if *self_authority.key != intermediary_.self_authority
             || *self_authority.key != PdaResolver::self_authority_create(intermediary.key, intermediary_.self_authority_pubkey_bump_seed)?
         {
             return Err(Error::IntermediaryInvalidAuthority.into());
         }
-        let initial_quote_token_amount = Account::unpack_unchecked(&quote_token_account.data.borrow())?.amount;
-        if amount_in > initial_quote_token_amount {
-            return Err(Error::TokenAccountInsufficientAmount.into());
-        }
+        let initial_quote_token_amount = Self::get_token_account_amount(quote_token_account)?;
         let mut initial_token_amount = 0;
         if token_account.data_is_empty() {
-            solana_program::program::invoke_signed(
-                &solana_program::system_instruction::create_account(
-                    intermediary_trader.key,
-                    token_account.key,
-                    token_account_rent_exemption_balance,
-                    <Account as Pack>::LEN as u64,
-                    token_program.key,
-                ),
-                vec![
-                    intermediary_trader.clone(),
-                    token_account.clone(),
-                ]
-                .as_slice(),
-                [PdaResolver::token_account_get_seeds(intermediary.key, token_mint_.key, [token_account_pubkey_bump_seed].as_slice()).as_slice()].as_slice(),
-            )?;
-            solana_program::program::invoke(
-                &spl_token::instruction::initialize_account(
-                    token_program.key,
-                    token_account.key,
-                    token_mint_.key,
-                    // Owner - intermediary.self_authority. That is, all manipulations with reducing the token amount
-                    // on the account are carried out through a this contract.
-                    self_authority.key,
-                )?,
-                vec![
-                    token_account.clone(),
-                    token_mint_.clone(),
-                    self_authority.clone(),
-                    rent.clone(),
-                ]
-                .as_slice(),
+            // There is nothing to sell from a token account that does not exist yet.
+            if !is_from_quote_to_token {
+                return Err(Error::TokenAccountInsufficientAmount.into());
+            }
+            Self::create_token_account(
+                intermediary_trader,
+                token_account,
+                token_mint_,
+                self_authority,
+                rent,
+                Self::get_token_mint_program(token_mint_, token_program, token_2022_program)?,
+                PdaResolver::token_account_get_seeds(intermediary.key, token_mint_.key, [token_account_pubkey_bump_seed].as_slice()).as_slice(),
             )?;
         } else {
-            initial_token_amount = Account::unpack_unchecked(&token_account.data.borrow())?.amount
+            initial_token_amount = Self::get_token_account_amount(token_account)?
+        }
+        let (in_token_account, out_token_account, in_mint, out_mint, initial_in_token_amount, initial_out_token_amount) = if is_from_quote_to_token {
+            (quote_token_account, token_account, &quote_mint, &token_mint, initial_quote_token_amount, initial_token_amount)
+        } else {
+            (token_account, quote_token_account, &token_mint, &quote_mint, initial_token_amount, initial_quote_token_amount)
+        };
+        if amount_in > initial_in_token_amount {
+            return Err(Error::TokenAccountInsufficientAmount.into());
         }
         let base_data = BaseData {
             accounts,
@@ -525,98 +926,1029 @@ impl Processor {
             is_from_quote_to_token,
             with_checks,
         };
-        let mut dexes_ = Vec::<(Dex_, &dyn Dex)>::with_capacity(dexes.len());
-        let mut dexes_btree_set = BTreeSet::<Dex_>::new();
-        '_a: for dex in dexes {
-            if !dexes_btree_set.insert(dex) {
-                return Err(Error::RepeatableDex.into());
+        const INTERMEDIARY_RESERVED_ACCOUNTS_QUANTUTY: usize = 11;
+        let dexes_ = Self::resolve_dexes_accounts_ranges(dexes, accounts, INTERMEDIARY_RESERVED_ACCOUNTS_QUANTUTY)?;
+        let is_split = matches!(swap_mode, SwapMode::Split { .. } | SwapMode::SplitByMarginalPrice { .. });
+        let is_best_execution = matches!(swap_mode, SwapMode::BestExecution);
+        // Dex, swap calculation result, amount_in, amount_out.
+        let mut executed_swaps = Vec::<(Dex_, SwapCalculationResult, u64, u64)>::with_capacity(dexes_.len());
+        // Only for SwapMode::BestExecution.
+        let mut runner_up_dex_with_swap_calculation_result = None::<(Dex_, SwapCalculationResult)>;
+        match swap_mode {
+            SwapMode::FirstMatch | SwapMode::BestExecution => {
+                let mut dex_with_swap_calculation_result = None::<(Dex_, &[AccountInfo], SwapCalculationResult)>;
+                'a: for (dex_, dex_accounts_) in dexes_.into_iter() {
+                    let swap_calculation_result = match Self::do_swap_calculation(dex_, &base_data, dex_accounts_)? {
+                        Some(swap_calculation_result_) => swap_calculation_result_,
+                        None => continue 'a,
+                    };
+                    if !is_best_execution {
+                        if swap_calculation_result.amount_out >= min_amount_out {
+                            dex_with_swap_calculation_result = Some((dex_, dex_accounts_, swap_calculation_result));
+                            break 'a;
+                        } else {
+                            continue 'a;
+                        }
+                    }
+                    // The swap is postponed until all Dexes are quoted.
+                    match dex_with_swap_calculation_result {
+                        Some(ref dex_with_swap_calculation_result_) if !swap_calculation_result.is_better_than(&dex_with_swap_calculation_result_.2) => {
+                            let is_runner_up = match runner_up_dex_with_swap_calculation_result {
+                                Some(ref runner_up_dex_with_swap_calculation_result_) => swap_calculation_result.is_better_than(&runner_up_dex_with_swap_calculation_result_.1),
+                                None => true,
+                            };
+                            if is_runner_up {
+                                runner_up_dex_with_swap_calculation_result = Some((dex_, swap_calculation_result));
+                            }
+                        }
+                        _ => {
+                            if let Some((runner_up_dex, _, runner_up_swap_calculation_result)) = dex_with_swap_calculation_result.take() {
+                                runner_up_dex_with_swap_calculation_result = Some((runner_up_dex, runner_up_swap_calculation_result));
+                            }
+                            dex_with_swap_calculation_result = Some((dex_, dex_accounts_, swap_calculation_result));
+                        }
+                    }
+                }
+                if let Some((dex_, dex_accounts_, swap_calculation_result)) = dex_with_swap_calculation_result {
+                    if swap_calculation_result.amount_out >= min_amount_out {
+                        Self::do_swap(dex_, &base_data, dex_accounts_)?;
+                        executed_swaps.push((dex_, swap_calculation_result, amount_in, 0));
+                    }
+                }
+            }
+            SwapMode::Split {
+                amounts_in,
+            } => {
+                if amounts_in.len() != dexes_.len() || amounts_in.iter().try_fold(0u64, |sum, amount_in_| sum.checked_add(*amount_in_)) != Some(amount_in) {
+                    return Err(Error::InvalidSwapSplit.into());
+                }
+                let mut dexes_with_swap_calculation_result = Vec::<(Dex_, &[AccountInfo], SwapCalculationResult, u64)>::with_capacity(dexes_.len());
+                '_a: for ((dex_, dex_accounts_), amount_in_) in dexes_.into_iter().zip(amounts_in) {
+                    if amount_in_ == 0 {
+                        continue '_a;
+                    }
+                    let base_data_ = BaseData {
+                        amount_in: amount_in_,
+                        min_amount_out: 0,
+                        ..base_data
+                    };
+                    match Self::do_swap_calculation(dex_, &base_data_, dex_accounts_)? {
+                        Some(swap_calculation_result) => dexes_with_swap_calculation_result.push((dex_, dex_accounts_, swap_calculation_result, amount_in_)),
+                        None => {
+                            msg!("4Fail. Dex {} is not available for the split part.", dex_.to_str());
+                            return Err(Error::InvalidSwapConditions.into());
+                        }
+                    }
+                }
+                Self::do_split_swap(&base_data, dexes_with_swap_calculation_result, out_token_account, &mut executed_swaps)?;
+            }
+            SwapMode::SplitByMarginalPrice {
+                parts,
+            } => {
+                if parts == 0 || amount_in < parts as u64 {
+                    return Err(Error::InvalidSwapSplit.into());
+                }
+                // Each part goes to the Dex that gives the biggest 'amount_out' increase for it. Thus, marginal prices
+                // of all used Dexes become approximately equal. Each part requires only one new swap calculation.
+                let part_amount_in = amount_in / parts as u64;
+                // Dex, first account index, allocated amount_in, swap calculation result for allocated amount_in,
+                // swap calculation result for allocated amount_in plus one more part.
+                let mut dexes_with_allocation = Vec::<(Dex_, &[AccountInfo], u64, Option<SwapCalculationResult>, Option<SwapCalculationResult>)>::with_capacity(dexes_.len());
+                '_a: for (dex_, dex_accounts_) in dexes_.into_iter() {
+                    let base_data_ = BaseData {
+                        amount_in: part_amount_in,
+                        min_amount_out: 0,
+                        ..base_data
+                    };
+                    let next_swap_calculation_result = Self::do_swap_calculation(dex_, &base_data_, dex_accounts_)?;
+                    dexes_with_allocation.push((dex_, dex_accounts_, 0, None, next_swap_calculation_result));
+                }
+                let mut last_allocation_index = None;
+                '_a: for _ in 0..parts {
+                    let mut allocation_index = None::<(usize, u64)>;
+                    '_b: for (index, dex_with_allocation) in dexes_with_allocation.iter().enumerate() {
+                        if let Some(ref next_swap_calculation_result) = dex_with_allocation.4 {
+                            let amount_out_increase = next_swap_calculation_result
+                                .amount_out
+                                .saturating_sub(dex_with_allocation.3.as_ref().map_or(0, |swap_calculation_result| swap_calculation_result.amount_out));
+                            if allocation_index.map_or(true, |(_, amount_out_increase_)| amount_out_increase > amount_out_increase_) {
+                                allocation_index = Some((index, amount_out_increase));
+                            }
+                        }
+                    }
+                    let index = match allocation_index {
+                        Some((index, _)) => index,
+                        None => {
+                            msg!("4Fail. No dex is available for the split part.");
+                            return Err(Error::InvalidSwapConditions.into());
+                        }
+                    };
+                    let dex_with_allocation = &mut dexes_with_allocation[index];
+                    dex_with_allocation.2 += part_amount_in;
+                    dex_with_allocation.3 = dex_with_allocation.4.take();
+                    let base_data_ = BaseData {
+                        amount_in: dex_with_allocation.2 + part_amount_in,
+                        min_amount_out: 0,
+                        ..base_data
+                    };
+                    dex_with_allocation.4 = Self::do_swap_calculation(dex_with_allocation.0, &base_data_, dex_with_allocation.1)?;
+                    last_allocation_index = Some(index);
+                }
+                // The remainder of the division goes to the last used Dex.
+                let remainder = amount_in - part_amount_in * parts as u64;
+                if let (Some(index), true) = (last_allocation_index, remainder > 0) {
+                    let dex_with_allocation = &mut dexes_with_allocation[index];
+                    dex_with_allocation.2 += remainder;
+                    let base_data_ = BaseData {
+                        amount_in: dex_with_allocation.2,
+                        min_amount_out: 0,
+                        ..base_data
+                    };
+                    dex_with_allocation.3 = Self::do_swap_calculation(dex_with_allocation.0, &base_data_, dex_with_allocation.1)?;
+                }
+                let mut dexes_with_swap_calculation_result = Vec::<(Dex_, &[AccountInfo], SwapCalculationResult, u64)>::with_capacity(dexes_with_allocation.len());
+                '_a: for (dex_, dex_accounts_, amount_in_, swap_calculation_result, _) in dexes_with_allocation.into_iter() {
+                    if amount_in_ == 0 {
+                        continue '_a;
+                    }
+                    match swap_calculation_result {
+                        Some(swap_calculation_result_) => dexes_with_swap_calculation_result.push((dex_, dex_accounts_, swap_calculation_result_, amount_in_)),
+                        None => {
+                            msg!("4Fail. Dex {} is not available for the split part.", dex_.to_str());
+                            return Err(Error::InvalidSwapConditions.into());
+                        }
+                    }
+                }
+                Self::do_split_swap(&base_data, dexes_with_swap_calculation_result, out_token_account, &mut executed_swaps)?;
+            }
+            SwapMode::ExactOut => {
+                if min_amount_out == 0 {
+                    return Err(Error::InvalidSwapConditions.into());
+                }
+                '_a: for (dex_, dex_accounts_) in dexes_.into_iter() {
+                    if let Some(swap_calculation_result) = Self::do_swap_calculation_base_out(dex_, &base_data, dex_accounts_)? {
+                        if swap_calculation_result.amount_in <= amount_in && swap_calculation_result.amount_out >= min_amount_out {
+                            Self::do_swap_base_out(dex_, &base_data, dex_accounts_, &swap_calculation_result)?;
+                            let amount_in_ = swap_calculation_result.amount_in;
+                            executed_swaps.push((dex_, swap_calculation_result, amount_in_, 0));
+                            break '_a;
+                        }
+                    }
+                }
             }
-            let dex_ = match dex {
-                Dex_::MeteoraV1 => &MeteoraV1 as &dyn Dex,
-                Dex_::RaydiumV4 => &RaydiumV4,
-            };
-            dexes_.push((dex, dex_));
         }
-        const INTERMEDIARY_RESERVED_ACCOUNTS_QUANTUTY: usize = 10;
-        let mut first_account_index = INTERMEDIARY_RESERVED_ACCOUNTS_QUANTUTY;
-        let mut previous_dex_swap_accounts_quantity: usize = 0;
-        let mut dex_with_swap_calculation_result = None;
-        'a: for (dex_, dex) in dexes_.into_iter() {
-            first_account_index += previous_dex_swap_accounts_quantity;
-            previous_dex_swap_accounts_quantity += dex.get_swap_accounts_quantity();
-            // Here returns Error, because we believe that the discrepancy between the data on the accounts
-            // and the method signature parameters is a logical error, and we cannot simply move on to the next Dex.
-            let swap_calculation_result = match dex.do_swap_calculation(&base_data, first_account_index) {
-                Ok(swap_calculation_result_) => swap_calculation_result_,
-                Err(program_error) => {
-                    msg!("0Fail. Invalid CPI accounts for Dex {},", dex_.to_str());
-                    return Err(program_error);
+        if executed_swaps.is_empty() {
+            msg!(
+                "3Fail. No matching dex found. In_mint: {}, out_mint: {}, amount_in : {}, min_amount_out: {}.",
+                in_mint,
+                out_mint,
+                amount_in,
+                min_amount_out,
+            );
+            return Err(Error::InvalidSwapConditions.into());
+        }
+        // Some Dexes transfer the native Sol to the WSol token account.
+        if quote_mint == spl_token::native_mint::ID {
+            solana_program::program::invoke(
+                &spl_token::instruction::sync_native(token_program.key, quote_token_account.key)?,
+                vec![
+                    quote_token_account.clone(),
+                ]
+                .as_slice(),
+            )?;
+        }
+        let new_in_token_amount = Self::get_token_account_amount(in_token_account)?;
+        let new_out_token_amount = Self::get_token_account_amount(out_token_account)?;
+        let amount_out = new_out_token_amount.saturating_sub(initial_out_token_amount);
+        if !is_split {
+            // There is only one executed swap.
+            executed_swaps[0].3 = amount_out;
+        }
+        if new_in_token_amount < (initial_in_token_amount - amount_in) || (new_out_token_amount as u128) < (initial_out_token_amount as u128 + min_amount_out as u128) {
+            '_a: for executed_swap in executed_swaps.iter() {
+                msg!(
+                    "2Fail. Invalid calculation logic. Dex: {}, pool: {}, in_mint: {}, out_mint: {}, amount_in : {}, amount_in_fee: {}, amount_out: {}, min_amount_out: {}.",
+                    executed_swap.0.to_str(),
+                    &executed_swap.1.pool,
+                    in_mint,
+                    out_mint,
+                    executed_swap.2,
+                    executed_swap.1.amount_in_fee,
+                    executed_swap.1.amount_out,
+                    min_amount_out,
+                );
+            }
+            return Err(Error::TokenAccountInvalidAmount.into());
+        }
+        // The received amount of the Token-2022 out mint is reduced by the transfer fee.
+        let out_token_mint = if is_from_quote_to_token {
+            token_mint_
+        } else {
+            quote_token_mint
+        };
+        let out_transfer_fee_config = match get_transfer_fee_config(out_token_mint) {
+            Some(transfer_fee_config) => Some((transfer_fee_config, Clock::get()?.epoch)),
+            None => None,
+        };
+        // The unknown price deviation fails the swap only if the limit is requested.
+        let (price_impact_bps, slippage_bps) = match Self::calculate_price_deviation_bps(executed_swaps.as_slice(), amount_out, out_transfer_fee_config.as_ref()) {
+            Some((price_impact_bps_, slippage_bps_)) => (Some(price_impact_bps_), Some(slippage_bps_)),
+            None => (None, None),
+        };
+        let is_deviation_exceeded = |deviation_bps: Option<u64>, max_deviation_bps: Option<u16>| -> bool {
+            match (deviation_bps, max_deviation_bps) {
+                (Some(deviation_bps_), Some(max_deviation_bps_)) => deviation_bps_ > max_deviation_bps_ as u64,
+                (None, Some(_)) => true,
+                (_, None) => false,
+            }
+        };
+        if is_deviation_exceeded(price_impact_bps, max_price_impact_bps) || is_deviation_exceeded(slippage_bps, max_slippage_bps) {
+            msg!(
+                "7Fail. Price deviation exceeded. In_mint: {}, out_mint: {}, price_impact_bps: {:?}, max_price_impact_bps: {:?}, slippage_bps: {:?}, max_slippage_bps: {:?}.",
+                in_mint,
+                out_mint,
+                price_impact_bps,
+                max_price_impact_bps,
+                slippage_bps,
+                max_slippage_bps,
+            );
+            return Err(Error::PriceDeviationExceeded.into());
+        }
+        '_a: for executed_swap in executed_swaps.iter() {
+            msg!(
+                "0Success. Dex: {}, pool: {}, in_mint: {}, out_mint: {}, amount_in : {}, amount_in_fee: {}, amount_out: {}, min_amount_out: {}.",
+                executed_swap.0.to_str(),
+                &executed_swap.1.pool,
+                in_mint,
+                out_mint,
+                executed_swap.2,
+                executed_swap.1.amount_in_fee,
+                executed_swap.3,
+                min_amount_out,
+            );
+        }
+        if is_best_execution {
+            match runner_up_dex_with_swap_calculation_result {
+                Some(runner_up_dex_with_swap_calculation_result_) => {
+                    msg!(
+                        "1Success. Runner-up dex: {}, pool: {}, amount_in_fee: {}, amount_out: {}.",
+                        runner_up_dex_with_swap_calculation_result_.0.to_str(),
+                        &runner_up_dex_with_swap_calculation_result_.1.pool,
+                        runner_up_dex_with_swap_calculation_result_.1.amount_in_fee,
+                        runner_up_dex_with_swap_calculation_result_.1.amount_out,
+                    );
                 }
-            };
-            let swap_calculation_result_ = match swap_calculation_result {
-                Some(swap_calculation_result__) => swap_calculation_result__,
-                None => continue 'a,
-            };
-            if swap_calculation_result_.amount_out >= min_amount_out {
-                dex_with_swap_calculation_result = Some((dex_, swap_calculation_result_));
-                if let Err(program_error) = dex.do_swap(&base_data, first_account_index) {
-                    msg!("1Fail. Invalid CPI accounts for Dex {},", dex_.to_str());
-                    return Err(program_error);
+                None => {
+                    msg!("2Success. No runner-up dex found.");
                 }
-                break 'a;
-            } else {
-                continue 'a;
             }
         }
-        match dex_with_swap_calculation_result {
-            Some(dex_with_swap_calculation_result_) => {
-                solana_program::program::invoke(
-                    &spl_token::instruction::sync_native(token_program.key, quote_token_account.key)?,
-                    vec![
-                        quote_token_account.clone(),
-                    ]
-                    .as_slice(),
-                )?;
-                let new_token_amount = Account::unpack_unchecked(&token_account.data.borrow())?.amount;
-                if Account::unpack_unchecked(&quote_token_account.data.borrow())?.amount < (initial_quote_token_amount - amount_in)
-                    || (new_token_amount as u128) < (initial_token_amount as u128 + min_amount_out as u128)
+        if is_split {
+            msg!(
+                "3Success. Split. In_mint: {}, out_mint: {}, amount_in : {}, amount_out: {}, min_amount_out: {}.",
+                in_mint,
+                out_mint,
+                amount_in,
+                amount_out,
+                min_amount_out,
+            );
+        }
+        msg!("4Success. Price_impact_bps: {:?}, slippage_bps: {:?}.", price_impact_bps, slippage_bps);
+        Ok(())
+    }
+    fn route(
+        hops: Vec<Hop>,
+        accounts: &[AccountInfo],
+        in_token_mint: Pubkey,
+        amount_in: u64,
+        min_amount_out: u64,
+        in_token_account_pubkey_bump_seed: u8,
+        with_checks: bool,
+    ) -> ProgramResult {
+        if amount_in == 0 {
+            return Err(Error::ZeroAmountIn.into());
+        }
+        if hops.is_empty() {
+            return Err(Error::ZeroHopsPresented.into());
+        }
+        let account_info_iter = &mut accounts.iter();
+        let intermediary = solana_program::account_info::next_account_info(account_info_iter)?;
+        let intermediary_trader = solana_program::account_info::next_account_info(account_info_iter)?;
+        let self_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let in_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let in_token_mint_ = solana_program::account_info::next_account_info(account_info_iter)?;
+        let system_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let rent = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_2022_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        if in_token_mint != *in_token_mint_.key {
+            return Err(Error::InvalidTokenMint.into());
+        }
+        if with_checks {
+            let is_in_quote_token_account = Self::is_quote_token_account(intermediary, in_token_account, &in_token_mint, in_token_account_pubkey_bump_seed);
+            if (!is_in_quote_token_account && *in_token_account.key != PdaResolver::token_account_create(intermediary.key, in_token_mint_.key, in_token_account_pubkey_bump_seed)?)
+                || *system_program.key != solana_program::system_program::ID
+                || *rent.key != solana_program::sysvar::rent::ID
+                || *token_program.key != spl_token::ID
+                || *token_2022_program.key != crate::extern_source::spl_token_2022::ID
+            {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            if !intermediary_trader.is_signer || !intermediary_trader.is_writable || !in_token_account.is_writable {
+                return Err(Error::InvalidAccountConfigurationFlags.into());
+            }
+        }
+        let intermediary_data = &intermediary.data.borrow();
+        let intermediary_ = bytemuck::try_from_bytes::<Intermediary>(intermediary_data).map_err(|_| Error::InvalidLogic)?;
+        if !intermediary_.is_initialized() {
+            return Err(Error::IntermediaryIsNotInitialized.into());
+        }
+        if *intermediary_trader.key != intermediary_.trader {
+            return Err(Error::IntermediaryInvalidTrader.into());
+        }
+        if *self_authority.key != intermediary_.self_authority
+            || *self_authority.key != PdaResolver::self_authority_create(intermediary.key, intermediary_.self_authority_pubkey_bump_seed)?
+        {
+            return Err(Error::IntermediaryInvalidAuthority.into());
+        }
+        if in_token_account.data_is_empty() || amount_in > Self::get_token_account_amount(in_token_account)? {
+            return Err(Error::TokenAccountInsufficientAmount.into());
+        }
+        const INTERMEDIARY_RESERVED_ACCOUNTS_QUANTUTY: usize = 9;
+        // Out token mint and out token account.
+        const HOP_RESERVED_ACCOUNTS_QUANTUTY: usize = 2;
+        let mut first_account_index = INTERMEDIARY_RESERVED_ACCOUNTS_QUANTUTY;
+        let mut hop_in_token_account = in_token_account;
+        let mut hop_in_token_mint = &in_token_mint;
+        let mut hop_amount_in = amount_in;
+        let hops_quantity = hops.len();
+        '_a: for (hop_index, hop) in hops.iter().enumerate() {
+            let account_info_iter = &mut accounts.iter().skip(first_account_index);
+            let hop_out_token_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+            let hop_out_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+            first_account_index += HOP_RESERVED_ACCOUNTS_QUANTUTY;
+            if *hop_out_token_mint.key != hop.out_token_mint || hop.out_token_mint == *hop_in_token_mint {
+                return Err(Error::InvalidTokenMint.into());
+            }
+            let is_last_hop = hop_index + 1 == hops_quantity;
+            let is_hop_out_quote_token_account =
+                is_last_hop && Self::is_quote_token_account(intermediary, hop_out_token_account, &hop.out_token_mint, hop.out_token_account_pubkey_bump_seed);
+            if with_checks {
+                if !is_hop_out_quote_token_account
+                    && *hop_out_token_account.key != PdaResolver::token_account_create(intermediary.key, hop_out_token_mint.key, hop.out_token_account_pubkey_bump_seed)?
                 {
+                    return Err(Error::InvalidAccountPubkey.into());
+                }
+                if !hop_out_token_account.is_writable {
+                    return Err(Error::InvalidAccountConfigurationFlags.into());
+                }
+            }
+            let mut initial_hop_out_token_amount = 0;
+            if hop_out_token_account.data_is_empty() {
+                // The treasury is created only by the quote mint registration.
+                if is_hop_out_quote_token_account {
+                    return Err(Error::QuoteMintIsNotRegistered.into());
+                }
+                Self::create_token_account(
+                    intermediary_trader,
+                    hop_out_token_account,
+                    hop_out_token_mint,
+                    self_authority,
+                    rent,
+                    Self::get_token_mint_program(hop_out_token_mint, token_program, token_2022_program)?,
+                    PdaResolver::token_account_get_seeds(intermediary.key, hop_out_token_mint.key, [hop.out_token_account_pubkey_bump_seed].as_slice()).as_slice(),
+                )?;
+            } else {
+                initial_hop_out_token_amount = Self::get_token_account_amount(hop_out_token_account)?;
+            }
+            let initial_hop_in_token_amount = Self::get_token_account_amount(hop_in_token_account)?;
+            // 'min_amount_out' is enforced only on the final hop.
+            let base_data = BaseData {
+                accounts,
+                intermediary,
+                quote_token_account: hop_in_token_account,
+                token_account: hop_out_token_account,
+                self_authority,
+                intermediary_,
+                token_mint: &hop.out_token_mint,
+                quote_mint: hop_in_token_mint,
+                amount_in: hop_amount_in,
+                min_amount_out: if is_last_hop {
+                    min_amount_out
+                } else {
+                    0
+                },
+                is_from_quote_to_token: true,
+                with_checks,
+            };
+            let dex_accounts = Self::get_dex_accounts(hop.dex, accounts, first_account_index, hop.accounts_quantity)?;
+            let swap_calculation_result = match Self::do_swap_calculation(hop.dex, &base_data, dex_accounts)? {
+                Some(swap_calculation_result_) => swap_calculation_result_,
+                None => {
                     msg!(
-                        "2Fail. Invalid calculation logic. Dex: {}, pool: {}, in_mint: {}, out_mint: {}, amount_in : {}, amount_in_fee: {}, amount_out: {}, min_amount_out: {}.",
-                        dex_with_swap_calculation_result_.0.to_str(),
-                        &dex_with_swap_calculation_result_.1.pool,
-                        &quote_mint,
-                        &token_mint,
-                        amount_in,
-                        dex_with_swap_calculation_result_.1.amount_in_fee,
-                        dex_with_swap_calculation_result_.1.amount_out,
-                        min_amount_out,
+                        "3Fail. Hop {} is not available. Dex: {}, pool: {}, in_mint: {}, out_mint: {}, amount_in : {}.",
+                        hop_index,
+                        hop.dex.to_str(),
+                        &hop.pool,
+                        hop_in_token_mint,
+                        &hop.out_token_mint,
+                        hop_amount_in,
                     );
-                    return Err(Error::TokenAccountInvalidAmount.into());
+                    return Err(Error::InvalidSwapConditions.into());
                 }
-                msg!(
-                    "0Success. Dex: {}, pool: {}, in_mint: {}, out_mint: {}, amount_in : {}, amount_in_fee: {}, amount_out: {}, min_amount_out: {}.",
-                    dex_with_swap_calculation_result_.0.to_str(),
-                    &dex_with_swap_calculation_result_.1.pool,
-                    &quote_mint,
-                    &token_mint,
-                    amount_in,
-                    dex_with_swap_calculation_result_.1.amount_in_fee,
-                    new_token_amount - initial_token_amount,
-                    min_amount_out,
-                )
+            };
+            if swap_calculation_result.pool != hop.pool {
+                return Err(Error::InvalidAccountPubkey.into());
             }
-            None => {
+            Self::do_swap(hop.dex, &base_data, dex_accounts)?;
+            first_account_index += dex_accounts.len();
+            let new_hop_in_token_amount = Self::get_token_account_amount(hop_in_token_account)?;
+            let new_hop_out_token_amount = Self::get_token_account_amount(hop_out_token_account)?;
+            let hop_amount_out = new_hop_out_token_amount.saturating_sub(initial_hop_out_token_amount);
+            if new_hop_in_token_amount < (initial_hop_in_token_amount - hop_amount_in) || hop_amount_out == 0 {
                 msg!(
-                    "3Fail. No matching dex found. In_mint: {}, out_mint: {}, amount_in : {}, min_amount_out: {}.",
-                    &quote_mint,
-                    &token_mint,
-                    amount_in,
-                    min_amount_out,
-
+                    "2Fail. Invalid calculation logic. Hop: {}, dex: {}, pool: {}, in_mint: {}, out_mint: {}, amount_in : {}, amount_in_fee: {}, amount_out: {}.",
+                    hop_index,
+                    hop.dex.to_str(),
+                    &hop.pool,
+                    hop_in_token_mint,
+                    &hop.out_token_mint,
+                    hop_amount_in,
+                    swap_calculation_result.amount_in_fee,
+                    swap_calculation_result.amount_out,
                 );
+                return Err(Error::TokenAccountInvalidAmount.into());
+            }
+            msg!(
+                "0Success. Hop: {}, dex: {}, pool: {}, in_mint: {}, out_mint: {}, amount_in : {}, amount_in_fee: {}, amount_out: {}.",
+                hop_index,
+                hop.dex.to_str(),
+                &hop.pool,
+                hop_in_token_mint,
+                &hop.out_token_mint,
+                hop_amount_in,
+                swap_calculation_result.amount_in_fee,
+                hop_amount_out,
+            );
+            hop_in_token_account = hop_out_token_account;
+            hop_in_token_mint = &hop.out_token_mint;
+            hop_amount_in = hop_amount_out;
+        }
+        if hop_amount_in < min_amount_out {
+            msg!(
+                "4Fail. Route amount_out is less than min_amount_out. In_mint: {}, out_mint: {}, amount_in : {}, amount_out: {}, min_amount_out: {}.",
+                &in_token_mint,
+                hop_in_token_mint,
+                amount_in,
+                hop_amount_in,
+                min_amount_out,
+            );
+            return Err(Error::TokenAccountInvalidAmount.into());
+        }
+        msg!(
+            "1Success. Route. In_mint: {}, out_mint: {}, amount_in : {}, amount_out: {}, min_amount_out: {}.",
+            &in_token_mint,
+            hop_in_token_mint,
+            amount_in,
+            hop_amount_in,
+            min_amount_out,
+        );
+        Ok(())
+    }
+    fn arbitrage(
+        buy_dex: Dex_,
+        buy_dex_accounts_quantity: u8,
+        sell_dex: Dex_,
+        sell_dex_accounts_quantity: u8,
+        accounts: &[AccountInfo],
+        token_mint: Pubkey,
+        amount_in: u64,
+        min_profit_lamports: u64,
+        token_account_pubkey_bump_seed: u8,
+        with_checks: bool,
+    ) -> ProgramResult {
+        let quote_mint = spl_token::native_mint::ID;
+        if token_mint == quote_mint {
+            return Err(Error::EqualMints.into());
+        }
+        if amount_in == 0 {
+            return Err(Error::ZeroAmountIn.into());
+        }
+        let account_info_iter = &mut accounts.iter();
+        let intermediary = solana_program::account_info::next_account_info(account_info_iter)?;
+        let intermediary_trader = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let self_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_token_mint = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_mint_ = solana_program::account_info::next_account_info(account_info_iter)?;
+        let system_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let rent = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_2022_program = solana_program::account_info::next_account_info(account_info_iter)?;
+        if token_mint != *token_mint_.key {
+            return Err(Error::InvalidTokenMint.into());
+        }
+        if with_checks {
+            if *token_account.key != PdaResolver::token_account_create(intermediary.key, token_mint_.key, token_account_pubkey_bump_seed)?
+                || *quote_token_mint.key != spl_token::native_mint::ID
+                || *system_program.key != solana_program::system_program::ID
+                || *rent.key != solana_program::sysvar::rent::ID
+                || *token_program.key != spl_token::ID
+                || *token_2022_program.key != crate::extern_source::spl_token_2022::ID
+            {
+                return Err(Error::InvalidAccountPubkey.into());
+            }
+            if !intermediary_trader.is_signer || !intermediary_trader.is_writable || !quote_token_account.is_writable || !token_account.is_writable {
+                return Err(Error::InvalidAccountConfigurationFlags.into());
+            }
+        }
+        let intermediary_data = &intermediary.data.borrow();
+        let intermediary_ = bytemuck::try_from_bytes::<Intermediary>(intermediary_data).map_err(|_| Error::InvalidLogic)?;
+        if !intermediary_.is_initialized() {
+            return Err(Error::IntermediaryIsNotInitialized.into());
+        }
+        if *intermediary_trader.key != intermediary_.trader {
+            return Err(Error::IntermediaryInvalidTrader.into());
+        }
+        if *quote_token_account.key != intermediary_.w_sol_token_account
+            || *quote_token_account.key != PdaResolver::token_account_create(intermediary.key, &spl_token::native_mint::ID, intermediary_.w_sol_token_account_pubkey_bump_seed)?
+        {
+            return Err(Error::IntermediaryInvalidWSolTokenAccount.into());
+        }
+        if *self_authority.key != intermediary_.self_authority
+            || *self_authority.key != PdaResolver::self_authority_create(intermediary.key, intermediary_.self_authority_pubkey_bump_seed)?
+        {
+            return Err(Error::IntermediaryInvalidAuthority.into());
+        }
+        let initial_quote_token_amount = Self::get_token_account_amount(quote_token_account)?;
+        if amount_in > initial_quote_token_amount {
+            return Err(Error::TokenAccountInsufficientAmount.into());
+        }
+        let mut initial_token_amount = 0;
+        if token_account.data_is_empty() {
+            Self::create_token_account(
+                intermediary_trader,
+                token_account,
+                token_mint_,
+                self_authority,
+                rent,
+                Self::get_token_mint_program(token_mint_, token_program, token_2022_program)?,
+                PdaResolver::token_account_get_seeds(intermediary.key, token_mint_.key, [token_account_pubkey_bump_seed].as_slice()).as_slice(),
+            )?;
+        } else {
+            initial_token_amount = Self::get_token_account_amount(token_account)?
+        }
+        // The sell should return the spent WSol and the profit.
+        let min_sell_amount_out = amount_in.checked_add(min_profit_lamports).ok_or(Error::CheckedAddOverflow)?;
+        const INTERMEDIARY_RESERVED_ACCOUNTS_QUANTUTY: usize = 11;
+        let buy_dex_accounts = Self::get_dex_accounts(buy_dex, accounts, INTERMEDIARY_RESERVED_ACCOUNTS_QUANTUTY, buy_dex_accounts_quantity)?;
+        let sell_dex_accounts = Self::get_dex_accounts(sell_dex, accounts, INTERMEDIARY_RESERVED_ACCOUNTS_QUANTUTY + buy_dex_accounts.len(), sell_dex_accounts_quantity)?;
+        let buy_base_data = BaseData {
+            accounts,
+            intermediary,
+            quote_token_account,
+            token_account,
+            self_authority,
+            intermediary_,
+            token_mint: &token_mint,
+            quote_mint: &quote_mint,
+            amount_in,
+            min_amount_out: 0,
+            is_from_quote_to_token: true,
+            with_checks,
+        };
+        let buy_swap_calculation_result = match Self::do_swap_calculation(buy_dex, &buy_base_data, buy_dex_accounts)? {
+            Some(buy_swap_calculation_result_) => buy_swap_calculation_result_,
+            None => {
+                msg!("3Fail. Buy dex {} is not available.", buy_dex.to_str());
+                return Err(Error::InvalidSwapConditions.into());
+            }
+        };
+        Self::do_swap(buy_dex, &buy_base_data, buy_dex_accounts)?;
+        // Exactly the received amount is sold.
+        let token_amount_out = Self::get_token_account_amount(token_account)?.saturating_sub(initial_token_amount);
+        if token_amount_out == 0 {
+            return Err(Error::TokenAccountInvalidAmount.into());
+        }
+        let sell_base_data = BaseData {
+            amount_in: token_amount_out,
+            min_amount_out: min_sell_amount_out,
+            is_from_quote_to_token: false,
+            ..buy_base_data
+        };
+        let sell_swap_calculation_result = match Self::do_swap_calculation(sell_dex, &sell_base_data, sell_dex_accounts)? {
+            Some(sell_swap_calculation_result_) => sell_swap_calculation_result_,
+            None => {
+                msg!("3Fail. Sell dex {} is not available.", sell_dex.to_str());
                 return Err(Error::InvalidSwapConditions.into());
             }
+        };
+        if sell_swap_calculation_result.pool == buy_swap_calculation_result.pool {
+            return Err(Error::RepeatableDex.into());
+        }
+        if sell_swap_calculation_result.amount_out < min_sell_amount_out {
+            msg!(
+                "4Fail. Not profitable. Buy dex: {}, buy pool: {}, sell dex: {}, sell pool: {}, mint: {}, amount_in : {}, token_amount: {}, amount_out: {}, min_profit_lamports: {}.",
+                buy_dex.to_str(),
+                &buy_swap_calculation_result.pool,
+                sell_dex.to_str(),
+                &sell_swap_calculation_result.pool,
+                &token_mint,
+                amount_in,
+                token_amount_out,
+                sell_swap_calculation_result.amount_out,
+                min_profit_lamports,
+            );
+            return Err(Error::InvalidSwapConditions.into());
+        }
+        Self::do_swap(sell_dex, &sell_base_data, sell_dex_accounts)?;
+        solana_program::program::invoke(
+            &spl_token::instruction::sync_native(token_program.key, quote_token_account.key)?,
+            vec![
+                quote_token_account.clone(),
+            ]
+            .as_slice(),
+        )?;
+        let new_quote_token_amount = Self::get_token_account_amount(quote_token_account)?;
+        let new_token_amount = Self::get_token_account_amount(token_account)?;
+        if (new_quote_token_amount as u128) < (initial_quote_token_amount as u128 + min_profit_lamports as u128) || new_token_amount < initial_token_amount {
+            msg!(
+                "2Fail. Invalid calculation logic. Buy dex: {}, buy pool: {}, sell dex: {}, sell pool: {}, mint: {}, amount_in : {}, token_amount: {}, amount_out: {}, min_profit_lamports: {}.",
+                buy_dex.to_str(),
+                &buy_swap_calculation_result.pool,
+                sell_dex.to_str(),
+                &sell_swap_calculation_result.pool,
+                &token_mint,
+                amount_in,
+                token_amount_out,
+                sell_swap_calculation_result.amount_out,
+                min_profit_lamports,
+            );
+            return Err(Error::TokenAccountInvalidAmount.into());
+        }
+        msg!(
+            "0Success. Arbitrage. Buy dex: {}, buy pool: {}, sell dex: {}, sell pool: {}, mint: {}, amount_in : {}, token_amount: {}, profit_lamports: {}, min_profit_lamports: {}.",
+            buy_dex.to_str(),
+            &buy_swap_calculation_result.pool,
+            sell_dex.to_str(),
+            &sell_swap_calculation_result.pool,
+            &token_mint,
+            amount_in,
+            token_amount_out,
+            new_quote_token_amount - initial_quote_token_amount,
+            min_profit_lamports,
+        );
+        Ok(())
+    }
+    // Executes one swap per part of the split 'amount_in'. The 'min_amount_out' is checked only for the sum of all parts.
+    fn do_split_swap<'a, 'b>(
+        base_data: &BaseData<'a, 'b>,
+        dexes_with_swap_calculation_result: Vec<(Dex_, &[AccountInfo<'b>], SwapCalculationResult, u64)>,
+        out_token_account: &AccountInfo<'b>,
+        executed_swaps: &mut Vec<(Dex_, SwapCalculationResult, u64, u64)>,
+    ) -> ProgramResult {
+        let mut amount_out: u128 = 0;
+        '_a: for dex_with_swap_calculation_result in dexes_with_swap_calculation_result.iter() {
+            amount_out += dex_with_swap_calculation_result.2.amount_out as u128;
+        }
+        if amount_out < base_data.min_amount_out as u128 {
+            return Ok(());
+        }
+        '_a: for (dex_, dex_accounts, swap_calculation_result, amount_in) in dexes_with_swap_calculation_result.into_iter() {
+            let base_data_ = BaseData {
+                amount_in,
+                min_amount_out: 0,
+                ..*base_data
+            };
+            let initial_out_token_amount = Self::get_token_account_amount(out_token_account)?;
+            Self::do_swap(dex_, &base_data_, dex_accounts)?;
+            let new_out_token_amount = Self::get_token_account_amount(out_token_account)?;
+            executed_swaps.push((dex_, swap_calculation_result, amount_in, new_out_token_amount.saturating_sub(initial_out_token_amount)));
+        }
+        Ok(())
+    }
+    // Calculates the swap on every Dex without the swap itself and returns the results as the return data.
+    fn quote(
+        dexes: Vec<DexAccountsRange>,
+        accounts: &[AccountInfo],
+        token_mint: Pubkey,
+        quote_mint: Pubkey,
+        amount_in: u64,
+        token_account_pubkey_bump_seed: u8,
+        quote_token_account_pubkey_bump_seed: u8,
+        is_from_quote_to_token: bool,
+        with_checks: bool,
+    ) -> ProgramResult {
+        if token_mint == quote_mint {
+            return Err(Error::EqualMints.into());
+        }
+        if amount_in == 0 {
+            return Err(Error::ZeroAmountIn.into());
+        }
+        if dexes.is_empty() {
+            return Err(Error::ZeroDexesPresented.into());
+        }
+        let account_info_iter = &mut accounts.iter();
+        let intermediary = solana_program::account_info::next_account_info(account_info_iter)?;
+        let quote_token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        let self_authority = solana_program::account_info::next_account_info(account_info_iter)?;
+        let token_account = solana_program::account_info::next_account_info(account_info_iter)?;
+        // The token account is not read, so it may not exist yet.
+        if with_checks && *token_account.key != PdaResolver::token_account_create(intermediary.key, &token_mint, token_account_pubkey_bump_seed)? {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        let intermediary_data = &intermediary.data.borrow();
+        let intermediary_ = bytemuck::try_from_bytes::<Intermediary>(intermediary_data).map_err(|_| Error::InvalidLogic)?;
+        if !intermediary_.is_initialized() {
+            return Err(Error::IntermediaryIsNotInitialized.into());
+        }
+        Self::check_quote_token_account(intermediary, intermediary_, quote_token_account, &quote_mint, quote_token_account_pubkey_bump_seed)?;
+        if *self_authority.key != intermediary_.self_authority
+            || *self_authority.key != PdaResolver::self_authority_create(intermediary.key, intermediary_.self_authority_pubkey_bump_seed)?
+        {
+            return Err(Error::IntermediaryInvalidAuthority.into());
+        }
+        let base_data = BaseData {
+            accounts,
+            intermediary,
+            quote_token_account,
+            token_account,
+            self_authority,
+            intermediary_,
+            token_mint: &token_mint,
+            quote_mint: &quote_mint,
+            amount_in,
+            min_amount_out: 0,
+            is_from_quote_to_token,
+            with_checks,
+        };
+        const INTERMEDIARY_RESERVED_ACCOUNTS_QUANTUTY: usize = 4;
+        let dexes_ = Self::resolve_dexes_accounts_ranges(dexes, accounts, INTERMEDIARY_RESERVED_ACCOUNTS_QUANTUTY)?;
+        // The unavailable Dexes are skipped. Each result contains the pool to match it with the Dex.
+        let mut swap_calculation_results = Vec::<SwapCalculationResult>::with_capacity(dexes_.len());
+        '_a: for (dex_, dex_accounts_) in dexes_.into_iter() {
+            if let Some(swap_calculation_result) = Self::do_swap_calculation(dex_, &base_data, dex_accounts_)? {
+                swap_calculation_results.push(swap_calculation_result);
+            }
+        }
+        solana_program::program::set_return_data(borsh::to_vec(&swap_calculation_results)?.as_slice());
+        Ok(())
+    }
+    // Returns the price impact and the slippage in basis points relatively to the pre-trade spot price of every executed swap.
+    // The price impact is measured for the quoted amount out without fees, the slippage - for the received amount out with fees.
+    // The out mint transfer fee is excluded from both, as it is not a price deviation.
+    // Returns 'None' if the spot price is unknown, e.g. the Dex does not report its reserves.
+    fn calculate_price_deviation_bps(
+        executed_swaps: &[(Dex_, SwapCalculationResult, u64, u64)],
+        amount_out: u64,
+        out_transfer_fee_config: Option<&(TransferFeeConfig, u64)>,
+    ) -> Option<(u64, u64)> {
+        const BPS_DENOMINATOR: u128 = 10_000;
+        let mut spot_amount_out: u128 = 0;
+        let mut spot_amount_out_without_fee: u128 = 0;
+        let mut quoted_amount_out: u128 = 0;
+        '_a: for (_, swap_calculation_result, amount_in, _) in executed_swaps.iter() {
+            if swap_calculation_result.in_reserve == 0 {
+                return None;
+            }
+            let amount_in_without_fee = amount_in.checked_sub(swap_calculation_result.amount_in_fee)?;
+            spot_amount_out += (*amount_in as u128) * (swap_calculation_result.out_reserve as u128) / (swap_calculation_result.in_reserve as u128);
+            spot_amount_out_without_fee += (amount_in_without_fee as u128) * (swap_calculation_result.out_reserve as u128) / (swap_calculation_result.in_reserve as u128);
+            quoted_amount_out += swap_calculation_result.amount_out as u128;
+        }
+        if let Some((transfer_fee_config, epoch)) = out_transfer_fee_config {
+            let deduct_transfer_fee = |spot_amount_out_: u128| -> Option<u128> {
+                let spot_amount_out__ = u64::try_from(spot_amount_out_).ok()?;
+                let transfer_fee = transfer_fee_config.calculate_epoch_fee(*epoch, spot_amount_out__)?;
+                Some(spot_amount_out__.checked_sub(transfer_fee)? as u128)
+            };
+            spot_amount_out = deduct_transfer_fee(spot_amount_out)?;
+            spot_amount_out_without_fee = deduct_transfer_fee(spot_amount_out_without_fee)?;
+        }
+        let deviation_bps = |spot_amount_out_: u128, amount_out_: u128| -> u64 {
+            if spot_amount_out_ == 0 {
+                return 0;
+            }
+            (spot_amount_out_.saturating_sub(amount_out_) * BPS_DENOMINATOR / spot_amount_out_) as u64
+        };
+        Some((deviation_bps(spot_amount_out_without_fee, quoted_amount_out), deviation_bps(spot_amount_out, amount_out as u128)))
+    }
+    // Returns the Dex and its swap accounts for every accounts range.
+    fn resolve_dexes_accounts_ranges<'a, 'b>(
+        dexes: Vec<DexAccountsRange>,
+        accounts: &'a [AccountInfo<'b>],
+        intermediary_reserved_accounts_quantity: usize,
+    ) -> Result<Vec<(Dex_, &'a [AccountInfo<'b>])>, ProgramError> {
+        let mut dexes_ = Vec::<(Dex_, &'a [AccountInfo<'b>])>::with_capacity(dexes.len());
+        // The same Dex may be presented several times with different pools.
+        let mut pools_btree_set = BTreeSet::<&Pubkey>::new();
+        '_a: for dex_accounts_range in dexes {
+            let dex__ = Self::get_dex(dex_accounts_range.dex);
+            let first_account_index = dex_accounts_range.first_account_index as usize;
+            if first_account_index < intermediary_reserved_accounts_quantity
+                || (dex_accounts_range.accounts_quantity as usize) < dex__.get_swap_accounts_quantity()
+                || dex_accounts_range.accounts_quantity as usize > dex__.get_max_swap_accounts_quantity()
+                || first_account_index + dex_accounts_range.accounts_quantity as usize > accounts.len()
+            {
+                msg!("5Fail. Invalid accounts range for Dex {}.", dex_accounts_range.dex.to_str());
+                return Err(Error::InvalidDexAccountsRange.into());
+            }
+            let pool = &accounts[first_account_index + dex__.get_pool_account_index()];
+            if !pools_btree_set.insert(pool.key) {
+                return Err(Error::RepeatableDex.into());
+            }
+            dexes_.push((
+                dex_accounts_range.dex,
+                &accounts[first_account_index..first_account_index + dex_accounts_range.accounts_quantity as usize],
+            ));
+        }
+        Ok(dexes_)
+    }
+    // Returns the Dex swap accounts, which start from the 'first_account_index'.
+    // The 'accounts_quantity' is variable for the Dex with the optional accounts.
+    fn get_dex_accounts<'a, 'b>(dex: Dex_, accounts: &'a [AccountInfo<'b>], first_account_index: usize, accounts_quantity: u8) -> Result<&'a [AccountInfo<'b>], ProgramError> {
+        let dex__ = Self::get_dex(dex);
+        let accounts_quantity_ = accounts_quantity as usize;
+        if accounts_quantity_ < dex__.get_swap_accounts_quantity()
+            || accounts_quantity_ > dex__.get_max_swap_accounts_quantity()
+            || first_account_index + accounts_quantity_ > accounts.len()
+        {
+            msg!("5Fail. Invalid accounts range for Dex {}.", dex.to_str());
+            return Err(Error::InvalidDexAccountsRange.into());
+        }
+        Ok(&accounts[first_account_index..first_account_index + accounts_quantity_])
+    }
+    // The treasury of the non-WSol quote mint is the PDA of its own seed, which differs from the PDA token account of the same mint
+    // bought by the swap. The WSol treasury is created during the initialization.
+    fn check_quote_token_account(
+        intermediary: &AccountInfo,
+        intermediary_: &Intermediary,
+        quote_token_account: &AccountInfo,
+        quote_mint: &Pubkey,
+        quote_token_account_pubkey_bump_seed: u8,
+    ) -> ProgramResult {
+        if *quote_mint == spl_token::native_mint::ID {
+            if *quote_token_account.key != intermediary_.w_sol_token_account
+                || *quote_token_account.key != PdaResolver::token_account_create(intermediary.key, &spl_token::native_mint::ID, intermediary_.w_sol_token_account_pubkey_bump_seed)?
+            {
+                return Err(Error::IntermediaryInvalidWSolTokenAccount.into());
+            }
+        } else if *quote_token_account.key != PdaResolver::quote_token_account_create(intermediary.key, quote_mint, quote_token_account_pubkey_bump_seed)? {
+            return Err(Error::IntermediaryInvalidQuoteTokenAccount.into());
+        }
+        Ok(())
+    }
+    // The WSol treasury is the PDA token account of the WSol mint, so only the treasury of the non-WSol quote mint is distinguished.
+    fn is_quote_token_account(intermediary: &AccountInfo, token_account: &AccountInfo, token_mint: &Pubkey, token_account_pubkey_bump_seed: u8) -> bool {
+        *token_mint != spl_token::native_mint::ID
+            && PdaResolver::quote_token_account_create(intermediary.key, token_mint, token_account_pubkey_bump_seed)
+                .is_ok_and(|quote_token_account| quote_token_account == *token_account.key)
+    }
+    // Reads the amount of the token account of both token programs, as the Token-2022 account may have the extensions.
+    fn get_token_account_amount(token_account: &AccountInfo) -> Result<u64, ProgramError> {
+        Ok(crate::extern_source::spl_token_2022::unpack_account_amount(&token_account.data.borrow()).ok_or(Error::InvalidAccountData)?)
+    }
+    // Returns the token program which owns the mint.
+    fn get_token_mint_program<'a, 'b>(
+        token_mint: &AccountInfo<'b>,
+        token_program: &'a AccountInfo<'b>,
+        token_2022_program: &'a AccountInfo<'b>,
+    ) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let token_mint_program = if *token_mint.owner == spl_token::ID {
+            token_program
+        } else if *token_mint.owner == crate::extern_source::spl_token_2022::ID {
+            token_2022_program
+        } else {
+            return Err(Error::InvalidTokenMint.into());
+        };
+        if token_mint_program.key != token_mint.owner {
+            return Err(Error::InvalidAccountPubkey.into());
+        }
+        Ok(token_mint_program)
+    }
+    // Owner - intermediary.self_authority. That is, all manipulations with reducing the token amount
+    // on the account are carried out through a this contract.
+    // The 'token_program' is the owner of the 'token_mint'. The Token-2022 account is created with the extensions required by the mint.
+    // The 'token_account_seeds' are the PDA seeds of the token account or of the quote mint treasury.
+    fn create_token_account<'a>(
+        payer: &AccountInfo<'a>,
+        token_account: &AccountInfo<'a>,
+        token_mint: &AccountInfo<'a>,
+        self_authority: &AccountInfo<'a>,
+        rent: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        token_account_seeds: &[&[u8]],
+    ) -> ProgramResult {
+        let is_token_2022 = *token_program.key == crate::extern_source::spl_token_2022::ID;
+        let token_account_length = if is_token_2022 {
+            solana_program::program::invoke(
+                &crate::extern_source::spl_token_2022::get_account_data_size(token_program.key, token_mint.key),
+                vec![
+                    token_mint.clone(),
+                ]
+                .as_slice(),
+            )?;
+            let (program_id, data) = solana_program::program::get_return_data().ok_or(Error::InvalidAccountData)?;
+            if program_id != *token_program.key {
+                return Err(Error::InvalidAccountData.into());
+            }
+            u64::from_le_bytes(data.as_slice().try_into().map_err(|_| Error::InvalidAccountData)?) as usize
+        } else {
+            <Account as Pack>::LEN
+        };
+        let rent_ = Rent::from_account_info(rent)?;
+        let token_account_rent_exemption_balance = rent_.minimum_balance(token_account_length);
+        if payer.lamports() < token_account_rent_exemption_balance {
+            return Err(Error::InvalidAccountLamports.into());
+        }
+        solana_program::program::invoke_signed(
+            &solana_program::system_instruction::create_account(payer.key, token_account.key, token_account_rent_exemption_balance, token_account_length as u64, token_program.key),
+            vec![
+                payer.clone(),
+                token_account.clone(),
+            ]
+            .as_slice(),
+            [token_account_seeds].as_slice(),
+        )?;
+        if is_token_2022 {
+            solana_program::program::invoke(
+                &crate::extern_source::spl_token_2022::initialize_account3(token_program.key, token_account.key, token_mint.key, self_authority.key),
+                vec![
+                    token_account.clone(),
+                    token_mint.clone(),
+                ]
+                .as_slice(),
+            )?;
+        } else {
+            solana_program::program::invoke(
+                &spl_token::instruction::initialize_account(token_program.key, token_account.key, token_mint.key, self_authority.key)?,
+                vec![
+                    token_account.clone(),
+                    token_mint.clone(),
+                    self_authority.clone(),
+                    rent.clone(),
+                ]
+                .as_slice(),
+            )?;
+        }
+        Ok(())
+    }
+    fn get_dex<'a, 'b, 'c>(dex: Dex_) -> &'a dyn Dex<'a, 'b, 'c> {
+        match dex {
+            Dex_::MeteoraV1 => &MeteoraV1,
+            Dex_::RaydiumV4 => &RaydiumV4,
+            Dex_::RaydiumCpmm => &RaydiumCpmm,
+            Dex_::RaydiumClmm => &RaydiumClmm,
+            Dex_::OrcaWhirlpool => &OrcaWhirlpool,
+            Dex_::MeteoraDlmm => &MeteoraDlmm,
+            Dex_::MeteoraDammV2 => &MeteoraDammV2,
+            Dex_::PumpFun => &PumpFun,
+            Dex_::PumpSwap => &PumpSwap,
+            Dex_::Phoenix => &Phoenix,
+            Dex_::RaydiumV4SwapOnly => &RaydiumV4SwapOnly,
+        }
+    }
+    fn do_swap_calculation<'a, 'b, 'c>(dex: Dex_, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        // Here returns Error, because we believe that the discrepancy between the data on the accounts
+        // and the method signature parameters is a logical error, and we cannot simply move on to the next Dex.
+        match Self::get_dex(dex).do_swap_calculation(base_data, dex_accounts) {
+            Ok(swap_calculation_result) => Ok(swap_calculation_result),
+            Err(program_error) => {
+                msg!("0Fail. Invalid CPI accounts for Dex {},", dex.to_str());
+                Err(program_error)
+            }
+        }
+    }
+    fn do_swap<'a, 'b, 'c>(dex: Dex_, base_data: &'a BaseData<'b, 'c>, dex_accounts: &'a [AccountInfo<'c>]) -> ProgramResult {
+        if let Err(program_error) = Self::get_dex(dex).do_swap(base_data, dex_accounts) {
+            msg!("1Fail. Invalid CPI accounts for Dex {},", dex.to_str());
+            return Err(program_error);
+        }
+        Ok(())
+    }
+    fn do_swap_calculation_base_out<'a, 'b, 'c>(
+        dex: Dex_,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+    ) -> Result<Option<SwapCalculationResult>, ProgramError> {
+        match Self::get_dex(dex).do_swap_calculation_base_out(base_data, dex_accounts) {
+            Ok(swap_calculation_result) => Ok(swap_calculation_result),
+            Err(program_error) => {
+                msg!("0Fail. Invalid CPI accounts for Dex {},", dex.to_str());
+                Err(program_error)
+            }
+        }
+    }
+    fn do_swap_base_out<'a, 'b, 'c>(
+        dex: Dex_,
+        base_data: &'a BaseData<'b, 'c>,
+        dex_accounts: &'a [AccountInfo<'c>],
+        swap_calculation_result: &SwapCalculationResult,
+    ) -> ProgramResult {
+        if let Err(program_error) = Self::get_dex(dex).do_swap_base_out(base_data, dex_accounts, swap_calculation_result) {
+            msg!("1Fail. Invalid CPI accounts for Dex {},", dex.to_str());
+            return Err(program_error);
         }
         Ok(())
     }
@@ -1,8 +1,16 @@
 use {
     crate::{
+        METEORA_DAMM_V2_PROGRAM_ID,
+        METEORA_DLMM_PROGRAM_ID,
         METEORA_V1_PROGRAM_ID,
         METEORA_V1_VAULT_PROGRAM_ID,
+        ORCA_WHIRLPOOL_PROGRAM_ID,
+        PHOENIX_PROGRAM_ID,
         PROGRAM_ID,
+        PUMP_FUN_PROGRAM_ID,
+        PUMP_SWAP_PROGRAM_ID,
+        RAYDIUM_CLMM_PROGRAM_ID,
+        RAYDIUM_CPMM_PROGRAM_ID,
         RAYDIUM_V4_PROGRAM_ID,
     },
     bytemuck::*,
@@ -22,8 +30,8 @@ pub const QUANTITY_OF_MUCH_USED_DYNAMIC_ACCOUNTS: u8 = {
     );
     QUANTITY_OF_MUCH_USED_DYNAMIC_ACCOUNTS_
 };
-pub const MUCH_USED_STATIC_ACCOUNTS: [Pubkey; 8] = {
-    const MUCH_USED_STATIC_ACCOUNTS_: [Pubkey; 8] = [
+pub const MUCH_USED_STATIC_ACCOUNTS: [Pubkey; 16] = {
+    const MUCH_USED_STATIC_ACCOUNTS_: [Pubkey; 16] = [
         spl_token::native_mint::ID,
         solana_program::system_program::ID,
         solana_program::sysvar::rent::ID,
@@ -32,6 +40,14 @@ pub const MUCH_USED_STATIC_ACCOUNTS: [Pubkey; 8] = {
         METEORA_V1_PROGRAM_ID,
         METEORA_V1_VAULT_PROGRAM_ID,
         RAYDIUM_V4_PROGRAM_ID,
+        RAYDIUM_CPMM_PROGRAM_ID,
+        RAYDIUM_CLMM_PROGRAM_ID,
+        ORCA_WHIRLPOOL_PROGRAM_ID,
+        METEORA_DLMM_PROGRAM_ID,
+        METEORA_DAMM_V2_PROGRAM_ID,
+        PUMP_FUN_PROGRAM_ID,
+        PUMP_SWAP_PROGRAM_ID,
+        PHOENIX_PROGRAM_ID,
     ];
     static_assertions::const_assert!(
         MUCH_USED_STATIC_ACCOUNTS_.len() <= u8::MAX as usize
@@ -121,6 +137,7 @@ impl PdaResolver {
     const TOKEN_ACCOUNT_SEED: &'static str = "tokenaccount";
     const TEMPORARY_W_SOL_TOKEN_ACCOUNT_SEED: &'static str = "temporarywsoltokenaccount";
     const SELF_AUTHORITY_SEED: &'static str = "selfauthority";
+    const QUOTE_TOKEN_ACCOUNT_SEED: &'static str = "quotetokenaccount";
     pub fn token_account_get_seeds<'a>(intermediary: &'a Pubkey, token_mint: &'a Pubkey, bump_seed: &'a [u8]) -> [&'a [u8]; 5] {
         [
             PROGRAM_ID.as_ref(),
@@ -189,18 +206,108 @@ impl PdaResolver {
             &PROGRAM_ID,
         )
     }
+    // The treasury of the non-WSol quote mint. It differs from the token account of the same mint,
+    // so the registration of the quote mint is the existence of this account.
+    pub fn quote_token_account_get_seeds<'a>(intermediary: &'a Pubkey, quote_token_mint: &'a Pubkey, bump_seed: &'a [u8]) -> [&'a [u8]; 5] {
+        [
+            PROGRAM_ID.as_ref(),
+            intermediary.as_ref(),
+            quote_token_mint.as_ref(),
+            Self::QUOTE_TOKEN_ACCOUNT_SEED.as_bytes(),
+            bump_seed,
+        ]
+    }
+    pub fn quote_token_account_create(intermediary: &Pubkey, quote_token_mint: &Pubkey, bump_seed: u8) -> Result<Pubkey, PubkeyError> {
+        Pubkey::create_program_address(Self::quote_token_account_get_seeds(intermediary, quote_token_mint, [bump_seed].as_slice()).as_slice(), &PROGRAM_ID)
+    }
+    pub fn quote_token_account_find(intermediary: &Pubkey, quote_token_mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            [
+                PROGRAM_ID.as_ref(),
+                intermediary.as_ref(),
+                quote_token_mint.as_ref(),
+                Self::QUOTE_TOKEN_ACCOUNT_SEED.as_bytes(),
+            ]
+            .as_slice(),
+            &PROGRAM_ID,
+        )
+    }
 }
 #[repr(C)]
 #[derive(Debug, borsh::BorshSerialize, borsh::BorshDeserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Dex {
     MeteoraV1,
     RaydiumV4,
+    RaydiumCpmm,
+    RaydiumClmm,
+    OrcaWhirlpool,
+    MeteoraDlmm,
+    MeteoraDammV2,
+    PumpFun,
+    PumpSwap,
+    Phoenix,
+    RaydiumV4SwapOnly,
 }
 impl Dex {
     pub fn to_str(&self) -> &'static str {
         match *self {
             Self::MeteoraV1 => "MeteoraV1",
             Self::RaydiumV4 => "RaydiumV4",
+            Self::RaydiumCpmm => "RaydiumCpmm",
+            Self::RaydiumClmm => "RaydiumClmm",
+            Self::OrcaWhirlpool => "OrcaWhirlpool",
+            Self::MeteoraDlmm => "MeteoraDlmm",
+            Self::MeteoraDammV2 => "MeteoraDammV2",
+            Self::PumpFun => "PumpFun",
+            Self::PumpSwap => "PumpSwap",
+            Self::Phoenix => "Phoenix",
+            Self::RaydiumV4SwapOnly => "RaydiumV4SwapOnly",
         }
     }
 }
+// The Dex and the range of its swap accounts in the instruction accounts.
+#[repr(C)]
+#[derive(Debug, borsh::BorshSerialize, borsh::BorshDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct DexAccountsRange {
+    pub dex: Dex,
+    pub first_account_index: u8,
+    pub accounts_quantity: u8,
+}
+#[repr(C)]
+#[derive(Debug, borsh::BorshSerialize, borsh::BorshDeserialize, Clone, PartialEq, Eq)]
+pub enum SwapMode {
+    // Swap on the first Dex which satisfies the 'min_amount_out'.
+    FirstMatch,
+    // Quote every Dex and swap on the Dex with the highest 'amount_out'.
+    BestExecution,
+    // Split 'amount_in' across Dexes. One value for each Dex in the same order, zero value skips the Dex.
+    // The sum of values should be equal to 'amount_in'.
+    Split {
+        amounts_in: Vec<u64>,
+    },
+    // Split 'amount_in' into equal parts, each of which goes to the Dex with the highest marginal 'amount_out'.
+    SplitByMarginalPrice {
+        parts: u8,
+    },
+    // Swap on the first Dex which gives exactly 'min_amount_out' for no more than 'amount_in'.
+    // Here 'amount_in' is the maximum amount in and 'min_amount_out' is the exact amount out.
+    ExactOut,
+}
+// One swap of the route. The output of the hop is the input of the next hop.
+#[repr(C)]
+#[derive(Debug, borsh::BorshSerialize, borsh::BorshDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Hop {
+    pub dex: Dex,
+    // The quantity of the Dex swap accounts, which follow the out token account of the hop.
+    pub accounts_quantity: u8,
+    pub pool: Pubkey,
+    pub out_token_mint: Pubkey,
+    pub out_token_account_pubkey_bump_seed: u8,
+}
+// The PDA token account of the mint to close.
+#[repr(C)]
+#[derive(Debug, borsh::BorshSerialize, borsh::BorshDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct TokenAccountToClose {
+    pub token_mint: Pubkey,
+    pub token_account_pubkey_bump_seed: u8,
+}